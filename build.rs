@@ -1,4 +1,44 @@
+// Messages whose bytes fields are generated as `bytes::Bytes`
+#[cfg(any(feature = "grpc", feature = "client"))]
+const WIRE_INTEGER_MESSAGES: [&str; 12] = [
+    ".zkp.PublicParameters",
+    ".zkp.Commitment",
+    ".zkp.ProofChallenge",
+    ".zkp.ChallengeResponse",
+    ".zkp.VerifyProofRequest",
+    ".zkp.ZKProofMessage",
+    ".zkp.v2.PublicParameters",
+    ".zkp.v2.Commitment",
+    ".zkp.v2.ChallengeValues",
+    ".zkp.v2.Challenge",
+    ".zkp.v2.ResponseRequest",
+    ".zkp.v2.Proof",
+];
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    tonic_build::compile_protos("proto/zkp.proto")?;
+    // the generated protocol is only needed by the gRPC server and client
+    #[cfg(any(feature = "grpc", feature = "client"))]
+    {
+        // the integers of the per-proof messages decode as slices of the received buffer rather than
+        // fresh copies; see convert::encode_integer for the sending side. zkp_prover.proto imports
+        // zkp.proto, so all three are compiled together to keep a single configuration of zkp.rs
+        tonic_build::configure()
+            .bytes(WIRE_INTEGER_MESSAGES)
+            .compile(&["proto/zkp.proto", "proto/zkp_v2.proto", "proto/zkp_prover.proto"], &["proto"])?;
+    }
+
+    // C header for the `ffi` module, kept in the repository for callers that don't build the crate
+    #[cfg(feature = "ffi")]
+    {
+        println!("cargo:rerun-if-changed=src/ffi.rs");
+        println!("cargo:rerun-if-changed=cbindgen.toml");
+        // only ffi.rs is parsed, so the rest of the crate's public items stay out of the header
+        let crate_dir = std::path::PathBuf::from(std::env::var("CARGO_MANIFEST_DIR")?);
+        cbindgen::Builder::new()
+            .with_config(cbindgen::Config::from_root_or_default(&crate_dir))
+            .with_src(crate_dir.join("src/ffi.rs"))
+            .generate()?
+            .write_to_file(crate_dir.join("include/zkp_chaum_pedersen.h"));
+    }
     Ok(())
-}
\ No newline at end of file
+}
@@ -0,0 +1,12 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+use zkp_chaum_pedersen_grpc::chaum_pedersen::ZKProof;
+
+fuzz_target!(|data: &[u8]| {
+    // the encoding is canonical, so whatever decodes must re-encode to the same bytes
+    if let Ok(proof) = ZKProof::from_bytes(data) {
+        assert_eq!(proof.to_bytes(), data);
+    }
+});
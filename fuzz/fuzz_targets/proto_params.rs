@@ -0,0 +1,22 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use prost::Message;
+
+use zkp_chaum_pedersen_grpc::chaum_pedersen::PublicParameters;
+use zkp_chaum_pedersen_grpc::zkp;
+
+// Parameters as a client receives them from InitializeProtocol
+fuzz_target!(|data: &[u8]| {
+    let Ok(proto) = zkp::PublicParameters::decode(data) else {
+        return;
+    };
+    if let Ok(params) = PublicParameters::try_from(proto) {
+        // anything the conversion accepts must survive the client's full validation without panicking
+        let _ = params.validate();
+        assert_eq!(
+            PublicParameters::try_from(zkp::PublicParameters::from(&params)).map(|p| p.digest()),
+            Ok(params.digest())
+        );
+    }
+});
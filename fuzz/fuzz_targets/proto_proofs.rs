@@ -0,0 +1,26 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use prost::Message;
+
+use zkp_chaum_pedersen_grpc::chaum_pedersen::{Commitment, MultiZKProof, ProofChallenge};
+use zkp_chaum_pedersen_grpc::zkp;
+
+// The request bodies of SendCommitment and VerifyMultiProof, decoded and converted as the server does
+fuzz_target!(|data: &[u8]| {
+    if let Ok(request) = zkp::CommitmentRequest::decode(data) {
+        if let Some(commitment) = request.commitment {
+            let _ = Commitment::try_from(commitment);
+        }
+        if let Some(values) = request.challenge_values {
+            let _ = ProofChallenge::try_from(values);
+        }
+    }
+    if let Ok(request) = zkp::MultiProofRequest::decode(data)
+        && let Some(proof) = request.proof
+        && let Ok(proof) = MultiZKProof::try_from(proof)
+    {
+        let round_trip = MultiZKProof::try_from(zkp::MultiStatementProof::from(&proof)).expect("re-encoding is valid");
+        assert_eq!(round_trip.challenge_hash, proof.challenge_hash);
+    }
+});
@@ -0,0 +1,27 @@
+#![no_main]
+
+use std::sync::OnceLock;
+
+use libfuzzer_sys::fuzz_target;
+use prost::Message;
+
+use zkp_chaum_pedersen_grpc::chaum_pedersen::{MultiZKProof, PublicParameters, Verifier, ZKProof};
+use zkp_chaum_pedersen_grpc::zkp;
+
+// Small fixed group, so inputs can hit values near p and q
+fn verifier() -> &'static Verifier {
+    static VERIFIER: OnceLock<Verifier> = OnceLock::new();
+    VERIFIER.get_or_init(|| Verifier::new(PublicParameters::from_seed(64, b"fuzz")))
+}
+
+// Arbitrary proofs, including out-of-range and zero values, must be rejected without panicking
+fuzz_target!(|data: &[u8]| {
+    if let Ok(proof) = ZKProof::from_bytes(data) {
+        let _ = verifier().verify_proof(&proof);
+    }
+    if let Ok(proto) = zkp::MultiStatementProof::decode(data)
+        && let Ok(proof) = MultiZKProof::try_from(proto)
+    {
+        let _ = verifier().verify_multi(&proof);
+    }
+});
@@ -0,0 +1,152 @@
+#ifndef ZKP_CHAUM_PEDERSEN_H
+#define ZKP_CHAUM_PEDERSEN_H
+
+/* Generated by cbindgen from src/ffi.rs; do not edit */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * Outcome of every call that can fail
+ */
+typedef enum ZkpStatus {
+  ZKP_STATUS_OK = 0,
+  /**
+   * A required pointer was null
+   */
+  ZKP_STATUS_NULL_POINTER = 1,
+  /**
+   * The parameters are malformed or fail validation
+   */
+  ZKP_STATUS_INVALID_PARAMETERS = 2,
+  /**
+   * A secret is not in [1, q)
+   */
+  ZKP_STATUS_INVALID_SECRET = 3,
+  /**
+   * The proof bytes do not decode; a proof that decodes but does not verify is not an error
+   */
+  ZKP_STATUS_INVALID_PROOF = 4,
+  /**
+   * The library panicked; nothing was written to the outputs
+   */
+  ZKP_STATUS_PANIC = 5,
+} ZkpStatus;
+
+/**
+ * An opaque parameter set (p, q, g); release with zkp_params_free
+ */
+typedef struct ZkpParams ZkpParams;
+
+/**
+ * Bytes allocated by the library; release with zkp_buffer_free
+ */
+typedef struct ZkpBuffer {
+  uint8_t *data;
+  size_t len;
+} ZkpBuffer;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Generates a fresh parameter set with a `bits`-bit safe prime p. This takes seconds at
+ * 2048 bits; generate once and share the result through zkp_params_to_bytes.
+ *
+ * # Safety
+ * `out` must be valid for writing a pointer.
+ */
+enum ZkpStatus zkp_params_generate(uint64_t bits, struct ZkpParams **out);
+
+/**
+ * Loads big-endian p, q and g received from elsewhere. They are validated in full (sizes,
+ * p = 2q + 1, primality, the order of g) since a verifier must not trust them blindly.
+ *
+ * # Safety
+ * Each input must point to `len` readable bytes, and `out` must be valid for writing a pointer.
+ */
+enum ZkpStatus zkp_params_from_bytes(const uint8_t *p,
+                                     size_t p_len,
+                                     const uint8_t *q,
+                                     size_t q_len,
+                                     const uint8_t *g,
+                                     size_t g_len,
+                                     struct ZkpParams **out);
+
+/**
+ * Writes p, q and g as big-endian byte strings, each to be released with zkp_buffer_free
+ *
+ * # Safety
+ * `params` must come from this library, and `p`, `q` and `g` must be valid for writing a buffer.
+ */
+enum ZkpStatus zkp_params_to_bytes(const struct ZkpParams *params,
+                                   struct ZkpBuffer *p,
+                                   struct ZkpBuffer *q,
+                                   struct ZkpBuffer *g);
+
+/**
+ * # Safety
+ * `params` must be null or come from this library, and must not be used afterwards.
+ */
+void zkp_params_free(struct ZkpParams *params);
+
+/**
+ * Proves knowledge of the secrets a and b (big-endian, each in [1, q)) behind the commitment
+ * (g^a, g^b, g^ab). The proof carries that commitment, so a verifier should also check it
+ * matches the one registered for the user (see zkp_proof_commitment).
+ *
+ * # Safety
+ * `params` must come from this library, each secret must point to `len` readable bytes, and
+ * `proof` must be valid for writing a buffer.
+ */
+enum ZkpStatus zkp_create_proof(const struct ZkpParams *params,
+                                const uint8_t *secret_a,
+                                size_t secret_a_len,
+                                const uint8_t *secret_b,
+                                size_t secret_b_len,
+                                struct ZkpBuffer *proof);
+
+/**
+ * Checks an encoded proof against the parameters and sets `verified`. ZKP_STATUS_OK with
+ * `verified` false means a well-formed proof that does not hold.
+ *
+ * # Safety
+ * `params` must come from this library, `proof` must point to `proof_len` readable bytes, and
+ * `verified` must be valid for writing a bool.
+ */
+enum ZkpStatus zkp_verify_proof(const struct ZkpParams *params,
+                                const uint8_t *proof,
+                                size_t proof_len,
+                                bool *verified);
+
+/**
+ * Writes the commitment (a1, b1, c1) an encoded proof was made for, as big-endian byte strings
+ * each to be released with zkp_buffer_free
+ *
+ * # Safety
+ * `proof` must point to `proof_len` readable bytes, and `a1`, `b1` and `c1` must be valid for
+ * writing a buffer.
+ */
+enum ZkpStatus zkp_proof_commitment(const uint8_t *proof,
+                                    size_t proof_len,
+                                    struct ZkpBuffer *a1,
+                                    struct ZkpBuffer *b1,
+                                    struct ZkpBuffer *c1);
+
+/**
+ * Releases a buffer returned by this library; a buffer with a null `data` is ignored
+ *
+ * # Safety
+ * `buffer` must come from this library and must not be freed twice.
+ */
+void zkp_buffer_free(struct ZkpBuffer buffer);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* ZKP_CHAUM_PEDERSEN_H */
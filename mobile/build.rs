@@ -0,0 +1,3 @@
+fn main() {
+    uniffi::generate_scaffolding("src/zkp_mobile.udl").expect("src/zkp_mobile.udl is a valid interface definition");
+}
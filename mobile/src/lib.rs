@@ -0,0 +1,221 @@
+// ClientError carries a tonic::Status, as in the main crate
+#![allow(clippy::result_large_err)]
+// The generated scaffolding has a blank line after one of its doc comments
+#![allow(clippy::empty_line_after_doc_comments)]
+
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use num_bigint::BigUint;
+use tokio::runtime::Runtime;
+use tonic::transport::{Certificate, ClientTlsConfig};
+
+use zkp_chaum_pedersen_grpc::client_lib::{ChaumPedersenClient, ClientError, ClientOptions};
+use zkp_chaum_pedersen_grpc::session::ChallengeMode;
+use zkp_chaum_pedersen_grpc::{Commitment, Prover, PublicParameters};
+
+// The objects behind src/zkp_mobile.udl; the generated scaffolding refers to them by name
+uniffi::include_scaffolding!("zkp_mobile");
+
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum MobileError {
+    InvalidParameters(String),
+    InvalidSecret,
+    InvalidChallenge,
+    RoundFinished,
+    ParameterMismatch,  // the verifier issued other parameters than the prover's
+    Client(ClientError),
+}
+
+impl fmt::Display for MobileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MobileError::InvalidParameters(reason) => write!(f, "invalid parameters: {}", reason),
+            MobileError::InvalidSecret => write!(f, "secrets must be in [1, q)"),
+            MobileError::InvalidChallenge => write!(f, "the challenge is not below q"),
+            MobileError::RoundFinished => write!(f, "this round was already answered"),
+            MobileError::ParameterMismatch => write!(f, "the verifier's parameters differ from the prover's"),
+            MobileError::Client(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for MobileError {}
+
+impl From<ClientError> for MobileError {
+    fn from(e: ClientError) -> Self {
+        MobileError::Client(e)
+    }
+}
+
+pub struct MobileCommitment {
+    pub a1: Vec<u8>,
+    pub b1: Vec<u8>,
+    pub c1: Vec<u8>,
+}
+
+impl From<Commitment> for MobileCommitment {
+    fn from(commitment: Commitment) -> Self {
+        Self {
+            a1: commitment.a1.to_bytes_be(),
+            b1: commitment.b1.to_bytes_be(),
+            c1: commitment.c1.to_bytes_be(),
+        }
+    }
+}
+
+pub struct MobileParams(PublicParameters);
+
+impl MobileParams {
+    pub fn from_bytes(p: Vec<u8>, q: Vec<u8>, g: Vec<u8>) -> Result<Self, MobileError> {
+        let [p, q, g] = [p, q, g].map(|value| BigUint::from_bytes_be(&value));
+        let params = PublicParameters::from_parts(p, q, g, None);
+        params
+            .validate()
+            .map_err(|e| MobileError::InvalidParameters(e.to_string()))?;
+        Ok(Self(params))
+    }
+
+    pub fn p(&self) -> Vec<u8> {
+        self.0.p().to_bytes_be()
+    }
+
+    pub fn q(&self) -> Vec<u8> {
+        self.0.q().to_bytes_be()
+    }
+
+    pub fn g(&self) -> Vec<u8> {
+        self.0.g().to_bytes_be()
+    }
+}
+
+pub struct MobileProver(Arc<Prover>);
+
+impl MobileProver {
+    pub fn generate(params: Arc<MobileParams>) -> Self {
+        Self(Arc::new(Prover::new(params.0.clone())))
+    }
+
+    pub fn from_secrets(params: Arc<MobileParams>, secret_a: Vec<u8>, secret_b: Vec<u8>) -> Result<Self, MobileError> {
+        let params = &params.0;
+        let [a, b] = [secret_a, secret_b].map(|secret| BigUint::from_bytes_be(&secret));
+        if [&a, &b].iter().any(|secret| secret.bits() == 0 || *secret >= params.q()) {
+            return Err(MobileError::InvalidSecret);
+        }
+        let prover = Prover::builder(params.clone())
+            .secrets(a, b)
+            .build()
+            .map_err(|e| MobileError::InvalidParameters(e.to_string()))?;
+        Ok(Self(Arc::new(prover)))
+    }
+
+    pub fn params(&self) -> Arc<MobileParams> {
+        Arc::new(MobileParams(self.0.params.clone()))
+    }
+
+    pub fn secret_a(&self) -> Vec<u8> {
+        self.0.secret_a.to_bytes_be()
+    }
+
+    pub fn secret_b(&self) -> Vec<u8> {
+        self.0.secret_b.to_bytes_be()
+    }
+
+    pub fn commitment(&self) -> MobileCommitment {
+        self.0.generate_commitment().into()
+    }
+
+    pub fn begin_round(&self) -> Arc<ProofRound> {
+        let (values, x) = self.0.generate_proof_challenge(&self.0.generate_commitment());
+        Arc::new(ProofRound {
+            prover: self.0.clone(),
+            y1: values.y1.to_bytes_be(),
+            y2: values.y2.to_bytes_be(),
+            nonce: Mutex::new(Some(x)),
+        })
+    }
+}
+
+pub struct ProofRound {
+    prover: Arc<Prover>,
+    y1: Vec<u8>,
+    y2: Vec<u8>,
+    nonce: Mutex<Option<BigUint>>,  // taken by the first respond
+}
+
+impl ProofRound {
+    pub fn y1(&self) -> Vec<u8> {
+        self.y1.clone()
+    }
+
+    pub fn y2(&self) -> Vec<u8> {
+        self.y2.clone()
+    }
+
+    pub fn respond(&self, challenge: Vec<u8>) -> Result<Vec<u8>, MobileError> {
+        let challenge = BigUint::from_bytes_be(&challenge);
+        if challenge >= *self.prover.params.q() {
+            return Err(MobileError::InvalidChallenge);
+        }
+        let x = self
+            .nonce
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .take()
+            .ok_or(MobileError::RoundFinished)?;
+        Ok(self.prover.generate_response(&x, &challenge).z.to_bytes_be())
+    }
+}
+
+// Owns the runtime its calls block on, so the app needs no async support of its own
+pub struct MobileClient {
+    runtime: Runtime,
+    client: ChaumPedersenClient,
+}
+
+impl MobileClient {
+    pub fn connect(endpoint: String, api_key: Option<String>, ca_certificate: Option<Vec<u8>>) -> Result<Self, MobileError> {
+        let runtime = Runtime::new().map_err(|e| ClientError::InvalidConfig(e.to_string()))?;
+        let options = ClientOptions {
+            api_key,
+            tls: ca_certificate.map(|pem| ClientTlsConfig::new().ca_certificate(Certificate::from_pem(pem))),
+            ..ClientOptions::default()
+        };
+        let client = runtime.block_on(ChaumPedersenClient::connect_with(&endpoint, options))?;
+        Ok(Self { runtime, client })
+    }
+
+    pub fn enroll(&self, user_id: String, bit_size: u32) -> Result<Arc<MobileProver>, MobileError> {
+        self.runtime.block_on(async {
+            let session = self.client.initialize(bit_size).await?;
+            let prover = Prover::new(session.params.clone());
+            self.client
+                .register(&session, &user_id, &prover.generate_commitment())
+                .await?;
+            Ok(Arc::new(MobileProver(Arc::new(prover))))
+        })
+    }
+
+    pub fn login(&self, user_id: String, prover: Arc<MobileProver>) -> Result<bool, MobileError> {
+        let prover = &prover.0;
+        self.runtime.block_on(async {
+            let session = self
+                .client
+                .initialize_for_user(&user_id, ChallengeMode::Interactive)
+                .await?;
+            let params = &session.params;
+            if (params.p(), params.q(), params.g()) != (prover.params.p(), prover.params.q(), prover.params.g()) {
+                return Err(MobileError::ParameterMismatch);
+            }
+            let commitment = prover.generate_commitment();
+            let (values, x) = prover.generate_proof_challenge(&commitment);
+            let challenge = self
+                .client
+                .send_commitment(&session, &commitment, &values.y1, &values.y2)
+                .await?;
+            let z = prover.generate_response(&x, &challenge).z;
+            Ok(self.client.prove(&session, &z).await?.verified)
+        })
+    }
+}
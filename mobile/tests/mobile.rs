@@ -0,0 +1,46 @@
+use std::sync::Arc;
+
+use tokio::runtime::Runtime;
+
+use zkp_chaum_pedersen_grpc::testing::TestServer;
+use zkp_chaum_pedersen_mobile::{MobileClient, MobileError, MobileParams, MobileProver};
+
+// The app's view: enroll once, keep the secrets, log in later with a prover rebuilt from them
+#[test]
+fn enrolled_prover_logs_in() {
+    // the mobile client blocks on its own runtime, so the server gets a separate one
+    let runtime = Runtime::new().unwrap();
+    let server = runtime.block_on(TestServer::start()).unwrap();
+
+    let client = MobileClient::connect(server.url(), None, None).unwrap();
+    let enrolled = client.enroll("alice".to_string(), 256).unwrap();
+
+    let params = enrolled.params();
+    let params = Arc::new(MobileParams::from_bytes(params.p(), params.q(), params.g()).unwrap());
+    let restored = Arc::new(MobileProver::from_secrets(params.clone(), enrolled.secret_a(), enrolled.secret_b()).unwrap());
+    assert!(client.login("alice".to_string(), restored).unwrap());
+
+    // a prover with other secrets does not match the registration
+    let other = Arc::new(MobileProver::generate(params));
+    assert!(matches!(client.login("alice".to_string(), other), Err(MobileError::Client(_))));
+}
+
+#[test]
+fn a_round_answers_once() {
+    let runtime = Runtime::new().unwrap();
+    let server = runtime.block_on(TestServer::start()).unwrap();
+    let client = MobileClient::connect(server.url(), None, None).unwrap();
+    let prover = client.enroll("bob".to_string(), 256).unwrap();
+
+    let round = prover.begin_round();
+    let q = prover.params().q();
+    assert!(matches!(round.respond(q), Err(MobileError::InvalidChallenge)));
+    round.respond(vec![7]).unwrap();
+    assert!(matches!(round.respond(vec![7]), Err(MobileError::RoundFinished)));
+
+    let zero = vec![0];
+    assert!(matches!(
+        MobileProver::from_secrets(prover.params(), zero, prover.secret_b()),
+        Err(MobileError::InvalidSecret)
+    ));
+}
@@ -0,0 +1,183 @@
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use hmac::{Hmac, Mac};
+use num_bigint::BigUint;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+use crate::chaum_pedersen::{wire, Commitment, PublicParameters};
+
+type HmacSha256 = Hmac<Sha256>;
+
+// One verification attempt, as recorded in the append-only audit log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub session_id: String,
+    pub params_digest: String,  // hex SHA-256 of the session's (p, q, g)
+    pub commitment: Option<Commitment>,
+    #[serde(with = "wire::biguint")]
+    pub challenge: BigUint,
+    #[serde(with = "wire::biguint")]
+    pub response: BigUint,
+    pub verified: bool,
+    pub timestamp: u64,  // seconds since the Unix epoch
+    pub peer: Option<String>,
+    pub signature: Option<String>,  // hex HMAC-SHA256 over the entry with this field unset
+}
+
+impl AuditEntry {
+    pub fn new(
+        session_id: &str,
+        params: &PublicParameters,
+        commitment: Option<Commitment>,
+        challenge: BigUint,
+        response: BigUint,
+        verified: bool,
+        peer: Option<String>,
+    ) -> Self {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+
+        Self {
+            session_id: session_id.to_string(),
+            params_digest: hex::encode(params.digest()),
+            commitment,
+            challenge,
+            response,
+            verified,
+            timestamp,
+            peer,
+            signature: None,
+        }
+    }
+
+    // The bytes covered by the signature: the JSON encoding of the entry without its signature
+    fn signing_payload(&self) -> Vec<u8> {
+        let unsigned = AuditEntry {
+            signature: None,
+            ..self.clone()
+        };
+        serde_json::to_vec(&unsigned).expect("audit entries always serialize")
+    }
+
+    pub fn sign(&mut self, key: &[u8]) {
+        let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts keys of any length");
+        mac.update(&self.signing_payload());
+        self.signature = Some(hex::encode(mac.finalize().into_bytes()));
+    }
+
+    pub fn verify_signature(&self, key: &[u8]) -> bool {
+        let signature = match self.signature.as_deref().map(hex::decode) {
+            Some(Ok(signature)) => signature,
+            _ => return false,
+        };
+        let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts keys of any length");
+        mac.update(&self.signing_payload());
+        mac.verify_slice(&signature).is_ok()
+    }
+}
+
+// Destination for audit entries. Implementations must only ever append.
+pub trait AuditSink: Send + Sync {
+    fn append(&self, entry: &AuditEntry) -> io::Result<()>;
+}
+
+// Writes one JSON object per line to a file opened in append mode
+#[derive(Debug)]
+pub struct FileAuditSink {
+    file: Mutex<File>,
+}
+
+impl FileAuditSink {
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self { file: Mutex::new(file) })
+    }
+}
+
+impl AuditSink for FileAuditSink {
+    fn append(&self, entry: &AuditEntry) -> io::Result<()> {
+        let mut line = serde_json::to_vec(entry)?;
+        line.push(b'\n');
+        let mut file = self.file.lock().unwrap();
+        file.write_all(&line)?;
+        file.flush()
+    }
+}
+
+#[cfg(feature = "audit-sqlite")]
+pub struct SqliteAuditSink {
+    conn: Mutex<rusqlite::Connection>,
+}
+
+#[cfg(feature = "audit-sqlite")]
+impl SqliteAuditSink {
+    pub fn open(path: impl AsRef<Path>) -> rusqlite::Result<Self> {
+        let conn = rusqlite::Connection::open(path)?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS audit_log (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                session_id TEXT NOT NULL,
+                timestamp INTEGER NOT NULL,
+                verified INTEGER NOT NULL,
+                entry TEXT NOT NULL
+            )",
+        )?;
+        Ok(Self { conn: Mutex::new(conn) })
+    }
+}
+
+#[cfg(feature = "audit-sqlite")]
+impl AuditSink for SqliteAuditSink {
+    fn append(&self, entry: &AuditEntry) -> io::Result<()> {
+        let json = serde_json::to_string(entry)?;
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO audit_log (session_id, timestamp, verified, entry) VALUES (?1, ?2, ?3, ?4)",
+            rusqlite::params![entry.session_id, entry.timestamp as i64, entry.verified, json],
+        )
+        .map_err(io::Error::other)?;
+        Ok(())
+    }
+}
+
+// Signs entries with the server key, if one is configured, before handing them to the sink
+pub struct AuditLog {
+    sink: Box<dyn AuditSink>,
+    signing_key: Option<Vec<u8>>,
+}
+
+impl AuditLog {
+    pub fn new(sink: Box<dyn AuditSink>) -> Self {
+        Self {
+            sink,
+            signing_key: None,
+        }
+    }
+
+    pub fn with_signing_key(mut self, key: Vec<u8>) -> Self {
+        self.signing_key = Some(key);
+        self
+    }
+
+    pub fn record(&self, mut entry: AuditEntry) -> io::Result<()> {
+        if let Some(key) = &self.signing_key {
+            entry.sign(key);
+        }
+        self.sink.append(&entry)
+    }
+}
+
+impl std::fmt::Debug for AuditLog {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AuditLog")
+            .field("signed", &self.signing_key.is_some())
+            .finish()
+    }
+}
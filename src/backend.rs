@@ -0,0 +1,342 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use num_bigint::BigUint;
+
+use crate::chaum_pedersen::{
+    compute_y1y2, compute_z, Commitment, GroupElement, ProofChallenge, ProofOptions, ProofResponse, Prover,
+    PublicParameters, Scalar, ZKProof,
+};
+
+// Refers to a nonce held inside a backend between `commit_nonce` and `respond`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NonceHandle(u64);
+
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum BackendError {
+    UnknownNonce,  // already consumed, or issued by another backend
+    Unsupported(&'static str),
+    Token(String),  // the hardware token or its driver reported an error
+}
+
+impl fmt::Display for BackendError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BackendError::UnknownNonce => write!(f, "nonce handle is not pending on this backend"),
+            BackendError::Unsupported(operation) => write!(f, "backend does not support {}", operation),
+            BackendError::Token(reason) => write!(f, "token error: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for BackendError {}
+
+// Holds the prover's secrets a and b and performs every operation that needs them, so an
+// implementation can keep them on a hardware token. The proof nonce x stays inside too: anyone
+// who sees both x and the response can solve for a.
+pub trait SecretBackend: Send + Sync + fmt::Debug {
+    fn params(&self) -> &PublicParameters;
+
+    // (g^a, g^b, g^(ab))
+    fn commitment(&self) -> Result<Commitment, BackendError>;
+
+    // Draws a fresh nonce x and returns (g^x, b1^x)
+    fn commit_nonce(&self, b1: &BigUint) -> Result<(ProofChallenge, NonceHandle), BackendError>;
+
+    // z = x + a*s mod q; the nonce is consumed either way
+    fn respond(&self, nonce: NonceHandle, challenge: &BigUint) -> Result<BigUint, BackendError>;
+}
+
+// A non-interactive proof made entirely inside `backend`, with the default Fiat-Shamir challenge
+pub fn create_proof(backend: &dyn SecretBackend) -> Result<ZKProof, BackendError> {
+    let commitment = backend.commitment()?;
+    let (challenge, nonce) = backend.commit_nonce(&commitment.b1)?;
+    let challenge_hash = ProofOptions::default().challenge(backend.params(), &commitment, &challenge);
+    let z = backend.respond(nonce, &challenge_hash)?;
+    Ok(ZKProof {
+        commitment,
+        challenge,
+        response: ProofResponse { z },
+        challenge_hash,
+    })
+}
+
+// Pending nonces, keyed by the handles given out for them
+#[derive(Debug)]
+struct NonceTable<T> {
+    next: AtomicU64,
+    pending: Mutex<HashMap<NonceHandle, T>>,
+}
+
+impl<T> NonceTable<T> {
+    fn new() -> Self {
+        Self {
+            next: AtomicU64::new(0),
+            pending: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn insert(&self, nonce: T) -> NonceHandle {
+        let handle = NonceHandle(self.next.fetch_add(1, Ordering::Relaxed));
+        self.pending.lock().unwrap().insert(handle, nonce);
+        handle
+    }
+
+    fn take(&self, handle: NonceHandle) -> Result<T, BackendError> {
+        self.pending.lock().unwrap().remove(&handle).ok_or(BackendError::UnknownNonce)
+    }
+}
+
+// Secrets in process memory, as held by a plain Prover
+#[derive(Debug)]
+pub struct SoftwareBackend {
+    prover: Prover,
+    nonces: NonceTable<BigUint>,
+}
+
+impl SoftwareBackend {
+    pub fn new(prover: Prover) -> Self {
+        Self {
+            prover,
+            nonces: NonceTable::new(),
+        }
+    }
+
+    // Fresh random secrets for `params`
+    pub fn generate(params: PublicParameters) -> Self {
+        Self::new(Prover::new(params))
+    }
+}
+
+impl SecretBackend for SoftwareBackend {
+    fn params(&self) -> &PublicParameters {
+        &self.prover.params
+    }
+
+    fn commitment(&self) -> Result<Commitment, BackendError> {
+        Ok(self.prover.generate_commitment())
+    }
+
+    fn commit_nonce(&self, b1: &BigUint) -> Result<(ProofChallenge, NonceHandle), BackendError> {
+        let params = &self.prover.params;
+        let x = Scalar::random(params);
+        let (y1, y2) = compute_y1y2(params, &x, &GroupElement::unchecked(b1.clone()));
+        let values = ProofChallenge {
+            y1: y1.into_biguint(),
+            y2: y2.into_biguint(),
+        };
+        Ok((values, self.nonces.insert(x.into_biguint())))
+    }
+
+    fn respond(&self, nonce: NonceHandle, challenge: &BigUint) -> Result<BigUint, BackendError> {
+        let x = self.nonces.take(nonce)?;
+        let params = &self.prover.params;
+        let z = compute_z(
+            params,
+            &Scalar::new(&x, params),
+            &Scalar::new(&self.prover.secret_a, params),
+            &Scalar::new(challenge, params),
+        );
+        Ok(z.into_biguint())
+    }
+}
+
+#[cfg(feature = "pkcs11")]
+pub use token::Pkcs11Backend;
+
+#[cfg(feature = "pkcs11")]
+mod token {
+    use std::fmt;
+    use std::path::Path;
+    use std::sync::Mutex;
+
+    use num_bigint::BigUint;
+    use pkcs11::types::*;
+    use pkcs11::Ctx;
+
+    use super::{BackendError, NonceHandle, NonceTable, SecretBackend};
+    use crate::chaum_pedersen::{Commitment, ProofChallenge, PublicParameters};
+
+    impl From<pkcs11::errors::Error> for BackendError {
+        fn from(e: pkcs11::errors::Error) -> Self {
+            BackendError::Token(e.to_string())
+        }
+    }
+
+    // One logged-in session; PKCS#11 does not allow a session to be used from two threads at once
+    struct Token {
+        ctx: Ctx,
+        session: CK_SESSION_HANDLE,
+    }
+
+    // A DH key pair over (p, g) whose private half never leaves the token
+    struct KeyPair {
+        private: CK_OBJECT_HANDLE,
+        public: BigUint,
+    }
+
+    impl Token {
+        fn generate_key_pair(&self, params: &PublicParameters) -> Result<KeyPair, BackendError> {
+            let (p, g) = (params.p().to_bytes_be(), params.g().to_bytes_be());
+            let public_template = [
+                CK_ATTRIBUTE::new(CKA_TOKEN).with_bool(&CK_FALSE),
+                CK_ATTRIBUTE::new(CKA_PRIME).with_biginteger(&p),
+                CK_ATTRIBUTE::new(CKA_BASE).with_biginteger(&g),
+            ];
+            let private_template = [
+                CK_ATTRIBUTE::new(CKA_TOKEN).with_bool(&CK_FALSE),
+                CK_ATTRIBUTE::new(CKA_PRIVATE).with_bool(&CK_TRUE),
+                CK_ATTRIBUTE::new(CKA_SENSITIVE).with_bool(&CK_TRUE),
+                CK_ATTRIBUTE::new(CKA_EXTRACTABLE).with_bool(&CK_FALSE),
+                CK_ATTRIBUTE::new(CKA_DERIVE).with_bool(&CK_TRUE),
+            ];
+            let mechanism = CK_MECHANISM {
+                mechanism: CKM_DH_PKCS_KEY_PAIR_GEN,
+                pParameter: std::ptr::null_mut(),
+                ulParameterLen: 0,
+            };
+            let (public, private) = self
+                .ctx
+                .generate_key_pair(self.session, &mechanism, &public_template, &private_template)?;
+            let value = self.read_value(public);
+            let _ = self.ctx.destroy_object(self.session, public);
+            Ok(KeyPair {
+                private,
+                public: BigUint::from_bytes_be(&value?),
+            })
+        }
+
+        // base^secret mod p through CKM_DH_PKCS_DERIVE, read back from the derived secret object.
+        // The result is a public protocol value, so the derived object is made extractable.
+        fn pow(
+            &self,
+            key: CK_OBJECT_HANDLE,
+            base: &BigUint,
+            params: &PublicParameters,
+        ) -> Result<BigUint, BackendError> {
+            let mut base = base.to_bytes_be();
+            let len = params.p().to_bytes_be().len() as CK_ULONG;
+            let mechanism = CK_MECHANISM {
+                mechanism: CKM_DH_PKCS_DERIVE,
+                pParameter: base.as_mut_ptr() as CK_VOID_PTR,
+                ulParameterLen: base.len() as CK_ULONG,
+            };
+            let template = [
+                CK_ATTRIBUTE::new(CKA_CLASS).with_ck_ulong(&CKO_SECRET_KEY),
+                CK_ATTRIBUTE::new(CKA_KEY_TYPE).with_ck_ulong(&CKK_GENERIC_SECRET),
+                CK_ATTRIBUTE::new(CKA_TOKEN).with_bool(&CK_FALSE),
+                CK_ATTRIBUTE::new(CKA_SENSITIVE).with_bool(&CK_FALSE),
+                CK_ATTRIBUTE::new(CKA_EXTRACTABLE).with_bool(&CK_TRUE),
+                CK_ATTRIBUTE::new(CKA_VALUE_LEN).with_ck_ulong(&len),
+            ];
+            let derived = self.ctx.derive_key(self.session, &mechanism, key, &template)?;
+            let value = self.read_value(derived);
+            let _ = self.ctx.destroy_object(self.session, derived);
+            Ok(BigUint::from_bytes_be(&value?))
+        }
+
+        fn read_value(&self, object: CK_OBJECT_HANDLE) -> Result<Vec<u8>, BackendError> {
+            // the first call only reports the length
+            let mut template = vec![CK_ATTRIBUTE::new(CKA_VALUE)];
+            self.ctx.get_attribute_value(self.session, object, &mut template)?;
+            let value = vec![0u8; template[0].ulValueLen as usize];
+            let mut template = vec![CK_ATTRIBUTE::new(CKA_VALUE).with_bytes(&value)];
+            match self.ctx.get_attribute_value(self.session, object, &mut template)? {
+                (CKR_OK, _) => Ok(value),
+                (rv, _) => Err(BackendError::Token(format!("reading CKA_VALUE returned {:#x}", rv))),
+            }
+        }
+    }
+
+    // Secrets as DH private keys generated on a PKCS#11 token over the session's (p, g). g^a is the
+    // public half, and g^(ab) and the nonce powers come from CKM_DH_PKCS_DERIVE, so neither a, b
+    // nor x is ever exported. PKCS#11 has no mechanism for the scalar response x + a*s mod q;
+    // `respond` reports that as unsupported rather than pulling a out of the token.
+    pub struct Pkcs11Backend {
+        params: PublicParameters,
+        token: Mutex<Token>,
+        a: KeyPair,
+        b: KeyPair,
+        nonces: NonceTable<CK_OBJECT_HANDLE>,
+    }
+
+    impl Pkcs11Backend {
+        // Loads the PKCS#11 module, logs in to `slot` and generates the two secrets there
+        pub fn generate(
+            module: impl AsRef<Path>,
+            slot: CK_SLOT_ID,
+            pin: &str,
+            params: PublicParameters,
+        ) -> Result<Self, BackendError> {
+            let ctx = Ctx::new_and_initialize(module)?;
+            let session = ctx.open_session(slot, CKF_SERIAL_SESSION | CKF_RW_SESSION, None, None)?;
+            ctx.login(session, CKU_USER, Some(pin))?;
+            let token = Token { ctx, session };
+
+            let a = token.generate_key_pair(&params)?;
+            let b = token.generate_key_pair(&params)?;
+            Ok(Self {
+                params,
+                token: Mutex::new(token),
+                a,
+                b,
+                nonces: NonceTable::new(),
+            })
+        }
+    }
+
+    impl fmt::Debug for Pkcs11Backend {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_struct("Pkcs11Backend")
+                .field("session", &self.token.lock().unwrap().session)
+                .field("a", &self.a.private)
+                .field("b", &self.b.private)
+                .finish()
+        }
+    }
+
+    impl SecretBackend for Pkcs11Backend {
+        fn params(&self) -> &PublicParameters {
+            &self.params
+        }
+
+        fn commitment(&self) -> Result<Commitment, BackendError> {
+            let token = self.token.lock().unwrap();
+            let c1 = token.pow(self.a.private, &self.b.public, &self.params)?;
+            Ok(Commitment {
+                a1: self.a.public.clone(),
+                b1: self.b.public.clone(),
+                c1,
+            })
+        }
+
+        fn commit_nonce(&self, b1: &BigUint) -> Result<(ProofChallenge, NonceHandle), BackendError> {
+            let token = self.token.lock().unwrap();
+            let x = token.generate_key_pair(&self.params)?;
+            let y2 = token.pow(x.private, b1, &self.params)?;
+            Ok((ProofChallenge { y1: x.public, y2 }, self.nonces.insert(x.private)))
+        }
+
+        fn respond(&self, nonce: NonceHandle, _challenge: &BigUint) -> Result<BigUint, BackendError> {
+            let key = self.nonces.take(nonce)?;
+            let token = self.token.lock().unwrap();
+            let _ = token.ctx.destroy_object(token.session, key);
+            Err(BackendError::Unsupported("the response x + a*s mod q over PKCS#11"))
+        }
+    }
+
+    impl Drop for Pkcs11Backend {
+        fn drop(&mut self) {
+            let token = self.token.get_mut().unwrap();
+            let nonces = std::mem::take(&mut *self.nonces.pending.lock().unwrap());
+            for key in nonces.into_values().chain([self.a.private, self.b.private]) {
+                let _ = token.ctx.destroy_object(token.session, key);
+            }
+            let _ = token.ctx.logout(token.session);
+            let _ = token.ctx.close_session(token.session);
+        }
+    }
+}
@@ -0,0 +1,105 @@
+use num_bigint::{BigUint, RandBigInt};
+use rand::rngs::OsRng;
+use serde::{Deserialize, Serialize};
+
+use crate::chaum_pedersen::{
+    ct_eq, generate_challenge, generate_one_shot_challenge, modpow, wire, Commitment, ProofChallenge,
+    PublicParameters, SecureRng, ZKProof,
+};
+
+// Many Chaum-Pedersen proofs in one message, checked together with one random linear combination.
+// This is batch verification only, not the aggregation into one proof with a single combined
+// response that was asked for: that cannot be built for these proofs. The challenges are dropped
+// since the verifier recomputes them from the commitment and (y1, y2), but each proof keeps its own
+// z. The second equation's base b1 differs per proof, so the responses cannot be merged into one
+// without the provers' secrets, and the batch is about as large as the proofs sent one by one.
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchEntry {
+    pub commitment: Commitment,
+    pub challenge: ProofChallenge,
+    #[serde(with = "wire::biguint")]
+    pub z: BigUint,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProofBatch {
+    pub entries: Vec<BatchEntry>,
+}
+
+// Bits of the random weights, and so the soundness error of the combined check
+const WEIGHT_BITS: u64 = 128;
+
+pub fn batch(proofs: &[ZKProof]) -> ProofBatch {
+    let entries = proofs
+        .iter()
+        .map(|proof| BatchEntry {
+            commitment: proof.commitment.clone(),
+            challenge: proof.challenge.clone(),
+            z: proof.response.z.clone(),
+        })
+        .collect();
+    ProofBatch { entries }
+}
+
+// Accepts only if every proof in the batch verifies, except with probability 2^-128
+pub fn verify(params: &PublicParameters, proof: &ProofBatch) -> bool {
+    verify_with_rng(params, proof, &mut OsRng)
+}
+
+// The weights must be unpredictable to the prover, so `rng` belongs to the verifier
+pub fn verify_with_rng(params: &PublicParameters, proof: &ProofBatch, rng: &mut dyn SecureRng) -> bool {
+    verify_with(params, proof, rng, |commitment, y1, y2| generate_challenge(params, commitment, y1, y2))
+}
+
+// As `verify`, for a batch sent to the server session `session_id`: every entry must be a proof from
+// `Prover::create_session_proof(session_id)`, so the batch verifies in no other session
+pub fn verify_for_session(params: &PublicParameters, session_id: &str, proof: &ProofBatch) -> bool {
+    verify_for_session_with_rng(params, session_id, proof, &mut OsRng)
+}
+
+pub fn verify_for_session_with_rng(
+    params: &PublicParameters,
+    session_id: &str,
+    proof: &ProofBatch,
+    rng: &mut dyn SecureRng,
+) -> bool {
+    verify_with(params, proof, rng, |commitment, y1, y2| {
+        generate_one_shot_challenge(session_id.as_bytes(), commitment, y1, y2, params.q())
+    })
+}
+
+fn verify_with<F>(params: &PublicParameters, proof: &ProofBatch, rng: &mut dyn SecureRng, challenge: F) -> bool
+where
+    F: Fn(&Commitment, &BigUint, &BigUint) -> BigUint,
+{
+    let (p, q) = (params.p(), params.q());
+    if proof.entries.is_empty() {
+        return false;
+    }
+
+    let one = BigUint::from(1u32);
+    let (mut g_exponent, mut right1) = (BigUint::from(0u32), one.clone());
+    let (mut left2, mut right2) = (one.clone(), one);
+    for entry in &proof.entries {
+        let Commitment { a1, b1, c1 } = &entry.commitment;
+        let ProofChallenge { y1, y2 } = &entry.challenge;
+        if ![a1, b1, c1, y1, y2].into_iter().all(|x| params.is_element(x)) || &entry.z >= q {
+            return false;
+        }
+
+        // Doubling the weight squares every element into the order-q subgroup, so a component of
+        // order 2 cannot cancel between proofs
+        let s = challenge(&entry.commitment, y1, y2);
+        let weight = (rng.gen_biguint(WEIGHT_BITS) + 1u32) << 1;
+
+        // Check: g^(sum w*z) = prod (a1^s * y1)^w and prod b1^(w*z) = prod (c1^s * y2)^w
+        g_exponent = (g_exponent + &weight * &entry.z) % q;
+        right1 = right1 * modpow(&(modpow(a1, &s, p) * y1 % p), &weight, p) % p;
+        left2 = left2 * modpow(b1, &(&weight * &entry.z), p) % p;
+        right2 = right2 * modpow(&(modpow(c1, &s, p) * y2 % p), &weight, p) % p;
+    }
+
+    let left1 = modpow(params.g(), &g_exponent, p);
+    ct_eq(&left1, &right1, p) & ct_eq(&left2, &right2, p)
+}
@@ -0,0 +1,207 @@
+use num_bigint::BigUint;
+#[cfg(feature = "std")]
+use rand::rngs::OsRng;
+
+use super::crypto::{generate_labeled_challenge, generate_secrets_with_rng};
+use super::rng::SecureRng;
+use super::security::SecurityPolicy;
+use super::validation::{ParameterError, ParameterPolicy};
+use super::{modpow, Commitment, ProofChallenge, Prover, PublicParameters, Verifier};
+
+// Derives the Fiat-Shamir challenge of a commitment proof from the transcript label and the whole
+// statement: the parameters, the commitment and (y1, y2). Leaving any of it out makes proofs forgeable.
+pub type ChallengeHash = fn(&'static [u8], &PublicParameters, &Commitment, &BigUint, &BigUint) -> BigUint;
+
+// How much a verifier checks beyond the proof equations
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Validation {
+    #[default]
+    Lenient,  // range checks only, as `Verifier::new` has always done
+    Strict,   // parameters must pass a ParameterPolicy, and every received element must lie in the order-q subgroup
+}
+
+// Settings a prover and its verifier have to agree on, plus the verifier's validation mode and the
+// prover's exponent policy. The label and hash apply to commitment proofs; pair and multi proofs
+// keep their fixed transcripts.
+#[derive(Debug, Clone, Copy)]
+pub struct ProofOptions {
+    pub label: &'static [u8],
+    pub challenge_hash: ChallengeHash,
+    pub validation: Validation,
+    pub security: Option<SecurityPolicy>,  // nonces of commitment and pair proofs keep to its floor
+}
+
+impl Default for ProofOptions {
+    fn default() -> Self {
+        Self {
+            label: b"chaum-pedersen/commitment",
+            challenge_hash: generate_labeled_challenge,
+            validation: Validation::Lenient,
+            security: None,
+        }
+    }
+}
+
+impl ProofOptions {
+    pub(crate) fn challenge(&self, params: &PublicParameters, commitment: &Commitment, values: &ProofChallenge) -> BigUint {
+        (self.challenge_hash)(self.label, params, commitment, &values.y1, &values.y2)
+    }
+
+    // Subgroup membership of received elements in strict mode; always true when lenient
+    pub(crate) fn accepts(&self, params: &PublicParameters, elements: &[&BigUint]) -> bool {
+        match self.validation {
+            Validation::Lenient => true,
+            Validation::Strict => elements
+                .iter()
+                .all(|x| params.is_element(x) && modpow(x, &params.q, &params.p) == 1u32.into()),
+        }
+    }
+}
+
+fn check_params(params: &PublicParameters, validation: Validation, policy: &ParameterPolicy) -> Result<(), ParameterError> {
+    match validation {
+        Validation::Lenient => Ok(()),
+        Validation::Strict => params.validate_with(policy),
+    }
+}
+
+// Configures a Prover; `Prover::new(params)` is `Prover::builder(params).build()` with defaults
+#[derive(Debug, Clone)]
+pub struct ProverBuilder {
+    params: PublicParameters,
+    secrets: Option<(BigUint, BigUint)>,
+    options: ProofOptions,
+    policy: ParameterPolicy,
+    precompute: bool,
+}
+
+impl ProverBuilder {
+    pub fn new(params: PublicParameters) -> Self {
+        Self {
+            params,
+            secrets: None,
+            options: ProofOptions::default(),
+            policy: ParameterPolicy::default(),
+            precompute: false,
+        }
+    }
+
+    // Existing secrets a and b instead of fresh ones
+    pub fn secrets(mut self, secret_a: BigUint, secret_b: BigUint) -> Self {
+        self.secrets = Some((secret_a, secret_b));
+        self
+    }
+
+    pub fn label(mut self, label: &'static [u8]) -> Self {
+        self.options.label = label;
+        self
+    }
+
+    pub fn challenge_hash(mut self, hash: ChallengeHash) -> Self {
+        self.options.challenge_hash = hash;
+        self
+    }
+
+    pub fn validation(mut self, validation: Validation) -> Self {
+        self.options.validation = validation;
+        self
+    }
+
+    // The policy strict validation checks the parameters against
+    pub fn policy(mut self, policy: ParameterPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    // Minimum exponent lengths, checked against the parameters and any given secrets at build time
+    pub fn security(mut self, policy: SecurityPolicy) -> Self {
+        self.options.security = Some(policy);
+        self
+    }
+
+    // Builds the fixed-base table for g now rather than on the first proof
+    pub fn precompute(mut self, precompute: bool) -> Self {
+        self.precompute = precompute;
+        self
+    }
+
+    #[cfg(feature = "std")]
+    pub fn build(self) -> Result<Prover, ParameterError> {
+        self.build_with_rng(&mut OsRng)
+    }
+
+    // Draws any missing secrets from `rng`; proof nonces still come from the `_with_rng` methods
+    pub fn build_with_rng<R: SecureRng + ?Sized>(self, rng: &mut R) -> Result<Prover, ParameterError> {
+        check_params(&self.params, self.options.validation, &self.policy)?;
+        if let Some(policy) = &self.options.security {
+            policy.check_params(&self.params)?;
+        }
+        if self.precompute {
+            self.params.g_table();
+        }
+        let q = &self.params.q;
+        let (secret_a, secret_b) = match (self.secrets, &self.options.security) {
+            (Some((a, b)), Some(policy)) => {
+                policy.check_secret(&a)?;
+                policy.check_secret(&b)?;
+                (a, b)
+            }
+            (Some(secrets), None) => secrets,
+            (None, Some(policy)) => (policy.secret_with_rng(q, rng), policy.secret_with_rng(q, rng)),
+            (None, None) => generate_secrets_with_rng(q, rng),
+        };
+        Ok(Prover {
+            params: self.params,
+            secret_a,
+            secret_b,
+            options: self.options,
+        })
+    }
+}
+
+// Configures a Verifier; `Verifier::new(params)` is `Verifier::builder(params).build()` with defaults.
+// A verifier never builds the table for g, but uses one its parameters already carry (see PrecomputedParams).
+#[derive(Debug, Clone)]
+pub struct VerifierBuilder {
+    params: PublicParameters,
+    options: ProofOptions,
+    policy: ParameterPolicy,
+}
+
+impl VerifierBuilder {
+    pub fn new(params: PublicParameters) -> Self {
+        Self {
+            params,
+            options: ProofOptions::default(),
+            policy: ParameterPolicy::default(),
+        }
+    }
+
+    pub fn label(mut self, label: &'static [u8]) -> Self {
+        self.options.label = label;
+        self
+    }
+
+    pub fn challenge_hash(mut self, hash: ChallengeHash) -> Self {
+        self.options.challenge_hash = hash;
+        self
+    }
+
+    pub fn validation(mut self, validation: Validation) -> Self {
+        self.options.validation = validation;
+        self
+    }
+
+    pub fn policy(mut self, policy: ParameterPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    pub fn build(self) -> Result<Verifier, ParameterError> {
+        check_params(&self.params, self.options.validation, &self.policy)?;
+        Ok(Verifier {
+            params: self.params,
+            options: self.options,
+        })
+    }
+}
@@ -0,0 +1,29 @@
+use num_bigint::{BigInt, BigUint};
+use num_traits::Zero;
+
+use super::primality::jacobi;
+use super::PublicParameters;
+
+// The order-q subgroup of a safe-prime group is the quadratic residues mod p. Since p = 3 mod 4,
+// -1 is not a residue, so exactly one of x and p - x lies in the subgroup and the smaller of the
+// two, which lies in [1, q], identifies an element. That is one bit shorter than x (a byte whenever
+// p is one bit past a byte boundary), and whatever value in [1, q] is received, decompressing it
+// gives a subgroup element, found with a Jacobi symbol rather than a modpow.
+impl PublicParameters {
+    // The representative of `x`, which must lie in the subgroup
+    pub fn compress_element(&self, x: &BigUint) -> BigUint {
+        let negated = &self.p - x;
+        if negated < *x { negated } else { x.clone() }
+    }
+
+    // The subgroup element a representative stands for; None outside [1, q]
+    pub fn decompress_element(&self, v: &BigUint) -> Option<BigUint> {
+        if v.is_zero() || v > &self.q {
+            return None;
+        }
+        match jacobi(&BigInt::from(v.clone()), &BigInt::from(self.p.clone())) {
+            1 => Some(v.clone()),
+            _ => Some(&self.p - v),
+        }
+    }
+}
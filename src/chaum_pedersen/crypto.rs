@@ -1,154 +1,611 @@
+#[cfg(feature = "std")]
 use rand::rngs::OsRng;
 use num_bigint::{BigUint, RandBigInt};
 use num_traits::{One};
 use num_integer::Integer;
 use sha2::{Sha256, Digest};
+use subtle::{Choice, ConstantTimeEq};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 
-fn generate_safe_prime_pair(bits: u64) -> (BigUint, BigUint) {
-    let mut rng = OsRng;
-    loop {
-        // Generate Sophie Germain prime q
-        let mut q = rng.gen_biguint(bits - 1);
-        if q.is_even() {
-            q += 1u32;
+use super::arith::modpow;
+use super::primality::{is_prime, small_primes, strong_probable_prime, PrimalityConfig, SMALL_PRIME_LIMIT};
+use super::fixed_base::FixedBase;
+use super::rng::SecureRng;
+use super::typed::{GroupElement, Scalar};
+use super::{Commitment, PublicParameters, Transcript};
+
+// Extra exponent bits a fixed-base table needs to cover the blinding in secret_fixed_pow
+#[cfg(feature = "hardened")]
+pub const FIXED_BASE_BLINDING_BITS: u64 = 64;
+#[cfg(not(feature = "hardened"))]
+pub const FIXED_BASE_BLINDING_BITS: u64 = 0;
+
+// Candidates tested from one random starting point before drawing a new one
+const SEARCH_WINDOW: u32 = 1 << 14;
+
+// Window starts drawn from the generator per round of the search
+const WINDOW_BATCH: usize = 16;
+
+// Shared state of a running prime search: another thread can cancel it, or read how many
+// candidates have made it past the sieve to a primality test so far. The counter is pointer-sized
+// so the search also builds for targets without 64-bit atomics.
+#[derive(Debug, Default)]
+pub struct PrimeSearch {
+    cancelled: AtomicBool,
+    candidates: AtomicUsize,
+}
+
+impl PrimeSearch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    pub fn candidates(&self) -> u64 {
+        self.candidates.load(Ordering::Relaxed) as u64
+    }
+}
+
+// Scans q, q + 2, q + 4, ... from `start`, forced odd and to full size. Residues of q modulo the
+// small primes are updated incrementally, and a candidate is tested only if neither q nor 2q + 1
+// has a small factor. A base-2 Miller-Rabin round on each number weeds out most survivors before
+// the full test.
+fn scan_window(
+    start: BigUint,
+    bits: u64,
+    primes: &[u32],
+    config: &PrimalityConfig,
+    search: &PrimeSearch,
+) -> Option<(BigUint, BigUint)> {
+    let mut q = start;
+    q.set_bit(bits - 2, true);  // keep q, and so p, at full size
+    if q.is_even() {
+        q += 1u32;
+    }
+
+    let mut residues: Vec<u32> = primes
+        .iter()
+        .map(|&r| (&q % r).try_into().expect("residue is below a u32 modulus"))
+        .collect();
+
+    // below this size q could itself be one of the sieving primes
+    let use_sieve = bits - 2 > u64::from(SMALL_PRIME_LIMIT.ilog2());
+
+    for _ in 0..SEARCH_WINDOW {
+        if search.is_cancelled() {
+            return None;
         }
-        
-        if is_probably_prime(&q, 40) {
-            // Safe prime p = 2q + 1
+
+        // q ≡ 0 means r | q, and q ≡ (r - 1) / 2 means r | 2q + 1
+        let sieved = use_sieve
+            && primes
+                .iter()
+                .zip(&residues)
+                .any(|(&r, &res)| res == 0 || res == (r - 1) / 2);
+
+        if !sieved {
+            search.candidates.fetch_add(1, Ordering::Relaxed);
             let p = &q * 2u32 + 1u32;
-            if is_probably_prime(&p, 40) {
-                return (p, q); // p is safe prime, q is Sophie Germain prime
+            let two = BigUint::from(2u32);
+            if q > two
+                && strong_probable_prime(&q, &two)
+                && strong_probable_prime(&p, &two)
+                && is_prime(&q, config)
+                && is_prime(&p, config)
+            {
+                return Some((p, q)); // p is safe prime, q is Sophie Germain prime
             }
         }
+
+        q += 2u32;
+        for (res, &r) in residues.iter_mut().zip(primes) {
+            *res = (*res + 2) % r;
+        }
+    }
+    None
+}
+
+// Scans the windows on every core and returns the hit in the earliest one, so the result depends
+// only on the starts and not on thread scheduling
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+fn scan_windows(
+    starts: Vec<BigUint>,
+    bits: u64,
+    primes: &[u32],
+    config: &PrimalityConfig,
+    search: &PrimeSearch,
+) -> Option<(BigUint, BigUint)> {
+    use rayon::iter::{IntoParallelIterator, ParallelIterator};
+
+    starts
+        .into_par_iter()
+        .find_map_first(|start| scan_window(start, bits, primes, config, search))
+}
+
+#[cfg(not(all(feature = "std", not(target_arch = "wasm32"))))]
+fn scan_windows(
+    starts: Vec<BigUint>,
+    bits: u64,
+    primes: &[u32],
+    config: &PrimalityConfig,
+    search: &PrimeSearch,
+) -> Option<(BigUint, BigUint)> {
+    starts
+        .into_iter()
+        .find_map(|start| scan_window(start, bits, primes, config, search))
+}
+
+// Draws window starts from `rng` a batch at a time until one holds a safe-prime pair. A seeded
+// generator yields the same pair on every platform. Returns None once the search is cancelled.
+fn generate_safe_prime_pair<R: SecureRng + ?Sized>(
+    bits: u64,
+    config: &PrimalityConfig,
+    rng: &mut R,
+    search: &PrimeSearch,
+) -> Option<(BigUint, BigUint)> {
+    let primes = small_primes();
+    while !search.is_cancelled() {
+        let starts = (0..WINDOW_BATCH).map(|_| rng.gen_biguint(bits - 1)).collect();
+        if let Some(pair) = scan_windows(starts, bits, &primes, config, search) {
+            return Some(pair);
+        }
     }
+    None
 }
 
-fn find_generator(p: &BigUint, q: &BigUint) -> BigUint {
-    let mut rng = OsRng;
+// Domain labels keeping the hash streams behind seeded primes and generators apart
+const SEEDED_PRIME_LABEL: &[u8] = b"zkp-chaum-pedersen-grpc/seeded-prime";
+const SEEDED_GENERATOR_LABEL: &[u8] = b"zkp-chaum-pedersen-grpc/seeded-generator";
+
+// Start of the search window for one counter: SHA-256(label || seed || counter || block), expanded
+// to `bits - 1` bits
+fn seeded_start(seed: &[u8], counter: u64, bits: u64) -> BigUint {
+    let width = (bits - 1).div_ceil(8) as usize;
+    let mut expanded = Vec::with_capacity(width + 32);
+    let mut block: u32 = 0;
+    while expanded.len() < width {
+        let mut hasher = Sha256::new();
+        hasher.update(SEEDED_PRIME_LABEL);
+        hasher.update((seed.len() as u64).to_be_bytes());
+        hasher.update(seed);
+        hasher.update(counter.to_be_bytes());
+        hasher.update(block.to_be_bytes());
+        expanded.extend_from_slice(&hasher.finalize());
+        block += 1;
+    }
+    expanded.truncate(width);
+    BigUint::from_bytes_be(&expanded) >> (width as u64 * 8 - (bits - 1))
+}
+
+// Canonical generator for seeded parameters, hashed from the same seed as the primes
+pub fn seeded_generator(seed: &[u8], p: &BigUint, q: &BigUint) -> BigUint {
+    derive_generator(&[SEEDED_GENERATOR_LABEL, seed].concat(), p, q)
+}
+
+// Verifiable generation in the spirit of FIPS 186-4 (A.1.1.2 for the primes, A.2.3 for g), adapted
+// to safe primes: counter c selects a window starting at a hash of (seed, c), and the first counter
+// whose window holds a safe-prime pair wins. Anyone holding the seed and counter can re-derive
+// (p, q, g), so the primes cannot have been picked with a trapdoor.
+pub fn generate_seeded_params(
+    bits: u64,
+    seed: &[u8],
+    config: &PrimalityConfig,
+    search: &PrimeSearch,
+) -> Option<(BigUint, BigUint, BigUint, u64)> {
+    let primes = small_primes();
+    let scan = |counter: u64| match search.is_cancelled() {
+        true => Some(None),
+        false => scan_window(seeded_start(seed, counter, bits), bits, &primes, config, search)
+            .map(|(p, q)| Some((p, q, counter))),
+    };
+
+    // lowest counter first, so the result does not depend on thread scheduling
+    #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+    let found = {
+        use rayon::iter::{IntoParallelIterator, ParallelIterator};
+        (0..u64::MAX).into_par_iter().find_map_first(scan)?
+    };
+    #[cfg(not(all(feature = "std", not(target_arch = "wasm32"))))]
+    let found = (0..u64::MAX).find_map(scan)?;
+
+    let (p, q, counter) = found?;
+    let g = seeded_generator(seed, &p, &q);
+    Some((p, q, g, counter))
+}
+
+// Re-derives the window for `counter` and checks that (p, q) is its first safe-prime pair and g
+// the canonical generator
+pub fn verify_seeded_params(
+    p: &BigUint,
+    q: &BigUint,
+    g: &BigUint,
+    seed: &[u8],
+    counter: u64,
+    config: &PrimalityConfig,
+) -> bool {
+    let bits = p.bits();
+    if bits < 3 {
+        return false;
+    }
+    let start = seeded_start(seed, counter, bits);
+    match scan_window(start, bits, &small_primes(), config, &PrimeSearch::new()) {
+        Some((found_p, found_q)) => &found_p == p && &found_q == q && &seeded_generator(seed, p, q) == g,
+        None => false,
+    }
+}
+
+fn find_generator<R: SecureRng + ?Sized>(p: &BigUint, q: &BigUint, rng: &mut R) -> BigUint {
     loop {
         let h = rng.gen_biguint_range(&BigUint::from(2u32), &(p - 1u32));
         
         // For safe primes p = 2q + 1, we compute g = h^2 mod p. this ensures g generates the subgroup of order q
-        let g = h.modpow(&BigUint::from(2u32), p);
+        let g = modpow(&h, &BigUint::from(2u32), p);
         
         // Check that g has order q (g^q = 1 mod p, g != 1)
-        if !g.is_one() && g.modpow(q, p).is_one() {
+        if !g.is_one() && modpow(&g, q, p).is_one() {
             return g;
         }
     }
 }
 
-// Simple Miller-Rabin primality test
-fn is_probably_prime(n: &BigUint, rounds: usize) -> bool {
-    if n < &BigUint::from(2u32) {
-        return false;
+// Big-endian encoding left-padded to the byte width of the modulus, so equal-sized
+// elements always serialize to the same length
+pub fn to_fixed_bytes(value: &BigUint, modulus: &BigUint) -> Vec<u8> {
+    let bytes = value.to_bytes_be();
+    let width = modulus.to_bytes_be().len().max(bytes.len());
+    let mut out = vec![0u8; width - bytes.len()];
+    out.extend_from_slice(&bytes);
+    out
+}
+
+fn ct_eq_choice(a: &BigUint, b: &BigUint, modulus: &BigUint) -> Choice {
+    let a = to_fixed_bytes(a, modulus);
+    let b = to_fixed_bytes(b, modulus);
+    if a.len() != b.len() {
+        return Choice::from(0);
     }
-    if n == &BigUint::from(2u32) || n == &BigUint::from(3u32) {
-        return true;
+    a.ct_eq(&b)
+}
+
+// Constant-time equality of two elements reduced mod `modulus`
+pub fn ct_eq(a: &BigUint, b: &BigUint, modulus: &BigUint) -> bool {
+    ct_eq_choice(a, b, modulus).into()
+}
+
+// Exponentiation with a secret exponent. With the `hardened` feature the exponent is blinded
+// by a random multiple of p - 1, which leaves the result unchanged but varies its bit pattern per call.
+pub fn secret_modpow(base: &BigUint, exp: &BigUint, p: &BigUint) -> BigUint {
+    #[cfg(feature = "hardened")]
+    {
+        let mut rng = OsRng;
+        let r = rng.gen_biguint(64);
+        let blinded = exp + r * (p - 1u32);
+        modpow(base, &blinded, p)
     }
-    if n.is_even() {
-        return false;
+
+    #[cfg(not(feature = "hardened"))]
+    {
+        modpow(base, exp, p)
     }
+}
 
-    // Write n-1 as d * 2^r
-    let mut d = n - 1u32;
-    let mut r = 0;
-    while d.is_even() {
-        d >>= 1;
-        r += 1;
+// Fixed-base counterpart of secret_modpow for an element of order q. With `hardened` the exponent
+// is blinded by a random multiple of q, which the table is sized to absorb (see FIXED_BASE_BLINDING_BITS).
+pub fn secret_fixed_pow(table: &FixedBase, exp: &BigUint, q: &BigUint) -> BigUint {
+    let exp = exp % q;
+
+    #[cfg(feature = "hardened")]
+    {
+        let mut rng = OsRng;
+        let r = rng.gen_biguint(FIXED_BASE_BLINDING_BITS);
+        table.pow(&(exp + r * q))
     }
 
-    let mut rng = OsRng;
-    
-    'witness_loop: for _ in 0..rounds {
-        let a = rng.gen_biguint_range(&BigUint::from(2u32), &(n - 1u32));
-        let mut x = a.modpow(&d, n);
-        
-        if x.is_one() || x == n - 1u32 {
-            continue 'witness_loop;
-        }
-        
-        for _ in 0..r - 1 {
-            x = x.modpow(&BigUint::from(2u32), n);
-            if x == n - 1u32 {
-                continue 'witness_loop;
-            }
-        }
-        return false;
+    #[cfg(not(feature = "hardened"))]
+    {
+        table.pow(&exp)
     }
-    true
 }
 
+// The functions drawing from OsRng need `std`; without it use the `_with_rng` variants
+#[cfg(feature = "std")]
 pub fn generate_params(bits: u64) -> (BigUint, BigUint, BigUint) {
-    let (p, q) = generate_safe_prime_pair(bits);
-    let g = find_generator(&p, &q);
-    (p, q, g)
+    generate_params_cancellable(bits, &PrimeSearch::new()).expect("generation is never cancelled")
+}
+
+// Like generate_params, but gives up and returns None soon after `search` is cancelled from another thread
+#[cfg(feature = "std")]
+pub fn generate_params_cancellable(bits: u64, search: &PrimeSearch) -> Option<(BigUint, BigUint, BigUint)> {
+    generate_params_with(bits, &PrimalityConfig::default(), &mut OsRng, search)
+}
+
+// Generation with an explicit primality test for p and q, trading assurance for speed, and with
+// the window starts and generator drawn from `rng`
+pub fn generate_params_with<R: SecureRng + ?Sized>(
+    bits: u64,
+    config: &PrimalityConfig,
+    rng: &mut R,
+    search: &PrimeSearch,
+) -> Option<(BigUint, BigUint, BigUint)> {
+    let (p, q) = generate_safe_prime_pair(bits, config, rng, search)?;
+    let g = find_generator(&p, &q, rng);
+    Some((p, q, g))
 }
 
+#[cfg(feature = "std")]
 pub fn generate_random_element(q: &BigUint) -> BigUint {
-    let mut rng = OsRng;
-    let q_minus_1 = q - &BigUint::one();
-    rng.gen_biguint_range(&BigUint::one(), &q_minus_1)
+    generate_random_element_with_rng(q, &mut OsRng)
 }
 
-pub fn generate_commitment(g: &BigUint, a: &BigUint, b: &BigUint, p: &BigUint) -> (BigUint, BigUint, BigUint) {
-    let a1 = g.modpow(a, p);
-    let b1 = g.modpow(b, p);
-    let c1 = g.modpow(&(a * b), p);
+// A nonzero scalar, uniform over [1, q) like the secrets
+pub fn generate_random_element_with_rng<R: SecureRng + ?Sized>(q: &BigUint, rng: &mut R) -> BigUint {
+    rng.gen_biguint_range(&BigUint::one(), q)
+}
+
+// (g^a, g^b, g^ab)
+pub fn generate_commitment(
+    params: &PublicParameters,
+    a: &Scalar,
+    b: &Scalar,
+) -> (GroupElement, GroupElement, GroupElement) {
+    let g = GroupElement::generator(params);
+    let a1 = g.pow(a, params);
+    let b1 = g.pow(b, params);
+    let c1 = g.pow(&a.mul(b, params), params);
     (a1, b1, c1)
 }
 
-pub fn generate_challenge(y1: &BigUint, y2: &BigUint, q: &BigUint) -> BigUint {
-    let mut hasher = Sha256::new();
-    hasher.update(y1.to_bytes_be());
-    hasher.update(y2.to_bytes_be());
-    
-    let hash = hasher.finalize();
-    let challenge = BigUint::from_bytes_be(&hash);
+// The Fiat-Shamir challenge of a standalone commitment proof. It covers the parameters and the
+// commitment as well as (y1, y2): over (y1, y2) alone, a forger could pick y1, y2 and z first and
+// solve the equations for a1 and c1.
+pub fn generate_challenge(params: &PublicParameters, commitment: &Commitment, y1: &BigUint, y2: &BigUint) -> BigUint {
+    generate_labeled_challenge(b"chaum-pedersen/commitment", params, commitment, y1, y2)
+}
 
-    challenge % q
+// The challenge a verifier issues in a Fiat-Shamir session. It covers the id of the session and the
+// digest of the commitment being proven, so it only ever answers for that commitment in that session.
+pub fn generate_session_challenge(
+    session_id: &[u8],
+    commitment: &Commitment,
+    y1: &BigUint,
+    y2: &BigUint,
+    q: &BigUint,
+) -> BigUint {
+    let mut transcript = Transcript::new(b"chaum-pedersen/session");
+    transcript.append_message(b"session", session_id);
+    transcript.append_message(b"commitment", &commitment.digest());
+    transcript.append_integer(b"y1", y1);
+    transcript.append_integer(b"y2", y2);
+    transcript.challenge_scalar(b"s", q)
 }
 
-pub fn compute_y1y2(x: &BigUint, g: &BigUint, b1: &BigUint, p: &BigUint) -> (BigUint, BigUint) {
-    let y1 = g.modpow(x, p);
-    let y2 = b1.modpow(x, p);
-    (y1, y2)
+// The challenge of a proof sent whole to ProveOneShot, bound like the session challenge to the id
+// of the session it is sent to, fresh from the server, so the proof is good in no other session
+pub fn generate_one_shot_challenge(
+    session_id: &[u8],
+    commitment: &Commitment,
+    y1: &BigUint,
+    y2: &BigUint,
+    q: &BigUint,
+) -> BigUint {
+    let mut transcript = Transcript::new(b"chaum-pedersen/one-shot");
+    transcript.append_message(b"session", session_id);
+    transcript.append_message(b"commitment", &commitment.digest());
+    transcript.append_integer(b"y1", y1);
+    transcript.append_integer(b"y2", y2);
+    transcript.challenge_scalar(b"s", q)
 }
 
-pub fn compute_z(x: &BigUint, a: &BigUint, s: &BigUint, q: &BigUint) -> BigUint {
-    (x + (a * s)) % q
+// The challenge of an interactive session: the same values plus verifier randomness the prover
+// cannot know in advance
+pub fn generate_interactive_challenge(
+    randomness: &[u8],
+    commitment: &Commitment,
+    y1: &BigUint,
+    y2: &BigUint,
+    q: &BigUint,
+) -> BigUint {
+    let mut transcript = Transcript::new(b"chaum-pedersen/interactive");
+    transcript.append_message(b"verifier-randomness", randomness);
+    transcript.append_message(b"commitment", &commitment.digest());
+    transcript.append_integer(b"y1", y1);
+    transcript.append_integer(b"y2", y2);
+    transcript.challenge_scalar(b"s", q)
 }
 
-pub fn verify_proof(
-    g: &BigUint,
-    b1: &BigUint,
+// The commitment-proof challenge under a caller-chosen transcript label
+pub fn generate_labeled_challenge(
+    label: &'static [u8],
+    params: &PublicParameters,
+    commitment: &Commitment,
     y1: &BigUint,
     y2: &BigUint,
-    a1: &BigUint,
-    c1: &BigUint,
-    s: &BigUint,
-    z: &BigUint,
-    p: &BigUint
+) -> BigUint {
+    let mut transcript = Transcript::new(label);
+    transcript.append_message(b"params", &params.digest());
+    transcript.append_message(b"commitment", &commitment.digest());
+    transcript.append_integer(b"y1", y1);
+    transcript.append_integer(b"y2", y2);
+    transcript.challenge_scalar(b"s", params.q())
+}
+
+// (g^x, b1^x) for the nonce x
+pub fn compute_y1y2(params: &PublicParameters, x: &Scalar, b1: &GroupElement) -> (GroupElement, GroupElement) {
+    let y1 = GroupElement::generator(params).pow(x, params);
+    let y2 = b1.pow(x, params);
+    (y1, y2)
+}
+
+// z = x + a*s mod q
+pub fn compute_z(params: &PublicParameters, x: &Scalar, a: &Scalar, s: &Scalar) -> Scalar {
+    x.mul_add(a, s, params)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn verify_proof(
+    params: &PublicParameters,
+    b1: &GroupElement,
+    y1: &GroupElement,
+    y2: &GroupElement,
+    a1: &GroupElement,
+    c1: &GroupElement,
+    s: &Scalar,
+    z: &Scalar,
 ) -> bool {
+    let g = GroupElement::generator(params);
     // Check: g^z mod p = a1^s * y1 mod p
-    let left1 = g.modpow(z, p);
-    let right1 = (a1.modpow(s, p) * y1) % p;
-    
-    // Check: b1^z mod p = c1^s * y2 mod p  
-    let left2 = b1.modpow(z, p);
-    let right2 = (c1.modpow(s, p) * y2) % p;
-    
-    left1 == right1 && left2 == right2
+    let left1 = g.pow_public(z, params);
+    let right1 = a1.pow_public(s, params).mul(y1, params);
+
+    // Check: b1^z mod p = c1^s * y2 mod p
+    let left2 = b1.pow_public(z, params);
+    let right2 = c1.pow_public(s, params).mul(y2, params);
+
+    // Both checks are always evaluated and combined without branching
+    let (first, second) = (left1.ct_eq(&right1, params), left2.ct_eq(&right2, params));
+    first & second
 }
 
+#[cfg(feature = "std")]
 pub fn generate_secrets(q: &BigUint) -> (BigUint, BigUint) {
-    let mut rng = OsRng;
+    generate_secrets_with_rng(q, &mut OsRng)
+}
+
+pub fn generate_secrets_with_rng<R: SecureRng + ?Sized>(q: &BigUint, rng: &mut R) -> (BigUint, BigUint) {
     let a = rng.gen_biguint_range(&BigUint::one(), q);
     let b = rng.gen_biguint_range(&BigUint::one(), q);
     (a, b)
 }
 
+#[cfg(feature = "std")]
 pub fn generate_prover_secret(q: &BigUint) -> BigUint {
-    let mut rng = OsRng;
+    generate_prover_secret_with_rng(q, &mut OsRng)
+}
+
+pub fn generate_prover_secret_with_rng<R: SecureRng + ?Sized>(q: &BigUint, rng: &mut R) -> BigUint {
     rng.gen_biguint_range(&BigUint::one(), q)
-}
\ No newline at end of file
+}
+
+pub(crate) fn compute_multi_commitments(k: &BigUint, bases: &[BigUint], p: &BigUint) -> Vec<BigUint> {
+    bases.iter().map(|base| secret_modpow(base, k, p)).collect()
+}
+
+pub(crate) fn generate_multi_challenge(
+    bases: &[BigUint],
+    values: &[BigUint],
+    commitments: &[BigUint],
+    q: &BigUint,
+) -> BigUint {
+    let mut transcript = Transcript::new(b"chaum-pedersen/multi-statement");
+    append_statements(&mut transcript, bases, values, commitments);
+    transcript.challenge_scalar(b"s", q)
+}
+
+// The challenge of a multi-statement proof sent to VerifyMultiProof, bound like a one-shot proof's
+// to the id of the session it is sent to
+pub(crate) fn generate_session_multi_challenge(
+    session_id: &[u8],
+    bases: &[BigUint],
+    values: &[BigUint],
+    commitments: &[BigUint],
+    q: &BigUint,
+) -> BigUint {
+    let mut transcript = Transcript::new(b"chaum-pedersen/multi-statement-session");
+    transcript.append_message(b"session", session_id);
+    append_statements(&mut transcript, bases, values, commitments);
+    transcript.challenge_scalar(b"s", q)
+}
+
+fn append_statements(transcript: &mut Transcript, bases: &[BigUint], values: &[BigUint], commitments: &[BigUint]) {
+    transcript.append_u64(b"statements", bases.len() as u64);
+    // Every statement and its commitment goes into the same transcript so a single challenge binds them all
+    for ((base, value), t) in bases.iter().zip(values).zip(commitments) {
+        transcript.append_integer(b"base", base);
+        transcript.append_integer(b"value", value);
+        transcript.append_integer(b"t", t);
+    }
+}
+
+pub(crate) fn verify_multi_proof(
+    bases: &[BigUint],
+    values: &[BigUint],
+    commitments: &[BigUint],
+    s: &BigUint,
+    z: &BigUint,
+    p: &BigUint,
+) -> bool {
+    if bases.is_empty() || bases.len() != values.len() || bases.len() != commitments.len() {
+        return false;
+    }
+
+    // Check for every statement: base^z mod p = value^s * t mod p
+    let all_hold = bases.iter().zip(values).zip(commitments).fold(Choice::from(1), |acc, ((base, value), t)| {
+        let left = modpow(base, z, p);
+        let right = (modpow(value, s, p) * t) % p;
+        acc & ct_eq_choice(&left, &right, p)
+    });
+    all_hold.into()
+}
+
+// Hash-to-group: expands SHA-256(seed || counter) to the width of p and squares the result,
+// which maps it into the subgroup of order q. Nobody knows log_g(h) for an h derived this way.
+pub fn derive_generator(seed: &[u8], p: &BigUint, q: &BigUint) -> BigUint {
+    try_derive_generator(seed, p, q).expect("p is a safe prime")
+}
+
+// Attempts before giving up on a group that is not what it claims to be. For a real safe prime a
+// square misses the subgroup only if it is 1, so the first attempt practically always succeeds.
+const GENERATOR_ATTEMPTS: usize = 64;
+
+// derive_generator for untrusted (p, q): None where p = 2q + 1 has no such subgroup, e.g. p = 9
+pub fn try_derive_generator(seed: &[u8], p: &BigUint, q: &BigUint) -> Option<BigUint> {
+    let width = p.to_bytes_be().len() + 16;
+    let mut counter: u32 = 0;
+    for _ in 0..GENERATOR_ATTEMPTS {
+        let mut expanded = Vec::with_capacity(width + 32);
+        while expanded.len() < width {
+            let mut hasher = Sha256::new();
+            hasher.update(seed);
+            hasher.update(counter.to_be_bytes());
+            expanded.extend_from_slice(&hasher.finalize());
+            counter += 1;
+        }
+        expanded.truncate(width);
+
+        let u = BigUint::from_bytes_be(&expanded) % p;
+        let h = modpow(&u, &BigUint::from(2u32), p);
+
+        if !h.is_one() && modpow(&h, q, p).is_one() {
+            return Some(h);
+        }
+    }
+    None
+}
+
+pub(crate) fn generate_pair_challenge(
+    g: &BigUint,
+    h: &BigUint,
+    y1: &BigUint,
+    y2: &BigUint,
+    t1: &BigUint,
+    t2: &BigUint,
+    q: &BigUint,
+) -> BigUint {
+    let mut transcript = Transcript::new(b"chaum-pedersen/generator-pair");
+    transcript.append_integer(b"g", g);
+    transcript.append_integer(b"h", h);
+    transcript.append_integer(b"y1", y1);
+    transcript.append_integer(b"y2", y2);
+    transcript.append_integer(b"t1", t1);
+    transcript.append_integer(b"t2", t2);
+    transcript.challenge_scalar(b"s", q)
+}
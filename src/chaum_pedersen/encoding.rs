@@ -0,0 +1,119 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
+use num_bigint::BigUint;
+
+use super::{Commitment, ProofChallenge, ProofResponse, ZKProof};
+
+// Format version written as the first byte of every encoded proof
+const PROOF_ENCODING_VERSION: u8 = 1;
+
+// Number of integers in an encoded ZKProof: a1, b1, c1, y1, y2, z, challenge_hash
+const PROOF_FIELD_COUNT: usize = 7;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DecodeError {
+    UnsupportedVersion(u8),
+    Truncated,
+    NonCanonicalInteger,
+    TrailingBytes(usize),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::UnsupportedVersion(v) => write!(f, "unsupported proof encoding version {}", v),
+            DecodeError::Truncated => write!(f, "encoded proof is truncated"),
+            DecodeError::NonCanonicalInteger => write!(f, "integer has leading zero bytes"),
+            DecodeError::TrailingBytes(n) => write!(f, "{} unexpected bytes after proof", n),
+        }
+    }
+}
+
+impl core::error::Error for DecodeError {}
+
+// Minimal big-endian magnitude: zero is the empty string and there are never leading zero bytes
+fn canonical_bytes(value: &BigUint) -> Vec<u8> {
+    if value.bits() == 0 {
+        Vec::new()
+    } else {
+        value.to_bytes_be()
+    }
+}
+
+pub fn write_integer(out: &mut Vec<u8>, value: &BigUint) {
+    let bytes = canonical_bytes(value);
+    out.extend_from_slice(&(bytes.len() as u32).to_be_bytes());
+    out.extend_from_slice(&bytes);
+}
+
+pub fn read_integer(input: &mut &[u8]) -> Result<BigUint, DecodeError> {
+    if input.len() < 4 {
+        return Err(DecodeError::Truncated);
+    }
+    let (len_bytes, rest) = input.split_at(4);
+    let len = u32::from_be_bytes([len_bytes[0], len_bytes[1], len_bytes[2], len_bytes[3]]) as usize;
+
+    if rest.len() < len {
+        return Err(DecodeError::Truncated);
+    }
+    let (value, rest) = rest.split_at(len);
+    if value.first() == Some(&0) {
+        return Err(DecodeError::NonCanonicalInteger);
+    }
+
+    *input = rest;
+    Ok(BigUint::from_bytes_be(value))
+}
+
+impl ZKProof {
+    // Version byte followed by each integer as a u32 big-endian length and its minimal big-endian bytes
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = vec![PROOF_ENCODING_VERSION];
+        for value in [
+            &self.commitment.a1,
+            &self.commitment.b1,
+            &self.commitment.c1,
+            &self.challenge.y1,
+            &self.challenge.y2,
+            &self.response.z,
+            &self.challenge_hash,
+        ] {
+            write_integer(&mut out, value);
+        }
+        out
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        let (&version, mut input) = bytes.split_first().ok_or(DecodeError::Truncated)?;
+        if version != PROOF_ENCODING_VERSION {
+            return Err(DecodeError::UnsupportedVersion(version));
+        }
+
+        let mut fields = Vec::with_capacity(PROOF_FIELD_COUNT);
+        for _ in 0..PROOF_FIELD_COUNT {
+            fields.push(read_integer(&mut input)?);
+        }
+        if !input.is_empty() {
+            return Err(DecodeError::TrailingBytes(input.len()));
+        }
+
+        let mut fields = fields.into_iter();
+        let mut next = || fields.next().expect("field count checked above");
+        Ok(ZKProof {
+            commitment: Commitment {
+                a1: next(),
+                b1: next(),
+                c1: next(),
+            },
+            challenge: ProofChallenge {
+                y1: next(),
+                y2: next(),
+            },
+            response: ProofResponse { z: next() },
+            challenge_hash: next(),
+        })
+    }
+}
@@ -0,0 +1,79 @@
+use alloc::vec::Vec;
+use core::fmt;
+
+use num_bigint::BigUint;
+use num_traits::One;
+
+use super::arith::modpow;
+
+// Precomputed powers of a single base for windowed exponentiation:
+// table[i][d] = base^(d * 2^(window * i)) mod p. An exponentiation is then one multiplication
+// per window and no squarings, which pays off for a base like g that is raised over and over.
+#[derive(Clone)]
+pub struct FixedBase {
+    base: BigUint,
+    modulus: BigUint,
+    window: u32,
+    table: Vec<Vec<BigUint>>,
+}
+
+impl FixedBase {
+    // 4-bit windows: 16 entries per window, about 2 MB of table for a 2048-bit group
+    pub const DEFAULT_WINDOW: u32 = 4;
+
+    pub fn new(base: &BigUint, modulus: &BigUint, max_exp_bits: u64) -> Self {
+        Self::with_window(base, modulus, max_exp_bits, Self::DEFAULT_WINDOW)
+    }
+
+    // `window` is the digit size in bits, between 1 and 8
+    pub fn with_window(base: &BigUint, modulus: &BigUint, max_exp_bits: u64, window: u32) -> Self {
+        assert!((1..=8).contains(&window), "window must be between 1 and 8 bits");
+        let digits = 1usize << window;
+        let windows = max_exp_bits.div_ceil(u64::from(window)) as usize;
+
+        let mut table = Vec::with_capacity(windows);
+        let mut current = base % modulus;  // base^(2^(window * i))
+        for _ in 0..windows {
+            let mut row = Vec::with_capacity(digits);
+            row.push(BigUint::one());
+            for d in 1..digits {
+                row.push((&row[d - 1] * &current) % modulus);
+            }
+            current = (&row[digits - 1] * &current) % modulus;
+            table.push(row);
+        }
+
+        Self {
+            base: base.clone(),
+            modulus: modulus.clone(),
+            window,
+            table,
+        }
+    }
+
+    pub fn max_exp_bits(&self) -> u64 {
+        self.table.len() as u64 * u64::from(self.window)
+    }
+
+    // Exponents wider than the table fall back to a plain modpow
+    pub fn pow(&self, exp: &BigUint) -> BigUint {
+        if exp.bits() > self.max_exp_bits() {
+            return modpow(&self.base, exp, &self.modulus);
+        }
+
+        // every window is multiplied in, zero digits included, so the work does not depend on the digits
+        exp.to_radix_le(1 << self.window)
+            .iter()
+            .zip(&self.table)
+            .fold(BigUint::one(), |acc, (&digit, row)| (acc * &row[digit as usize]) % &self.modulus)
+    }
+}
+
+impl fmt::Debug for FixedBase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FixedBase")
+            .field("window", &self.window)
+            .field("max_exp_bits", &self.max_exp_bits())
+            .finish()
+    }
+}
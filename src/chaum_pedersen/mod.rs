@@ -1,32 +1,106 @@
+pub mod arith;
+pub mod builder;
+pub mod compression;
 pub mod crypto;
+pub mod encoding;
+pub mod fixed_base;
+pub mod precomputed;
+pub mod primality;
+pub mod rng;
+pub mod security;
+pub mod transcript;
+pub mod typed;
+pub mod validation;
+pub mod wire;
+
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
 
 use num_bigint::BigUint;
+use once_cell::race::OnceBox;
+#[cfg(feature = "std")]
+use rand::rngs::OsRng;
 use serde::{Serialize, Deserialize};
+use sha2::{Digest, Sha256};
 
+pub use arith::{is_probable_prime, modpow};
+pub use builder::{ChallengeHash, ProofOptions, ProverBuilder, Validation, VerifierBuilder};
 pub use crypto::*;
+pub use encoding::DecodeError;
+pub use fixed_base::FixedBase;
+pub use precomputed::PrecomputedParams;
+pub use primality::{is_prime, PrimalityConfig};
+pub use rng::SecureRng;
+pub use security::{ExponentSize, SecurityPolicy};
+pub use transcript::{ChallengeSpace, Transcript};
+pub use typed::{GroupElement, Scalar};
+pub use validation::{ParameterError, ParameterPolicy};
+
+use crate::sigma::SigmaProtocol;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PublicParameters {
-    pub p: BigUint,  // Safe prime p = 2q + 1
-    pub q: BigUint,  // Sophie Germain prime (order of subgroup)
-    pub g: BigUint,  // Generator of subgroup of order q
+    #[serde(with = "wire::biguint")]
+    p: BigUint,  // Safe prime p = 2q + 1
+    #[serde(with = "wire::biguint")]
+    q: BigUint,  // Sophie Germain prime (order of subgroup)
+    #[serde(with = "wire::biguint")]
+    g: BigUint,  // Generator of subgroup of order q
+    #[serde(default)]
+    pub h: Option<SecondGenerator>,
+    #[serde(default)]
+    pub provenance: Option<Provenance>,  // set when (p, q, g) were derived from a public seed
+    #[serde(skip)]
+    g_table: OnceBox<Arc<FixedBase>>,  // built on first use, shared by clones
+}
+
+// Public seed and winning counter of verifiably generated parameters
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Provenance {
+    pub seed: Vec<u8>,
+    pub counter: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SecondGenerator {
+    #[serde(with = "wire::biguint")]
+    pub h: BigUint,      // Independent generator of the same subgroup
+    pub seed: Vec<u8>,   // Public seed h was hashed from
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Commitment {
+    #[serde(with = "wire::biguint")]
     pub a1: BigUint,  // g^a mod p
+    #[serde(with = "wire::biguint")]
     pub b1: BigUint,  // g^b mod p  
+    #[serde(with = "wire::biguint")]
     pub c1: BigUint,  // g^(ab) mod p
 }
 
+// Re-randomizes (g^a, g^b, g^ab) into (g^(a+t), g^(bs), g^((a+t)bs)), a fresh Diffie-Hellman tuple
+// that looks unrelated to the original, so sessions using different values cannot be linked by
+// their commitments. Revealing the values later shows that two commitments belong together.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Rerandomization {
+    #[serde(with = "wire::biguint")]
+    pub shift: BigUint,  // t, added to a
+    #[serde(with = "wire::biguint")]
+    pub scale: BigUint,  // s, multiplies b; nonzero mod q
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProofChallenge {
+    #[serde(with = "wire::biguint")]
     pub y1: BigUint,  // g^x mod p
+    #[serde(with = "wire::biguint")]
     pub y2: BigUint,  // b1^x mod p
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProofResponse {
+    #[serde(with = "wire::biguint")]
     pub z: BigUint,   // x + a*s mod q
 }
 
@@ -35,58 +109,332 @@ pub struct ZKProof {
     pub commitment: Commitment,
     pub challenge: ProofChallenge,
     pub response: ProofResponse,
+    #[serde(with = "wire::biguint")]
+    pub challenge_hash: BigUint,
+}
+
+// Proof that log_g(y1) = log_h(y2) for the fixed generator pair (g, h)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeneratorPairProof {
+    #[serde(with = "wire::biguint")]
+    pub y1: BigUint,  // g^a mod p
+    #[serde(with = "wire::biguint")]
+    pub y2: BigUint,  // h^a mod p
+    pub challenge: ProofChallenge,  // (g^x, h^x) mod p
+    pub response: ProofResponse,
+    #[serde(with = "wire::biguint")]
     pub challenge_hash: BigUint,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Statement {
+    #[serde(with = "wire::biguint")]
+    pub base: BigUint,   // Base of the statement
+    #[serde(with = "wire::biguint")]
+    pub value: BigUint,  // base^x mod p
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MultiZKProof {
+    pub statements: Vec<Statement>,
+    #[serde(with = "wire::biguint_vec")]
+    pub commitments: Vec<BigUint>,  // base_i^k mod p, one per statement
+    pub response: ProofResponse,
+    #[serde(with = "wire::biguint")]
+    pub challenge_hash: BigUint,
+}
+
+impl Rerandomization {
+    #[cfg(feature = "std")]
+    pub fn random(params: &PublicParameters) -> Self {
+        Self::random_with_rng(params, &mut OsRng)
+    }
+
+    pub fn random_with_rng<R: SecureRng + ?Sized>(params: &PublicParameters, rng: &mut R) -> Self {
+        Self {
+            shift: generate_prover_secret_with_rng(&params.q, rng),
+            scale: generate_prover_secret_with_rng(&params.q, rng),
+        }
+    }
+}
+
+impl Commitment {
+    // SHA-256 over the length-prefixed a1, b1 and c1, binding session challenges to the commitment
+    pub fn digest(&self) -> [u8; 32] {
+        let mut encoded = Vec::new();
+        for value in [&self.a1, &self.b1, &self.c1] {
+            encoding::write_integer(&mut encoded, value);
+        }
+        Sha256::digest(&encoded).into()
+    }
+
+    // Computable by anyone holding the commitment and `r`, but only the prover, who knows a, can
+    // prove the result; use `Prover::rerandomize` with the same `r` for that
+    pub fn rerandomize(&self, params: &PublicParameters, r: &Rerandomization) -> Commitment {
+        let (p, q) = (&params.p, &params.q);
+        let a1 = &self.a1 * secret_fixed_pow(params.g_table(), &r.shift, q) % p;
+        let b1 = secret_modpow(&self.b1, &r.scale, p);
+        let c1 = secret_modpow(&(&self.c1 * secret_modpow(&self.b1, &r.shift, p) % p), &r.scale, p);
+        Commitment { a1, b1, c1 }
+    }
+}
+
 impl PublicParameters {
+    #[cfg(feature = "std")]
     pub fn new(bits: u64) -> Self {
         let (p, q, g) = generate_params(bits);
-        Self { p, q, g }
+        Self::from_parts(p, q, g, None)
+    }
+
+    // Generates fresh parameters unless `search` is cancelled first
+    #[cfg(feature = "std")]
+    pub fn new_cancellable(bits: u64, search: &PrimeSearch) -> Option<Self> {
+        let (p, q, g) = generate_params_cancellable(bits, search)?;
+        Some(Self::from_parts(p, q, g, None))
+    }
+
+    // Generates parameters whose primes pass the given test
+    #[cfg(feature = "std")]
+    pub fn with_primality(bits: u64, config: &PrimalityConfig) -> Self {
+        Self::with_rng(bits, config, &mut OsRng)
+    }
+
+    // Generates parameters from a caller-supplied generator; a seeded one always gives the same set
+    pub fn with_rng<R: SecureRng + ?Sized>(bits: u64, config: &PrimalityConfig, rng: &mut R) -> Self {
+        let (p, q, g) = generate_params_with(bits, config, rng, &PrimeSearch::new())
+            .expect("generation is never cancelled");
+        Self::from_parts(p, q, g, None)
+    }
+
+    pub fn from_parts(p: BigUint, q: BigUint, g: BigUint, h: Option<SecondGenerator>) -> Self {
+        Self {
+            p,
+            q,
+            g,
+            h,
+            provenance: None,
+            g_table: OnceBox::new(),
+        }
+    }
+
+    // (p, q, g) are read-only, so the cached table for g always matches g; to change them, build
+    // a new set with `from_parts`
+    pub fn p(&self) -> &BigUint {
+        &self.p
+    }
+
+    pub fn q(&self) -> &BigUint {
+        &self.q
+    }
+
+    pub fn g(&self) -> &BigUint {
+        &self.g
+    }
+
+    // Verifiable parameters derived from `seed`; publish the seed so others can check them
+    pub fn from_seed(bits: u64, seed: &[u8]) -> Self {
+        Self::from_seed_cancellable(bits, seed, &PrimeSearch::new()).expect("generation is never cancelled")
+    }
+
+    pub fn from_seed_cancellable(bits: u64, seed: &[u8], search: &PrimeSearch) -> Option<Self> {
+        let (p, q, g, counter) = generate_seeded_params(bits, seed, &PrimalityConfig::default(), search)?;
+        let mut params = Self::from_parts(p, q, g, None);
+        params.provenance = Some(Provenance {
+            seed: seed.to_vec(),
+            counter,
+        });
+        Some(params)
+    }
+
+    // True if the parameters carry a seed and (p, q, g) are exactly what that seed produces
+    pub fn verify_provenance(&self) -> bool {
+        match &self.provenance {
+            Some(provenance) => verify_seeded_params(
+                &self.p,
+                &self.q,
+                &self.g,
+                &provenance.seed,
+                provenance.counter,
+                &PrimalityConfig::default(),
+            ),
+            None => false,
+        }
+    }
+
+    // Fixed-base table for g, sized for exponents mod q plus any blinding
+    pub fn g_table(&self) -> &FixedBase {
+        self.g_table
+            .get_or_init(|| Box::new(Arc::new(FixedBase::new(&self.g, &self.p, self.q.bits() + FIXED_BASE_BLINDING_BITS))))
+    }
+
+    // The table for g if something already built it; verifiers use it but never build one
+    pub fn built_g_table(&self) -> Option<&FixedBase> {
+        self.g_table.get().map(|table| &**table)
+    }
+
+    // SHA-256 over the length-prefixed p, q and g, identifying a parameter set in logs and transcripts
+    pub fn digest(&self) -> [u8; 32] {
+        let mut encoded = Vec::new();
+        for value in [&self.p, &self.q, &self.g] {
+            encoding::write_integer(&mut encoded, value);
+        }
+        Sha256::digest(&encoded).into()
+    }
+
+    pub fn with_second_generator(mut self, seed: &[u8]) -> Self {
+        let h = derive_generator(seed, &self.p, &self.q);
+        self.h = Some(SecondGenerator { h, seed: seed.to_vec() });
+        self
+    }
+
+    // Re-derives h from its published seed so a party can check it was not chosen with a known log
+    pub fn verify_second_generator(&self) -> bool {
+        match &self.h {
+            Some(second) => try_derive_generator(&second.seed, &self.p, &self.q).as_ref() == Some(&second.h),
+            None => false,
+        }
+    }
+
+    // Range check for group elements received from a peer: 0 < x < p
+    pub fn is_element(&self, x: &BigUint) -> bool {
+        x.bits() > 0 && x < &self.p
     }
 }
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Prover {
     pub params: PublicParameters,
+    #[serde(with = "wire::biguint")]
     pub secret_a: BigUint,
+    #[serde(with = "wire::biguint")]
     pub secret_b: BigUint,
+    #[serde(skip)]
+    pub options: ProofOptions,  // not persisted; a loaded prover uses the defaults
 }
 
 impl Prover {
+    #[cfg(feature = "std")]
     pub fn new(params: PublicParameters) -> Self {
-        let (secret_a, secret_b) = generate_secrets(&params.q);
+        Self::with_rng(params, &mut OsRng)
+    }
+
+    pub fn builder(params: PublicParameters) -> ProverBuilder {
+        ProverBuilder::new(params)
+    }
+
+    // A prover with fresh secrets over shared precomputed parameters; `Prover::builder` takes
+    // `precomputed.params().clone()` for the other options, and shares the table just the same
+    #[cfg(feature = "std")]
+    pub fn with_precomputation(precomputed: &PrecomputedParams) -> Self {
+        Self::new(precomputed.params().clone())
+    }
+
+    // Draws the secrets from `rng`; the `_with_rng` methods below do the same for proof nonces
+    pub fn with_rng<R: SecureRng + ?Sized>(params: PublicParameters, rng: &mut R) -> Self {
+        let (secret_a, secret_b) = generate_secrets_with_rng(&params.q, rng);
         Self {
             params,
             secret_a,
             secret_b,
+            options: ProofOptions::default(),
+        }
+    }
+
+    // The prover behind `commitment.rerandomize(params, r)`, holding a + t and b*s mod q. Its proofs
+    // verify against the re-randomized commitment like any other.
+    pub fn rerandomize(&self, r: &Rerandomization) -> Prover {
+        let q = &self.params.q;
+        Prover {
+            params: self.params.clone(),
+            secret_a: (&self.secret_a + &r.shift) % q,
+            secret_b: &self.secret_b * &r.scale % q,
+            options: self.options,
         }
     }
 
     pub fn generate_commitment(&self) -> Commitment {
+        let params = &self.params;
         let (a1, b1, c1) = generate_commitment(
-            &self.params.g,
-            &self.secret_a,
-            &self.secret_b,
-            &self.params.p,
+            params,
+            &Scalar::new(&self.secret_a, params),
+            &Scalar::new(&self.secret_b, params),
         );
-        Commitment { a1, b1, c1 }
+        Commitment {
+            a1: a1.into_biguint(),
+            b1: b1.into_biguint(),
+            c1: c1.into_biguint(),
+        }
     }
 
+    #[cfg(feature = "std")]
     pub fn generate_proof_challenge(&self, commitment: &Commitment) -> (ProofChallenge, BigUint) {
-        let x = generate_prover_secret(&self.params.q);
-        let (y1, y2) = compute_y1y2(&x, &self.params.g, &commitment.b1, &self.params.p);
-        
-        (ProofChallenge { y1, y2 }, x)
+        self.generate_proof_challenge_with_rng(commitment, &mut OsRng)
+    }
+
+    pub fn generate_proof_challenge_with_rng<R: SecureRng + ?Sized>(
+        &self,
+        commitment: &Commitment,
+        rng: &mut R,
+    ) -> (ProofChallenge, BigUint) {
+        let params = &self.params;
+        let x = self.nonce_with_rng(rng);
+        let (y1, y2) = compute_y1y2(params, &x, &GroupElement::unchecked(commitment.b1.clone()));
+
+        let values = ProofChallenge {
+            y1: y1.into_biguint(),
+            y2: y2.into_biguint(),
+        };
+        (values, x.into_biguint())
+    }
+
+    // Uniform over [1, q), or above the floor of the prover's security policy if it has one
+    fn nonce_with_rng<R: SecureRng + ?Sized>(&self, rng: &mut R) -> Scalar {
+        match &self.options.security {
+            Some(policy) => Scalar::new(&policy.nonce_with_rng(&self.params.q, rng), &self.params),
+            None => Scalar::random_with_rng(&self.params, rng),
+        }
     }
 
     pub fn generate_response(&self, x: &BigUint, challenge_hash: &BigUint) -> ProofResponse {
-        let z = compute_z(x, &self.secret_a, challenge_hash, &self.params.q);
-        ProofResponse { z }
+        let params = &self.params;
+        let z = compute_z(
+            params,
+            &Scalar::new(x, params),
+            &Scalar::new(&self.secret_a, params),
+            &Scalar::new(challenge_hash, params),
+        );
+        ProofResponse { z: z.into_biguint() }
     }
 
+    #[cfg(feature = "std")]
     pub fn create_proof(&self) -> ZKProof {
+        self.create_proof_with_rng(&mut OsRng)
+    }
+
+    pub fn create_proof_with_rng<R: SecureRng + ?Sized>(&self, rng: &mut R) -> ZKProof {
+        let commitment = self.generate_commitment();
+        let (challenge, x) = self.generate_proof_challenge_with_rng(&commitment, rng);
+        let challenge_hash = self.options.challenge(&self.params, &commitment, &challenge);
+        let response = self.generate_response(&x, &challenge_hash);
+
+        ZKProof {
+            commitment,
+            challenge,
+            response,
+            challenge_hash,
+        }
+    }
+
+    // A proof for ProveOneShot in the server session `session_id`, spelled as the server gave it
+    #[cfg(feature = "std")]
+    pub fn create_session_proof(&self, session_id: &str) -> ZKProof {
+        self.create_session_proof_with_rng(session_id, &mut OsRng)
+    }
+
+    pub fn create_session_proof_with_rng<R: SecureRng + ?Sized>(&self, session_id: &str, rng: &mut R) -> ZKProof {
         let commitment = self.generate_commitment();
-        let (challenge, x) = self.generate_proof_challenge(&commitment);
-        let challenge_hash = generate_challenge(&challenge.y1, &challenge.y2, &self.params.q);
+        let (challenge, x) = self.generate_proof_challenge_with_rng(&commitment, rng);
+        let challenge_hash =
+            generate_one_shot_challenge(session_id.as_bytes(), &commitment, &challenge.y1, &challenge.y2, &self.params.q);
         let response = self.generate_response(&x, &challenge_hash);
 
         ZKProof {
@@ -96,39 +444,338 @@ impl Prover {
             challenge_hash,
         }
     }
+
+    // Proves log_g(g^a) = log_h(h^a) against the parameters' fixed generator pair
+    #[cfg(feature = "std")]
+    pub fn create_pair_proof(&self) -> Option<GeneratorPairProof> {
+        self.create_pair_proof_with_rng(&mut OsRng)
+    }
+
+    pub fn create_pair_proof_with_rng<R: SecureRng + ?Sized>(&self, rng: &mut R) -> Option<GeneratorPairProof> {
+        let params = &self.params;
+        let h = GroupElement::unchecked(params.h.as_ref()?.h.clone());
+
+        let a = Scalar::new(&self.secret_a, params);
+        let (y1, y2) = compute_y1y2(params, &a, &h);
+        let x = self.nonce_with_rng(rng);
+        let (t1, t2) = compute_y1y2(params, &x, &h);
+        let [h, y1, y2, t1, t2] = [h, y1, y2, t1, t2].map(GroupElement::into_biguint);
+        let challenge_hash = generate_pair_challenge(&params.g, &h, &y1, &y2, &t1, &t2, &params.q);
+        let response = self.generate_response(x.as_biguint(), &challenge_hash);
+
+        Some(GeneratorPairProof {
+            y1,
+            y2,
+            challenge: ProofChallenge { y1: t1, y2: t2 },
+            response,
+            challenge_hash,
+        })
+    }
+}
+
+// Proves that every statement shares the same discrete log x under a single Fiat-Shamir challenge
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MultiProver {
+    pub params: PublicParameters,
+    #[serde(with = "wire::biguint")]
+    pub secret: BigUint,
+    pub statements: Vec<Statement>,
+}
+
+impl MultiProver {
+    pub fn new(params: PublicParameters, secret: BigUint, pairs: Vec<(BigUint, BigUint)>) -> Self {
+        let statements = pairs
+            .into_iter()
+            .map(|(base, value)| Statement { base, value })
+            .collect();
+        Self {
+            params,
+            secret,
+            statements,
+        }
+    }
+
+    // Builds the statements by raising each base to the prover's secret
+    pub fn from_bases(params: PublicParameters, secret: BigUint, bases: Vec<BigUint>) -> Self {
+        let pairs = bases
+            .into_iter()
+            .map(|base| {
+                let value = secret_modpow(&base, &secret, &params.p);
+                (base, value)
+            })
+            .collect();
+        Self::new(params, secret, pairs)
+    }
+
+    #[cfg(feature = "std")]
+    pub fn create_proof(&self) -> MultiZKProof {
+        self.create_proof_with_rng(&mut OsRng)
+    }
+
+    pub fn create_proof_with_rng<R: SecureRng + ?Sized>(&self, rng: &mut R) -> MultiZKProof {
+        self.prove_with(rng, generate_multi_challenge)
+    }
+
+    // A proof for VerifyMultiProof in the server session `session_id`, which verifies in no other
+    #[cfg(feature = "std")]
+    pub fn create_session_proof(&self, session_id: &str) -> MultiZKProof {
+        self.create_session_proof_with_rng(session_id, &mut OsRng)
+    }
+
+    pub fn create_session_proof_with_rng<R: SecureRng + ?Sized>(&self, session_id: &str, rng: &mut R) -> MultiZKProof {
+        self.prove_with(rng, |bases, values, commitments, q| {
+            generate_session_multi_challenge(session_id.as_bytes(), bases, values, commitments, q)
+        })
+    }
+
+    fn prove_with<R, F>(&self, rng: &mut R, challenge: F) -> MultiZKProof
+    where
+        R: SecureRng + ?Sized,
+        F: FnOnce(&[BigUint], &[BigUint], &[BigUint], &BigUint) -> BigUint,
+    {
+        let (bases, values) = self.split_statements();
+        let k = generate_prover_secret_with_rng(&self.params.q, rng);
+        let commitments = compute_multi_commitments(&k, &bases, &self.params.p);
+        let challenge_hash = challenge(&bases, &values, &commitments, &self.params.q);
+        let params = &self.params;
+        let z = compute_z(
+            params,
+            &Scalar::new(&k, params),
+            &Scalar::new(&self.secret, params),
+            &Scalar::new(&challenge_hash, params),
+        );
+
+        MultiZKProof {
+            statements: self.statements.clone(),
+            commitments,
+            response: ProofResponse { z: z.into_biguint() },
+            challenge_hash,
+        }
+    }
+
+    fn split_statements(&self) -> (Vec<BigUint>, Vec<BigUint>) {
+        self.statements
+            .iter()
+            .map(|st| (st.base.clone(), st.value.clone()))
+            .unzip()
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct Verifier {
     pub params: PublicParameters,
+    pub options: ProofOptions,
 }
 
 impl Verifier {
     pub fn new(params: PublicParameters) -> Self {
-        Self { params }
+        Self {
+            params,
+            options: ProofOptions::default(),
+        }
+    }
+
+    pub fn builder(params: PublicParameters) -> VerifierBuilder {
+        VerifierBuilder::new(params)
+    }
+
+    // Checks g^z through the shared table instead of a full modpow per proof
+    pub fn with_precomputation(precomputed: &PrecomputedParams) -> Self {
+        Self::new(precomputed.params().clone())
     }
 
     pub fn verify_proof(&self, proof: &ZKProof) -> bool {
-        let expected_challenge = generate_challenge(
+        let expected_challenge = self.options.challenge(&self.params, &proof.commitment, &proof.challenge);
+        self.verify_proof_with_challenge(proof, &expected_challenge)
+    }
+
+    // Checks a proof from `Prover::create_session_proof`, which only verifies in `session_id`
+    pub fn verify_session_proof(&self, session_id: &str, proof: &ZKProof) -> bool {
+        let expected_challenge = generate_one_shot_challenge(
+            session_id.as_bytes(),
+            &proof.commitment,
             &proof.challenge.y1,
             &proof.challenge.y2,
             &self.params.q,
         );
-        
-        if expected_challenge != proof.challenge_hash {
+        self.verify_proof_with_challenge(proof, &expected_challenge)
+    }
+
+    fn verify_proof_with_challenge(&self, proof: &ZKProof, expected_challenge: &BigUint) -> bool {
+        let elements = [
+            &proof.commitment.a1,
+            &proof.commitment.b1,
+            &proof.commitment.c1,
+            &proof.challenge.y1,
+            &proof.challenge.y2,
+        ];
+        if !self.options.accepts(&self.params, &elements) {
+            return false;
+        }
+
+        if !ct_eq(expected_challenge, &proof.challenge_hash, &self.params.q) {
+            return false;
+        }
+
+        verify_values(
+            &self.params,
+            [&proof.commitment.b1, &proof.challenge.y1, &proof.challenge.y2, &proof.commitment.a1, &proof.commitment.c1],
+            &proof.challenge_hash,
+            &proof.response.z,
+        )
+    }
+    pub fn verify_pair_proof(&self, proof: &GeneratorPairProof) -> bool {
+        let h = match &self.params.h {
+            Some(second) => &second.h,
+            None => return false,
+        };
+        let elements = [&proof.y1, &proof.y2, &proof.challenge.y1, &proof.challenge.y2];
+        if !self.options.accepts(&self.params, &elements) {
             return false;
         }
 
-        verify_proof(
+        let expected_challenge = generate_pair_challenge(
             &self.params.g,
-            &proof.commitment.b1,
+            h,
+            &proof.y1,
+            &proof.y2,
             &proof.challenge.y1,
             &proof.challenge.y2,
-            &proof.commitment.a1,
-            &proof.commitment.c1,
+            &self.params.q,
+        );
+
+        if !ct_eq(&expected_challenge, &proof.challenge_hash, &self.params.q) {
+            return false;
+        }
+
+        // Same equations as the commitment proof with b1 = h, a1 = y1 and c1 = y2
+        verify_values(
+            &self.params,
+            [h, &proof.challenge.y1, &proof.challenge.y2, &proof.y1, &proof.y2],
+            &proof.challenge_hash,
+            &proof.response.z,
+        )
+    }
+
+    pub fn verify_multi(&self, proof: &MultiZKProof) -> bool {
+        self.verify_multi_with(proof, generate_multi_challenge)
+    }
+
+    // Checks a proof from `MultiProver::create_session_proof`, which only verifies in `session_id`
+    pub fn verify_multi_session_proof(&self, session_id: &str, proof: &MultiZKProof) -> bool {
+        self.verify_multi_with(proof, |bases, values, commitments, q| {
+            generate_session_multi_challenge(session_id.as_bytes(), bases, values, commitments, q)
+        })
+    }
+
+    fn verify_multi_with<F>(&self, proof: &MultiZKProof, challenge: F) -> bool
+    where
+        F: FnOnce(&[BigUint], &[BigUint], &[BigUint], &BigUint) -> BigUint,
+    {
+        let (bases, values): (Vec<BigUint>, Vec<BigUint>) = proof
+            .statements
+            .iter()
+            .map(|st| (st.base.clone(), st.value.clone()))
+            .unzip();
+        let elements: Vec<&BigUint> = bases.iter().chain(&values).chain(&proof.commitments).collect();
+        if !self.options.accepts(&self.params, &elements) {
+            return false;
+        }
+
+        let expected_challenge = challenge(&bases, &values, &proof.commitments, &self.params.q);
+
+        if !ct_eq(&expected_challenge, &proof.challenge_hash, &self.params.q) {
+            return false;
+        }
+
+        verify_multi_proof(
+            &bases,
+            &values,
+            &proof.commitments,
             &proof.challenge_hash,
             &proof.response.z,
             &self.params.p,
         )
     }
-}
\ No newline at end of file
+}
+
+// verify_proof over (b1, y1, y2, a1, c1) as sent; false unless they are all in range
+pub(crate) fn verify_values(params: &PublicParameters, values: [&BigUint; 5], s: &BigUint, z: &BigUint) -> bool {
+    let [Some(b1), Some(y1), Some(y2), Some(a1), Some(c1)] = values.map(|value| GroupElement::new(value, params)) else {
+        return false;
+    };
+    verify_proof(params, &b1, &y1, &y2, &a1, &c1, &Scalar::new(s, params), &Scalar::new(z, params))
+}
+
+// Chaum-Pedersen as a sigma protocol. The statement is a commitment (a1, b1, c1) with
+// log_g(a1) = log_b1(c1), the witness is a, and the prover's first move is (y1, y2).
+#[derive(Debug, Clone)]
+pub struct ChaumPedersen {
+    pub params: PublicParameters,
+}
+
+impl ChaumPedersen {
+    pub fn new(params: PublicParameters) -> Self {
+        Self { params }
+    }
+}
+
+impl SigmaProtocol for ChaumPedersen {
+    type Statement = Commitment;
+    type Witness = BigUint;
+    type Commitment = ProofChallenge;
+    type Nonce = BigUint;
+    type Response = ProofResponse;
+
+    const LABEL: &'static [u8] = b"chaum-pedersen";
+
+    fn challenge_modulus(&self) -> &BigUint {
+        &self.params.q
+    }
+
+    fn commit(&self, statement: &Commitment, _witness: &BigUint, rng: &mut dyn SecureRng) -> (ProofChallenge, BigUint) {
+        let params = &self.params;
+        let x = Scalar::random_with_rng(params, rng);
+        let (y1, y2) = compute_y1y2(params, &x, &GroupElement::unchecked(statement.b1.clone()));
+        let values = ProofChallenge {
+            y1: y1.into_biguint(),
+            y2: y2.into_biguint(),
+        };
+        (values, x.into_biguint())
+    }
+
+    fn respond(&self, witness: &BigUint, nonce: BigUint, challenge: &BigUint) -> ProofResponse {
+        let params = &self.params;
+        let z = compute_z(
+            params,
+            &Scalar::new(&nonce, params),
+            &Scalar::new(witness, params),
+            &Scalar::new(challenge, params),
+        );
+        ProofResponse { z: z.into_biguint() }
+    }
+
+    fn verify(
+        &self,
+        statement: &Commitment,
+        commitment: &ProofChallenge,
+        challenge: &BigUint,
+        response: &ProofResponse,
+    ) -> bool {
+        verify_values(
+            &self.params,
+            [&statement.b1, &commitment.y1, &commitment.y2, &statement.a1, &statement.c1],
+            challenge,
+            &response.z,
+        )
+    }
+
+    fn append_transcript(&self, transcript: &mut Transcript, statement: &Commitment, commitment: &ProofChallenge) {
+        transcript.append_integer(b"g", &self.params.g);
+        transcript.append_integer(b"a1", &statement.a1);
+        transcript.append_integer(b"b1", &statement.b1);
+        transcript.append_integer(b"c1", &statement.c1);
+        transcript.append_integer(b"y1", &commitment.y1);
+        transcript.append_integer(b"y2", &commitment.y2);
+    }
+}
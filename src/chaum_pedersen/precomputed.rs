@@ -0,0 +1,29 @@
+use alloc::sync::Arc;
+
+use super::fixed_base::FixedBase;
+use super::PublicParameters;
+
+// Setup derived once from a parameter set, for a server or prover that runs many proofs over it:
+// the fixed-base table for g, which both the prover's g^r and the verifier's g^z go through. Hold
+// it in an Arc and build provers and verifiers from it; their parameters share the one table.
+// num-bigint sets up its Montgomery form inside each modpow, so there is no context to keep.
+#[derive(Debug, Clone)]
+pub struct PrecomputedParams {
+    params: PublicParameters,
+}
+
+impl PrecomputedParams {
+    // Builds the table now, so no proof pays for it
+    pub fn new(params: PublicParameters) -> Arc<Self> {
+        params.g_table();
+        Arc::new(Self { params })
+    }
+
+    pub fn params(&self) -> &PublicParameters {
+        &self.params
+    }
+
+    pub fn g_table(&self) -> &FixedBase {
+        self.params.g_table()
+    }
+}
@@ -0,0 +1,326 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use num_bigint::{BigInt, BigUint, Sign};
+use num_integer::Integer;
+use num_traits::{One, Zero};
+
+use super::arith::{is_probable_prime, modpow};
+
+// Odd primes below this bound are used for trial division and for sieving safe-prime candidates
+pub(crate) const SMALL_PRIME_LIMIT: u32 = 2000;
+
+pub(crate) fn small_primes() -> Vec<u32> {
+    let mut composite = vec![false; SMALL_PRIME_LIMIT as usize + 1];
+    let mut primes = Vec::new();
+    for n in 3..=SMALL_PRIME_LIMIT {
+        if composite[n as usize] || n % 2 == 0 {
+            continue;
+        }
+        primes.push(n);
+        for multiple in (n * n..=SMALL_PRIME_LIMIT).step_by(n as usize) {
+            composite[multiple as usize] = true;
+        }
+    }
+    primes
+}
+
+// How much work a primality test spends. Baillie-PSW has no known counterexample; the extra
+// random-base Miller-Rabin rounds guard against a composite crafted to fool it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PrimalityConfig {
+    pub trial_division: bool,  // divide by the small primes before anything else
+    pub baillie_psw: bool,     // strong base-2 Miller-Rabin plus a strong Lucas test
+    pub extra_rounds: usize,   // random-base Miller-Rabin rounds after that; at least one without BPSW
+}
+
+impl PrimalityConfig {
+    // Baillie-PSW alone, enough for randomly drawn candidates
+    pub const FAST: Self = Self {
+        trial_division: true,
+        baillie_psw: true,
+        extra_rounds: 0,
+    };
+
+    pub const STANDARD: Self = Self {
+        trial_division: true,
+        baillie_psw: true,
+        extra_rounds: 8,
+    };
+
+    // Baillie-PSW on top of the 40 Miller-Rabin rounds this crate used to run alone
+    pub const PARANOID: Self = Self {
+        trial_division: true,
+        baillie_psw: true,
+        extra_rounds: 40,
+    };
+}
+
+impl Default for PrimalityConfig {
+    fn default() -> Self {
+        Self::STANDARD
+    }
+}
+
+pub fn is_prime(n: &BigUint, config: &PrimalityConfig) -> bool {
+    if n < &BigUint::from(2u32) {
+        return false;
+    }
+    if n.is_even() {
+        return n == &BigUint::from(2u32);
+    }
+
+    if config.trial_division {
+        for p in small_primes() {
+            if n == &BigUint::from(p) {
+                return true;
+            }
+            if (n % p).is_zero() {
+                return false;
+            }
+        }
+    }
+
+    if config.baillie_psw && !(strong_probable_prime(n, &BigUint::from(2u32)) && strong_lucas_probable_prime(n)) {
+        return false;
+    }
+
+    let rounds = if config.baillie_psw { config.extra_rounds } else { config.extra_rounds.max(1) };
+    rounds == 0 || is_probable_prime(n, rounds)
+}
+
+// Miller-Rabin with a fixed base, for odd n > 2
+pub(crate) fn strong_probable_prime(n: &BigUint, base: &BigUint) -> bool {
+    let n_minus_1 = n - 1u32;
+    let s = n_minus_1.trailing_zeros().unwrap_or(0);
+    let d = &n_minus_1 >> s;
+
+    let mut x = modpow(&(base % n), &d, n);
+    if x.is_one() || x == n_minus_1 || x.is_zero() {
+        return true;
+    }
+    for _ in 1..s {
+        x = &x * &x % n;
+        if x == n_minus_1 {
+            return true;
+        }
+    }
+    false
+}
+
+// Jacobi symbol (a/n) for odd positive n
+pub(crate) fn jacobi(a: &BigInt, n: &BigInt) -> i32 {
+    let mut a = a.mod_floor(n);
+    let mut n = n.clone();
+    let mut result = 1;
+    while !a.is_zero() {
+        while a.is_even() {
+            a >>= 1;
+            let r = &n % 8u32;
+            if r == BigInt::from(3) || r == BigInt::from(5) {
+                result = -result;
+            }
+        }
+        core::mem::swap(&mut a, &mut n);
+        let three = BigInt::from(3);
+        if (&a % 4u32) == three && (&n % 4u32) == three {
+            result = -result;
+        }
+        a = a.mod_floor(&n);
+    }
+    if n.is_one() { result } else { 0 }
+}
+
+// Strong Lucas probable-prime test with Selfridge's parameters (P = 1, Q = (1 - D) / 4), for odd n > 2
+pub(crate) fn strong_lucas_probable_prime(n: &BigUint) -> bool {
+    // no suitable D exists for perfect squares
+    let root = n.sqrt();
+    if &(&root * &root) == n {
+        return false;
+    }
+
+    let n_int = BigInt::from_biguint(Sign::Plus, n.clone());
+    let mut d = BigInt::from(5);
+    loop {
+        match jacobi(&d, &n_int) {
+            -1 => break,
+            // gcd(D, n) > 1; n is composite unless it is |D| itself
+            0 if d.magnitude() != n => return false,
+            _ => {}
+        }
+        d = if d.sign() == Sign::Minus { -d + 2u32 } else { -(d + 2u32) };
+    }
+    let q = (BigInt::one() - &d) / 4i32;
+
+    let reduce = |x: BigInt| x.mod_floor(&n_int);
+    // halving mod n, valid because n is odd
+    let halve = |x: BigInt| {
+        let x = reduce(x);
+        if x.is_odd() { (x + &n_int) >> 1 } else { x >> 1 }
+    };
+
+    // n + 1 = k * 2^s with k odd
+    let n_plus_1 = n + 1u32;
+    let s = n_plus_1.trailing_zeros().unwrap_or(0);
+    let k = &n_plus_1 >> s;
+
+    // U_1 = 1, V_1 = P = 1, Q^1; then walk the bits of k below the top one
+    let mut u = BigInt::one();
+    let mut v = BigInt::one();
+    let mut q_k = reduce(q.clone());
+    for i in (0..k.bits() - 1).rev() {
+        u = reduce(&u * &v);
+        v = reduce(&v * &v - &q_k * 2u32);
+        q_k = reduce(&q_k * &q_k);
+        if k.bit(i) {
+            let next_u = halve(&u + &v);
+            v = halve(&d * &u + &v);
+            u = next_u;
+            q_k = reduce(&q_k * &q);
+        }
+    }
+
+    if u.is_zero() || v.is_zero() {
+        return true;
+    }
+    for _ in 1..s {
+        v = reduce(&v * &v - &q_k * 2u32);
+        if v.is_zero() {
+            return true;
+        }
+        q_k = reduce(&q_k * &q_k);
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Every preset, plus the switches the presets never turn off. Without Baillie-PSW, composites are
+    // only caught with probability 1 - 4^-rounds, so that case runs enough rounds never to flake.
+    const CONFIGS: [PrimalityConfig; 5] = [
+        PrimalityConfig::FAST,
+        PrimalityConfig::STANDARD,
+        PrimalityConfig::PARANOID,
+        PrimalityConfig {
+            trial_division: false,
+            baillie_psw: true,
+            extra_rounds: 0,
+        },
+        PrimalityConfig {
+            trial_division: false,
+            baillie_psw: false,
+            extra_rounds: 40,
+        },
+    ];
+
+    fn big(n: u64) -> BigUint {
+        BigUint::from(n)
+    }
+
+    fn mersenne(exponent: u32) -> BigUint {
+        (BigUint::one() << exponent) - 1u32
+    }
+
+    #[test]
+    fn default_is_standard() {
+        assert_eq!(PrimalityConfig::default(), PrimalityConfig::STANDARD);
+    }
+
+    #[test]
+    fn small_primes_and_composites() {
+        let primes = [2u64, 3, 5, 7, 11, 13, 1999, 2003, 7919, 104_729];
+        let composites = [0u64, 1, 4, 9, 15, 91, 1001, 2001, 2003 * 2011];
+        for config in &CONFIGS {
+            for n in primes {
+                assert!(is_prime(&big(n), config), "{} under {:?}", n, config);
+            }
+            for n in composites {
+                assert!(!is_prime(&big(n), config), "{} under {:?}", n, config);
+            }
+        }
+        assert_eq!(small_primes().len(), 302);
+        assert_eq!(small_primes().last(), Some(&1999));
+    }
+
+    #[test]
+    fn large_primes_and_their_products() {
+        for config in &CONFIGS {
+            assert!(is_prime(&mersenne(61), config));
+            assert!(is_prime(&mersenne(89), config));
+            assert!(!is_prime(&(mersenne(61) * mersenne(31)), config));
+            assert!(!is_prime(&mersenne(67), config));
+        }
+    }
+
+    #[test]
+    fn carmichael_numbers() {
+        for n in [561u64, 41041] {
+            for config in &CONFIGS {
+                assert!(!is_prime(&big(n), config), "{} under {:?}", n, config);
+            }
+        }
+    }
+
+    // They pass Miller-Rabin to base 2, so the Lucas half of Baillie-PSW has to catch them
+    #[test]
+    fn strong_base_2_pseudoprimes() {
+        for n in [2047u64, 3277, 4033] {
+            assert!(strong_probable_prime(&big(n), &big(2)), "{}", n);
+            assert!(!strong_lucas_probable_prime(&big(n)), "{}", n);
+            for config in &CONFIGS {
+                assert!(!is_prime(&big(n), config), "{} under {:?}", n, config);
+            }
+        }
+    }
+
+    // And the other way round
+    #[test]
+    fn strong_lucas_pseudoprimes() {
+        for n in [5459u64, 5777] {
+            assert!(strong_lucas_probable_prime(&big(n)), "{}", n);
+            assert!(!strong_probable_prime(&big(n), &big(2)), "{}", n);
+            for config in &CONFIGS {
+                assert!(!is_prime(&big(n), config), "{} under {:?}", n, config);
+            }
+        }
+    }
+
+    #[test]
+    fn perfect_squares() {
+        for root in [3u64, 5, 2003, 7919, 104_729] {
+            let n = big(root * root);
+            assert!(!strong_lucas_probable_prime(&n), "{}", n);
+            for config in &CONFIGS {
+                assert!(!is_prime(&n, config), "{} under {:?}", n, config);
+            }
+        }
+        let square = mersenne(61) * mersenne(61);
+        assert!(!strong_lucas_probable_prime(&square));
+        assert!(!is_prime(&square, &PrimalityConfig::FAST));
+    }
+
+    #[test]
+    fn jacobi_symbols() {
+        let cases = [
+            (1, 1, 1),
+            (0, 1, 1),
+            (2, 3, -1),
+            (2, 7, 1),
+            (3, 7, -1),
+            (30, 7, 1),
+            (-1, 5, 1),
+            (-1, 7, -1),
+            (5, 21, 1),
+            (8, 21, -1),
+            (-7, 15, 1),
+            (6, 9, 0),
+            (19, 45, 1),
+            (1001, 9907, -1),
+        ];
+        for (a, n, expected) in cases {
+            assert_eq!(jacobi(&BigInt::from(a), &BigInt::from(n)), expected, "({}/{})", a, n);
+        }
+    }
+}
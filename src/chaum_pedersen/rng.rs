@@ -0,0 +1,8 @@
+use rand::{CryptoRng, RngCore};
+
+// Any cryptographically secure generator, usable as `&mut dyn SecureRng`. Secrets, nonces and
+// parameters can be drawn from one of these instead of OsRng: a seeded generator makes tests
+// reproducible, and a deployment can wrap an HSM's entropy source.
+pub trait SecureRng: RngCore + CryptoRng {}
+
+impl<R: RngCore + CryptoRng + ?Sized> SecureRng for R {}
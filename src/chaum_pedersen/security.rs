@@ -0,0 +1,92 @@
+use num_bigint::{BigUint, RandBigInt};
+use num_traits::One;
+
+use super::rng::SecureRng;
+use super::validation::ParameterError;
+use super::PublicParameters;
+
+// How long a prover's secrets may be drawn. Pollard's lambda finds an n-bit exponent in about
+// 2^(n/2) steps whatever the group size, so no exponent may be shorter than twice the security level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExponentSize {
+    #[default]
+    Full,   // uniform over [1, q), resampled in the rare case it falls below the floor
+    Short,  // secrets of exactly twice the security level, for cheaper commitments in large groups
+}
+
+// Exponent lengths a prover insists on; see `ProverBuilder::security`. Short exponents rely on the
+// short-exponent discrete log assumption, which holds in the prime-order subgroup of a safe-prime
+// group but is weaker than the full one. They only apply to the secrets a and b: proof nonces are
+// always drawn mod q, since z = x + a*s mod q must hide a*s, which spans the whole of q even when
+// a is short, and a short x would leak it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SecurityPolicy {
+    pub security_bits: u64,
+    pub exponents: ExponentSize,
+}
+
+impl Default for SecurityPolicy {
+    fn default() -> Self {
+        Self {
+            security_bits: 128,
+            exponents: ExponentSize::Full,
+        }
+    }
+}
+
+impl SecurityPolicy {
+    pub fn short_exponents(security_bits: u64) -> Self {
+        Self {
+            security_bits,
+            exponents: ExponentSize::Short,
+        }
+    }
+
+    // The floor every secret and nonce has to reach
+    pub fn min_exponent_bits(&self) -> u64 {
+        2 * self.security_bits
+    }
+
+    // The subgroup must hold exponents of the floor's length; short ones need it to be longer still,
+    // or they would be no shorter than full ones
+    pub fn check_params(&self, params: &PublicParameters) -> Result<(), ParameterError> {
+        let (bits, min) = (params.q.bits(), self.min_exponent_bits());
+        let enough = match self.exponents {
+            ExponentSize::Full => bits >= min,
+            ExponentSize::Short => bits > min,
+        };
+        match enough {
+            true => Ok(()),
+            false => Err(ParameterError::SubgroupTooSmall { bits, min }),
+        }
+    }
+
+    // Refuses a secret supplied from elsewhere that falls below the floor
+    pub fn check_secret(&self, secret: &BigUint) -> Result<(), ParameterError> {
+        let (bits, min) = (secret.bits(), self.min_exponent_bits());
+        match bits >= min {
+            true => Ok(()),
+            false => Err(ParameterError::SecretTooShort { bits, min }),
+        }
+    }
+
+    // Assumes `check_params` passed
+    pub(crate) fn secret_with_rng<R: SecureRng + ?Sized>(&self, q: &BigUint, rng: &mut R) -> BigUint {
+        match self.exponents {
+            ExponentSize::Full => self.nonce_with_rng(q, rng),
+            ExponentSize::Short => {
+                let top = BigUint::one() << (self.min_exponent_bits() - 1);
+                rng.gen_biguint_range(&top, &(&top << 1))
+            }
+        }
+    }
+
+    pub(crate) fn nonce_with_rng<R: SecureRng + ?Sized>(&self, q: &BigUint, rng: &mut R) -> BigUint {
+        loop {
+            let x = rng.gen_biguint_range(&BigUint::one(), q);
+            if x.bits() >= self.min_exponent_bits() {
+                return x;
+            }
+        }
+    }
+}
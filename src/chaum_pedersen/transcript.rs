@@ -0,0 +1,119 @@
+use alloc::vec::Vec;
+use core::fmt;
+
+use num_bigint::BigUint;
+use sha2::{Digest, Sha256};
+
+use super::encoding;
+
+// Fiat-Shamir transcript in the style of Merlin. Every message is absorbed under a label, and each
+// challenge depends on the protocol label, every earlier message and every earlier challenge, so
+// different proof types and different steps of one proof never derive the same challenge.
+// Composed proofs share one transcript by passing it along.
+#[derive(Clone)]
+pub struct Transcript {
+    state: Sha256,
+}
+
+impl Transcript {
+    pub fn new(label: &'static [u8]) -> Self {
+        let mut transcript = Self { state: Sha256::new() };
+        transcript.append_message(b"dom-sep", label);
+        transcript
+    }
+
+    fn absorb(&mut self, bytes: &[u8]) {
+        self.state.update((bytes.len() as u32).to_be_bytes());
+        self.state.update(bytes);
+    }
+
+    pub fn append_message(&mut self, label: &'static [u8], message: &[u8]) {
+        self.absorb(label);
+        self.absorb(message);
+    }
+
+    pub fn append_u64(&mut self, label: &'static [u8], value: u64) {
+        self.append_message(label, &value.to_be_bytes());
+    }
+
+    pub fn append_integer(&mut self, label: &'static [u8], value: &BigUint) {
+        let mut encoded = Vec::new();
+        encoding::write_integer(&mut encoded, value);
+        self.append_message(label, &encoded);
+    }
+
+    // Draws a challenge uniform over [0, modulus), then feeds the seed back so the next challenge
+    // differs
+    pub fn challenge_scalar(&mut self, label: &'static [u8], modulus: &BigUint) -> BigUint {
+        self.challenge_in(label, &ChallengeSpace::new(modulus.clone()))
+    }
+
+    pub fn challenge_in(&mut self, label: &'static [u8], space: &ChallengeSpace) -> BigUint {
+        self.absorb(label);
+        let seed = self.state.clone().finalize();
+        self.append_message(b"challenge", &seed);
+        space.sample(&seed)
+    }
+}
+
+// The set a challenge is drawn from, [0, bound). Sampling expands the transcript hash to the
+// bound's full width and rejects candidates past it, so every challenge is equally likely and
+// soundness grows with the bound rather than stopping at the hash's 256 bits.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChallengeSpace {
+    bound: BigUint,
+}
+
+impl ChallengeSpace {
+    // Challenges below `modulus`, usually the group order q
+    pub fn new(modulus: BigUint) -> Self {
+        assert!(modulus.bits() > 0, "a challenge space needs a nonzero bound");
+        Self { bound: modulus }
+    }
+
+    // `bits`-bit challenges, or all of [0, modulus) if that is smaller. Shorter challenges cost the
+    // verifier less at the price of soundness 2^-bits.
+    pub fn with_bits(bits: u64, modulus: &BigUint) -> Self {
+        let bound = BigUint::from(1u32) << bits;
+        Self::new(bound.min(modulus.clone()))
+    }
+
+    pub fn bound(&self) -> &BigUint {
+        &self.bound
+    }
+
+    // Deterministic in `seed`. Over half of all candidates land below the bound, so it takes a
+    // handful of draws at most.
+    pub fn sample(&self, seed: &[u8]) -> BigUint {
+        let bits = self.bound.bits();
+        let len = bits.div_ceil(8) as usize;
+        let excess = (len as u64 * 8 - bits) as u32;
+        for attempt in 0u32.. {
+            let mut candidate = Vec::with_capacity(len + 32);
+            let mut block: u32 = 0;
+            while candidate.len() < len {
+                let digest = Sha256::new()
+                    .chain_update(seed)
+                    .chain_update(attempt.to_be_bytes())
+                    .chain_update(block.to_be_bytes())
+                    .finalize();
+                candidate.extend_from_slice(&digest);
+                block += 1;
+            }
+            candidate.truncate(len);
+            candidate[0] &= 0xff >> excess;
+
+            let value = BigUint::from_bytes_be(&candidate);
+            if value < self.bound {
+                return value;
+            }
+        }
+        unreachable!("a candidate lands below the bound long before the attempts run out")
+    }
+}
+
+impl fmt::Debug for Transcript {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Transcript").finish_non_exhaustive()
+    }
+}
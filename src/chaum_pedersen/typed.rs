@@ -0,0 +1,102 @@
+use num_bigint::BigUint;
+#[cfg(feature = "std")]
+use rand::rngs::OsRng;
+
+use super::crypto::{ct_eq, generate_prover_secret_with_rng, secret_fixed_pow, secret_modpow};
+use super::arith::modpow;
+use super::rng::SecureRng;
+use super::PublicParameters;
+
+// A value of the group Z_p*: products and powers of it are taken mod p. The crypto functions take
+// these and Scalars along with the parameters, so a modulus is never passed by hand and an
+// exponent can't be reduced mod p or an element mod q.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GroupElement(BigUint);
+
+// An exponent, reduced mod the group order q
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Scalar(BigUint);
+
+impl GroupElement {
+    // None unless 0 < value < p; membership of the order-q subgroup is not checked
+    pub fn new(value: &BigUint, params: &PublicParameters) -> Option<Self> {
+        params.is_element(value).then(|| Self(value.clone()))
+    }
+
+    // For values this crate computed or checked already
+    pub(crate) fn unchecked(value: BigUint) -> Self {
+        Self(value)
+    }
+
+    pub fn generator(params: &PublicParameters) -> Self {
+        Self(params.g.clone())
+    }
+
+    pub fn as_biguint(&self) -> &BigUint {
+        &self.0
+    }
+
+    pub fn into_biguint(self) -> BigUint {
+        self.0
+    }
+
+    // self^exponent for a secret exponent; powers of g use the parameters' fixed-base table
+    pub fn pow(&self, exponent: &Scalar, params: &PublicParameters) -> GroupElement {
+        if self.0 == params.g {
+            Self(secret_fixed_pow(params.g_table(), &exponent.0, &params.q))
+        } else {
+            Self(secret_modpow(&self.0, &exponent.0, &params.p))
+        }
+    }
+
+    // self^exponent for a public exponent, e.g. on the verifier's side; powers of g use the
+    // fixed-base table when the parameters already have one, as precomputed ones do
+    pub fn pow_public(&self, exponent: &Scalar, params: &PublicParameters) -> GroupElement {
+        match params.built_g_table() {
+            Some(table) if self.0 == params.g => Self(table.pow(&exponent.0)),
+            _ => Self(modpow(&self.0, &exponent.0, &params.p)),
+        }
+    }
+
+    pub fn mul(&self, other: &GroupElement, params: &PublicParameters) -> GroupElement {
+        Self(&self.0 * &other.0 % &params.p)
+    }
+
+    // Constant-time equality
+    pub fn ct_eq(&self, other: &GroupElement, params: &PublicParameters) -> bool {
+        ct_eq(&self.0, &other.0, &params.p)
+    }
+}
+
+impl Scalar {
+    pub fn new(value: &BigUint, params: &PublicParameters) -> Self {
+        Self(value % &params.q)
+    }
+
+    // Uniform over [1, q)
+    #[cfg(feature = "std")]
+    pub fn random(params: &PublicParameters) -> Self {
+        Self::random_with_rng(params, &mut OsRng)
+    }
+
+    pub fn random_with_rng<R: SecureRng + ?Sized>(params: &PublicParameters, rng: &mut R) -> Self {
+        Self(generate_prover_secret_with_rng(&params.q, rng))
+    }
+
+    pub fn as_biguint(&self) -> &BigUint {
+        &self.0
+    }
+
+    pub fn into_biguint(self) -> BigUint {
+        self.0
+    }
+
+    pub fn mul(&self, other: &Scalar, params: &PublicParameters) -> Scalar {
+        Self(&self.0 * &other.0 % &params.q)
+    }
+
+    // self + a*c mod q
+    pub fn mul_add(&self, a: &Scalar, c: &Scalar, params: &PublicParameters) -> Scalar {
+        Self((&self.0 + &a.0 * &c.0) % &params.q)
+    }
+}
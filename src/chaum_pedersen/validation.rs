@@ -0,0 +1,108 @@
+use core::fmt;
+
+use num_bigint::BigUint;
+use num_traits::One;
+
+use super::arith::modpow;
+use super::primality::{is_prime, PrimalityConfig};
+use super::PublicParameters;
+
+// What a party accepts from parameters it did not generate itself
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParameterPolicy {
+    pub min_bits: u64,
+    pub max_bits: u64,
+    pub require_provenance: bool,  // only accept parameters derived from a published seed
+    pub primality: PrimalityConfig,
+}
+
+impl Default for ParameterPolicy {
+    fn default() -> Self {
+        Self {
+            min_bits: 256,
+            max_bits: 8192,
+            require_provenance: false,
+            primality: PrimalityConfig::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ParameterError {
+    SizeOutOfPolicy { bits: u64, min: u64, max: u64 },
+    NotSafePrimeForm,
+    CompositeQ,
+    CompositeP,
+    BadGenerator,
+    BadSecondGenerator,
+    MissingProvenance,
+    ProvenanceMismatch,
+    SubgroupTooSmall { bits: u64, min: u64 },
+    SecretTooShort { bits: u64, min: u64 },
+}
+
+impl fmt::Display for ParameterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParameterError::SizeOutOfPolicy { bits, min, max } => {
+                write!(f, "p has {} bits, outside the accepted {}..={}", bits, min, max)
+            }
+            ParameterError::NotSafePrimeForm => write!(f, "p is not of the form 2q + 1"),
+            ParameterError::CompositeQ => write!(f, "q is not prime"),
+            ParameterError::CompositeP => write!(f, "p is not prime"),
+            ParameterError::BadGenerator => write!(f, "g does not generate the order-q subgroup"),
+            ParameterError::BadSecondGenerator => write!(f, "h does not match its published seed"),
+            ParameterError::MissingProvenance => write!(f, "parameters carry no seed to verify them against"),
+            ParameterError::ProvenanceMismatch => write!(f, "parameters do not match their published seed"),
+            ParameterError::SubgroupTooSmall { bits, min } => {
+                write!(f, "q has {} bits, too few for {}-bit exponents", bits, min)
+            }
+            ParameterError::SecretTooShort { bits, min } => {
+                write!(f, "secret has {} bits, below the {}-bit floor", bits, min)
+            }
+        }
+    }
+}
+
+impl core::error::Error for ParameterError {}
+
+impl PublicParameters {
+    // Full check under the default policy, for parameters received from someone else
+    pub fn validate(&self) -> Result<(), ParameterError> {
+        self.validate_with(&ParameterPolicy::default())
+    }
+
+    // Cheap structural checks run before the primality tests, and provenance last since it re-runs
+    // the seeded search
+    pub fn validate_with(&self, policy: &ParameterPolicy) -> Result<(), ParameterError> {
+        let bits = self.p.bits();
+        if !(policy.min_bits..=policy.max_bits).contains(&bits) {
+            return Err(ParameterError::SizeOutOfPolicy {
+                bits,
+                min: policy.min_bits,
+                max: policy.max_bits,
+            });
+        }
+        if self.p != &self.q * 2u32 + 1u32 {
+            return Err(ParameterError::NotSafePrimeForm);
+        }
+        if self.g <= BigUint::one() || self.g >= self.p || !modpow(&self.g, &self.q, &self.p).is_one() {
+            return Err(ParameterError::BadGenerator);
+        }
+        if !is_prime(&self.q, &policy.primality) {
+            return Err(ParameterError::CompositeQ);
+        }
+        if !is_prime(&self.p, &policy.primality) {
+            return Err(ParameterError::CompositeP);
+        }
+        if self.h.is_some() && !self.verify_second_generator() {
+            return Err(ParameterError::BadSecondGenerator);
+        }
+        match &self.provenance {
+            Some(_) if !self.verify_provenance() => Err(ParameterError::ProvenanceMismatch),
+            None if policy.require_provenance => Err(ParameterError::MissingProvenance),
+            _ => Ok(()),
+        }
+    }
+}
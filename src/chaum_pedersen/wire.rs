@@ -0,0 +1,153 @@
+// Serde helpers for the non-protobuf wire formats. BigUint fields serialize as lowercase hex
+// strings in human-readable formats (JSON) and as big-endian byte strings in binary ones (CBOR).
+// The hex has two digits per byte of the big-endian encoding, the same bytes protobuf and CBOR
+// carry, so a verifier in another language decodes every format the same way. The width is fixed
+// per byte rather than per group, since a proof does not carry its group; left-pad if needed.
+
+use alloc::vec::Vec;
+use core::fmt;
+
+use num_bigint::BigUint;
+use serde::de::{self, Visitor};
+#[cfg(any(feature = "json", feature = "cbor"))]
+use serde::{de::DeserializeOwned, Serialize};
+
+pub mod biguint {
+    use alloc::string::String;
+
+    use num_bigint::BigUint;
+    use serde::de;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use super::BytesVisitor;
+
+    pub fn serialize<S: Serializer>(value: &BigUint, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&super::to_hex(&value.to_bytes_be()))
+        } else {
+            serializer.serialize_bytes(&value.to_bytes_be())
+        }
+    }
+
+    // Odd-length hex, which releases before byte-aligned hex wrote, still reads
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<BigUint, D::Error> {
+        if deserializer.is_human_readable() {
+            let hex = String::deserialize(deserializer)?;
+            super::from_hex(&hex).ok_or_else(|| de::Error::custom("invalid hex integer"))
+        } else {
+            deserializer.deserialize_bytes(BytesVisitor)
+        }
+    }
+}
+
+// Unpadded base64url of the big-endian bytes in human-readable formats, for integrators whose own
+// types want the shorter text encoding; binary formats get the same byte strings as `biguint`
+pub mod biguint_base64url {
+    use alloc::string::String;
+
+    use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+    use base64::Engine;
+    use num_bigint::BigUint;
+    use serde::de;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use super::BytesVisitor;
+
+    pub fn serialize<S: Serializer>(value: &BigUint, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&URL_SAFE_NO_PAD.encode(value.to_bytes_be()))
+        } else {
+            serializer.serialize_bytes(&value.to_bytes_be())
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<BigUint, D::Error> {
+        if deserializer.is_human_readable() {
+            let text = String::deserialize(deserializer)?;
+            match URL_SAFE_NO_PAD.decode(text) {
+                Ok(bytes) if !bytes.is_empty() => Ok(BigUint::from_bytes_be(&bytes)),
+                _ => Err(de::Error::custom("invalid base64url integer")),
+            }
+        } else {
+            deserializer.deserialize_bytes(BytesVisitor)
+        }
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> alloc::string::String {
+    const DIGITS: &[u8; 16] = b"0123456789abcdef";
+    bytes
+        .iter()
+        .flat_map(|byte| [DIGITS[usize::from(byte >> 4)], DIGITS[usize::from(byte & 0xf)]])
+        .map(char::from)
+        .collect()
+}
+
+// Lowercase hex digits only, with no sign, prefix, separators or upper case
+fn from_hex(text: &str) -> Option<BigUint> {
+    let valid = !text.is_empty() && text.bytes().all(|c| matches!(c, b'0'..=b'9' | b'a'..=b'f'));
+    valid.then(|| BigUint::parse_bytes(text.as_bytes(), 16)).flatten()
+}
+
+struct BytesVisitor;
+
+impl<'de> Visitor<'de> for BytesVisitor {
+    type Value = BigUint;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a big-endian byte string")
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<BigUint, E> {
+        Ok(BigUint::from_bytes_be(v))
+    }
+
+    fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<BigUint, A::Error> {
+        let mut bytes = Vec::new();
+        while let Some(byte) = seq.next_element::<u8>()? {
+            bytes.push(byte);
+        }
+        Ok(BigUint::from_bytes_be(&bytes))
+    }
+}
+
+pub mod biguint_vec {
+    use alloc::vec::Vec;
+
+    use num_bigint::BigUint;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    #[derive(Serialize, Deserialize)]
+    struct Element(#[serde(with = "super::biguint")] BigUint);
+
+    pub fn serialize<S: Serializer>(values: &[BigUint], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(values.iter().map(|v| Element(v.clone())))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<BigUint>, D::Error> {
+        let elements = Vec::<Element>::deserialize(deserializer)?;
+        Ok(elements.into_iter().map(|e| e.0).collect())
+    }
+}
+
+#[cfg(feature = "json")]
+pub fn to_json<T: Serialize>(value: &T) -> Result<String, serde_json::Error> {
+    serde_json::to_string(value)
+}
+
+#[cfg(feature = "json")]
+pub fn from_json<T: DeserializeOwned>(json: &str) -> Result<T, serde_json::Error> {
+    serde_json::from_str(json)
+}
+
+#[cfg(feature = "cbor")]
+pub fn to_cbor<T: Serialize>(value: &T) -> Result<Vec<u8>, ciborium::ser::Error<std::io::Error>> {
+    let mut out = Vec::new();
+    ciborium::into_writer(value, &mut out)?;
+    Ok(out)
+}
+
+#[cfg(feature = "cbor")]
+pub fn from_cbor<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, ciborium::de::Error<std::io::Error>> {
+    ciborium::from_reader(bytes)
+}
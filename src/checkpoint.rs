@@ -0,0 +1,96 @@
+use std::fmt;
+
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Nonce};
+use num_bigint::BigUint;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+
+use crate::chaum_pedersen::{wire, Commitment, ProofChallenge, Prover, SecureRng};
+use crate::client_lib::ProtocolSession;
+
+// Bound into every sealed state, so a blob only opens as a protocol state of this layout
+const ASSOCIATED_DATA: &[u8] = b"zkp-chaum-pedersen/protocol-state/v1";
+const NONCE_LEN: usize = 12;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum StateError {
+    Truncated,
+    Decryption,  // wrong key, or the blob was altered
+    Malformed(String),
+}
+
+impl fmt::Display for StateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StateError::Truncated => write!(f, "saved state is too short"),
+            StateError::Decryption => write!(f, "saved state does not decrypt under this key"),
+            StateError::Malformed(reason) => write!(f, "saved state is malformed: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for StateError {}
+
+// A protocol run between commitment and response: everything `ChaumPedersenClient::complete`
+// needs to finish it, including the prover's secrets and nonce, so it is only stored encrypted
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProtocolState {
+    pub session: ProtocolSession,
+    pub prover: Prover,
+    pub commitment: Commitment,
+    pub challenge_values: ProofChallenge,  // (y1, y2) = (g^x, b1^x)
+    #[serde(with = "wire::biguint")]
+    pub nonce: BigUint,  // x
+}
+
+impl ProtocolState {
+    // Commits and draws the nonce locally; nothing is sent until the state is completed
+    pub fn new(session: ProtocolSession, prover: Prover) -> Self {
+        Self::new_with_rng(session, prover, &mut OsRng)
+    }
+
+    pub fn new_with_rng<R: SecureRng + ?Sized>(session: ProtocolSession, prover: Prover, rng: &mut R) -> Self {
+        let commitment = prover.generate_commitment();
+        let (challenge_values, nonce) = prover.generate_proof_challenge_with_rng(&commitment, rng);
+        Self {
+            session,
+            prover,
+            commitment,
+            challenge_values,
+            nonce,
+        }
+    }
+
+    // ChaCha20-Poly1305 under `key`: a random nonce followed by the encrypted JSON state
+    pub fn save(&self, key: &[u8; 32]) -> Vec<u8> {
+        let plaintext = serde_json::to_vec(self).expect("protocol state always serializes");
+        let mut nonce = [0u8; NONCE_LEN];
+        OsRng.fill_bytes(&mut nonce);
+        let payload = Payload {
+            msg: &plaintext,
+            aad: ASSOCIATED_DATA,
+        };
+        let ciphertext = ChaCha20Poly1305::new(key.into())
+            .encrypt(Nonce::from_slice(&nonce), payload)
+            .expect("encryption only fails for oversized messages");
+        [&nonce[..], &ciphertext].concat()
+    }
+
+    pub fn load(blob: &[u8], key: &[u8; 32]) -> Result<Self, StateError> {
+        if blob.len() < NONCE_LEN {
+            return Err(StateError::Truncated);
+        }
+        let (nonce, ciphertext) = blob.split_at(NONCE_LEN);
+        let payload = Payload {
+            msg: ciphertext,
+            aad: ASSOCIATED_DATA,
+        };
+        let plaintext = ChaCha20Poly1305::new(key.into())
+            .decrypt(Nonce::from_slice(nonce), payload)
+            .map_err(|_| StateError::Decryption)?;
+        serde_json::from_slice(&plaintext).map_err(|e| StateError::Malformed(e.to_string()))
+    }
+}
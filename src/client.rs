@@ -1,123 +1,196 @@
-use num_bigint::BigUint;
-use tonic::transport::Channel;
-use tonic::Request;
-use zkp_chaum_pedersen_grpc::chaum_pedersen;
-
-pub mod zkp_client {
-    tonic::include_proto!("zkp");
+use tonic::codec::CompressionEncoding;
+use tonic::Code;
+use tonic::transport::{Certificate, ClientTlsConfig, Identity};
+use zkp_chaum_pedersen_grpc::chaum_pedersen::{ProofChallenge, Prover};
+use zkp_chaum_pedersen_grpc::chaum_pedersen::PublicParameters;
+use zkp_chaum_pedersen_grpc::client_lib::{ChaumPedersenClient, ClientError, ClientOptions, ParamsPin};
+use zkp_chaum_pedersen_grpc::evidence;
+use zkp_chaum_pedersen_grpc::proof_file::ProofFile;
+
+// TLS is used when ZKP_TLS_CA_CERT is set; ZKP_TLS_CLIENT_CERT/KEY add a client identity for mTLS.
+// The server certificate is checked against ZKP_TLS_DOMAIN, defaulting to localhost.
+fn tls_from_env() -> Result<Option<ClientTlsConfig>, Box<dyn std::error::Error>> {
+    let ca = match std::env::var("ZKP_TLS_CA_CERT") {
+        Ok(path) => std::fs::read(path)?,
+        Err(_) => return Ok(None),
+    };
+
+    let domain = std::env::var("ZKP_TLS_DOMAIN").unwrap_or_else(|_| "localhost".to_string());
+    let mut tls = ClientTlsConfig::new()
+        .ca_certificate(Certificate::from_pem(ca))
+        .domain_name(domain);
+    if let (Ok(cert), Ok(key)) = (std::env::var("ZKP_TLS_CLIENT_CERT"), std::env::var("ZKP_TLS_CLIENT_KEY")) {
+        tls = tls.identity(Identity::from_pem(std::fs::read(cert)?, std::fs::read(key)?));
+    }
+    Ok(Some(tls))
 }
 
-use zkp_client::chaum_pedersen_service_client::ChaumPedersenServiceClient;
-use zkp_client::*;
+// ZKP_COMPRESSION=gzip or zstd compresses requests
+fn compression_from_env() -> Result<Option<CompressionEncoding>, Box<dyn std::error::Error>> {
+    match std::env::var("ZKP_COMPRESSION").as_deref() {
+        Err(_) | Ok("") | Ok("none") => Ok(None),
+        Ok("gzip") => Ok(Some(CompressionEncoding::Gzip)),
+        Ok("zstd") => Ok(Some(CompressionEncoding::Zstd)),
+        Ok(other) => Err(format!("unknown ZKP_COMPRESSION '{}', expected gzip or zstd", other).into()),
+    }
+}
 
-use chaum_pedersen::{
-    PublicParameters as CryptoPublicParameters,
-    Prover, generate_prover_secret, compute_y1y2, compute_z
-};
+// `--pin-params HEX` (or ZKP_PIN_PARAMS) accepts only the parameter set with that fingerprint.
+// `--known-params FILE` (or ZKP_KNOWN_PARAMS) is trust on first use: the first run records the
+// fingerprint it was given and later runs refuse any other.
+fn params_pin(pin: Option<String>, known: Option<&str>) -> Result<ParamsPin, Box<dyn std::error::Error>> {
+    let recorded = match known {
+        Some(path) => match std::fs::read_to_string(path) {
+            Ok(text) => Some(text.trim().to_string()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => return Err(format!("cannot read {}: {}", path, e).into()),
+        },
+        None => None,
+    };
+    match pin.or(recorded) {
+        Some(text) => {
+            let fingerprint = hex::decode(&text)
+                .ok()
+                .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+                .ok_or_else(|| format!("'{}' is not a 32-byte hex fingerprint", text))?;
+            Ok(ParamsPin::Fingerprint(fingerprint))
+        }
+        None if known.is_some() => Ok(ParamsPin::FirstSeen),
+        None => Ok(ParamsPin::Any),
+    }
+}
 
-#[derive(Debug)]
-pub struct ChaumPedersenClient {
-    client: ChaumPedersenServiceClient<Channel>,
+// Air-gapped mode: `--write-proof FILE [--bits N]` generates parameters locally and writes a
+// non-interactive proof for zkp-verify, without contacting a server
+fn write_proof_file(path: &str, bits: u64) -> Result<(), Box<dyn std::error::Error>> {
+    println!("Generating {}-bit parameters locally...", bits);
+    let prover = Prover::new(PublicParameters::new(bits));
+    let file = ProofFile::create(&prover);
+    file.write(path)?;
+    println!("Wrote proof to {}", path);
+    println!("   Parameters digest: {}", hex::encode(file.params.digest()));
+    Ok(())
 }
 
-impl ChaumPedersenClient {
-    pub async fn connect(addr: &str) -> Result<Self, Box<dyn std::error::Error>> {
-        let client = ChaumPedersenServiceClient::connect(addr.to_string()).await?;
-        Ok(Self { client })
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let flag = |name: &str| args.iter().position(|arg| arg == name).and_then(|i| args.get(i + 1));
+    if let Some(path) = flag("--write-proof") {
+        let bits = flag("--bits").map(|bits| bits.parse()).transpose()?.unwrap_or(512);
+        return write_proof_file(path, bits);
     }
 
-    pub async fn run_protocol(&mut self, bit_size: u32) -> Result<bool, Box<dyn std::error::Error>> {
-        println!("Starting Chaum-Pedersen Zero-Knowledge Proof Protocol");
-
-        println!("Getting public parameters from verifier...");
-        let init_request = Request::new(InitializeRequest { bit_size });
-        let init_response = self.client.initialize_protocol(init_request).await?.into_inner();
-
-        let session_id = init_response.session_id;
-        let params = init_response.params.ok_or("Missing parameters")?;
-
-        let crypto_params = CryptoPublicParameters {
-            p: BigUint::from_bytes_be(&params.p),
-            q: BigUint::from_bytes_be(&params.q),
-            g: BigUint::from_bytes_be(&params.g),
-        };
-
-        println!("Received public parameters");
-        println!("   Session ID: {}", session_id);
-        println!("   Safe prime p: {} bits", crypto_params.p.bits());
-        println!("   Sophie Germain prime q: {} bits", crypto_params.q.bits());
-
-        println!("\nGenerating secrets and commitment...");
-        let prover = Prover::new(crypto_params.clone());
-        let commitment = prover.generate_commitment();
-        
-        println!("Generated commitment:");
-        println!("   a1 = g^a mod p");
-        println!("   b1 = g^b mod p"); 
-        println!("   c1 = g^(a*b) mod p");
-
-        println!("\nGenerating proof challenge values...");
-        let x = generate_prover_secret(&crypto_params.q);
-        let (y1, y2) = compute_y1y2(&x, &crypto_params.g, &commitment.b1, &crypto_params.p);
-
-        println!("Generated challenge values:");
-        println!("   y1 = g^x mod p");
-        println!("   y2 = b1^x mod p");
-
-        println!("\nSending commitment and challenge values...");
-        let commitment_request = Request::new(CommitmentRequest {
-            session_id: session_id.clone(),
-            commitment: Some(Commitment {
-                a1: commitment.a1.to_bytes_be(),
-                b1: commitment.b1.to_bytes_be(),
-                c1: commitment.c1.to_bytes_be(),
-            }),
-            challenge_values: Some(ProofChallenge {
-                y1: y1.to_bytes_be(),
-                y2: y2.to_bytes_be(),
-            }),
-        });
-
-        let challenge_response = self.client.send_commitment(commitment_request).await?.into_inner();
-        let challenge = BigUint::from_bytes_be(&challenge_response.challenge);
-
-        println!("Received challenge from verifier");
-
-        println!("\nComputing proof response...");
-        let z = compute_z(&x, &prover.secret_a, &challenge, &crypto_params.q);
-
-        println!("Computed response z = x + a*s mod q (here s is the challenge)");
-
-        println!("\nSending response for verification...");
-        let verify_request = Request::new(VerifyProofRequest {
-            session_id: session_id.clone(),
-            z: z.to_bytes_be(),
-        });
-
-        let verify_response = self.client.verify_proof(verify_request).await?.into_inner();
-
-        if verify_response.verified {
-            println!("SUCCESS: {}", verify_response.message);
-            println!("Verified: The prover demonstrated knowledge of the discrete logarithm without revealing the secret value");
-        } else {
-            println!("FAILED: {}", verify_response.message);
-            println!("The zero-knowledge proof verification failed!");
-        }
+    // ZKP_OTLP_ENDPOINT exports client spans, e.g. to a Jaeger collector at http://localhost:4317
+    #[cfg(feature = "otel")]
+    if let Ok(endpoint) = std::env::var("ZKP_OTLP_ENDPOINT") {
+        use tracing_subscriber::layer::SubscriberExt;
+        use tracing_subscriber::util::SubscriberInitExt;
+        let otel = zkp_chaum_pedersen_grpc::telemetry::layer("grpc-zkp-client", &endpoint)?;
+        tracing_subscriber::registry()
+            .with(tracing_subscriber::filter::LevelFilter::INFO)
+            .with(otel)
+            .init();
+    }
+
+    let known_params = flag("--known-params").cloned().or_else(|| std::env::var("ZKP_KNOWN_PARAMS").ok());
+    let pin = flag("--pin-params").cloned().or_else(|| std::env::var("ZKP_PIN_PARAMS").ok());
+    let params_pin = params_pin(pin, known_params.as_deref())?;
+
+    let tls = tls_from_env()?;
+    let addr = if tls.is_some() { "https://[::1]:50051" } else { "http://[::1]:50051" };
+    let options = ClientOptions {
+        tls,
+        api_key: std::env::var("ZKP_API_KEY").ok(),
+        compression: compression_from_env()?,
+        params_pin,
+        ..ClientOptions::default()
+    };
+    let client = ChaumPedersenClient::connect_with(addr, options).await?;
 
-        Ok(verify_response.verified)
+    println!("Connected to Chaum-Pedersen ZKP Server.");
+    let result = run_protocol(&client).await;
+    if let (Some(path), ParamsPin::FirstSeen, Some(fingerprint)) = (&known_params, params_pin, client.pinned_params()) {
+        std::fs::write(path, hex::encode(fingerprint))?;
+        println!("Pinned parameters {} in {}", hex::encode(fingerprint), path);
     }
+    #[cfg(feature = "otel")]
+    zkp_chaum_pedersen_grpc::telemetry::shutdown();
+    result
 }
 
-#[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let mut client = ChaumPedersenClient::connect("http://[::1]:50051").await?;
+// 512 bits, moved into the server's allowed range; servers without GetCapabilities get 512
+async fn negotiate_bit_size(client: &ChaumPedersenClient) -> Result<u32, ClientError> {
+    const PREFERRED_BITS: u32 = 512;
+    match client.capabilities().await {
+        Ok(capabilities) => Ok(PREFERRED_BITS.max(capabilities.min_bits).min(capabilities.max_bits)),
+        Err(ClientError::Rpc { status, .. }) if status.code() == Code::Unimplemented => Ok(PREFERRED_BITS),
+        Err(e) => Err(e),
+    }
+}
 
-    println!("Connected to Chaum-Pedersen ZKP Server.");
-    
-    let bit_size = 512;
-    let result = client.run_protocol(bit_size).await?;
+// The interactive protocol, step by step; a single span covers it so the whole flow is one trace
+#[tracing::instrument(skip_all)]
+async fn run_protocol(client: &ChaumPedersenClient) -> Result<(), Box<dyn std::error::Error>> {
+    println!("Starting Chaum-Pedersen Zero-Knowledge Proof Protocol");
+
+    println!("Getting public parameters from verifier...");
+    let bit_size = negotiate_bit_size(client).await?;
+    let session = client.initialize(bit_size).await?;
+    let params = &session.params;
+
+    println!("Received public parameters");
+    println!("   Session ID: {}", session.session_id);
+    println!("   Safe prime p: {} bits", params.p().bits());
+    println!("   Sophie Germain prime q: {} bits", params.q().bits());
+    println!("   Fingerprint: {}", hex::encode(params.digest()));
+    if let Some(provenance) = &params.provenance {
+        println!("   Derived from public seed {} (verified)", hex::encode(&provenance.seed));
+    }
+
+    println!("\nGenerating secrets and commitment...");
+    let prover = Prover::new(params.clone());
+    let commitment = prover.generate_commitment();
+
+    println!("Generated commitment:");
+    println!("   a1 = g^a mod p");
+    println!("   b1 = g^b mod p");
+    println!("   c1 = g^(a*b) mod p");
+
+    println!("\nGenerating proof challenge values...");
+    let (ProofChallenge { y1, y2 }, x) = prover.generate_proof_challenge(&commitment);
+
+    println!("Generated challenge values:");
+    println!("   y1 = g^x mod p");
+    println!("   y2 = b1^x mod p");
+
+    println!("\nSending commitment and challenge values...");
+    let challenge = client.send_commitment(&session, &commitment, &y1, &y2).await?;
 
-    println!("Result: {}", if result { "Proof has been verified." } else { "Proof has failed!" });
+    println!("Received challenge from verifier");
+
+    println!("\nComputing proof response...");
+    let z = prover.generate_response(&x, &challenge).z;
+
+    println!("Computed response z = x + a*s mod q (here s is the challenge)");
+
+    println!("\nSending response for verification...");
+    let verification = client.prove(&session, &z).await?;
+
+    if verification.verified {
+        println!("SUCCESS: {}", verification.message);
+        println!("Verified: The prover demonstrated knowledge of the discrete logarithm without revealing the secret value");
+    } else {
+        println!("FAILED: {}", verification.message);
+        println!("The zero-knowledge proof verification failed!");
+    }
+
+    println!("Result: {}", if verification.verified { "Proof has been verified." } else { "Proof has failed!" });
+
+    let signed = client.transcript(&session).await?;
+    let transcript = evidence::open(&signed, None)?;
+    println!("\nSigned transcript: {} bytes, verdict {}", signed.transcript.len(), transcript.verified);
+    println!("   Server key: {}", hex::encode(&signed.public_key));
 
     Ok(())
-}
\ No newline at end of file
+}
@@ -0,0 +1,380 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Mutex;
+
+use num_bigint::BigUint;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use tonic::Status;
+
+use crate::chaum_pedersen::{
+    generate_interactive_challenge, generate_session_challenge, ChaumPedersen, Commitment, ProofChallenge, ProofResponse,
+    PublicParameters, Verifier, ZKProof,
+};
+use crate::client_lib::{ChaumPedersenClient, ClientError, ProtocolSession, Verification};
+use crate::registry::Registration;
+use crate::session::{ChallengeMode, CommitmentOrigin, SessionId};
+use crate::sigma::SigmaProtocol;
+use crate::zkp;
+
+// The verifier calls a sign-in flow makes. Code written against this trait runs on a
+// ChaumPedersenClient in production and on a MockZkpClient in unit tests.
+#[tonic::async_trait]
+pub trait ZkpClientApi: Send + Sync {
+    async fn initialize(&self, bit_size: u32) -> Result<ProtocolSession, ClientError>;
+
+    async fn initialize_for_user(&self, user_id: &str, mode: ChallengeMode) -> Result<ProtocolSession, ClientError>;
+
+    // The verifier's challenge for (y1, y2)
+    async fn send_commitment(
+        &self,
+        session: &ProtocolSession,
+        commitment: &Commitment,
+        y1: &BigUint,
+        y2: &BigUint,
+    ) -> Result<BigUint, ClientError>;
+
+    async fn prove(&self, session: &ProtocolSession, z: &BigUint) -> Result<Verification, ClientError>;
+
+    async fn prove_one_shot(&self, session: &ProtocolSession, proof: &ZKProof) -> Result<Verification, ClientError>;
+
+    async fn register(
+        &self,
+        session: &ProtocolSession,
+        user_id: &str,
+        commitment: &Commitment,
+    ) -> Result<zkp::Registration, ClientError>;
+
+    async fn revoke_registration(&self, user_id: &str) -> Result<zkp::Registration, ClientError>;
+
+    async fn cancel(&self, session_id: &str) -> Result<(), ClientError>;
+}
+
+#[tonic::async_trait]
+impl ZkpClientApi for ChaumPedersenClient {
+    async fn initialize(&self, bit_size: u32) -> Result<ProtocolSession, ClientError> {
+        ChaumPedersenClient::initialize(self, bit_size).await
+    }
+
+    async fn initialize_for_user(&self, user_id: &str, mode: ChallengeMode) -> Result<ProtocolSession, ClientError> {
+        ChaumPedersenClient::initialize_for_user(self, user_id, mode).await
+    }
+
+    async fn send_commitment(
+        &self,
+        session: &ProtocolSession,
+        commitment: &Commitment,
+        y1: &BigUint,
+        y2: &BigUint,
+    ) -> Result<BigUint, ClientError> {
+        ChaumPedersenClient::send_commitment(self, session, commitment, y1, y2).await
+    }
+
+    async fn prove(&self, session: &ProtocolSession, z: &BigUint) -> Result<Verification, ClientError> {
+        ChaumPedersenClient::prove(self, session, z).await
+    }
+
+    async fn prove_one_shot(&self, session: &ProtocolSession, proof: &ZKProof) -> Result<Verification, ClientError> {
+        ChaumPedersenClient::prove_one_shot(self, session, proof).await
+    }
+
+    async fn register(
+        &self,
+        session: &ProtocolSession,
+        user_id: &str,
+        commitment: &Commitment,
+    ) -> Result<zkp::Registration, ClientError> {
+        ChaumPedersenClient::register(self, session, user_id, commitment).await
+    }
+
+    async fn revoke_registration(&self, user_id: &str) -> Result<zkp::Registration, ClientError> {
+        ChaumPedersenClient::revoke_registration(self, user_id).await
+    }
+
+    async fn cancel(&self, session_id: &str) -> Result<(), ClientError> {
+        ChaumPedersenClient::cancel(self, session_id).await
+    }
+}
+
+// A session as the mock verifier tracks it
+#[derive(Debug, Clone)]
+struct MockSession {
+    user_id: Option<String>,
+    mode: ChallengeMode,
+    exchange: Option<(Commitment, ProofChallenge, BigUint)>, // (commitment, (y1, y2), challenge)
+    completed: bool,
+}
+
+#[derive(Debug, Default)]
+struct MockState {
+    sessions: HashMap<String, MockSession>,
+    registrations: HashMap<String, Registration>,
+    used_values: HashSet<(BigUint, BigUint)>,
+    failures: HashMap<&'static str, VecDeque<Status>>,
+    calls: Vec<&'static str>,
+}
+
+// An in-memory verifier behind the client API: sessions, challenges, registrations and real proof
+// checks, all over one fixed parameter set and with the server's status codes. Errors can be
+// queued per RPC to exercise a flow's failure paths, and the RPCs made are recorded in order.
+#[derive(Debug)]
+pub struct MockZkpClient {
+    params: PublicParameters,
+    state: Mutex<MockState>,
+}
+
+impl MockZkpClient {
+    // Every session is over `params`, whatever bit size is asked for
+    pub fn new(params: PublicParameters) -> Self {
+        Self {
+            params,
+            state: Mutex::new(MockState::default()),
+        }
+    }
+
+    // Registers `commitment` for `user_id` up front, as if done in an earlier run
+    pub fn with_registration(self, user_id: &str, commitment: Commitment) -> Self {
+        let registration = Registration::new(self.params.clone(), commitment);
+        self.state.lock().unwrap().registrations.insert(user_id.to_string(), registration);
+        self
+    }
+
+    pub fn params(&self) -> &PublicParameters {
+        &self.params
+    }
+
+    // Makes the next call to `rpc` (e.g. "VerifyProof") fail with `status`; queued failures are
+    // returned in order
+    pub fn fail_next(&self, rpc: &'static str, status: Status) {
+        self.state.lock().unwrap().failures.entry(rpc).or_default().push_back(status);
+    }
+
+    // The RPCs made so far, by their gRPC method names
+    pub fn calls(&self) -> Vec<&'static str> {
+        self.state.lock().unwrap().calls.clone()
+    }
+
+    // Records the call and runs `f` on the state unless a failure was queued for it
+    fn call<T>(&self, rpc: &'static str, f: impl FnOnce(&mut MockState) -> Result<T, Status>) -> Result<T, ClientError> {
+        let mut state = self.state.lock().unwrap();
+        state.calls.push(rpc);
+        let queued = state.failures.get_mut(rpc).and_then(VecDeque::pop_front);
+        match queued {
+            Some(status) => Err(status),
+            None => f(&mut state),
+        }
+        .map_err(|status| ClientError::Rpc { rpc, status })
+    }
+
+    fn open_session(&self, state: &mut MockState, user_id: Option<String>, mode: ChallengeMode) -> ProtocolSession {
+        let session_id = SessionId::generate().to_string();
+        let commitment_origin = match user_id {
+            Some(_) => CommitmentOrigin::Registered,
+            None => CommitmentOrigin::Client,
+        };
+        state.sessions.insert(session_id.clone(), MockSession {
+            user_id,
+            mode,
+            exchange: None,
+            completed: false,
+        });
+        ProtocolSession {
+            session_id,
+            params: self.params.clone(),
+            challenge_mode: mode,
+            commitment_origin,
+            compressed_elements: false,
+        }
+    }
+
+    // The session a proof can still be sent for
+    fn open<'a>(state: &'a mut MockState, session_id: &str) -> Result<&'a mut MockSession, Status> {
+        let session = state
+            .sessions
+            .get_mut(session_id)
+            .ok_or_else(|| Status::not_found("Session not found"))?;
+        if session.completed {
+            return Err(Status::failed_precondition("This session's proof was already checked"));
+        }
+        Ok(session)
+    }
+
+    fn check_registered(
+        state: &MockState,
+        user_id: Option<&str>,
+        params: &PublicParameters,
+        commitment: &Commitment,
+    ) -> Result<(), Status> {
+        if let Some(user_id) = user_id
+            && !active_registration(state, user_id)?.matches(params, commitment)
+        {
+            return Err(Status::failed_precondition("The commitment does not match this user's registration"));
+        }
+        Ok(())
+    }
+}
+
+fn active_registration<'a>(state: &'a MockState, user_id: &str) -> Result<&'a Registration, Status> {
+    let registration = state
+        .registrations
+        .get(user_id)
+        .ok_or_else(|| Status::not_found("No commitment is registered for this user"))?;
+    if registration.revoked {
+        return Err(Status::failed_precondition("This user's registration was revoked"));
+    }
+    Ok(registration)
+}
+
+fn registration_info(user_id: &str, registration: &Registration) -> zkp::Registration {
+    zkp::Registration {
+        user_id: user_id.to_string(),
+        commitment: Some(zkp::Commitment::from(&registration.commitment)),
+        params_digest: registration.params.digest().to_vec(),
+        created_at: registration.created_at,
+        updated_at: registration.updated_at,
+        revoked: registration.revoked,
+        has_recovery: registration.recovery.is_some(),
+    }
+}
+
+fn verdict(verified: bool) -> Verification {
+    let message = if verified {
+        "Zero-knowledge proof verified successfully!"
+    } else {
+        "Zero-knowledge proof verification failed!"
+    };
+    Verification {
+        verified,
+        message: message.to_string(),
+    }
+}
+
+#[tonic::async_trait]
+impl ZkpClientApi for MockZkpClient {
+    async fn initialize(&self, _bit_size: u32) -> Result<ProtocolSession, ClientError> {
+        self.call("InitializeProtocol", |state| {
+            Ok(self.open_session(state, None, ChallengeMode::FiatShamir))
+        })
+    }
+
+    async fn initialize_for_user(&self, user_id: &str, mode: ChallengeMode) -> Result<ProtocolSession, ClientError> {
+        self.call("InitializeProtocol", |state| {
+            active_registration(state, user_id)?;
+            Ok(self.open_session(state, Some(user_id.to_string()), mode))
+        })
+    }
+
+    async fn send_commitment(
+        &self,
+        session: &ProtocolSession,
+        commitment: &Commitment,
+        y1: &BigUint,
+        y2: &BigUint,
+    ) -> Result<BigUint, ClientError> {
+        self.call("SendCommitment", |state| {
+            let (user_id, issued) = {
+                let mock = Self::open(state, &session.session_id)?;
+                (mock.user_id.clone(), mock.exchange.clone())
+            };
+            Self::check_registered(state, user_id.as_deref(), &self.params, commitment)?;
+            // a resent (y1, y2) gets the challenge it was already given, as from the server
+            if let Some((_, sent, challenge)) = issued
+                && sent.y1 == *y1
+                && sent.y2 == *y2
+            {
+                return Ok(challenge);
+            }
+            if !state.used_values.insert((y1.clone(), y2.clone())) {
+                return Err(Status::already_exists("These (y1, y2) values were already used in another session"));
+            }
+
+            let mock = Self::open(state, &session.session_id)?;
+            let challenge = match mock.mode {
+                ChallengeMode::FiatShamir => {
+                    generate_session_challenge(session.session_id.as_bytes(), commitment, y1, y2, self.params.q())
+                }
+                ChallengeMode::Interactive => {
+                    let mut randomness = [0u8; 32];
+                    OsRng.fill_bytes(&mut randomness);
+                    generate_interactive_challenge(&randomness, commitment, y1, y2, self.params.q())
+                }
+            };
+            let values = ProofChallenge {
+                y1: y1.clone(),
+                y2: y2.clone(),
+            };
+            mock.exchange = Some((commitment.clone(), values, challenge.clone()));
+            Ok(challenge)
+        })
+    }
+
+    async fn prove(&self, session: &ProtocolSession, z: &BigUint) -> Result<Verification, ClientError> {
+        self.call("VerifyProof", |state| {
+            let mock = Self::open(state, &session.session_id)?;
+            let (commitment, values, challenge) = mock
+                .exchange
+                .clone()
+                .ok_or_else(|| Status::failed_precondition("No challenge has been issued for this session"))?;
+            mock.completed = true;
+            let response = ProofResponse { z: z.clone() };
+            let verified = ChaumPedersen::new(self.params.clone()).verify(&commitment, &values, &challenge, &response);
+            Ok(verdict(verified))
+        })
+    }
+
+    async fn prove_one_shot(&self, session: &ProtocolSession, proof: &ZKProof) -> Result<Verification, ClientError> {
+        self.call("ProveOneShot", |state| {
+            let mock = Self::open(state, &session.session_id)?;
+            if mock.exchange.is_some() {
+                return Err(Status::failed_precondition("This session is in the middle of an interactive run"));
+            }
+            let user_id = mock.user_id.clone();
+            Self::check_registered(state, user_id.as_deref(), &self.params, &proof.commitment)?;
+            Self::open(state, &session.session_id)?.completed = true;
+            Ok(verdict(Verifier::new(self.params.clone()).verify_session_proof(&session.session_id, proof)))
+        })
+    }
+
+    async fn register(
+        &self,
+        session: &ProtocolSession,
+        user_id: &str,
+        commitment: &Commitment,
+    ) -> Result<zkp::Registration, ClientError> {
+        self.call("RegisterCommitment", |state| {
+            if !state.sessions.contains_key(&session.session_id) {
+                return Err(Status::not_found("Session not found"));
+            }
+            let version = match state.registrations.get(user_id) {
+                None => 1,
+                Some(existing) if existing.revoked => existing.version + 1,
+                Some(_) => return Err(Status::already_exists("This user already has a registered commitment")),
+            };
+            let registration = Registration {
+                version,
+                ..Registration::new(self.params.clone(), commitment.clone())
+            };
+            let info = registration_info(user_id, &registration);
+            state.registrations.insert(user_id.to_string(), registration);
+            Ok(info)
+        })
+    }
+
+    async fn revoke_registration(&self, user_id: &str) -> Result<zkp::Registration, ClientError> {
+        self.call("RevokeCommitment", |state| {
+            let revoked = active_registration(state, user_id)?.revoked();
+            let info = registration_info(user_id, &revoked);
+            state.registrations.insert(user_id.to_string(), revoked);
+            Ok(info)
+        })
+    }
+
+    async fn cancel(&self, session_id: &str) -> Result<(), ClientError> {
+        self.call("CancelSession", |state| {
+            state
+                .sessions
+                .remove(session_id)
+                .map(|_| ())
+                .ok_or_else(|| Status::not_found("Session not found"))
+        })
+    }
+}
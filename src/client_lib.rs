@@ -0,0 +1,1537 @@
+use std::fmt;
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use num_bigint::BigUint;
+use rand::rngs::OsRng;
+use serde::{Deserialize, Serialize};
+use tonic::codec::CompressionEncoding;
+use tonic::metadata::{Ascii, MetadataValue};
+use tonic::transport::{Channel, ClientTlsConfig, Endpoint};
+use tonic::{Code, Request, Response, Status};
+use tracing::{info_span, instrument, Instrument};
+
+use crate::batch;
+use crate::auth::API_KEY_HEADER;
+use crate::backend::{BackendError, SecretBackend, SoftwareBackend};
+use crate::chaum_pedersen::{
+    secret_fixed_pow, Commitment, ParameterError, ParameterPolicy, ProofChallenge, ProofResponse, PublicParameters,
+    ZKProof,
+};
+use crate::checkpoint::{ProtocolState, StateError};
+use crate::convert::{
+    compress_commitment, decode_dleq, decode_integer, encode_compact_dleq, encode_dh_share, encode_dleq, encode_integer, encode_range,
+};
+use crate::group::{Dleq, DleqStatement, Group, GroupKind};
+use crate::interop::CompactDleqProof;
+use crate::key_exchange::{self, DhShare};
+use crate::pedersen::{Opening, Pedersen, PedersenCommitment};
+use crate::range;
+use crate::schnorr::{Schnorr, SchnorrStatement};
+use crate::session::{ChallengeMode, CommitmentOrigin, CHALLENGE_EXPIRED};
+use crate::sigma::SigmaProtocol;
+use crate::threshold::PartialCommitment;
+use crate::voprf;
+use crate::zkp;
+use crate::zkp::chaum_pedersen_service_client::ChaumPedersenServiceClient;
+use crate::zkp::prover::prover_service_client::ProverServiceClient;
+use crate::zkp::v2::chaum_pedersen_service_client::ChaumPedersenServiceClient as ChaumPedersenServiceV2Client;
+
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ClientError {
+    InvalidConfig(String),
+    Unreachable { attempts: u32, message: String },
+    DeadlineExceeded { rpc: &'static str },
+    Rpc { rpc: &'static str, status: Status },
+    InvalidResponse(String),
+    InvalidParameters(ParameterError),  // the server's parameters failed `ClientOptions::param_policy`
+    ParametersChanged { expected: [u8; 32], actual: [u8; 32] },  // the server's parameters are not the pinned ones
+    Backend(BackendError),
+    State(StateError),  // a saved protocol state could not be opened
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClientError::InvalidConfig(reason) => write!(f, "invalid client configuration: {}", reason),
+            ClientError::Unreachable { attempts, message } => {
+                write!(f, "server unreachable after {} attempt(s): {}", attempts, message)
+            }
+            ClientError::DeadlineExceeded { rpc } => write!(f, "{} did not complete before its deadline", rpc),
+            ClientError::Rpc { rpc, status } => write!(f, "{} failed: {} ({:?})", rpc, status.message(), status.code()),
+            ClientError::InvalidResponse(reason) => write!(f, "invalid response from server: {}", reason),
+            ClientError::InvalidParameters(e) => write!(f, "server sent unacceptable parameters: {}", e),
+            ClientError::ParametersChanged { expected, actual } => write!(
+                f,
+                "server sent parameters {} instead of the pinned {}",
+                hex::encode(actual),
+                hex::encode(expected)
+            ),
+            ClientError::Backend(e) => write!(f, "secret backend failed: {}", e),
+            ClientError::State(e) => write!(f, "cannot resume: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ClientError {}
+
+// Exponential backoff for connection attempts and idempotent RPCs
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 4,
+            initial_backoff: Duration::from_millis(200),
+            max_backoff: Duration::from_secs(5),
+        }
+    }
+}
+
+impl RetryPolicy {
+    fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.initial_backoff.saturating_mul(factor).min(self.max_backoff)
+    }
+}
+
+// Which parameter sets sessions may run over, by `PublicParameters::digest`. Pinning keeps a verifier,
+// or whoever answers in its place, from substituting parameters of its choosing in a later session.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ParamsPin {
+    #[default]
+    Any,  // anything `param_policy` accepts, e.g. fresh parameters for every session
+    FirstSeen,  // trust on first use: the first session's parameters, then only those
+    Fingerprint([u8; 32]),
+}
+
+#[derive(Debug, Clone)]
+pub struct ClientOptions {
+    pub retry: RetryPolicy,
+    pub connect_timeout: Duration,
+    pub initialize_deadline: Duration,  // parameter generation can take minutes at large bit sizes
+    pub rpc_deadline: Duration,
+    pub tls: Option<ClientTlsConfig>,
+    pub api_key: Option<String>,
+    pub param_policy: ParameterPolicy,
+    pub params_pin: ParamsPin,
+    // Encoding for requests; gzip and zstd responses are accepted either way
+    pub compression: Option<CompressionEncoding>,
+    // Asks for modp sessions that take commitments and (y1, y2) compressed; servers that predate
+    // compression open plain sessions, which the client then uses as they are
+    pub compressed_elements: bool,
+}
+
+impl Default for ClientOptions {
+    fn default() -> Self {
+        Self {
+            retry: RetryPolicy::default(),
+            connect_timeout: Duration::from_secs(5),
+            initialize_deadline: Duration::from_secs(300),
+            rpc_deadline: Duration::from_secs(10),
+            tls: None,
+            api_key: None,
+            param_policy: ParameterPolicy::default(),
+            params_pin: ParamsPin::Any,
+            compression: None,
+            compressed_elements: false,
+        }
+    }
+}
+
+// A session opened by `initialize`, with the parameters the verifier issued for it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProtocolSession {
+    pub session_id: String,
+    pub params: PublicParameters,
+    pub challenge_mode: ChallengeMode,
+    #[serde(default)]
+    pub commitment_origin: CommitmentOrigin,  // registered for the sessions of `initialize_for_user`
+    #[serde(default)]
+    pub compressed_elements: bool,  // commitments and (y1, y2) are sent compressed
+}
+
+// A session opened by `initialize_group` over a fixed curve group
+#[derive(Debug, Clone)]
+pub struct GroupSession {
+    pub session_id: String,
+    pub group: GroupKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Verification {
+    pub verified: bool,
+    pub message: String,
+}
+
+// A key held by a prover daemon: its parameters and the public commitment to its secrets
+#[derive(Debug, Clone)]
+pub struct RemoteKey {
+    pub key_id: String,
+    pub params: PublicParameters,
+    pub commitment: Commitment,
+}
+
+// A nonce a prover daemon drew for `key_id`, pending until answered with `remote_respond`
+#[derive(Debug, Clone)]
+pub struct RemoteNonce {
+    pub key_id: String,
+    pub nonce_id: String,
+    pub values: ProofChallenge,  // (y1, y2)
+}
+
+// Talks to a verifier's ChaumPedersenService and, for the RPCs marked as such, to a prover
+// daemon's ProverService; one endpoint rarely serves both
+#[derive(Debug)]
+pub struct ChaumPedersenClient {
+    client: ChaumPedersenServiceClient<Channel>,
+    client_v2: ChaumPedersenServiceV2Client<Channel>,
+    client_prover: ProverServiceClient<Channel>,
+    api_key: Option<MetadataValue<Ascii>>,
+    pinned_params: Mutex<Option<[u8; 32]>>,
+    options: ClientOptions,
+}
+
+impl ChaumPedersenClient {
+    pub async fn connect(addr: &str) -> Result<Self, ClientError> {
+        Self::connect_with(addr, ClientOptions::default()).await
+    }
+
+    pub async fn connect_with(addr: &str, options: ClientOptions) -> Result<Self, ClientError> {
+        let mut endpoint = Endpoint::from_shared(addr.to_string())
+            .map_err(|e| ClientError::InvalidConfig(e.to_string()))?
+            .connect_timeout(options.connect_timeout);
+        if let Some(tls) = options.tls.clone() {
+            endpoint = endpoint
+                .tls_config(tls)
+                .map_err(|e| ClientError::InvalidConfig(e.to_string()))?;
+        }
+        let api_key = options
+            .api_key
+            .as_deref()
+            .map(|key| key.parse())
+            .transpose()
+            .map_err(|_| ClientError::InvalidConfig("API key must be printable ASCII".to_string()))?;
+
+        let attempts = options.retry.max_attempts.max(1);
+        let mut attempt = 1;
+        let channel = loop {
+            match endpoint.connect().await {
+                Ok(channel) => break channel,
+                Err(e) if attempt >= attempts => {
+                    return Err(ClientError::Unreachable {
+                        attempts,
+                        message: match std::error::Error::source(&e) {
+                            Some(cause) => format!("{}: {}", e, cause),
+                            None => e.to_string(),
+                        },
+                    });
+                }
+                Err(_) => {
+                    tokio::time::sleep(options.retry.backoff(attempt)).await;
+                    attempt += 1;
+                }
+            }
+        };
+
+        let mut client = ChaumPedersenServiceClient::new(channel.clone())
+            .accept_compressed(CompressionEncoding::Gzip)
+            .accept_compressed(CompressionEncoding::Zstd);
+        let mut client_v2 = ChaumPedersenServiceV2Client::new(channel.clone())
+            .accept_compressed(CompressionEncoding::Gzip)
+            .accept_compressed(CompressionEncoding::Zstd);
+        let mut client_prover = ProverServiceClient::new(channel)
+            .accept_compressed(CompressionEncoding::Gzip)
+            .accept_compressed(CompressionEncoding::Zstd);
+        if let Some(encoding) = options.compression {
+            client = client.send_compressed(encoding);
+            client_v2 = client_v2.send_compressed(encoding);
+            client_prover = client_prover.send_compressed(encoding);
+        }
+
+        let pinned_params = match options.params_pin {
+            ParamsPin::Fingerprint(fingerprint) => Some(fingerprint),
+            ParamsPin::Any | ParamsPin::FirstSeen => None,
+        };
+        Ok(Self {
+            client,
+            client_v2,
+            client_prover,
+            api_key,
+            pinned_params: Mutex::new(pinned_params),
+            options,
+        })
+    }
+
+    // Wraps a message in a request carrying the API key, if one is configured, and the current trace
+    fn request<T>(&self, message: T) -> Request<T> {
+        let mut request = Request::new(message);
+        if let Some(key) = &self.api_key {
+            request.metadata_mut().insert(API_KEY_HEADER, key.clone());
+        }
+        #[cfg(feature = "otel")]
+        crate::telemetry::inject_context(request.metadata_mut());
+        request
+    }
+
+    // Runs one RPC under a deadline. Idempotent RPCs are retried with backoff while the server is unavailable;
+    // anything else gets exactly one attempt.
+    async fn call<T, R, F, Fut>(
+        &self,
+        rpc: &'static str,
+        idempotent: bool,
+        deadline: Duration,
+        message: T,
+        send: F,
+    ) -> Result<R, ClientError>
+    where
+        T: Clone,
+        F: FnMut(ChaumPedersenServiceClient<Channel>, Request<T>) -> Fut,
+        Fut: Future<Output = Result<Response<R>, Status>>,
+    {
+        self.call_on(&self.client, rpc, idempotent, deadline, message, send).await
+    }
+
+    // `call` against either version's generated client
+    async fn call_on<C, T, R, F, Fut>(
+        &self,
+        client: &C,
+        rpc: &'static str,
+        idempotent: bool,
+        deadline: Duration,
+        message: T,
+        mut send: F,
+    ) -> Result<R, ClientError>
+    where
+        C: Clone,
+        T: Clone,
+        F: FnMut(C, Request<T>) -> Fut,
+        Fut: Future<Output = Result<Response<R>, Status>>,
+    {
+        let attempts = if idempotent { self.options.retry.max_attempts.max(1) } else { 1 };
+
+        for attempt in 1..=attempts {
+            // one span per attempt, entered while the request is built so the server joins this trace
+            let span = info_span!("rpc", otel.name = rpc, otel.kind = "client", attempt);
+            let mut request = span.in_scope(|| self.request(message.clone()));
+            request.set_timeout(deadline);
+
+            let sent = tokio::time::timeout(deadline, send(client.clone(), request)).instrument(span);
+            let status = match sent.await {
+                Ok(Ok(response)) => return Ok(response.into_inner()),
+                Ok(Err(status)) => status,
+                Err(_) => Status::deadline_exceeded("client-side deadline elapsed"),
+            };
+
+            let last_attempt = attempt == attempts;
+            match status.code() {
+                // the server's verdict on a late response, which a retry cannot change
+                Code::DeadlineExceeded if status.metadata().contains_key(CHALLENGE_EXPIRED) => {
+                    return Err(ClientError::Rpc { rpc, status });
+                }
+                Code::Unavailable if last_attempt => {
+                    return Err(ClientError::Unreachable {
+                        attempts,
+                        message: status.message().to_string(),
+                    });
+                }
+                Code::DeadlineExceeded if last_attempt => return Err(ClientError::DeadlineExceeded { rpc }),
+                Code::Unavailable | Code::DeadlineExceeded => {
+                    // a server shedding load says how long to stay away, within the policy's bounds
+                    let backoff = self.options.retry.backoff(attempt);
+                    let delay = retry_after(&status).map_or(backoff, |hint| hint.clamp(backoff, self.options.retry.max_backoff));
+                    tokio::time::sleep(delay).await;
+                }
+                _ => return Err(ClientError::Rpc { rpc, status }),
+            }
+        }
+
+        unreachable!("the final attempt always returns")
+    }
+
+    // The bit sizes, groups, message sizes and optional features the server supports and allows
+    #[instrument(skip(self))]
+    pub async fn capabilities(&self) -> Result<zkp::Capabilities, ClientError> {
+        self.call(
+            "GetCapabilities",
+            true,
+            self.options.rpc_deadline,
+            zkp::CapabilitiesRequest {},
+            |mut client, request| async move { client.get_capabilities(request).await },
+        )
+        .await
+    }
+
+    // The versions, groups and hash functions the server supports. Servers that only speak v1
+    // answer with `Code::Unimplemented`.
+    #[instrument(skip(self))]
+    pub async fn server_capabilities(&self) -> Result<zkp::v2::ServerCapabilities, ClientError> {
+        self.call_on(
+            &self.client_v2,
+            "GetServerCapabilities",
+            true,
+            self.options.rpc_deadline,
+            zkp::v2::CapabilitiesRequest {},
+            |mut client, request| async move { client.get_server_capabilities(request).await },
+        )
+        .await
+    }
+
+    // Asks the verifier for fresh parameters and opens a session with Fiat-Shamir challenges
+    pub async fn initialize(&self, bit_size: u32) -> Result<ProtocolSession, ClientError> {
+        self.initialize_with_mode(bit_size, ChallengeMode::FiatShamir).await
+    }
+
+    // Same, with challenges derived in `mode`; fails if the server does not support it
+    #[instrument(skip(self))]
+    pub async fn initialize_with_mode(&self, bit_size: u32, mode: ChallengeMode) -> Result<ProtocolSession, ClientError> {
+        let response = self
+            .call(
+                "InitializeProtocol",
+                true,
+                self.options.initialize_deadline,
+                zkp::InitializeRequest {
+                    bit_size,
+                    group: zkp::GroupKind::GroupModp.into(),
+                    challenge_mode: zkp::ChallengeMode::from(mode).into(),
+                    user_id: String::new(),
+                    recovery: false,
+                    compressed_elements: self.options.compressed_elements,
+                },
+                |mut client, request| async move { client.initialize_protocol(request).await },
+            )
+            .await?;
+
+        let session = self.accept_session(response, mode, CommitmentOrigin::Client)?;
+        if session.params.p().bits() != u64::from(bit_size) {
+            return Err(ClientError::InvalidResponse(format!(
+                "requested {}-bit parameters, got {} bits",
+                bit_size,
+                session.params.p().bits()
+            )));
+        }
+        Ok(session)
+    }
+
+    // Opens a session over the parameters `user_id` registered with. The server checks the run
+    // against the registered commitment, so `send_commitment` must send that one.
+    #[instrument(skip(self))]
+    pub async fn initialize_for_user(&self, user_id: &str, mode: ChallengeMode) -> Result<ProtocolSession, ClientError> {
+        self.open_user_session(user_id, false, mode).await
+    }
+
+    // Opens a session over the parameters of the recovery commitment `user_id` registered, to be
+    // concluded with `recover_account`
+    #[instrument(skip(self))]
+    pub async fn initialize_for_recovery(&self, user_id: &str, mode: ChallengeMode) -> Result<ProtocolSession, ClientError> {
+        self.open_user_session(user_id, true, mode).await
+    }
+
+    async fn open_user_session(&self, user_id: &str, recovery: bool, mode: ChallengeMode) -> Result<ProtocolSession, ClientError> {
+        let response = self
+            .call(
+                "InitializeProtocol",
+                true,
+                self.options.rpc_deadline,
+                zkp::InitializeRequest {
+                    bit_size: 0,
+                    group: zkp::GroupKind::GroupModp.into(),
+                    challenge_mode: zkp::ChallengeMode::from(mode).into(),
+                    user_id: user_id.to_string(),
+                    recovery,
+                    compressed_elements: self.options.compressed_elements,
+                },
+                |mut client, request| async move { client.initialize_protocol(request).await },
+            )
+            .await?;
+        self.accept_session(response, mode, CommitmentOrigin::Registered)
+    }
+
+    // Checks the mode, commitment origin and parameters of a freshly opened modp session
+    fn accept_session(
+        &self,
+        response: zkp::InitializeResponse,
+        mode: ChallengeMode,
+        origin: CommitmentOrigin,
+    ) -> Result<ProtocolSession, ClientError> {
+        // servers from before challenge modes leave the field unset, which reads as Fiat-Shamir
+        if ChallengeMode::from(response.challenge_mode()) != mode {
+            return Err(ClientError::InvalidResponse(format!("server did not open a session with {} challenges", mode)));
+        }
+        // likewise an unset origin reads as client, so only a registered one where none was asked for is wrong
+        if CommitmentOrigin::from(response.commitment_origin()) == CommitmentOrigin::Registered && origin == CommitmentOrigin::Client {
+            return Err(ClientError::InvalidResponse("server claims a commitment this client never sent".to_string()));
+        }
+        if response.compressed_elements && !self.options.compressed_elements {
+            return Err(ClientError::InvalidResponse("server expects compressed elements this client never asked for".to_string()));
+        }
+        let params = response
+            .params
+            .ok_or_else(|| ClientError::InvalidResponse("missing parameters".to_string()))?;
+
+        let params = PublicParameters::try_from(params).map_err(|e| ClientError::InvalidResponse(e.to_string()))?;
+        // never trust the verifier's group: a composite q or a small-order g can leak the secret
+        params.validate_with(&self.options.param_policy).map_err(ClientError::InvalidParameters)?;
+        // older servers leave the fingerprint out; one that sends it must mean the parameters it sent
+        let fingerprint = params.digest();
+        if !response.params_fingerprint.is_empty() && response.params_fingerprint != fingerprint {
+            return Err(ClientError::InvalidResponse("parameter fingerprint does not match the parameters".to_string()));
+        }
+        self.check_pin(fingerprint)?;
+
+        Ok(ProtocolSession {
+            session_id: response.session_id,
+            params,
+            challenge_mode: mode,
+            commitment_origin: origin,
+            compressed_elements: response.compressed_elements,
+        })
+    }
+
+    // Holds a session's parameters to the pin, taking the first set seen under `ParamsPin::FirstSeen`
+    fn check_pin(&self, fingerprint: [u8; 32]) -> Result<(), ClientError> {
+        if self.options.params_pin == ParamsPin::Any {
+            return Ok(());
+        }
+        let mut pinned = self.pinned_params.lock().unwrap();
+        match *pinned {
+            Some(expected) if expected != fingerprint => Err(ClientError::ParametersChanged {
+                expected,
+                actual: fingerprint,
+            }),
+            Some(_) => Ok(()),
+            None => {
+                *pinned = Some(fingerprint);
+                Ok(())
+            }
+        }
+    }
+
+    // The fingerprint sessions are held to, once there is one; store it and pass it back as
+    // `ParamsPin::Fingerprint` to keep trusting the same parameters in a later run
+    pub fn pinned_params(&self) -> Option<[u8; 32]> {
+        *self.pinned_params.lock().unwrap()
+    }
+
+    // Opens a session over a curve group; nothing is generated, so this returns at once. Use
+    // `initialize` for the modp group.
+    #[instrument(skip(self))]
+    pub async fn initialize_group(&self, group: GroupKind) -> Result<GroupSession, ClientError> {
+        if group == GroupKind::Modp {
+            return Err(ClientError::InvalidConfig("modp sessions are opened with initialize".to_string()));
+        }
+        let response = self
+            .call(
+                "InitializeProtocol",
+                true,
+                self.options.rpc_deadline,
+                zkp::InitializeRequest {
+                    bit_size: 0,
+                    group: zkp::GroupKind::from(group).into(),
+                    challenge_mode: zkp::ChallengeMode::ChallengeFiatShamir.into(),
+                    user_id: String::new(),
+                    recovery: false,
+                    compressed_elements: false,
+                },
+                |mut client, request| async move { client.initialize_protocol(request).await },
+            )
+            .await?;
+
+        if response.group() != zkp::GroupKind::from(group) {
+            return Err(ClientError::InvalidResponse(format!("asked for a {} session", group)));
+        }
+        Ok(GroupSession {
+            session_id: response.session_id,
+            group,
+        })
+    }
+
+    // Proves y1 = g^x and y2 = h^x to the verifier in one round. `group` must be the session's group.
+    #[instrument(skip_all, fields(session_id = %session_id))]
+    pub async fn prove_dleq<G: Group>(
+        &self,
+        session_id: &str,
+        group: G,
+        statement: &DleqStatement<G::Element>,
+        x: &G::Scalar,
+    ) -> Result<Verification, ClientError> {
+        let dleq = Dleq::new(group);
+        let proof = dleq.prove_for_session(session_id, statement, x);
+        let request = zkp::DleqProofRequest {
+            session_id: session_id.to_string(),
+            proof: Some(encode_dleq(&dleq.group, statement, &proof)),
+        };
+        let response = self
+            .call(
+                "VerifyDleqProof",
+                false,
+                self.options.rpc_deadline,
+                request,
+                |mut client, request| async move { client.verify_dleq_proof(request).await },
+            )
+            .await?;
+
+        Ok(Verification {
+            verified: response.verified,
+            message: response.message,
+        })
+    }
+
+    // Forwards a proof in the (c, s) encoding of dalek-based crates, e.g. one received from a client
+    // of such a crate, for checking over the session's group. `group` must be the session's group,
+    // and `domain` must end with the session id, e.g. `interop::session_domain(app, session_id)`.
+    #[instrument(skip_all, fields(session_id = %session_id))]
+    pub async fn verify_compact_dleq<G: Group>(
+        &self,
+        session_id: &str,
+        group: G,
+        domain: &[u8],
+        statement: &DleqStatement<G::Element>,
+        proof: &CompactDleqProof<G>,
+    ) -> Result<Verification, ClientError> {
+        let request = encode_compact_dleq(&group, session_id, domain, statement, proof);
+        let response = self
+            .call(
+                "VerifyCompactDleqProof",
+                false,
+                self.options.rpc_deadline,
+                request,
+                |mut client, request| async move { client.verify_compact_dleq_proof(request).await },
+            )
+            .await?;
+
+        Ok(Verification {
+            verified: response.verified,
+            message: response.message,
+        })
+    }
+
+    // The server's VOPRF public key over `kind`, whose implementation `group` is. Fetch it once and
+    // keep it: an evaluation is only as trustworthy as the key it is checked against.
+    #[instrument(skip(self, group))]
+    pub async fn oprf_public_key<G: Group>(&self, kind: GroupKind, group: &G) -> Result<G::Element, ClientError> {
+        let response = self
+            .call(
+                "GetOprfPublicKey",
+                true,
+                self.options.rpc_deadline,
+                zkp::OprfPublicKeyRequest {
+                    group: zkp::GroupKind::from(kind).into(),
+                },
+                |mut client, request| async move { client.get_oprf_public_key(request).await },
+            )
+            .await?;
+
+        group
+            .decode_element(&response.public_key)
+            .ok_or_else(|| ClientError::InvalidResponse("public key is not an element of the group".to_string()))
+    }
+
+    // The PRF outputs for `inputs` under the server's key, with the inputs blinded on the way and
+    // every evaluation checked against `pk`
+    #[instrument(skip_all, fields(inputs = inputs.len()))]
+    pub async fn evaluate_oprf<G: Group + Clone>(
+        &self,
+        kind: GroupKind,
+        group: G,
+        pk: &G::Element,
+        inputs: &[&[u8]],
+    ) -> Result<Vec<[u8; 32]>, ClientError> {
+        let blinds: Vec<_> = inputs.iter().map(|input| voprf::blind(&group, input)).collect();
+        let request = zkp::OprfEvaluationRequest {
+            group: zkp::GroupKind::from(kind).into(),
+            blinded_elements: blinds.iter().map(|(_, blinded)| group.encode_element(blinded)).collect(),
+        };
+        let response = self
+            .call(
+                "EvaluateOprf",
+                true,
+                self.options.rpc_deadline,
+                request,
+                |mut client, request| async move { client.evaluate_oprf(request).await },
+            )
+            .await?;
+
+        if response.evaluations.len() != inputs.len() {
+            return Err(ClientError::InvalidResponse(format!(
+                "{} evaluations for {} inputs",
+                response.evaluations.len(),
+                inputs.len()
+            )));
+        }
+        inputs
+            .iter()
+            .zip(&blinds)
+            .zip(&response.evaluations)
+            .map(|((input, (blind, blinded)), evaluation)| {
+                let (statement, proof) = decode_dleq(&group, evaluation)
+                    .map_err(|e| ClientError::InvalidResponse(e.to_string()))?;
+                voprf::finalize(&group, pk, input, blind, blinded, &statement.y2, &proof)
+                    .ok_or_else(|| ClientError::InvalidResponse("evaluation proof failed to verify".to_string()))
+            })
+            .collect()
+    }
+
+    // Proves that the share g^a and base^a use the same a, bound to this session. Returns the share
+    // along with the verdict.
+    #[instrument(skip_all, fields(session_id = %session_id))]
+    pub async fn prove_dh_share<G: Group + Clone>(
+        &self,
+        session_id: &str,
+        group: &G,
+        base: &G::Element,
+        a: &G::Scalar,
+    ) -> Result<(DhShare<G::Element>, Verification), ClientError> {
+        let (share, proof) = key_exchange::prove_share(group, base, a, session_id.as_bytes());
+        let request = zkp::DhShareRequest {
+            session_id: session_id.to_string(),
+            proof: Some(encode_dh_share(group, &share, &proof)),
+        };
+        let response = self
+            .call(
+                "VerifyDhShare",
+                false,
+                self.options.rpc_deadline,
+                request,
+                |mut client, request| async move { client.verify_dh_share(request).await },
+            )
+            .await?;
+
+        let verification = Verification {
+            verified: response.verified,
+            message: response.message,
+        };
+        Ok((share, verification))
+    }
+
+    // Proves that `commitment` opens to a value below 2^bits, over the session's parameters. Fails with
+    // InvalidConfig if the value is out of range or the parameters have no second generator.
+    #[instrument(skip_all, fields(session_id = %session.session_id))]
+    pub async fn prove_range(
+        &self,
+        session: &ProtocolSession,
+        commitment: &PedersenCommitment,
+        opening: &Opening,
+        bits: u32,
+    ) -> Result<Verification, ClientError> {
+        let pedersen = Pedersen::new(session.params.clone())
+            .ok_or_else(|| ClientError::InvalidConfig("parameters have no second generator".to_string()))?;
+        let proof = range::prove_for_session(&pedersen, &session.session_id, commitment, opening, bits)
+            .ok_or_else(|| ClientError::InvalidConfig(format!("value does not fit in {} bits", bits)))?;
+
+        let request = zkp::RangeProofRequest {
+            session_id: session.session_id.clone(),
+            proof: Some(encode_range(commitment, bits, &proof)),
+        };
+        let response = self
+            .call(
+                "VerifyRangeProof",
+                false,
+                self.options.rpc_deadline,
+                request,
+                |mut client, request| async move { client.verify_range_proof(request).await },
+            )
+            .await?;
+
+        Ok(Verification {
+            verified: response.verified,
+            message: response.message,
+        })
+    }
+
+    // Sends the commitment and challenge values (y1, y2) and returns the verifier's challenge
+    #[instrument(skip_all, fields(session_id = %session.session_id))]
+    pub async fn send_commitment(
+        &self,
+        session: &ProtocolSession,
+        commitment: &Commitment,
+        y1: &BigUint,
+        y2: &BigUint,
+    ) -> Result<BigUint, ClientError> {
+        let request = commitment_request(session, commitment, y1, y2);
+
+        // the server hands out one challenge per (y1, y2), so resending the same commitment yields the same challenge
+        let response = self
+            .call(
+                "SendCommitment",
+                true,
+                self.options.rpc_deadline,
+                request,
+                |mut client, request| async move { client.send_commitment(request).await },
+            )
+            .await?;
+
+        decode_integer("challenge", &response.challenge).map_err(|e| ClientError::InvalidResponse(e.to_string()))
+    }
+
+    // Sends the response z and returns the verifier's decision. This ends the session either way.
+    #[instrument(skip_all, fields(session_id = %session.session_id))]
+    pub async fn prove(&self, session: &ProtocolSession, z: &BigUint) -> Result<Verification, ClientError> {
+        let request = zkp::VerifyProofRequest {
+            session_id: session.session_id.clone(),
+            z: encode_integer(z),
+        };
+
+        // the server consumes the session on verification, so a retry could never succeed
+        let response = self
+            .call(
+                "VerifyProof",
+                false,
+                self.options.rpc_deadline,
+                request,
+                |mut client, request| async move { client.verify_proof(request).await },
+            )
+            .await?;
+
+        Ok(Verification {
+            verified: response.verified,
+            message: response.message,
+        })
+    }
+
+    // Sends a whole non-interactive proof in place of `send_commitment` and `prove`. The proof must be
+    // over the session's parameters and bound to the session, i.e. from
+    // `Prover::create_session_proof(&session.session_id)` on a `Prover` built on `session.params`.
+    #[instrument(skip_all, fields(session_id = %session.session_id))]
+    pub async fn prove_one_shot(&self, session: &ProtocolSession, proof: &ZKProof) -> Result<Verification, ClientError> {
+        let request = zkp::OneShotProofRequest {
+            session_id: session.session_id.clone(),
+            proof: Some(zkp::ZkProofMessage::from(proof)),
+        };
+
+        // a resent proof gets the verdict already given, so this is safe to retry
+        let response = self
+            .call(
+                "ProveOneShot",
+                true,
+                self.options.rpc_deadline,
+                request,
+                |mut client, request| async move { client.prove_one_shot(request).await },
+            )
+            .await?;
+
+        Ok(Verification {
+            verified: response.verified,
+            message: response.message,
+        })
+    }
+
+    // Sends many finished proofs over the session's parameters as one message, each made with
+    // `Prover::create_session_proof(&session.session_id)`. They pass or fail together, and the session
+    // ends if they pass.
+    #[instrument(skip_all, fields(session_id = %session.session_id, proofs = proofs.len()))]
+    pub async fn prove_batch(&self, session: &ProtocolSession, proofs: &[ZKProof]) -> Result<Verification, ClientError> {
+        let request = zkp::ProofBatchRequest {
+            session_id: session.session_id.clone(),
+            proof: Some(zkp::ProofBatch::from(&batch::batch(proofs))),
+        };
+        let response = self
+            .call(
+                "VerifyProofBatch",
+                false,
+                self.options.rpc_deadline,
+                request,
+                |mut client, request| async move { client.verify_proof_batch(request).await },
+            )
+            .await?;
+
+        Ok(Verification {
+            verified: response.verified,
+            message: response.message,
+        })
+    }
+
+    // Adds a statement to a multi-proof session, returning its index and challenge. Answer it with
+    // `answer_statement`, then check them all with `finalize`.
+    #[instrument(skip_all, fields(session_id = %session.session_id))]
+    pub async fn add_statement(
+        &self,
+        session: &ProtocolSession,
+        commitment: &Commitment,
+        y1: &BigUint,
+        y2: &BigUint,
+    ) -> Result<(u32, BigUint), ClientError> {
+        let request = commitment_request(session, commitment, y1, y2);
+
+        // a resend would add the statement a second time
+        let response = self
+            .call(
+                "AddStatement",
+                false,
+                self.options.rpc_deadline,
+                request,
+                |mut client, request| async move { client.add_statement(request).await },
+            )
+            .await?;
+
+        let challenge = decode_integer("challenge", &response.challenge)
+            .map_err(|e| ClientError::InvalidResponse(e.to_string()))?;
+        Ok((response.index, challenge))
+    }
+
+    #[instrument(skip_all, fields(session_id = %session.session_id))]
+    pub async fn answer_statement(&self, session: &ProtocolSession, index: u32, z: &BigUint) -> Result<(), ClientError> {
+        let request = zkp::StatementAnswer {
+            session_id: session.session_id.clone(),
+            index,
+            z: z.to_bytes_be(),
+        };
+        self.call(
+            "AnswerStatement",
+            false,
+            self.options.rpc_deadline,
+            request,
+            |mut client, request| async move { client.answer_statement(request).await },
+        )
+        .await?;
+        Ok(())
+    }
+
+    // Has the server check every statement of the session, which ends it
+    #[instrument(skip_all, fields(session_id = %session.session_id))]
+    pub async fn finalize(&self, session: &ProtocolSession) -> Result<zkp::FinalizeResponse, ClientError> {
+        let request = zkp::FinalizeRequest {
+            session_id: session.session_id.clone(),
+        };
+        self.call(
+            "FinalizeSession",
+            false,
+            self.options.rpc_deadline,
+            request,
+            |mut client, request| async move { client.finalize_session(request).await },
+        )
+        .await
+    }
+
+    // Sends one party's share of a joint proof. Returns the joint challenge if this was the last
+    // party to commit; the others fetch it with `threshold_challenge`.
+    #[instrument(skip_all, fields(session_id = %session.session_id, party = party))]
+    pub async fn submit_partial_commitment(
+        &self,
+        session: &ProtocolSession,
+        party: u32,
+        parties: u32,
+        b1: &BigUint,
+        commitment: &PartialCommitment,
+    ) -> Result<Option<BigUint>, ClientError> {
+        let request = zkp::PartialCommitmentRequest {
+            session_id: session.session_id.clone(),
+            party,
+            parties,
+            b1: b1.to_bytes_be(),
+            a1: commitment.share.a1.to_bytes_be(),
+            c1: commitment.share.c1.to_bytes_be(),
+            challenge_values: Some(zkp::ProofChallenge::from(&commitment.values)),
+        };
+
+        // a resend is refused as a duplicate commitment
+        let response = self
+            .call(
+                "SubmitPartialCommitment",
+                false,
+                self.options.rpc_deadline,
+                request,
+                |mut client, request| async move { client.submit_partial_commitment(request).await },
+            )
+            .await?;
+
+        if response.challenge.is_empty() {
+            return Ok(None);
+        }
+        decode_integer("challenge", &response.challenge)
+            .map(Some)
+            .map_err(|e| ClientError::InvalidResponse(e.to_string()))
+    }
+
+    // The joint challenge; fails with FAILED_PRECONDITION until every party has committed
+    #[instrument(skip_all, fields(session_id = %session.session_id))]
+    pub async fn threshold_challenge(&self, session: &ProtocolSession) -> Result<BigUint, ClientError> {
+        let request = zkp::ThresholdChallengeRequest {
+            session_id: session.session_id.clone(),
+        };
+        let response = self
+            .call(
+                "GetThresholdChallenge",
+                true,
+                self.options.rpc_deadline,
+                request,
+                |mut client, request| async move { client.get_threshold_challenge(request).await },
+            )
+            .await?;
+
+        decode_integer("challenge", &response.challenge).map_err(|e| ClientError::InvalidResponse(e.to_string()))
+    }
+
+    // Sends one party's response; the reply carries the assembled proof once every party has answered
+    #[instrument(skip_all, fields(session_id = %session.session_id, party = party))]
+    pub async fn submit_partial_response(
+        &self,
+        session: &ProtocolSession,
+        party: u32,
+        response: &ProofResponse,
+    ) -> Result<zkp::PartialResponseResult, ClientError> {
+        let request = zkp::PartialResponseRequest {
+            session_id: session.session_id.clone(),
+            party,
+            z: response.z.to_bytes_be(),
+        };
+        self.call(
+            "SubmitPartialResponse",
+            false,
+            self.options.rpc_deadline,
+            request,
+            |mut client, request| async move { client.submit_partial_response(request).await },
+        )
+        .await
+    }
+
+    // Fetches the server-signed transcript of a session whose proof has been checked; open it with
+    // `evidence::open` to verify the signature
+    #[instrument(skip_all, fields(session_id = %session.session_id))]
+    pub async fn transcript(&self, session: &ProtocolSession) -> Result<zkp::SignedTranscript, ClientError> {
+        let request = zkp::TranscriptRequest {
+            session_id: session.session_id.clone(),
+        };
+        self.call(
+            "GetSessionTranscript",
+            true,
+            self.options.rpc_deadline,
+            request,
+            |mut client, request| async move { client.get_session_transcript(request).await },
+        )
+        .await
+    }
+
+    // Proves knowledge of x with y = g^x interactively over an open session
+    #[instrument(skip_all, fields(session_id = %session.session_id))]
+    pub async fn prove_schnorr(&self, session: &ProtocolSession, x: &BigUint) -> Result<Verification, ClientError> {
+        let schnorr = Schnorr::new(session.params.clone());
+        let (statement, commitment, k) = {
+            let (schnorr, x) = (schnorr.clone(), x.clone());
+            run_blocking("schnorr_commit", move || {
+                let params = &schnorr.params;
+                let statement = SchnorrStatement {
+                    y: secret_fixed_pow(params.g_table(), &x, params.q()),
+                };
+                let (commitment, k) = schnorr.commit(&statement, &x, &mut OsRng);
+                (statement, commitment, k)
+            })
+            .await
+        };
+
+        let request = zkp::SchnorrCommitmentRequest {
+            session_id: session.session_id.clone(),
+            y: statement.y.to_bytes_be(),
+            t: commitment.t.to_bytes_be(),
+        };
+        // the challenge is derived from the session id and (y, t), so a resend yields the same challenge
+        let response = self
+            .call(
+                "SendSchnorrCommitment",
+                true,
+                self.options.rpc_deadline,
+                request,
+                |mut client, request| async move { client.send_schnorr_commitment(request).await },
+            )
+            .await?;
+        let challenge = decode_integer("challenge", &response.challenge)
+            .map_err(|e| ClientError::InvalidResponse(e.to_string()))?;
+
+        let request = zkp::SchnorrVerifyRequest {
+            session_id: session.session_id.clone(),
+            s: schnorr.respond(x, k, &challenge).s.to_bytes_be(),
+        };
+        let response = self
+            .call(
+                "VerifySchnorrProof",
+                false,
+                self.options.rpc_deadline,
+                request,
+                |mut client, request| async move { client.verify_schnorr_proof(request).await },
+            )
+            .await?;
+
+        Ok(Verification {
+            verified: response.verified,
+            message: response.message,
+        })
+    }
+
+    // Finishes a run from its state: sends the commitment unless the server already issued a
+    // challenge for it, then the response. Every step is safe to repeat, so a run interrupted
+    // anywhere, even after the response was sent, completes with the server's verdict.
+    #[instrument(skip_all, fields(session_id = %state.session.session_id))]
+    pub async fn complete(&self, state: &ProtocolState) -> Result<Verification, ClientError> {
+        let session = &state.session;
+        let resumed = self
+            .call(
+                "ResumeSession",
+                true,
+                self.options.rpc_deadline,
+                zkp::ResumeRequest {
+                    session_id: session.session_id.clone(),
+                },
+                |mut client, request| async move { client.resume_session(request).await },
+            )
+            .await?;
+        if resumed.params_digest != session.params.digest() {
+            return Err(ClientError::InvalidResponse("session runs over different parameters".to_string()));
+        }
+
+        let challenge = if resumed.challenge.is_empty() {
+            let ProofChallenge { y1, y2 } = &state.challenge_values;
+            self.send_commitment(session, &state.commitment, y1, y2).await?
+        } else {
+            decode_integer("challenge", &resumed.challenge).map_err(|e| ClientError::InvalidResponse(e.to_string()))?
+        };
+        let z = state.prover.generate_response(&state.nonce, &challenge).z;
+        self.prove(session, &z).await
+    }
+
+    // Registers `commitment`, made over the session's parameters, as the user's. Fails with
+    // `AlreadyExists` if the user has a registration that was not revoked.
+    #[instrument(skip(self, session, commitment), fields(session_id = %session.session_id))]
+    pub async fn register(
+        &self,
+        session: &ProtocolSession,
+        user_id: &str,
+        commitment: &Commitment,
+    ) -> Result<zkp::Registration, ClientError> {
+        self.call(
+            "RegisterCommitment",
+            false,
+            self.options.rpc_deadline,
+            register_request(session, user_id, commitment, None),
+            |mut client, request| async move { client.register_commitment(request).await },
+        )
+        .await
+    }
+
+    // Same, also escrowing `recovery_commitment` over the same parameters. Its secret, kept apart
+    // from the primary one, can later re-bind the account through `recover_account`.
+    #[instrument(skip(self, session, commitment, recovery_commitment), fields(session_id = %session.session_id))]
+    pub async fn register_with_recovery(
+        &self,
+        session: &ProtocolSession,
+        user_id: &str,
+        commitment: &Commitment,
+        recovery_commitment: &Commitment,
+    ) -> Result<zkp::Registration, ClientError> {
+        self.call(
+            "RegisterCommitment",
+            false,
+            self.options.rpc_deadline,
+            register_request(session, user_id, commitment, Some(recovery_commitment)),
+            |mut client, request| async move { client.register_commitment(request).await },
+        )
+        .await
+    }
+
+    // Replaces the user's registered commitment with one made over the session's parameters; the
+    // client must present operator credentials
+    #[instrument(skip(self, session, commitment), fields(session_id = %session.session_id))]
+    pub async fn rotate_registration(
+        &self,
+        session: &ProtocolSession,
+        user_id: &str,
+        commitment: &Commitment,
+    ) -> Result<zkp::Registration, ClientError> {
+        self.call(
+            "RotateCommitment",
+            true,
+            self.options.rpc_deadline,
+            register_request(session, user_id, commitment, None),
+            |mut client, request| async move { client.rotate_commitment(request).await },
+        )
+        .await
+    }
+
+    // Answers the challenge of a registered user's session with `z` and, if that proves the
+    // registered secret, registers `new_commitment` over `new_session`'s parameters in its place.
+    // `new_session` may be `session` itself. Not retried: a lost response is checked with `registration`.
+    #[instrument(skip_all, fields(session_id = %session.session_id))]
+    pub async fn rotate_secret(
+        &self,
+        session: &ProtocolSession,
+        z: &BigUint,
+        new_session: &ProtocolSession,
+        new_commitment: &Commitment,
+    ) -> Result<zkp::RotateSecretResponse, ClientError> {
+        self.call(
+            "RotateSecret",
+            false,
+            self.options.rpc_deadline,
+            rotate_secret_request(session, z, new_session, new_commitment, None),
+            |mut client, request| async move { client.rotate_secret(request).await },
+        )
+        .await
+    }
+
+    // `rotate_secret` that also replaces the recovery commitment with `new_recovery_commitment`,
+    // made over `new_session`'s parameters from a separate secret
+    #[instrument(skip_all, fields(session_id = %session.session_id))]
+    pub async fn rotate_secret_and_recovery(
+        &self,
+        session: &ProtocolSession,
+        z: &BigUint,
+        new_session: &ProtocolSession,
+        new_commitment: &Commitment,
+        new_recovery_commitment: &Commitment,
+    ) -> Result<zkp::RotateSecretResponse, ClientError> {
+        self.call(
+            "RotateSecret",
+            false,
+            self.options.rpc_deadline,
+            rotate_secret_request(session, z, new_session, new_commitment, Some(new_recovery_commitment)),
+            |mut client, request| async move { client.rotate_secret(request).await },
+        )
+        .await
+    }
+
+    // `rotate_secret` for a session from `initialize_for_recovery`: `z` proves the recovery secret,
+    // and `new_commitment` becomes the primary one. The recovery commitment stays registered.
+    #[instrument(skip_all, fields(session_id = %session.session_id))]
+    pub async fn recover_account(
+        &self,
+        session: &ProtocolSession,
+        z: &BigUint,
+        new_session: &ProtocolSession,
+        new_commitment: &Commitment,
+    ) -> Result<zkp::RotateSecretResponse, ClientError> {
+        self.call(
+            "RecoverAccount",
+            false,
+            self.options.rpc_deadline,
+            rotate_secret_request(session, z, new_session, new_commitment, None),
+            |mut client, request| async move { client.recover_account(request).await },
+        )
+        .await
+    }
+
+    // Needs operator credentials, as does registering the user again afterwards
+    #[instrument(skip(self))]
+    pub async fn revoke_registration(&self, user_id: &str) -> Result<zkp::Registration, ClientError> {
+        self.call(
+            "RevokeCommitment",
+            true,
+            self.options.rpc_deadline,
+            zkp::UserRequest {
+                user_id: user_id.to_string(),
+            },
+            |mut client, request| async move { client.revoke_commitment(request).await },
+        )
+        .await
+    }
+
+    #[instrument(skip(self))]
+    pub async fn registration(&self, user_id: &str) -> Result<zkp::Registration, ClientError> {
+        self.call(
+            "GetRegistration",
+            true,
+            self.options.rpc_deadline,
+            zkp::UserRequest {
+                user_id: user_id.to_string(),
+            },
+            |mut client, request| async move { client.get_registration(request).await },
+        )
+        .await
+    }
+
+    // The stage and timestamps of any session, for debugging a run that seems stuck
+    #[instrument(skip_all, fields(session_id = %session_id))]
+    pub async fn session_status(&self, session_id: &str) -> Result<zkp::SessionStatus, ClientError> {
+        self.call(
+            "GetSessionStatus",
+            true,
+            self.options.rpc_deadline,
+            zkp::SessionStatusRequest {
+                session_id: session_id.to_string(),
+            },
+            |mut client, request| async move { client.get_session_status(request).await },
+        )
+        .await
+    }
+
+    // Tells the server this session will not be completed, so it can drop its state now
+    #[instrument(skip_all, fields(session_id = %session_id))]
+    pub async fn cancel(&self, session_id: &str) -> Result<(), ClientError> {
+        self.call(
+            "CancelSession",
+            true,
+            self.options.rpc_deadline,
+            zkp::CancelRequest {
+                session_id: session_id.to_string(),
+            },
+            |mut client, request| async move { client.cancel_session(request).await },
+        )
+        .await?;
+        Ok(())
+    }
+
+    // Completes a run saved with `ProtocolState::save` under `key`
+    pub async fn resume(&self, saved: &[u8], key: &[u8; 32]) -> Result<Verification, ClientError> {
+        let state = ProtocolState::load(saved, key).map_err(ClientError::State)?;
+        self.complete(&state).await
+    }
+
+    // Runs the whole interactive protocol with freshly generated secrets
+    pub async fn run_protocol(&self, bit_size: u32) -> Result<Verification, ClientError> {
+        self.run_protocol_with(bit_size, |params| Ok(SoftwareBackend::generate(params)))
+            .await
+    }
+
+    // Same, with the secrets held by a backend that `backend_for` sets up for the session's
+    // parameters, e.g. a Pkcs11Backend generating them on a token
+    #[instrument(skip(self, backend_for))]
+    pub async fn run_protocol_with<B, F>(&self, bit_size: u32, backend_for: F) -> Result<Verification, ClientError>
+    where
+        B: SecretBackend + 'static,
+        F: FnOnce(PublicParameters) -> Result<B, BackendError> + Send + 'static,
+    {
+        let session = self.initialize(bit_size).await?;
+
+        let params = session.params.clone();
+        let (backend, commitment, ProofChallenge { y1, y2 }, nonce) = run_blocking("commit", move || {
+            let backend = backend_for(params)?;
+            let commitment = backend.commitment()?;
+            let (values, nonce) = backend.commit_nonce(&commitment.b1)?;
+            Ok((backend, commitment, values, nonce))
+        })
+        .await
+        .map_err(ClientError::Backend)?;
+
+        let challenge = self.send_commitment(&session, &commitment, &y1, &y2).await?;
+        let z = run_blocking("respond", move || backend.respond(nonce, &challenge))
+            .await
+            .map_err(ClientError::Backend)?;
+
+        self.prove(&session, &z).await
+    }
+
+    // Prover role: the RPCs below go to a prover daemon's ProverService, not to a verifier
+
+    // Has the prover generate secrets for `key_id` over `params` and returns their commitment.
+    // `AlreadyExists` if the key id is taken.
+    #[instrument(skip(self, params))]
+    pub async fn create_remote_key(&self, key_id: &str, params: &PublicParameters) -> Result<RemoteKey, ClientError> {
+        let info = self
+            .call_on(
+                &self.client_prover,
+                "CreateKey",
+                false,
+                self.options.rpc_deadline,
+                zkp::prover::CreateKeyRequest {
+                    key_id: key_id.to_string(),
+                    params: Some(zkp::PublicParameters::from(params)),
+                },
+                |mut client, request| async move { client.create_key(request).await },
+            )
+            .await?;
+        remote_key(info)
+    }
+
+    #[instrument(skip(self))]
+    pub async fn remote_key(&self, key_id: &str) -> Result<RemoteKey, ClientError> {
+        let info = self
+            .call_on(
+                &self.client_prover,
+                "GetKey",
+                true,
+                self.options.rpc_deadline,
+                zkp::prover::KeyRequest {
+                    key_id: key_id.to_string(),
+                },
+                |mut client, request| async move { client.get_key(request).await },
+            )
+            .await?;
+        remote_key(info)
+    }
+
+    #[instrument(skip(self))]
+    pub async fn remote_keys(&self) -> Result<Vec<String>, ClientError> {
+        let list = self
+            .call_on(
+                &self.client_prover,
+                "ListKeys",
+                true,
+                self.options.rpc_deadline,
+                zkp::prover::ListKeysRequest {},
+                |mut client, request| async move { client.list_keys(request).await },
+            )
+            .await?;
+        Ok(list.key_ids)
+    }
+
+    // Has the prover draw a nonce for `key_id`; only (y1, y2) come back
+    #[instrument(skip(self))]
+    pub async fn remote_commit(&self, key_id: &str) -> Result<RemoteNonce, ClientError> {
+        let commitment = self
+            .call_on(
+                &self.client_prover,
+                "Commit",
+                false,
+                self.options.rpc_deadline,
+                zkp::prover::KeyRequest {
+                    key_id: key_id.to_string(),
+                },
+                |mut client, request| async move { client.commit(request).await },
+            )
+            .await?;
+        let values = commitment
+            .challenge_values
+            .ok_or_else(|| ClientError::InvalidResponse("missing challenge values".to_string()))?;
+        Ok(RemoteNonce {
+            key_id: key_id.to_string(),
+            nonce_id: commitment.nonce_id,
+            values: ProofChallenge::try_from(values).map_err(|e| ClientError::InvalidResponse(e.to_string()))?,
+        })
+    }
+
+    // The prover's z for `challenge`. Consumes the nonce, so it is never retried.
+    #[instrument(skip_all, fields(key_id = %nonce.key_id))]
+    pub async fn remote_respond(&self, nonce: &RemoteNonce, challenge: &BigUint) -> Result<BigUint, ClientError> {
+        let response = self
+            .call_on(
+                &self.client_prover,
+                "Respond",
+                false,
+                self.options.rpc_deadline,
+                zkp::prover::RespondRequest {
+                    key_id: nonce.key_id.clone(),
+                    nonce_id: nonce.nonce_id.clone(),
+                    challenge: challenge.to_bytes_be(),
+                },
+                |mut client, request| async move { client.respond(request).await },
+            )
+            .await?;
+        decode_integer("z", &response.z).map_err(|e| ClientError::InvalidResponse(e.to_string()))
+    }
+
+    // A whole standalone non-interactive proof made by the prover. It is bound to no session, so
+    // `prove_one_shot` refuses it; sign in with `run_protocol_remote` instead.
+    #[instrument(skip(self))]
+    pub async fn remote_proof(&self, key_id: &str) -> Result<ZKProof, ClientError> {
+        let proof = self
+            .call_on(
+                &self.client_prover,
+                "CreateProof",
+                false,
+                self.options.rpc_deadline,
+                zkp::prover::KeyRequest {
+                    key_id: key_id.to_string(),
+                },
+                |mut client, request| async move { client.create_proof(request).await },
+            )
+            .await?;
+        ZKProof::try_from(proof).map_err(|e| ClientError::InvalidResponse(e.to_string()))
+    }
+
+    // Signs `user_id` in to this verifier with the key `prover` holds for them: the verifier's
+    // challenge is answered by the prover daemon, and no secret passes through this process
+    #[instrument(skip(self, prover))]
+    pub async fn run_protocol_remote(
+        &self,
+        prover: &ChaumPedersenClient,
+        user_id: &str,
+        key_id: &str,
+    ) -> Result<Verification, ClientError> {
+        let session = self.initialize_for_user(user_id, ChallengeMode::FiatShamir).await?;
+        let key = prover.remote_key(key_id).await?;
+        if key.params.digest() != session.params.digest() {
+            return Err(ClientError::InvalidConfig(format!(
+                "key {} is not over the parameters {} registered with",
+                key_id, user_id
+            )));
+        }
+
+        let nonce = prover.remote_commit(key_id).await?;
+        let challenge = self
+            .send_commitment(&session, &key.commitment, &nonce.values.y1, &nonce.values.y2)
+            .await?;
+        let z = prover.remote_respond(&nonce, &challenge).await?;
+        self.prove(&session, &z).await
+    }
+}
+
+fn remote_key(info: zkp::prover::KeyInfo) -> Result<RemoteKey, ClientError> {
+    let invalid = |e: crate::convert::ConversionError| ClientError::InvalidResponse(e.to_string());
+    let params = info
+        .params
+        .ok_or_else(|| ClientError::InvalidResponse("missing parameters".to_string()))?;
+    let commitment = info
+        .commitment
+        .ok_or_else(|| ClientError::InvalidResponse("missing commitment".to_string()))?;
+    Ok(RemoteKey {
+        key_id: info.key_id,
+        params: PublicParameters::try_from(params).map_err(invalid)?,
+        commitment: Commitment::try_from(commitment).map_err(invalid)?,
+    })
+}
+
+// The retry-after hint, in seconds, of a server refusing new sessions under load
+fn retry_after(status: &Status) -> Option<Duration> {
+    let secs = status.metadata().get("retry-after")?.to_str().ok()?.parse().ok()?;
+    Some(Duration::from_secs(secs))
+}
+
+// Keeps the prover's modular exponentiations off the async executor, in a span named after `phase`
+async fn run_blocking<T: Send + 'static>(phase: &'static str, work: impl FnOnce() -> T + Send + 'static) -> T {
+    let span = info_span!("crypto", otel.name = phase);
+    tokio::task::spawn_blocking(move || span.in_scope(work))
+        .await
+        .unwrap_or_else(|e| std::panic::resume_unwind(e.into_panic()))
+}
+
+fn register_request(
+    session: &ProtocolSession,
+    user_id: &str,
+    commitment: &Commitment,
+    recovery_commitment: Option<&Commitment>,
+) -> zkp::RegisterRequest {
+    zkp::RegisterRequest {
+        user_id: user_id.to_string(),
+        session_id: session.session_id.clone(),
+        commitment: Some(zkp::Commitment::from(commitment)),
+        recovery_commitment: recovery_commitment.map(zkp::Commitment::from),
+    }
+}
+
+fn rotate_secret_request(
+    session: &ProtocolSession,
+    z: &BigUint,
+    new_session: &ProtocolSession,
+    new_commitment: &Commitment,
+    new_recovery_commitment: Option<&Commitment>,
+) -> zkp::RotateSecretRequest {
+    zkp::RotateSecretRequest {
+        session_id: session.session_id.clone(),
+        z: z.to_bytes_be(),
+        new_commitment: Some(zkp::Commitment::from(new_commitment)),
+        params_session_id: new_session.session_id.clone(),
+        new_recovery_commitment: new_recovery_commitment.map(zkp::Commitment::from),
+    }
+}
+
+// The request for SendCommitment and AddStatement, compressed if the session asked for it
+fn commitment_request(session: &ProtocolSession, commitment: &Commitment, y1: &BigUint, y2: &BigUint) -> zkp::CommitmentRequest {
+    let params = &session.params;
+    let (commitment, y1, y2) = match session.compressed_elements {
+        true => (compress_commitment(commitment, params), params.compress_element(y1), params.compress_element(y2)),
+        false => (zkp::Commitment::from(commitment), y1.clone(), y2.clone()),
+    };
+    zkp::CommitmentRequest {
+        session_id: session.session_id.clone(),
+        commitment: Some(commitment),
+        challenge_values: Some(zkp::ProofChallenge {
+            y1: encode_integer(&y1),
+            y2: encode_integer(&y2),
+        }),
+    }
+}
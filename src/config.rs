@@ -0,0 +1,800 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer};
+
+// Prefix of the environment variable for every config key, e.g. ZKP_LISTEN_ADDR
+pub const ENV_PREFIX: &str = "ZKP_";
+// and of the prover daemon's keys, e.g. ZKP_PROVER_LISTEN_ADDR, so both can share an environment
+pub const PROVER_ENV_PREFIX: &str = "ZKP_PROVER_";
+
+// Smallest and largest bit sizes an operator may allow at all
+const HARD_MIN_BITS: u32 = 128;
+const HARD_MAX_BITS: u32 = 8192;
+
+// A message limit below this would refuse ordinary requests; HTTP/2 caps flow-control windows at 2^31 - 1
+const MIN_MESSAGE_SIZE: usize = 64 * 1024;
+const MAX_WINDOW_SIZE: u32 = (1 << 31) - 1;
+
+const LOG_LEVELS: [&str; 5] = ["error", "warn", "info", "debug", "trace"];
+
+pub const COMPRESSION_ENCODINGS: [&str; 2] = ["gzip", "zstd"];
+pub const WEBHOOK_EVENTS: [&str; 3] = ["all", "success", "failure"];
+pub const SECRET_BACKENDS: [&str; 2] = ["software", "pkcs11"];
+
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ConfigError {
+    Io(PathBuf, std::io::Error),
+    Parse(PathBuf, toml::de::Error),
+    UnknownKey(String),
+    MissingValue(String),
+    InvalidValue { key: String, value: String, reason: String },
+    Invalid(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(path, e) => write!(f, "cannot read config file {}: {}", path.display(), e),
+            ConfigError::Parse(path, e) => write!(f, "invalid config file {}: {}", path.display(), e),
+            ConfigError::UnknownKey(key) => write!(f, "unknown config option '{}'", key),
+            ConfigError::MissingValue(flag) => write!(f, "missing value for {}", flag),
+            ConfigError::InvalidValue { key, value, reason } => {
+                write!(f, "invalid value '{}' for {}: {}", value, key, reason)
+            }
+            ConfigError::Invalid(reason) => write!(f, "invalid configuration: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+// Server settings, layered as defaults < TOML file < ZKP_* environment variables < command-line flags.
+// Every key can be set in all three places: `listen_addr` in TOML, ZKP_LISTEN_ADDR, --listen-addr.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    #[serde(deserialize_with = "one_or_many")]
+    pub listen_addr: Vec<SocketAddr>,  // TCP addresses to serve on, e.g. one IPv4 and one IPv6
+    pub listen_socket: Option<PathBuf>,  // Unix domain socket to serve on as well
+    #[serde(deserialize_with = "one_or_many")]
+    pub rest_listen_addr: Vec<SocketAddr>,  // HTTP/JSON gateway addresses; needs the rest feature
+    pub tls_cert: Option<PathBuf>,
+    pub tls_key: Option<PathBuf>,
+    pub tls_client_ca: Option<PathBuf>,
+    pub session_ttl_secs: u64,
+    pub challenge_ttl_secs: u64,  // how long an issued challenge can be answered, by the server's clock
+    pub session_store: Option<PathBuf>,  // SQLite file for sessions; in memory if unset
+    pub registry_store: Option<PathBuf>,  // SQLite file for registered commitments; in memory if unset
+    pub min_bits: u32,
+    pub max_bits: u32,
+    pub param_pool_size: usize,      // parameter sets kept ready per pooled bit size, 0 disables the pool
+    pub param_pool_bits: Vec<u32>,
+    pub verifiable_params: bool,  // derive (p, q, g) from a published seed
+    pub params_cache_dir: Option<PathBuf>,
+    pub params_cache_key: Option<String>,  // hex MAC key; a key file is created in the cache dir if unset
+    pub params_cache_max_per_bits: usize,
+    pub log_level: String,
+    pub otlp_endpoint: Option<String>,  // OTLP/gRPC collector for trace export; needs the otel feature
+    pub cors_allowed_origins: Vec<String>,
+    pub compression: Vec<String>,  // encodings responses may be sent in; compressed requests are always accepted
+    pub api_keys: Vec<String>,
+    pub allowed_client_cns: Vec<String>,
+    pub operator_api_keys: Vec<String>,  // may rotate, revoke and re-register any user's commitment
+    pub operator_client_cns: Vec<String>,
+    pub audit_log: Option<PathBuf>,
+    pub audit_key: Option<String>,   // hex HMAC key for signing audit entries
+    pub webhook_url: Option<String>,  // http(s) endpoint verification events are POSTed to
+    pub webhook_events: String,  // all, success or failure
+    pub webhook_timeout_secs: u64,
+    pub transcript_key: Option<String>,  // hex Ed25519 seed for signing session transcripts; random per run if unset
+    pub transcript_dir: Option<PathBuf>,  // directory concluded runs' signed transcripts are archived to
+    pub transcript_db: Option<PathBuf>,   // SQLite file to archive them to instead; needs transcript-sqlite
+    pub transcript_retention_days: u64,   // 0 keeps archived transcripts for good
+    pub oprf_key: Option<String>,  // hex seed the VOPRF evaluation keys derive from; random per run if unset
+    pub init_requests_per_minute: u32,
+    pub max_concurrent_generations: usize,
+    // timeout in seconds from each bit size up, e.g. { 2048 = 60, 4096 = 300 }
+    #[serde(deserialize_with = "bit_size_keys")]
+    pub generation_timeout_secs: BTreeMap<u32, u64>,
+    pub max_failed_verifications: u32,  // per session and per client before a lockout
+    pub lockout_secs: u64,
+    pub replay_cache_size: usize,  // (y1, y2) pairs remembered to refuse replays, 0 disables the check
+    pub max_statements_per_session: usize,  // statements one multi-proof session may hold
+    pub max_threshold_parties: u32,  // provers one threshold proof may be split across
+    pub max_batched_proofs: usize,  // proofs one VerifyProofBatch request may carry
+    pub shed_max_sessions: usize,  // sessions held before initialize is refused, 0 disables
+    pub shed_max_generations: usize,  // parameter searches running before initialize is refused, 0 disables
+    pub shed_retry_after_secs: u64,  // retry-after hint sent with a refusal
+    pub max_decoding_message_size: usize,  // largest request either service accepts, in bytes
+    pub max_encoding_message_size: usize,  // largest response either service sends
+    pub max_concurrent_streams: Option<u32>,  // concurrent RPCs per HTTP/2 connection; unlimited if unset
+    pub initial_stream_window_size: Option<u32>,  // HTTP/2 flow-control windows in bytes; h2's 64 KiB if unset
+    pub initial_connection_window_size: Option<u32>,
+    pub keepalive_interval_secs: Option<u64>,  // HTTP/2 pings to idle clients; none if unset
+    pub keepalive_timeout_secs: u64,  // how long a ping may go unanswered before the connection is closed
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            listen_addr: vec!["[::1]:50051".parse().expect("valid default address")],
+            listen_socket: None,
+            rest_listen_addr: Vec::new(),
+            tls_cert: None,
+            tls_key: None,
+            tls_client_ca: None,
+            session_ttl_secs: 300,
+            challenge_ttl_secs: 60,
+            session_store: None,
+            registry_store: None,
+            min_bits: 256,
+            max_bits: 4096,
+            param_pool_size: 0,
+            param_pool_bits: vec![512],
+            verifiable_params: false,
+            params_cache_dir: None,
+            params_cache_key: None,
+            params_cache_max_per_bits: 8,
+            log_level: "info".to_string(),
+            otlp_endpoint: None,
+            cors_allowed_origins: Vec::new(),
+            compression: Vec::new(),
+            api_keys: Vec::new(),
+            allowed_client_cns: Vec::new(),
+            operator_api_keys: Vec::new(),
+            operator_client_cns: Vec::new(),
+            audit_log: None,
+            audit_key: None,
+            webhook_url: None,
+            webhook_events: "all".to_string(),
+            webhook_timeout_secs: 5,
+            transcript_key: None,
+            transcript_dir: None,
+            transcript_db: None,
+            transcript_retention_days: 0,
+            oprf_key: None,
+            init_requests_per_minute: 10,
+            max_concurrent_generations: 4,
+            generation_timeout_secs: BTreeMap::new(),
+            max_failed_verifications: 5,
+            lockout_secs: 300,
+            replay_cache_size: 100_000,
+            max_statements_per_session: 16,
+            max_threshold_parties: 16,
+            max_batched_proofs: 1024,
+            shed_max_sessions: 0,
+            shed_max_generations: 0,
+            shed_retry_after_secs: 5,
+            max_decoding_message_size: 4 * 1024 * 1024,  // tonic's default for decoding
+            max_encoding_message_size: 4 * 1024 * 1024,
+            max_concurrent_streams: None,
+            initial_stream_window_size: None,
+            initial_connection_window_size: None,
+            keepalive_interval_secs: None,
+            keepalive_timeout_secs: 20,
+        }
+    }
+}
+
+fn invalid(key: &str, value: &str, reason: impl Into<String>) -> ConfigError {
+    ConfigError::InvalidValue {
+        key: key.to_string(),
+        value: value.to_string(),
+        reason: reason.into(),
+    }
+}
+
+fn parse<T: std::str::FromStr>(key: &str, value: &str) -> Result<T, ConfigError>
+where
+    T::Err: fmt::Display,
+{
+    value.parse().map_err(|e: T::Err| invalid(key, value, e.to_string()))
+}
+
+// Parses `bits=value` pairs, comma-separated
+fn parse_bit_size_map(key: &str, value: &str) -> Result<BTreeMap<u32, u64>, ConfigError> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(|item| {
+            let (bits, value) = item
+                .split_once('=')
+                .ok_or_else(|| invalid(key, item, "expected bits=value"))?;
+            Ok((parse(key, bits.trim())?, parse(key, value.trim())?))
+        })
+        .collect()
+}
+
+// A single address is accepted as well as a list, so older config files keep working
+fn one_or_many<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<SocketAddr>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(SocketAddr),
+        Many(Vec<SocketAddr>),
+    }
+
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(addr) => vec![addr],
+        OneOrMany::Many(addrs) => addrs,
+    })
+}
+
+// TOML table keys are always strings, so bit sizes are parsed out of them
+fn bit_size_keys<'de, D: Deserializer<'de>>(deserializer: D) -> Result<BTreeMap<u32, u64>, D::Error> {
+    BTreeMap::<String, u64>::deserialize(deserializer)?
+        .into_iter()
+        .map(|(bits, value)| Ok((bits.parse().map_err(serde::de::Error::custom)?, value)))
+        .collect()
+}
+
+fn parse_list<T: std::str::FromStr>(key: &str, value: &str) -> Result<Vec<T>, ConfigError>
+where
+    T::Err: fmt::Display,
+{
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(|item| parse(key, item))
+        .collect()
+}
+
+impl ServerConfig {
+    pub const KEYS: [&'static str; 55] = [
+        "listen_addr",
+        "listen_socket",
+        "rest_listen_addr",
+        "tls_cert",
+        "tls_key",
+        "tls_client_ca",
+        "session_ttl_secs",
+        "challenge_ttl_secs",
+        "session_store",
+        "registry_store",
+        "min_bits",
+        "max_bits",
+        "param_pool_size",
+        "param_pool_bits",
+        "verifiable_params",
+        "params_cache_dir",
+        "params_cache_key",
+        "params_cache_max_per_bits",
+        "log_level",
+        "otlp_endpoint",
+        "cors_allowed_origins",
+        "compression",
+        "api_keys",
+        "allowed_client_cns",
+        "operator_api_keys",
+        "operator_client_cns",
+        "audit_log",
+        "audit_key",
+        "webhook_url",
+        "webhook_events",
+        "webhook_timeout_secs",
+        "transcript_key",
+        "transcript_dir",
+        "transcript_db",
+        "transcript_retention_days",
+        "oprf_key",
+        "init_requests_per_minute",
+        "max_concurrent_generations",
+        "generation_timeout_secs",
+        "max_failed_verifications",
+        "lockout_secs",
+        "replay_cache_size",
+        "max_statements_per_session",
+        "max_threshold_parties",
+        "max_batched_proofs",
+        "shed_max_sessions",
+        "shed_max_generations",
+        "shed_retry_after_secs",
+        "max_decoding_message_size",
+        "max_encoding_message_size",
+        "max_concurrent_streams",
+        "initial_stream_window_size",
+        "initial_connection_window_size",
+        "keepalive_interval_secs",
+        "keepalive_timeout_secs",
+    ];
+
+    pub fn from_file(path: &Path) -> Result<Self, ConfigError> {
+        read_file(path)
+    }
+
+    // Loads the full layered configuration from the process environment and the given arguments
+    // (without the program name). The file is taken from --config or ZKP_CONFIG.
+    pub fn load(args: impl IntoIterator<Item = String>) -> Result<Self, ConfigError> {
+        load_layered(args, ENV_PREFIX, &Self::KEYS, Self::from_file, Self::set, Self::validate)
+    }
+
+    // Applies a single string-valued setting; list values are comma-separated
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), ConfigError> {
+        match key {
+            "listen_addr" => self.listen_addr = parse_list(key, value)?,
+            "listen_socket" => self.listen_socket = Some(PathBuf::from(value)),
+            "rest_listen_addr" => self.rest_listen_addr = parse_list(key, value)?,
+            "tls_cert" => self.tls_cert = Some(PathBuf::from(value)),
+            "tls_key" => self.tls_key = Some(PathBuf::from(value)),
+            "tls_client_ca" => self.tls_client_ca = Some(PathBuf::from(value)),
+            "session_ttl_secs" => self.session_ttl_secs = parse(key, value)?,
+            "challenge_ttl_secs" => self.challenge_ttl_secs = parse(key, value)?,
+            "session_store" => self.session_store = Some(PathBuf::from(value)),
+            "registry_store" => self.registry_store = Some(PathBuf::from(value)),
+            "min_bits" => self.min_bits = parse(key, value)?,
+            "max_bits" => self.max_bits = parse(key, value)?,
+            "param_pool_size" => self.param_pool_size = parse(key, value)?,
+            "param_pool_bits" => self.param_pool_bits = parse_list(key, value)?,
+            "verifiable_params" => self.verifiable_params = parse(key, value)?,
+            "params_cache_dir" => self.params_cache_dir = Some(PathBuf::from(value)),
+            "params_cache_key" => self.params_cache_key = Some(value.to_string()),
+            "params_cache_max_per_bits" => self.params_cache_max_per_bits = parse(key, value)?,
+            "log_level" => self.log_level = value.to_lowercase(),
+            "otlp_endpoint" => self.otlp_endpoint = Some(value.to_string()),
+            "cors_allowed_origins" => self.cors_allowed_origins = parse_list(key, value)?,
+            "compression" => self.compression = parse_list(key, &value.to_lowercase())?,
+            "api_keys" => self.api_keys = parse_list(key, value)?,
+            "allowed_client_cns" => self.allowed_client_cns = parse_list(key, value)?,
+            "operator_api_keys" => self.operator_api_keys = parse_list(key, value)?,
+            "operator_client_cns" => self.operator_client_cns = parse_list(key, value)?,
+            "audit_log" => self.audit_log = Some(PathBuf::from(value)),
+            "audit_key" => self.audit_key = Some(value.to_string()),
+            "webhook_url" => self.webhook_url = Some(value.to_string()),
+            "webhook_events" => self.webhook_events = value.to_lowercase(),
+            "webhook_timeout_secs" => self.webhook_timeout_secs = parse(key, value)?,
+            "transcript_key" => self.transcript_key = Some(value.to_string()),
+            "transcript_dir" => self.transcript_dir = Some(PathBuf::from(value)),
+            "transcript_db" => self.transcript_db = Some(PathBuf::from(value)),
+            "transcript_retention_days" => self.transcript_retention_days = parse(key, value)?,
+            "oprf_key" => self.oprf_key = Some(value.to_string()),
+            "init_requests_per_minute" => self.init_requests_per_minute = parse(key, value)?,
+            "max_concurrent_generations" => self.max_concurrent_generations = parse(key, value)?,
+            "generation_timeout_secs" => self.generation_timeout_secs = parse_bit_size_map(key, value)?,
+            "max_failed_verifications" => self.max_failed_verifications = parse(key, value)?,
+            "lockout_secs" => self.lockout_secs = parse(key, value)?,
+            "replay_cache_size" => self.replay_cache_size = parse(key, value)?,
+            "max_statements_per_session" => self.max_statements_per_session = parse(key, value)?,
+            "max_threshold_parties" => self.max_threshold_parties = parse(key, value)?,
+            "max_batched_proofs" => self.max_batched_proofs = parse(key, value)?,
+            "shed_max_sessions" => self.shed_max_sessions = parse(key, value)?,
+            "shed_max_generations" => self.shed_max_generations = parse(key, value)?,
+            "shed_retry_after_secs" => self.shed_retry_after_secs = parse(key, value)?,
+            "max_decoding_message_size" => self.max_decoding_message_size = parse(key, value)?,
+            "max_encoding_message_size" => self.max_encoding_message_size = parse(key, value)?,
+            "max_concurrent_streams" => self.max_concurrent_streams = Some(parse(key, value)?),
+            "initial_stream_window_size" => self.initial_stream_window_size = Some(parse(key, value)?),
+            "initial_connection_window_size" => self.initial_connection_window_size = Some(parse(key, value)?),
+            "keepalive_interval_secs" => self.keepalive_interval_secs = Some(parse(key, value)?),
+            "keepalive_timeout_secs" => self.keepalive_timeout_secs = parse(key, value)?,
+            _ => return Err(ConfigError::UnknownKey(key.to_string())),
+        }
+        Ok(())
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.listen_addr.is_empty() && self.listen_socket.is_none() {
+            return Err(ConfigError::Invalid("set listen_addr, listen_socket or both".to_string()));
+        }
+        if cfg!(not(unix)) && self.listen_socket.is_some() {
+            return Err(ConfigError::Invalid("listen_socket needs a Unix platform".to_string()));
+        }
+        if self.min_bits < HARD_MIN_BITS || self.max_bits > HARD_MAX_BITS {
+            return Err(ConfigError::Invalid(format!(
+                "allowed bit sizes must lie within {}..={}",
+                HARD_MIN_BITS, HARD_MAX_BITS
+            )));
+        }
+        if self.min_bits > self.max_bits {
+            return Err(ConfigError::Invalid(format!(
+                "min_bits ({}) is greater than max_bits ({})",
+                self.min_bits, self.max_bits
+            )));
+        }
+        if let Some(bits) = self.param_pool_bits.iter().find(|bits| !self.bits_allowed(**bits)) {
+            return Err(invalid("param_pool_bits", &bits.to_string(), "outside the allowed bit sizes"));
+        }
+        if self.session_ttl_secs == 0 {
+            return Err(invalid("session_ttl_secs", "0", "must be positive"));
+        }
+        if self.challenge_ttl_secs == 0 {
+            return Err(invalid("challenge_ttl_secs", "0", "must be positive"));
+        }
+        if cfg!(not(feature = "transcript-sqlite")) && self.transcript_db.is_some() {
+            return Err(ConfigError::Invalid("transcript_db requires the transcript-sqlite feature".to_string()));
+        }
+        if self.transcript_dir.is_some() && self.transcript_db.is_some() {
+            return Err(ConfigError::Invalid("set at most one of transcript_dir and transcript_db".to_string()));
+        }
+        if cfg!(not(feature = "session-sqlite")) && self.session_store.is_some() {
+            return Err(ConfigError::Invalid("session_store requires the session-sqlite feature".to_string()));
+        }
+        if cfg!(not(feature = "registry-sqlite")) && self.registry_store.is_some() {
+            return Err(ConfigError::Invalid("registry_store requires the registry-sqlite feature".to_string()));
+        }
+        if cfg!(not(feature = "otel")) && self.otlp_endpoint.is_some() {
+            return Err(ConfigError::Invalid("otlp_endpoint requires the otel feature".to_string()));
+        }
+        if cfg!(not(feature = "rest")) && !self.rest_listen_addr.is_empty() {
+            return Err(ConfigError::Invalid("rest_listen_addr requires the rest feature".to_string()));
+        }
+        if self.params_cache_max_per_bits == 0 {
+            return Err(invalid("params_cache_max_per_bits", "0", "must be positive"));
+        }
+        if let Some(key) = &self.params_cache_key {
+            if self.params_cache_dir.is_none() {
+                return Err(ConfigError::Invalid("params_cache_key requires params_cache_dir".to_string()));
+            }
+            if hex::decode(key).is_err() {
+                return Err(invalid("params_cache_key", "<redacted>", "must be hex"));
+            }
+        }
+        if self.max_concurrent_generations == 0 {
+            return Err(invalid("max_concurrent_generations", "0", "must be positive"));
+        }
+        if let Some((bits, _)) = self.generation_timeout_secs.iter().find(|(_, secs)| **secs == 0) {
+            return Err(invalid("generation_timeout_secs", &format!("{}=0", bits), "timeouts must be positive"));
+        }
+        if self.max_failed_verifications == 0 {
+            return Err(invalid("max_failed_verifications", "0", "must be positive"));
+        }
+        if self.lockout_secs == 0 {
+            return Err(invalid("lockout_secs", "0", "must be positive"));
+        }
+        if self.max_statements_per_session == 0 {
+            return Err(invalid("max_statements_per_session", "0", "must be positive"));
+        }
+        if self.max_threshold_parties == 0 {
+            return Err(invalid("max_threshold_parties", "0", "must be positive"));
+        }
+        if self.max_batched_proofs == 0 {
+            return Err(invalid("max_batched_proofs", "0", "must be positive"));
+        }
+        if self.shed_max_generations > self.max_concurrent_generations {
+            return Err(invalid(
+                "shed_max_generations",
+                &self.shed_max_generations.to_string(),
+                "above max_concurrent_generations, so it could never be reached",
+            ));
+        }
+        if self.shed_retry_after_secs == 0 {
+            return Err(invalid("shed_retry_after_secs", "0", "must be positive"));
+        }
+        for (key, size) in [
+            ("max_decoding_message_size", self.max_decoding_message_size),
+            ("max_encoding_message_size", self.max_encoding_message_size),
+        ] {
+            if size < MIN_MESSAGE_SIZE {
+                return Err(invalid(key, &size.to_string(), format!("must be at least {} bytes", MIN_MESSAGE_SIZE)));
+            }
+        }
+        if self.max_concurrent_streams == Some(0) {
+            return Err(invalid("max_concurrent_streams", "0", "must be positive"));
+        }
+        for (key, size) in [
+            ("initial_stream_window_size", self.initial_stream_window_size),
+            ("initial_connection_window_size", self.initial_connection_window_size),
+        ] {
+            if let Some(size) = size
+                && !(1..=MAX_WINDOW_SIZE).contains(&size)
+            {
+                return Err(invalid(key, &size.to_string(), format!("must lie within 1..={}", MAX_WINDOW_SIZE)));
+            }
+        }
+        if self.keepalive_interval_secs == Some(0) {
+            return Err(invalid("keepalive_interval_secs", "0", "must be positive"));
+        }
+        if self.keepalive_timeout_secs == 0 {
+            return Err(invalid("keepalive_timeout_secs", "0", "must be positive"));
+        }
+        if !LOG_LEVELS.contains(&self.log_level.as_str()) {
+            return Err(invalid("log_level", &self.log_level, format!("expected one of {}", LOG_LEVELS.join(", "))));
+        }
+        if let Some(encoding) = self.compression.iter().find(|e| !COMPRESSION_ENCODINGS.contains(&e.as_str())) {
+            return Err(invalid(
+                "compression",
+                encoding,
+                format!("expected one of {}", COMPRESSION_ENCODINGS.join(", ")),
+            ));
+        }
+        if self.tls_cert.is_some() != self.tls_key.is_some() {
+            return Err(ConfigError::Invalid("tls_cert and tls_key must be set together".to_string()));
+        }
+        if self.tls_client_ca.is_some() && self.tls_cert.is_none() {
+            return Err(ConfigError::Invalid("tls_client_ca requires tls_cert and tls_key".to_string()));
+        }
+        if let Some(key) = &self.audit_key {
+            if self.audit_log.is_none() {
+                return Err(ConfigError::Invalid("audit_key requires audit_log".to_string()));
+            }
+            if hex::decode(key).is_err() {
+                return Err(invalid("audit_key", "<redacted>", "must be hex"));
+            }
+        }
+        if let Some(url) = &self.webhook_url
+            && !(url.starts_with("http://") || url.starts_with("https://"))
+        {
+            return Err(invalid("webhook_url", url, "must be an http or https URL"));
+        }
+        if !WEBHOOK_EVENTS.contains(&self.webhook_events.as_str()) {
+            return Err(invalid(
+                "webhook_events",
+                &self.webhook_events,
+                format!("expected one of {}", WEBHOOK_EVENTS.join(", ")),
+            ));
+        }
+        if self.webhook_timeout_secs == 0 {
+            return Err(invalid("webhook_timeout_secs", "0", "must be positive"));
+        }
+        if let Some(key) = &self.transcript_key
+            && hex::decode(key.trim()).map_or(true, |seed| seed.len() != 32)
+        {
+            return Err(invalid("transcript_key", "<redacted>", "must be 32 hex-encoded bytes"));
+        }
+        if let Some(key) = &self.oprf_key
+            && hex::decode(key.trim()).map_or(true, |seed| seed.len() != 32)
+        {
+            return Err(invalid("oprf_key", "<redacted>", "must be 32 hex-encoded bytes"));
+        }
+        Ok(())
+    }
+
+    pub fn bits_allowed(&self, bits: u32) -> bool {
+        (self.min_bits..=self.max_bits).contains(&bits)
+    }
+
+    pub fn session_ttl(&self) -> Duration {
+        Duration::from_secs(self.session_ttl_secs)
+    }
+
+    pub fn challenge_ttl(&self) -> Duration {
+        Duration::from_secs(self.challenge_ttl_secs)
+    }
+
+    pub fn transcript_retention(&self) -> Option<Duration> {
+        (self.transcript_retention_days > 0).then(|| Duration::from_secs(self.transcript_retention_days * 86_400))
+    }
+
+    pub fn lockout(&self) -> Duration {
+        Duration::from_secs(self.lockout_secs)
+    }
+}
+
+// Prover daemon settings, layered the same way as the server's under ZKP_PROVER_* variables
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProverConfig {
+    #[serde(deserialize_with = "one_or_many")]
+    pub listen_addr: Vec<SocketAddr>,
+    pub listen_socket: Option<PathBuf>,
+    pub tls_cert: Option<PathBuf>,
+    pub tls_key: Option<PathBuf>,
+    pub tls_client_ca: Option<PathBuf>,
+    pub api_keys: Vec<String>,
+    pub allowed_client_cns: Vec<String>,
+    pub log_level: String,
+    pub backend: String,  // software or pkcs11, where secrets of new keys are generated
+    pub key_store: Option<PathBuf>,  // JSON file software keys are kept in; in memory only if unset
+    pub pkcs11_module: Option<PathBuf>,
+    pub pkcs11_slot: u64,
+    pub pkcs11_pin: Option<String>,
+    pub min_bits: u32,  // group sizes keys may be created over
+    pub max_bits: u32,
+    pub max_keys: usize,
+    pub max_pending_nonces: usize,  // per key; Commit is refused until some are answered
+}
+
+impl Default for ProverConfig {
+    fn default() -> Self {
+        Self {
+            listen_addr: vec!["[::1]:50052".parse().expect("valid default address")],
+            listen_socket: None,
+            tls_cert: None,
+            tls_key: None,
+            tls_client_ca: None,
+            api_keys: Vec::new(),
+            allowed_client_cns: Vec::new(),
+            log_level: "info".to_string(),
+            backend: "software".to_string(),
+            key_store: None,
+            pkcs11_module: None,
+            pkcs11_slot: 0,
+            pkcs11_pin: None,
+            min_bits: 256,
+            max_bits: 4096,
+            max_keys: 1024,
+            max_pending_nonces: 16,
+        }
+    }
+}
+
+impl ProverConfig {
+    pub const KEYS: [&'static str; 17] = [
+        "listen_addr",
+        "listen_socket",
+        "tls_cert",
+        "tls_key",
+        "tls_client_ca",
+        "api_keys",
+        "allowed_client_cns",
+        "log_level",
+        "backend",
+        "key_store",
+        "pkcs11_module",
+        "pkcs11_slot",
+        "pkcs11_pin",
+        "min_bits",
+        "max_bits",
+        "max_keys",
+        "max_pending_nonces",
+    ];
+
+    pub fn from_file(path: &Path) -> Result<Self, ConfigError> {
+        read_file(path)
+    }
+
+    // As ServerConfig::load, with the file taken from --config or ZKP_PROVER_CONFIG
+    pub fn load(args: impl IntoIterator<Item = String>) -> Result<Self, ConfigError> {
+        load_layered(args, PROVER_ENV_PREFIX, &Self::KEYS, Self::from_file, Self::set, Self::validate)
+    }
+
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), ConfigError> {
+        match key {
+            "listen_addr" => self.listen_addr = parse_list(key, value)?,
+            "listen_socket" => self.listen_socket = Some(PathBuf::from(value)),
+            "tls_cert" => self.tls_cert = Some(PathBuf::from(value)),
+            "tls_key" => self.tls_key = Some(PathBuf::from(value)),
+            "tls_client_ca" => self.tls_client_ca = Some(PathBuf::from(value)),
+            "api_keys" => self.api_keys = parse_list(key, value)?,
+            "allowed_client_cns" => self.allowed_client_cns = parse_list(key, value)?,
+            "log_level" => self.log_level = value.to_lowercase(),
+            "backend" => self.backend = value.to_lowercase(),
+            "key_store" => self.key_store = Some(PathBuf::from(value)),
+            "pkcs11_module" => self.pkcs11_module = Some(PathBuf::from(value)),
+            "pkcs11_slot" => self.pkcs11_slot = parse(key, value)?,
+            "pkcs11_pin" => self.pkcs11_pin = Some(value.to_string()),
+            "min_bits" => self.min_bits = parse(key, value)?,
+            "max_bits" => self.max_bits = parse(key, value)?,
+            "max_keys" => self.max_keys = parse(key, value)?,
+            "max_pending_nonces" => self.max_pending_nonces = parse(key, value)?,
+            _ => return Err(ConfigError::UnknownKey(key.to_string())),
+        }
+        Ok(())
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.listen_addr.is_empty() && self.listen_socket.is_none() {
+            return Err(ConfigError::Invalid("set listen_addr, listen_socket or both".to_string()));
+        }
+        if cfg!(not(unix)) && self.listen_socket.is_some() {
+            return Err(ConfigError::Invalid("listen_socket needs a Unix platform".to_string()));
+        }
+        if self.min_bits < HARD_MIN_BITS || self.max_bits > HARD_MAX_BITS {
+            return Err(ConfigError::Invalid(format!(
+                "allowed bit sizes must lie within {}..={}",
+                HARD_MIN_BITS, HARD_MAX_BITS
+            )));
+        }
+        if self.min_bits > self.max_bits {
+            return Err(ConfigError::Invalid(format!(
+                "min_bits ({}) is greater than max_bits ({})",
+                self.min_bits, self.max_bits
+            )));
+        }
+        if self.max_keys == 0 {
+            return Err(invalid("max_keys", "0", "must be positive"));
+        }
+        if self.max_pending_nonces == 0 {
+            return Err(invalid("max_pending_nonces", "0", "must be positive"));
+        }
+        if !LOG_LEVELS.contains(&self.log_level.as_str()) {
+            return Err(invalid("log_level", &self.log_level, format!("expected one of {}", LOG_LEVELS.join(", "))));
+        }
+        if !SECRET_BACKENDS.contains(&self.backend.as_str()) {
+            return Err(invalid(
+                "backend",
+                &self.backend,
+                format!("expected one of {}", SECRET_BACKENDS.join(", ")),
+            ));
+        }
+        if self.backend == "pkcs11" {
+            if cfg!(not(feature = "pkcs11")) {
+                return Err(ConfigError::Invalid("backend = pkcs11 requires the pkcs11 feature".to_string()));
+            }
+            if self.pkcs11_module.is_none() || self.pkcs11_pin.is_none() {
+                return Err(ConfigError::Invalid("backend = pkcs11 requires pkcs11_module and pkcs11_pin".to_string()));
+            }
+            // token keys are session objects and cannot be written out
+            if self.key_store.is_some() {
+                return Err(ConfigError::Invalid("key_store only holds software keys".to_string()));
+            }
+        }
+        if self.tls_cert.is_some() != self.tls_key.is_some() {
+            return Err(ConfigError::Invalid("tls_cert and tls_key must be set together".to_string()));
+        }
+        if self.tls_client_ca.is_some() && self.tls_cert.is_none() {
+            return Err(ConfigError::Invalid("tls_client_ca requires tls_cert and tls_key".to_string()));
+        }
+        Ok(())
+    }
+
+    pub fn bits_allowed(&self, bits: u32) -> bool {
+        (self.min_bits..=self.max_bits).contains(&bits)
+    }
+}
+
+fn read_file<C: DeserializeOwned>(path: &Path) -> Result<C, ConfigError> {
+    let text = std::fs::read_to_string(path).map_err(|e| ConfigError::Io(path.to_path_buf(), e))?;
+    toml::from_str(&text).map_err(|e| ConfigError::Parse(path.to_path_buf(), e))
+}
+
+// Defaults < TOML file < `prefix`* environment variables < command-line flags, for any of the configs
+fn load_layered<C: Default>(
+    args: impl IntoIterator<Item = String>,
+    prefix: &str,
+    keys: &[&str],
+    from_file: fn(&Path) -> Result<C, ConfigError>,
+    set: fn(&mut C, &str, &str) -> Result<(), ConfigError>,
+    validate: fn(&C) -> Result<(), ConfigError>,
+) -> Result<C, ConfigError> {
+    let flags = parse_flags(args, keys)?;
+
+    let file = flags
+        .iter()
+        .find(|(key, _)| key == "config")
+        .map(|(_, value)| PathBuf::from(value))
+        .or_else(|| std::env::var_os(format!("{}CONFIG", prefix)).map(PathBuf::from));
+
+    let mut config = match file {
+        Some(path) => from_file(&path)?,
+        None => C::default(),
+    };
+
+    for key in keys {
+        if let Ok(value) = std::env::var(format!("{}{}", prefix, key.to_uppercase())) {
+            set(&mut config, key, &value)?;
+        }
+    }
+
+    for (key, value) in flags.iter().filter(|(key, _)| key != "config") {
+        set(&mut config, key, value)?;
+    }
+
+    validate(&config)?;
+    Ok(config)
+}
+
+// Accepts `--some-key value` and `--some-key=value`, returning (some_key, value) pairs
+fn parse_flags(args: impl IntoIterator<Item = String>, keys: &[&str]) -> Result<Vec<(String, String)>, ConfigError> {
+    let mut flags = Vec::new();
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        let flag = arg
+            .strip_prefix("--")
+            .ok_or_else(|| ConfigError::UnknownKey(arg.clone()))?;
+
+        let (name, value) = match flag.split_once('=') {
+            Some((name, value)) => (name.to_string(), value.to_string()),
+            None => {
+                let value = args.next().ok_or_else(|| ConfigError::MissingValue(arg.clone()))?;
+                (flag.to_string(), value)
+            }
+        };
+
+        let key = name.replace('-', "_");
+        if key != "config" && !keys.contains(&key.as_str()) {
+            return Err(ConfigError::UnknownKey(arg));
+        }
+        flags.push((key, value));
+    }
+
+    Ok(flags)
+}
@@ -0,0 +1,765 @@
+use std::fmt;
+
+use num_bigint::BigUint;
+use prost::bytes::Bytes;
+use tonic::Status;
+
+use crate::batch::{BatchEntry, ProofBatch};
+use crate::chaum_pedersen::{
+    Commitment, MultiZKProof, ProofChallenge, ProofResponse, Provenance, PublicParameters, SecondGenerator,
+    Statement, ZKProof,
+};
+use crate::group::{DleqCommitment, DleqProof, DleqStatement, Group, GroupKind};
+use crate::interop::CompactDleqProof;
+use crate::key_exchange::{DhShare, DhShareProof};
+use crate::pedersen::PedersenCommitment;
+use crate::range::{self, BitCommitment, BitResponse, RangeProof};
+use crate::session::{ChallengeMode, CommitmentOrigin, Stage};
+use crate::sigma::SigmaProof;
+use crate::zkp;
+
+// Why a protobuf message could not be turned into its crypto counterpart
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ConversionError {
+    MissingField(&'static str),
+    NonCanonical(&'static str),
+    Invalid { field: &'static str, reason: &'static str },
+    TooLong { field: &'static str, max: usize },
+}
+
+impl fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConversionError::MissingField(field) => write!(f, "missing {}", field),
+            ConversionError::NonCanonical(field) => write!(f, "{} has leading zero bytes", field),
+            ConversionError::Invalid { field, reason } => write!(f, "invalid {}: {}", field, reason),
+            ConversionError::TooLong { field, max } => write!(f, "{} is longer than {} bytes", field, max),
+        }
+    }
+}
+
+impl std::error::Error for ConversionError {}
+
+impl From<ConversionError> for Status {
+    fn from(e: ConversionError) -> Self {
+        Status::invalid_argument(e.to_string())
+    }
+}
+
+// Longest integer accepted from the wire: an element of the largest group a server may allow
+// (8192 bits). Anything longer is refused before a BigUint is allocated for it.
+pub const MAX_INTEGER_BYTES: usize = 8192 / 8;
+
+// Integers travel as big-endian bytes. An empty field means the sender left it unset, and only zero
+// itself may start with a zero byte, so every value has exactly one encoding.
+pub fn decode_integer(field: &'static str, bytes: &[u8]) -> Result<BigUint, ConversionError> {
+    check_length(field, bytes, MAX_INTEGER_BYTES)?;
+    match bytes {
+        [] => Err(ConversionError::MissingField(field)),
+        [0, _, ..] => Err(ConversionError::NonCanonical(field)),
+        _ => Ok(BigUint::from_bytes_be(bytes)),
+    }
+}
+
+// The sending side of decode_integer for the messages whose fields are generated as `Bytes` (see
+// build.rs): the big-endian buffer BigUint allocates becomes the field itself, with no further copy
+pub fn encode_integer(value: &BigUint) -> Bytes {
+    Bytes::from(value.to_bytes_be())
+}
+
+fn check_length(field: &'static str, bytes: &[u8], max: usize) -> Result<(), ConversionError> {
+    match bytes.len() > max {
+        true => Err(ConversionError::TooLong { field, max }),
+        false => Ok(()),
+    }
+}
+
+// The longest encodings a session over some parameters can legitimately be sent: group elements
+// (commitments, y1, y2) lie below p, challenges and responses below q. Checking these first keeps
+// a hostile client from making the server parse and exponentiate oversized integers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FieldLimits {
+    pub element: usize,
+    pub scalar: usize,
+}
+
+impl FieldLimits {
+    pub fn for_params(params: &PublicParameters) -> Self {
+        Self {
+            element: params.p().bits().div_ceil(8) as usize,
+            scalar: params.q().bits().div_ceil(8) as usize,
+        }
+    }
+
+    pub fn element(&self, field: &'static str, bytes: &[u8]) -> Result<BigUint, ConversionError> {
+        check_length(field, bytes, self.element)?;
+        decode_integer(field, bytes)
+    }
+
+    pub fn scalar(&self, field: &'static str, bytes: &[u8]) -> Result<BigUint, ConversionError> {
+        check_length(field, bytes, self.scalar)?;
+        decode_integer(field, bytes)
+    }
+
+    pub fn check_commitment(&self, commitment: &zkp::Commitment) -> Result<(), ConversionError> {
+        check_length("a1", &commitment.a1, self.element)?;
+        check_length("b1", &commitment.b1, self.element)?;
+        check_length("c1", &commitment.c1, self.element)
+    }
+
+    pub fn check_challenge_values(&self, values: &zkp::ProofChallenge) -> Result<(), ConversionError> {
+        check_length("y1", &values.y1, self.element)?;
+        check_length("y2", &values.y2, self.element)
+    }
+
+    pub fn check_proof(&self, proof: &zkp::ZkProofMessage) -> Result<(), ConversionError> {
+        if let Some(commitment) = &proof.commitment {
+            self.check_commitment(commitment)?;
+        }
+        if let Some(values) = &proof.challenge_values {
+            self.check_challenge_values(values)?;
+        }
+        check_length("challenge", &proof.challenge, self.scalar)?;
+        check_length("z", &proof.z, self.scalar)
+    }
+}
+
+fn invalid(field: &'static str, reason: &'static str) -> ConversionError {
+    ConversionError::Invalid { field, reason }
+}
+
+impl TryFrom<zkp::PublicParameters> for PublicParameters {
+    type Error = ConversionError;
+
+    // Checks the group structure (p = 2q + 1, g of order q) and that h, if sent, matches its seed.
+    // Primality of p and q is not re-tested here.
+    fn try_from(proto: zkp::PublicParameters) -> Result<Self, Self::Error> {
+        let p = decode_integer("p", &proto.p)?;
+        let q = decode_integer("q", &proto.q)?;
+        let g = decode_integer("g", &proto.g)?;
+
+        if p != &q * 2u32 + 1u32 {
+            return Err(invalid("p", "not of the form 2q + 1"));
+        }
+        let one = BigUint::from(1u32);
+        if g <= one || g >= p || g.modpow(&q, &p) != one {
+            return Err(invalid("g", "not a generator of the order-q subgroup"));
+        }
+
+        let h = if proto.h.is_empty() {
+            None
+        } else {
+            let h = decode_integer("h", &proto.h)?;
+            if proto.h_seed.is_empty() {
+                return Err(ConversionError::MissingField("h_seed"));
+            }
+            Some(SecondGenerator { h, seed: proto.h_seed.to_vec() })
+        };
+
+        let mut params = PublicParameters::from_parts(p, q, g, h);
+        if params.h.is_some() && !params.verify_second_generator() {
+            return Err(invalid("h", "does not match its published seed"));
+        }
+        // provenance is carried along, not checked; verify_provenance re-runs the search
+        if !proto.seed.is_empty() {
+            params.provenance = Some(Provenance {
+                seed: proto.seed.to_vec(),
+                counter: proto.seed_counter,
+            });
+        }
+        Ok(params)
+    }
+}
+
+impl From<&PublicParameters> for zkp::PublicParameters {
+    fn from(params: &PublicParameters) -> Self {
+        zkp::PublicParameters {
+            p: encode_integer(params.p()),
+            q: encode_integer(params.q()),
+            g: encode_integer(params.g()),
+            h: params.h.as_ref().map(|second| encode_integer(&second.h)).unwrap_or_default(),
+            h_seed: params.h.as_ref().map(|second| Bytes::copy_from_slice(&second.seed)).unwrap_or_default(),
+            seed: params.provenance.as_ref().map(|provenance| Bytes::copy_from_slice(&provenance.seed)).unwrap_or_default(),
+            seed_counter: params.provenance.as_ref().map_or(0, |provenance| provenance.counter),
+        }
+    }
+}
+
+impl TryFrom<zkp::Commitment> for Commitment {
+    type Error = ConversionError;
+
+    fn try_from(proto: zkp::Commitment) -> Result<Self, Self::Error> {
+        Ok(Commitment {
+            a1: decode_integer("a1", &proto.a1)?,
+            b1: decode_integer("b1", &proto.b1)?,
+            c1: decode_integer("c1", &proto.c1)?,
+        })
+    }
+}
+
+impl From<&Commitment> for zkp::Commitment {
+    fn from(commitment: &Commitment) -> Self {
+        zkp::Commitment {
+            a1: encode_integer(&commitment.a1),
+            b1: encode_integer(&commitment.b1),
+            c1: encode_integer(&commitment.c1),
+        }
+    }
+}
+
+impl TryFrom<zkp::ProofChallenge> for ProofChallenge {
+    type Error = ConversionError;
+
+    fn try_from(proto: zkp::ProofChallenge) -> Result<Self, Self::Error> {
+        Ok(ProofChallenge {
+            y1: decode_integer("y1", &proto.y1)?,
+            y2: decode_integer("y2", &proto.y2)?,
+        })
+    }
+}
+
+impl From<&ProofChallenge> for zkp::ProofChallenge {
+    fn from(challenge: &ProofChallenge) -> Self {
+        zkp::ProofChallenge {
+            y1: encode_integer(&challenge.y1),
+            y2: encode_integer(&challenge.y2),
+        }
+    }
+}
+
+// Commitments and (y1, y2) of a session with compressed elements travel as their representatives
+// in [1, q] (see PublicParameters::compress_element). They are decoded as usual, then expanded here.
+pub fn compress_commitment(commitment: &Commitment, params: &PublicParameters) -> zkp::Commitment {
+    zkp::Commitment {
+        a1: encode_integer(&params.compress_element(&commitment.a1)),
+        b1: encode_integer(&params.compress_element(&commitment.b1)),
+        c1: encode_integer(&params.compress_element(&commitment.c1)),
+    }
+}
+
+pub fn decompress_commitment(commitment: Commitment, params: &PublicParameters) -> Result<Commitment, ConversionError> {
+    Ok(Commitment {
+        a1: decompress("a1", &commitment.a1, params)?,
+        b1: decompress("b1", &commitment.b1, params)?,
+        c1: decompress("c1", &commitment.c1, params)?,
+    })
+}
+
+pub fn compress_challenge_values(values: &ProofChallenge, params: &PublicParameters) -> zkp::ProofChallenge {
+    zkp::ProofChallenge {
+        y1: encode_integer(&params.compress_element(&values.y1)),
+        y2: encode_integer(&params.compress_element(&values.y2)),
+    }
+}
+
+pub fn decompress_challenge_values(values: ProofChallenge, params: &PublicParameters) -> Result<ProofChallenge, ConversionError> {
+    Ok(ProofChallenge {
+        y1: decompress("y1", &values.y1, params)?,
+        y2: decompress("y2", &values.y2, params)?,
+    })
+}
+
+fn decompress(field: &'static str, value: &BigUint, params: &PublicParameters) -> Result<BigUint, ConversionError> {
+    params
+        .decompress_element(value)
+        .ok_or(invalid(field, "not a compressed element"))
+}
+
+impl TryFrom<zkp::Statement> for Statement {
+    type Error = ConversionError;
+
+    fn try_from(proto: zkp::Statement) -> Result<Self, Self::Error> {
+        Ok(Statement {
+            base: decode_integer("statement base", &proto.base)?,
+            value: decode_integer("statement value", &proto.value)?,
+        })
+    }
+}
+
+impl From<&Statement> for zkp::Statement {
+    fn from(statement: &Statement) -> Self {
+        zkp::Statement {
+            base: statement.base.to_bytes_be(),
+            value: statement.value.to_bytes_be(),
+        }
+    }
+}
+
+impl TryFrom<zkp::MultiStatementProof> for MultiZKProof {
+    type Error = ConversionError;
+
+    fn try_from(proto: zkp::MultiStatementProof) -> Result<Self, Self::Error> {
+        if proto.statements.is_empty() {
+            return Err(ConversionError::MissingField("statements"));
+        }
+        if proto.statements.len() != proto.commitments.len() {
+            return Err(invalid("commitments", "expected one per statement"));
+        }
+
+        Ok(MultiZKProof {
+            statements: proto
+                .statements
+                .into_iter()
+                .map(Statement::try_from)
+                .collect::<Result<_, _>>()?,
+            commitments: proto
+                .commitments
+                .iter()
+                .map(|t| decode_integer("commitment", t))
+                .collect::<Result<_, _>>()?,
+            response: ProofResponse {
+                z: decode_integer("z", &proto.z)?,
+            },
+            challenge_hash: decode_integer("challenge", &proto.challenge)?,
+        })
+    }
+}
+
+impl From<&MultiZKProof> for zkp::MultiStatementProof {
+    fn from(proof: &MultiZKProof) -> Self {
+        zkp::MultiStatementProof {
+            statements: proof.statements.iter().map(zkp::Statement::from).collect(),
+            commitments: proof.commitments.iter().map(BigUint::to_bytes_be).collect(),
+            challenge: proof.challenge_hash.to_bytes_be(),
+            z: proof.response.z.to_bytes_be(),
+        }
+    }
+}
+
+impl TryFrom<zkp::ZkProofMessage> for ZKProof {
+    type Error = ConversionError;
+
+    fn try_from(proto: zkp::ZkProofMessage) -> Result<Self, Self::Error> {
+        Ok(ZKProof {
+            commitment: Commitment::try_from(proto.commitment.ok_or(ConversionError::MissingField("commitment"))?)?,
+            challenge: ProofChallenge::try_from(
+                proto.challenge_values.ok_or(ConversionError::MissingField("challenge_values"))?,
+            )?,
+            response: ProofResponse {
+                z: decode_integer("z", &proto.z)?,
+            },
+            challenge_hash: decode_integer("challenge", &proto.challenge)?,
+        })
+    }
+}
+
+impl From<&ZKProof> for zkp::ZkProofMessage {
+    fn from(proof: &ZKProof) -> Self {
+        zkp::ZkProofMessage {
+            commitment: Some(zkp::Commitment::from(&proof.commitment)),
+            challenge_values: Some(zkp::ProofChallenge::from(&proof.challenge)),
+            challenge: encode_integer(&proof.challenge_hash),
+            z: encode_integer(&proof.response.z),
+        }
+    }
+}
+
+impl TryFrom<zkp::ProofBatch> for ProofBatch {
+    type Error = ConversionError;
+
+    fn try_from(proto: zkp::ProofBatch) -> Result<Self, Self::Error> {
+        if proto.commitments.is_empty() {
+            return Err(ConversionError::MissingField("commitments"));
+        }
+        if proto.challenge_values.len() != proto.commitments.len() || proto.z.len() != proto.commitments.len() {
+            return Err(invalid("challenge_values", "expected one commitment, (y1, y2) and z per proof"));
+        }
+
+        let entries = proto
+            .commitments
+            .into_iter()
+            .zip(proto.challenge_values)
+            .zip(&proto.z)
+            .map(|((commitment, challenge), z)| {
+                Ok(BatchEntry {
+                    commitment: Commitment::try_from(commitment)?,
+                    challenge: ProofChallenge::try_from(challenge)?,
+                    z: decode_integer("z", z)?,
+                })
+            })
+            .collect::<Result<_, ConversionError>>()?;
+        Ok(ProofBatch { entries })
+    }
+}
+
+impl From<&ProofBatch> for zkp::ProofBatch {
+    fn from(proof: &ProofBatch) -> Self {
+        zkp::ProofBatch {
+            commitments: proof.entries.iter().map(|entry| zkp::Commitment::from(&entry.commitment)).collect(),
+            challenge_values: proof
+                .entries
+                .iter()
+                .map(|entry| zkp::ProofChallenge::from(&entry.challenge))
+                .collect(),
+            z: proof.entries.iter().map(|entry| entry.z.to_bytes_be()).collect(),
+        }
+    }
+}
+
+impl From<zkp::GroupKind> for GroupKind {
+    fn from(proto: zkp::GroupKind) -> Self {
+        match proto {
+            zkp::GroupKind::GroupModp => GroupKind::Modp,
+            zkp::GroupKind::GroupSecp256k1 => GroupKind::Secp256k1,
+            zkp::GroupKind::GroupP256 => GroupKind::P256,
+            zkp::GroupKind::GroupBls12381G1 => GroupKind::Bls12381G1,
+            zkp::GroupKind::GroupRistretto255 => GroupKind::Ristretto255,
+        }
+    }
+}
+
+impl From<GroupKind> for zkp::GroupKind {
+    fn from(group: GroupKind) -> Self {
+        match group {
+            GroupKind::Modp => zkp::GroupKind::GroupModp,
+            GroupKind::Secp256k1 => zkp::GroupKind::GroupSecp256k1,
+            GroupKind::P256 => zkp::GroupKind::GroupP256,
+            GroupKind::Bls12381G1 => zkp::GroupKind::GroupBls12381G1,
+            GroupKind::Ristretto255 => zkp::GroupKind::GroupRistretto255,
+        }
+    }
+}
+
+impl From<zkp::ChallengeMode> for ChallengeMode {
+    fn from(proto: zkp::ChallengeMode) -> Self {
+        match proto {
+            zkp::ChallengeMode::ChallengeFiatShamir => ChallengeMode::FiatShamir,
+            zkp::ChallengeMode::ChallengeInteractive => ChallengeMode::Interactive,
+        }
+    }
+}
+
+impl From<ChallengeMode> for zkp::ChallengeMode {
+    fn from(mode: ChallengeMode) -> Self {
+        match mode {
+            ChallengeMode::FiatShamir => zkp::ChallengeMode::ChallengeFiatShamir,
+            ChallengeMode::Interactive => zkp::ChallengeMode::ChallengeInteractive,
+        }
+    }
+}
+
+impl From<zkp::CommitmentOrigin> for CommitmentOrigin {
+    fn from(proto: zkp::CommitmentOrigin) -> Self {
+        match proto {
+            zkp::CommitmentOrigin::CommitmentClient => CommitmentOrigin::Client,
+            zkp::CommitmentOrigin::CommitmentRegistered => CommitmentOrigin::Registered,
+        }
+    }
+}
+
+impl From<CommitmentOrigin> for zkp::CommitmentOrigin {
+    fn from(origin: CommitmentOrigin) -> Self {
+        match origin {
+            CommitmentOrigin::Client => zkp::CommitmentOrigin::CommitmentClient,
+            CommitmentOrigin::Registered => zkp::CommitmentOrigin::CommitmentRegistered,
+        }
+    }
+}
+
+impl From<Stage> for zkp::SessionStage {
+    fn from(stage: Stage) -> Self {
+        match stage {
+            Stage::Initialized => zkp::SessionStage::StageInitialized,
+            Stage::ChallengeIssued => zkp::SessionStage::StageChallengeIssued,
+            Stage::Verified => zkp::SessionStage::StageVerified,
+            Stage::Failed => zkp::SessionStage::StageFailed,
+            Stage::Expired => zkp::SessionStage::StageExpired,
+            Stage::Cancelled => zkp::SessionStage::StageCancelled,
+        }
+    }
+}
+
+impl From<GroupKind> for zkp::v2::GroupKind {
+    fn from(group: GroupKind) -> Self {
+        match group {
+            GroupKind::Modp => zkp::v2::GroupKind::GroupModp,
+            GroupKind::Secp256k1 => zkp::v2::GroupKind::GroupSecp256k1,
+            GroupKind::P256 => zkp::v2::GroupKind::GroupP256,
+            GroupKind::Bls12381G1 => zkp::v2::GroupKind::GroupBls12381G1,
+            GroupKind::Ristretto255 => zkp::v2::GroupKind::GroupRistretto255,
+        }
+    }
+}
+
+// zkp.v2 requests map onto their v1 counterparts, which the server already handles. The enums of
+// both packages share their values, so those are copied as they are.
+impl From<zkp::v2::InitializeRequest> for zkp::InitializeRequest {
+    fn from(v2: zkp::v2::InitializeRequest) -> Self {
+        Self {
+            bit_size: v2.bit_size,
+            group: v2.group,
+            challenge_mode: v2.challenge_mode,
+            user_id: String::new(),
+            recovery: false,
+            compressed_elements: v2.compressed_elements,
+        }
+    }
+}
+
+impl From<zkp::v2::Commitment> for zkp::Commitment {
+    fn from(v2: zkp::v2::Commitment) -> Self {
+        Self {
+            a1: v2.a1,
+            b1: v2.b1,
+            c1: v2.c1,
+        }
+    }
+}
+
+impl From<zkp::v2::ChallengeValues> for zkp::ProofChallenge {
+    fn from(v2: zkp::v2::ChallengeValues) -> Self {
+        Self { y1: v2.y1, y2: v2.y2 }
+    }
+}
+
+impl From<zkp::v2::CommitmentRequest> for zkp::CommitmentRequest {
+    fn from(v2: zkp::v2::CommitmentRequest) -> Self {
+        Self {
+            session_id: v2.session_id,
+            commitment: v2.commitment.map(Into::into),
+            challenge_values: v2.challenge_values.map(Into::into),
+        }
+    }
+}
+
+impl From<zkp::v2::ResponseRequest> for zkp::VerifyProofRequest {
+    fn from(v2: zkp::v2::ResponseRequest) -> Self {
+        Self {
+            session_id: v2.session_id,
+            z: v2.z,
+        }
+    }
+}
+
+impl From<zkp::v2::ProofRequest> for zkp::OneShotProofRequest {
+    fn from(v2: zkp::v2::ProofRequest) -> Self {
+        Self {
+            session_id: v2.session_id,
+            proof: v2.proof.map(|proof| zkp::ZkProofMessage {
+                commitment: proof.commitment.map(Into::into),
+                challenge_values: proof.challenge_values.map(Into::into),
+                challenge: proof.challenge,
+                z: proof.z,
+            }),
+        }
+    }
+}
+
+impl From<zkp::v2::SessionRequest> for zkp::SessionStatusRequest {
+    fn from(v2: zkp::v2::SessionRequest) -> Self {
+        Self {
+            session_id: v2.session_id,
+        }
+    }
+}
+
+impl From<zkp::v2::SessionRequest> for zkp::CancelRequest {
+    fn from(v2: zkp::v2::SessionRequest) -> Self {
+        Self {
+            session_id: v2.session_id,
+        }
+    }
+}
+
+impl From<zkp::PublicParameters> for zkp::v2::PublicParameters {
+    fn from(v1: zkp::PublicParameters) -> Self {
+        Self {
+            p: v1.p,
+            q: v1.q,
+            g: v1.g,
+            h: v1.h,
+            h_seed: v1.h_seed,
+            seed: v1.seed,
+            seed_counter: v1.seed_counter,
+        }
+    }
+}
+
+fn decode_element<G: Group>(group: &G, field: &'static str, bytes: &[u8]) -> Result<G::Element, ConversionError> {
+    group
+        .decode_element(bytes)
+        .ok_or_else(|| invalid(field, "not an encoded element of the group"))
+}
+
+// Decodes the commitment, challenge and response of a DLEQ-shaped proof. z must be reduced.
+fn decode_sigma<G: Group>(
+    group: &G,
+    t1: &[u8],
+    t2: &[u8],
+    challenge: &[u8],
+    z: &[u8],
+) -> Result<DleqProof<G>, ConversionError> {
+    let commitment = DleqCommitment {
+        t1: decode_element(group, "t1", t1)?,
+        t2: decode_element(group, "t2", t2)?,
+    };
+    let z = decode_integer("z", z)?;
+    if &z >= group.order() {
+        return Err(invalid("z", "not reduced mod the group order"));
+    }
+
+    Ok(SigmaProof {
+        commitment,
+        challenge: decode_integer("challenge", challenge)?,
+        response: group.scalar_from_integer(&z),
+    })
+}
+
+// Decodes a DLEQ proof over `group`. Every element must be a canonical non-identity encoding.
+pub fn decode_dleq<G: Group>(
+    group: &G,
+    proto: &zkp::DleqProof,
+) -> Result<(DleqStatement<G::Element>, DleqProof<G>), ConversionError> {
+    let statement = DleqStatement {
+        g: decode_element(group, "g", &proto.g)?,
+        h: decode_element(group, "h", &proto.h)?,
+        y1: decode_element(group, "y1", &proto.y1)?,
+        y2: decode_element(group, "y2", &proto.y2)?,
+    };
+    let proof = decode_sigma(group, &proto.t1, &proto.t2, &proto.challenge, &proto.z)?;
+    Ok((statement, proof))
+}
+
+pub fn encode_dleq<G: Group>(group: &G, statement: &DleqStatement<G::Element>, proof: &DleqProof<G>) -> zkp::DleqProof {
+    zkp::DleqProof {
+        g: group.encode_element(&statement.g),
+        h: group.encode_element(&statement.h),
+        y1: group.encode_element(&statement.y1),
+        y2: group.encode_element(&statement.y2),
+        t1: group.encode_element(&proof.commitment.t1),
+        t2: group.encode_element(&proof.commitment.t2),
+        challenge: proof.challenge.to_bytes_be(),
+        z: group.scalar_to_integer(&proof.response).to_bytes_be(),
+    }
+}
+
+// The request's statement and proof; the domain is left in the request
+pub fn decode_compact_dleq<G: Group>(
+    group: &G,
+    proto: &zkp::CompactDleqProofRequest,
+) -> Result<(DleqStatement<G::Element>, CompactDleqProof<G>), ConversionError> {
+    let statement = DleqStatement {
+        g: decode_element(group, "g", &proto.g)?,
+        h: decode_element(group, "h", &proto.h)?,
+        y1: decode_element(group, "y1", &proto.y1)?,
+        y2: decode_element(group, "y2", &proto.y2)?,
+    };
+    let proof = CompactDleqProof::from_bytes(group, &proto.proof)
+        .ok_or_else(|| invalid("proof", "not two reduced scalars"))?;
+    Ok((statement, proof))
+}
+
+pub fn encode_compact_dleq<G: Group>(
+    group: &G,
+    session_id: &str,
+    domain: &[u8],
+    statement: &DleqStatement<G::Element>,
+    proof: &CompactDleqProof<G>,
+) -> zkp::CompactDleqProofRequest {
+    zkp::CompactDleqProofRequest {
+        session_id: session_id.to_string(),
+        g: group.encode_element(&statement.g),
+        h: group.encode_element(&statement.h),
+        y1: group.encode_element(&statement.y1),
+        y2: group.encode_element(&statement.y2),
+        proof: proof.to_bytes(group),
+        domain: domain.to_vec(),
+    }
+}
+
+pub fn decode_blinded_elements<G: Group>(
+    group: &G,
+    proto: &zkp::OprfEvaluationRequest,
+) -> Result<Vec<G::Element>, ConversionError> {
+    proto
+        .blinded_elements
+        .iter()
+        .map(|bytes| decode_element(group, "blinded_elements", bytes))
+        .collect()
+}
+
+pub fn decode_dh_share<G: Group>(
+    group: &G,
+    proto: &zkp::DhShareProof,
+) -> Result<(DhShare<G::Element>, DhShareProof<G>), ConversionError> {
+    let share = DhShare {
+        share: decode_element(group, "share", &proto.share)?,
+        base: decode_element(group, "base", &proto.base)?,
+        derived: decode_element(group, "derived", &proto.derived)?,
+    };
+    let proof = decode_sigma(group, &proto.t1, &proto.t2, &proto.challenge, &proto.z)?;
+    Ok((share, proof))
+}
+
+pub fn encode_dh_share<G: Group>(group: &G, share: &DhShare<G::Element>, proof: &DhShareProof<G>) -> zkp::DhShareProof {
+    zkp::DhShareProof {
+        share: group.encode_element(&share.share),
+        base: group.encode_element(&share.base),
+        derived: group.encode_element(&share.derived),
+        t1: group.encode_element(&proof.commitment.t1),
+        t2: group.encode_element(&proof.commitment.t2),
+        challenge: proof.challenge.to_bytes_be(),
+        z: group.scalar_to_integer(&proof.response).to_bytes_be(),
+    }
+}
+
+// Decodes a range proof into its commitment, bit count and proof. Group membership is left to the
+// verifier.
+pub fn decode_range(proto: &zkp::RangeProof) -> Result<(PedersenCommitment, u32, RangeProof), ConversionError> {
+    if proto.bits == 0 || proto.bits > range::MAX_BITS {
+        return Err(invalid("bits", "out of range"));
+    }
+    if proto.bit_proofs.len() != proto.bits as usize {
+        return Err(invalid("bit_proofs", "expected one per bit"));
+    }
+
+    let (commitment, response) = proto
+        .bit_proofs
+        .iter()
+        .map(|bit| {
+            let commitment = BitCommitment {
+                c: decode_integer("c", &bit.c)?,
+                t0: decode_integer("t0", &bit.t0)?,
+                t1: decode_integer("t1", &bit.t1)?,
+            };
+            let response = BitResponse {
+                c0: decode_integer("c0", &bit.c0)?,
+                s0: decode_integer("s0", &bit.s0)?,
+                s1: decode_integer("s1", &bit.s1)?,
+            };
+            Ok((commitment, response))
+        })
+        .collect::<Result<Vec<_>, ConversionError>>()?
+        .into_iter()
+        .unzip();
+
+    let proof = SigmaProof {
+        commitment,
+        challenge: decode_integer("challenge", &proto.challenge)?,
+        response,
+    };
+    let c = decode_integer("commitment", &proto.commitment)?;
+    Ok((PedersenCommitment { c }, proto.bits, proof))
+}
+
+pub fn encode_range(commitment: &PedersenCommitment, bits: u32, proof: &RangeProof) -> zkp::RangeProof {
+    zkp::RangeProof {
+        commitment: commitment.c.to_bytes_be(),
+        bits,
+        bit_proofs: proof
+            .commitment
+            .iter()
+            .zip(&proof.response)
+            .map(|(commitment, response)| zkp::BitProof {
+                c: commitment.c.to_bytes_be(),
+                t0: commitment.t0.to_bytes_be(),
+                t1: commitment.t1.to_bytes_be(),
+                c0: response.c0.to_bytes_be(),
+                s0: response.s0.to_bytes_be(),
+                s1: response.s1.to_bytes_be(),
+            })
+            .collect(),
+        challenge: proof.challenge.to_bytes_be(),
+    }
+}
@@ -0,0 +1,44 @@
+use rand::rngs::OsRng;
+
+use crate::chaum_pedersen::{SecureRng, Transcript};
+use crate::group::{Dleq, DleqStatement, Group};
+use crate::sigma::{self, SigmaProtocol};
+
+pub use crate::group::DleqProof;
+
+// The textbook statement y1 = g^x and y2 = h^x for one secret x, without the (a, b) secrets and
+// commitment tuple of `chaum_pedersen::Prover`. `group` is any `Group`, e.g. `PublicParameters`
+// for the modp group.
+
+pub fn prove<G: Group + Clone>(group: &G, g: &G::Element, h: &G::Element, x: &G::Scalar) -> DleqProof<G> {
+    prove_with_rng(group, g, h, x, &mut OsRng)
+}
+
+pub fn prove_with_rng<G: Group + Clone>(
+    group: &G,
+    g: &G::Element,
+    h: &G::Element,
+    x: &G::Scalar,
+    rng: &mut dyn SecureRng,
+) -> DleqProof<G> {
+    let dleq = Dleq::new(group.clone());
+    let statement = dleq.statement(g.clone(), h.clone(), x);
+    sigma::prove_with_rng(&dleq, &mut Transcript::new(Dleq::<G>::LABEL), &statement, x, rng)
+}
+
+pub fn verify<G: Group + Clone>(
+    group: &G,
+    g: &G::Element,
+    h: &G::Element,
+    y1: &G::Element,
+    y2: &G::Element,
+    proof: &DleqProof<G>,
+) -> bool {
+    let statement = DleqStatement {
+        g: g.clone(),
+        h: h.clone(),
+        y1: y1.clone(),
+        y2: y2.clone(),
+    };
+    Dleq::new(group.clone()).verify_proof(&statement, proof)
+}
@@ -0,0 +1,145 @@
+use num_bigint::BigUint;
+use rand::rngs::OsRng;
+use subtle::ConstantTimeEq;
+
+use crate::chaum_pedersen::{SecureRng, Transcript};
+use crate::group::{Dleq, DleqProof, DleqStatement, Group};
+use crate::sigma;
+
+// Exponential ElGamal over any `Group`: m is encrypted as (g^r, g^m * pk^r), so ciphertexts add
+// homomorphically and small plaintexts (tallies, bids) can be recovered by search.
+
+#[derive(Debug, Clone)]
+pub struct Ciphertext<E> {
+    pub c1: E,  // g^r
+    pub c2: E,  // g^m * pk^r
+}
+
+// Shows that a ciphertext encrypts a claimed m: log_g(c1) = log_pk(c2 / g^m) = r
+pub type EncryptionProof<G> = DleqProof<G>;
+
+#[derive(Debug, Clone)]
+pub struct ElGamal<G> {
+    pub group: G,
+}
+
+impl<G: Group + Clone> ElGamal<G> {
+    pub fn new(group: G) -> Self {
+        Self { group }
+    }
+
+    // Fresh secret key sk and public key pk = g^sk
+    pub fn generate_keypair(&self) -> (G::Scalar, G::Element) {
+        self.generate_keypair_with_rng(&mut OsRng)
+    }
+
+    pub fn generate_keypair_with_rng(&self, rng: &mut dyn SecureRng) -> (G::Scalar, G::Element) {
+        let sk = self.group.random_scalar(rng);
+        let pk = self.group.exp(&self.group.generator(), &sk);
+        (sk, pk)
+    }
+
+    // The ciphertext and the randomness r, which the sender keeps to prove the encryption
+    pub fn encrypt(&self, pk: &G::Element, m: u64) -> (Ciphertext<G::Element>, G::Scalar) {
+        self.encrypt_with_rng(pk, m, &mut OsRng)
+    }
+
+    pub fn encrypt_with_rng(
+        &self,
+        pk: &G::Element,
+        m: u64,
+        rng: &mut dyn SecureRng,
+    ) -> (Ciphertext<G::Element>, G::Scalar) {
+        let group = &self.group;
+        let r = group.random_scalar(rng);
+        let c1 = group.exp(&group.generator(), &r);
+        let c2 = group.mul(&self.encode(m), &group.exp(pk, &r));
+        (Ciphertext { c1, c2 }, r)
+    }
+
+    // Encrypts the sum of the plaintexts
+    pub fn add(&self, a: &Ciphertext<G::Element>, b: &Ciphertext<G::Element>) -> Ciphertext<G::Element> {
+        Ciphertext {
+            c1: self.group.mul(&a.c1, &b.c1),
+            c2: self.group.mul(&a.c2, &b.c2),
+        }
+    }
+
+    // Recovers m by trying 0..=max, so only small plaintexts are practical. None if m > max.
+    pub fn decrypt(&self, sk: &G::Scalar, ciphertext: &Ciphertext<G::Element>, max: u64) -> Option<u64> {
+        let group = &self.group;
+        let shared = group.exp(&ciphertext.c1, &self.negate(sk));
+        let target = group.encode_element(&group.mul(&ciphertext.c2, &shared));
+
+        let g = group.generator();
+        let mut candidate = self.encode(0);
+        for m in 0..=max {
+            if bool::from(group.encode_element(&candidate).ct_eq(&target)) {
+                return Some(m);
+            }
+            candidate = group.mul(&candidate, &g);
+        }
+        None
+    }
+
+    pub fn prove_encryption(
+        &self,
+        pk: &G::Element,
+        ciphertext: &Ciphertext<G::Element>,
+        m: u64,
+        r: &G::Scalar,
+    ) -> EncryptionProof<G> {
+        self.prove_encryption_with_rng(pk, ciphertext, m, r, &mut OsRng)
+    }
+
+    pub fn prove_encryption_with_rng(
+        &self,
+        pk: &G::Element,
+        ciphertext: &Ciphertext<G::Element>,
+        m: u64,
+        r: &G::Scalar,
+        rng: &mut dyn SecureRng,
+    ) -> EncryptionProof<G> {
+        let statement = self.statement(pk, ciphertext, m);
+        let dleq = Dleq::new(self.group.clone());
+        sigma::prove_with_rng(&dleq, &mut transcript(m), &statement, r, rng)
+    }
+
+    pub fn verify_encryption(
+        &self,
+        pk: &G::Element,
+        ciphertext: &Ciphertext<G::Element>,
+        m: u64,
+        proof: &EncryptionProof<G>,
+    ) -> bool {
+        let statement = self.statement(pk, ciphertext, m);
+        sigma::verify_with_transcript(&Dleq::new(self.group.clone()), &mut transcript(m), &statement, proof)
+    }
+
+    // g^m
+    fn encode(&self, m: u64) -> G::Element {
+        self.group.exp(&self.group.generator(), &self.group.scalar_from_integer(&BigUint::from(m)))
+    }
+
+    fn negate(&self, scalar: &G::Scalar) -> G::Scalar {
+        let order = self.group.order();
+        self.group.scalar_from_integer(&((order - self.group.scalar_to_integer(scalar)) % order))
+    }
+
+    fn statement(&self, pk: &G::Element, ciphertext: &Ciphertext<G::Element>, m: u64) -> DleqStatement<G::Element> {
+        let group = &self.group;
+        let unblinded = group.exp(&group.generator(), &self.negate(&group.scalar_from_integer(&BigUint::from(m))));
+        DleqStatement {
+            g: group.generator(),
+            h: pk.clone(),
+            y1: ciphertext.c1.clone(),
+            y2: group.mul(&ciphertext.c2, &unblinded),
+        }
+    }
+}
+
+fn transcript(m: u64) -> Transcript {
+    let mut transcript = Transcript::new(b"elgamal-encryption");
+    transcript.append_message(b"m", &m.to_be_bytes());
+    transcript
+}
@@ -0,0 +1,320 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use num_bigint::BigUint;
+use prost::Message;
+use rand::rngs::OsRng;
+
+use crate::chaum_pedersen::{Commitment, ProofChallenge, PublicParameters};
+use crate::session::{now_secs, Outcome, SessionId};
+use crate::zkp;
+
+// Signed session transcripts, the evidence GetSessionTranscript hands out. The signature is Ed25519
+// so the prover and third-party auditors can check it with the server's public key alone.
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum EvidenceError {
+    Malformed(String),
+    UnexpectedKey,
+    BadSignature,
+}
+
+impl fmt::Display for EvidenceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EvidenceError::Malformed(reason) => write!(f, "malformed signed transcript: {}", reason),
+            EvidenceError::UnexpectedKey => write!(f, "transcript was signed by an unexpected key"),
+            EvidenceError::BadSignature => write!(f, "transcript signature does not verify"),
+        }
+    }
+}
+
+impl std::error::Error for EvidenceError {}
+
+// Builds the transcript of a concluded Chaum-Pedersen run
+pub fn transcript(
+    session_id: &str,
+    params: &PublicParameters,
+    commitment: &Commitment,
+    challenge_values: &ProofChallenge,
+    challenge: &BigUint,
+    outcome: &Outcome,
+) -> zkp::SessionTranscript {
+    zkp::SessionTranscript {
+        session_id: session_id.to_string(),
+        params_digest: params.digest().to_vec(),
+        commitment: Some(zkp::Commitment::from(commitment)),
+        challenge_values: Some(zkp::ProofChallenge::from(challenge_values)),
+        challenge: challenge.to_bytes_be(),
+        z: outcome.z.to_bytes_be(),
+        verified: outcome.verified,
+        verified_at: outcome.at,
+        commitment_digest: commitment.digest().to_vec(),
+    }
+}
+
+pub struct TranscriptSigner {
+    key: SigningKey,
+}
+
+impl TranscriptSigner {
+    // A fresh key, good for one server run; transcripts it signed cannot be checked against a later run's key
+    pub fn generate() -> Self {
+        Self {
+            key: SigningKey::generate(&mut OsRng),
+        }
+    }
+
+    pub fn from_seed(seed: &[u8; 32]) -> Self {
+        Self {
+            key: SigningKey::from_bytes(seed),
+        }
+    }
+
+    pub fn public_key(&self) -> [u8; 32] {
+        self.key.verifying_key().to_bytes()
+    }
+
+    pub fn sign(&self, transcript: &zkp::SessionTranscript) -> zkp::SignedTranscript {
+        let bytes = transcript.encode_to_vec();
+        zkp::SignedTranscript {
+            signature: self.key.sign(&bytes).to_bytes().to_vec(),
+            transcript: bytes,
+            public_key: self.public_key().to_vec(),
+        }
+    }
+}
+
+impl fmt::Debug for TranscriptSigner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TranscriptSigner")
+            .field("public_key", &hex::encode(self.public_key()))
+            .finish_non_exhaustive()
+    }
+}
+
+// Checks the signature and, when `expected_key` is given, that the transcript was signed with it.
+// Without an expected key this only shows the blob is intact, not who produced it.
+pub fn open(
+    signed: &zkp::SignedTranscript,
+    expected_key: Option<&[u8; 32]>,
+) -> Result<zkp::SessionTranscript, EvidenceError> {
+    let public_key: [u8; 32] = signed
+        .public_key
+        .as_slice()
+        .try_into()
+        .map_err(|_| EvidenceError::Malformed("public key must be 32 bytes".to_string()))?;
+    if expected_key.is_some_and(|expected| expected != &public_key) {
+        return Err(EvidenceError::UnexpectedKey);
+    }
+    let key = VerifyingKey::from_bytes(&public_key).map_err(|e| EvidenceError::Malformed(e.to_string()))?;
+    let signature = Signature::from_slice(&signed.signature).map_err(|e| EvidenceError::Malformed(e.to_string()))?;
+    key.verify(&signed.transcript, &signature)
+        .map_err(|_| EvidenceError::BadSignature)?;
+
+    zkp::SessionTranscript::decode(signed.transcript.as_slice()).map_err(|e| EvidenceError::Malformed(e.to_string()))
+}
+
+// Where the server archives the signed transcripts of concluded runs, so they outlive the session
+// and a relying party can later show a third party that a proof was verified, and when. Each is
+// stored once; a store may refuse or overwrite a second one for the same session.
+pub trait TranscriptSink: Send + Sync + fmt::Debug {
+    fn store(&self, id: &SessionId, signed: &zkp::SignedTranscript, verified_at: u64) -> io::Result<()>;
+    fn load(&self, id: &SessionId) -> io::Result<Option<zkp::SignedTranscript>>;
+    // Drops transcripts verified more than `retention` ago, returning how many went
+    fn prune(&self, retention: Duration) -> io::Result<usize>;
+}
+
+// Keeps nothing; transcripts are then only available while their session lasts
+#[derive(Debug, Default)]
+pub struct NoopTranscriptSink;
+
+impl TranscriptSink for NoopTranscriptSink {
+    fn store(&self, _id: &SessionId, _signed: &zkp::SignedTranscript, _verified_at: u64) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn load(&self, _id: &SessionId) -> io::Result<Option<zkp::SignedTranscript>> {
+        Ok(None)
+    }
+
+    fn prune(&self, _retention: Duration) -> io::Result<usize> {
+        Ok(0)
+    }
+}
+
+// One file per session in a directory, `<session id>.pb` holding the encoded SignedTranscript
+#[derive(Debug)]
+pub struct FileTranscriptSink {
+    dir: PathBuf,
+}
+
+impl FileTranscriptSink {
+    pub fn open(dir: impl Into<PathBuf>) -> io::Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(Self { dir })
+    }
+
+    fn path(&self, id: &SessionId) -> PathBuf {
+        self.dir.join(format!("{}.pb", id))
+    }
+}
+
+impl TranscriptSink for FileTranscriptSink {
+    // written to a temporary file and renamed, so a crash never leaves half a transcript
+    fn store(&self, id: &SessionId, signed: &zkp::SignedTranscript, _verified_at: u64) -> io::Result<()> {
+        let partial = self.dir.join(format!("{}.partial", id));
+        fs::write(&partial, signed.encode_to_vec())?;
+        fs::rename(partial, self.path(id))
+    }
+
+    fn load(&self, id: &SessionId) -> io::Result<Option<zkp::SignedTranscript>> {
+        match fs::read(self.path(id)) {
+            Ok(bytes) => zkp::SignedTranscript::decode(bytes.as_slice())
+                .map(Some)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    // reads the verification time from each transcript rather than trusting file times
+    fn prune(&self, retention: Duration) -> io::Result<usize> {
+        let cutoff = now_secs().saturating_sub(retention.as_secs());
+        let mut pruned = 0;
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().is_none_or(|extension| extension != "pb") {
+                continue;
+            }
+            let verified_at = zkp::SignedTranscript::decode(fs::read(&path)?.as_slice())
+                .ok()
+                .and_then(|signed| zkp::SessionTranscript::decode(signed.transcript.as_slice()).ok())
+                .map(|transcript| transcript.verified_at);
+            // an unreadable file is left for someone to look at, not silently dropped
+            if verified_at.is_some_and(|at| at < cutoff) {
+                fs::remove_file(path)?;
+                pruned += 1;
+            }
+        }
+        Ok(pruned)
+    }
+}
+
+#[cfg(feature = "transcript-sqlite")]
+#[derive(Debug)]
+pub struct SqliteTranscriptSink {
+    conn: std::sync::Mutex<rusqlite::Connection>,
+}
+
+#[cfg(feature = "transcript-sqlite")]
+impl SqliteTranscriptSink {
+    pub fn open(path: impl AsRef<std::path::Path>) -> rusqlite::Result<Self> {
+        let conn = rusqlite::Connection::open(path)?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS transcripts (
+                session_id TEXT PRIMARY KEY,
+                verified_at INTEGER NOT NULL,
+                signed BLOB NOT NULL
+            )",
+        )?;
+        Ok(Self {
+            conn: std::sync::Mutex::new(conn),
+        })
+    }
+}
+
+#[cfg(feature = "transcript-sqlite")]
+impl TranscriptSink for SqliteTranscriptSink {
+    fn store(&self, id: &SessionId, signed: &zkp::SignedTranscript, verified_at: u64) -> io::Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO transcripts (session_id, verified_at, signed) VALUES (?1, ?2, ?3)",
+            rusqlite::params![id.to_string(), verified_at as i64, signed.encode_to_vec()],
+        )
+        .map_err(io::Error::other)?;
+        Ok(())
+    }
+
+    fn load(&self, id: &SessionId) -> io::Result<Option<zkp::SignedTranscript>> {
+        use rusqlite::OptionalExtension;
+
+        let conn = self.conn.lock().unwrap();
+        let bytes: Option<Vec<u8>> = conn
+            .query_row("SELECT signed FROM transcripts WHERE session_id = ?1", [id.to_string()], |row| row.get(0))
+            .optional()
+            .map_err(io::Error::other)?;
+        bytes
+            .map(|bytes| zkp::SignedTranscript::decode(bytes.as_slice()).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)))
+            .transpose()
+    }
+
+    fn prune(&self, retention: Duration) -> io::Result<usize> {
+        let cutoff = now_secs().saturating_sub(retention.as_secs()) as i64;
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM transcripts WHERE verified_at < ?1", [cutoff])
+            .map_err(io::Error::other)
+    }
+}
+
+// How often the archive prunes, at most; pruning may walk the whole store
+const PRUNE_INTERVAL: Duration = Duration::from_secs(3600);
+
+// A transcript sink and how long its transcripts are kept. Without a retention they are kept for good.
+#[derive(Debug)]
+pub struct TranscriptArchive {
+    sink: Box<dyn TranscriptSink>,
+    retention: Option<Duration>,
+    last_pruned: AtomicU64,
+}
+
+impl TranscriptArchive {
+    pub fn new(sink: Box<dyn TranscriptSink>) -> Self {
+        Self {
+            sink,
+            retention: None,
+            last_pruned: AtomicU64::new(0),
+        }
+    }
+
+    pub fn with_retention(mut self, retention: Duration) -> Self {
+        self.retention = Some(retention);
+        self
+    }
+
+    pub fn store(&self, id: &SessionId, signed: &zkp::SignedTranscript, verified_at: u64) -> io::Result<()> {
+        self.sink.store(id, signed, verified_at)
+    }
+
+    pub fn load(&self, id: &SessionId) -> io::Result<Option<zkp::SignedTranscript>> {
+        self.sink.load(id)
+    }
+
+    // Applies the retention if the last pruning was long enough ago
+    pub fn prune_if_due(&self) -> io::Result<usize> {
+        let Some(retention) = self.retention else {
+            return Ok(0);
+        };
+        let now = now_secs();
+        let last = self.last_pruned.load(Ordering::Relaxed);
+        if now.saturating_sub(last) < PRUNE_INTERVAL.as_secs()
+            || self.last_pruned.compare_exchange(last, now, Ordering::Relaxed, Ordering::Relaxed).is_err()
+        {
+            return Ok(0);
+        }
+        self.sink.prune(retention)
+    }
+}
+
+impl Default for TranscriptArchive {
+    fn default() -> Self {
+        Self::new(Box::new(NoopTranscriptSink))
+    }
+}
@@ -0,0 +1,265 @@
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::{ptr, slice};
+
+use num_bigint::BigUint;
+
+use crate::chaum_pedersen::{ParameterPolicy, Prover, PublicParameters, Verifier, ZKProof};
+
+// C ABI over the prover and verifier, for C and C++ services checking proofs made by this crate.
+// Integers cross as big-endian byte strings and proofs in the encoding of `ZKProof::to_bytes`.
+// The header is include/zkp_chaum_pedersen.h, regenerated by cbindgen when building with `ffi`,
+// so the `///` comments here are what C callers read.
+
+/// Outcome of every call that can fail
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ZkpStatus {
+    Ok = 0,
+    /// A required pointer was null
+    NullPointer = 1,
+    /// The parameters are malformed or fail validation
+    InvalidParameters = 2,
+    /// A secret is not in [1, q)
+    InvalidSecret = 3,
+    /// The proof bytes do not decode; a proof that decodes but does not verify is not an error
+    InvalidProof = 4,
+    /// The library panicked; nothing was written to the outputs
+    Panic = 5,
+}
+
+/// An opaque parameter set (p, q, g); release with zkp_params_free
+pub struct ZkpParams(PublicParameters);
+
+/// Bytes allocated by the library; release with zkp_buffer_free
+#[repr(C)]
+#[derive(Debug)]
+pub struct ZkpBuffer {
+    pub data: *mut u8,
+    pub len: usize,
+}
+
+impl ZkpBuffer {
+    fn new(bytes: Vec<u8>) -> Self {
+        let bytes = bytes.into_boxed_slice();
+        let len = bytes.len();
+        Self {
+            data: Box::into_raw(bytes).cast(),
+            len,
+        }
+    }
+}
+
+// Runs `f`, turning a panic into ZkpStatus::Panic so it never unwinds into C
+fn guard(f: impl FnOnce() -> ZkpStatus) -> ZkpStatus {
+    catch_unwind(AssertUnwindSafe(f)).unwrap_or(ZkpStatus::Panic)
+}
+
+// A (pointer, length) input; null is only accepted for an empty one
+unsafe fn input<'a>(data: *const u8, len: usize) -> Option<&'a [u8]> {
+    if data.is_null() {
+        return (len == 0).then_some(&[]);
+    }
+    Some(unsafe { slice::from_raw_parts(data, len) })
+}
+
+/// Generates a fresh parameter set with a `bits`-bit safe prime p. This takes seconds at
+/// 2048 bits; generate once and share the result through zkp_params_to_bytes.
+///
+/// # Safety
+/// `out` must be valid for writing a pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn zkp_params_generate(bits: u64, out: *mut *mut ZkpParams) -> ZkpStatus {
+    if out.is_null() {
+        return ZkpStatus::NullPointer;
+    }
+    guard(|| {
+        let policy = ParameterPolicy::default();
+        if !(policy.min_bits..=policy.max_bits).contains(&bits) {
+            return ZkpStatus::InvalidParameters;
+        }
+        let params = Box::new(ZkpParams(PublicParameters::new(bits)));
+        unsafe { out.write(Box::into_raw(params)) };
+        ZkpStatus::Ok
+    })
+}
+
+/// Loads big-endian p, q and g received from elsewhere. They are validated in full (sizes,
+/// p = 2q + 1, primality, the order of g) since a verifier must not trust them blindly.
+///
+/// # Safety
+/// Each input must point to `len` readable bytes, and `out` must be valid for writing a pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn zkp_params_from_bytes(
+    p: *const u8,
+    p_len: usize,
+    q: *const u8,
+    q_len: usize,
+    g: *const u8,
+    g_len: usize,
+    out: *mut *mut ZkpParams,
+) -> ZkpStatus {
+    let (Some(p), Some(q), Some(g)) = (unsafe { input(p, p_len) }, unsafe { input(q, q_len) }, unsafe { input(g, g_len) })
+    else {
+        return ZkpStatus::NullPointer;
+    };
+    if out.is_null() {
+        return ZkpStatus::NullPointer;
+    }
+    guard(|| {
+        let [p, q, g] = [p, q, g].map(BigUint::from_bytes_be);
+        let params = PublicParameters::from_parts(p, q, g, None);
+        if params.validate().is_err() {
+            return ZkpStatus::InvalidParameters;
+        }
+        unsafe { out.write(Box::into_raw(Box::new(ZkpParams(params)))) };
+        ZkpStatus::Ok
+    })
+}
+
+/// Writes p, q and g as big-endian byte strings, each to be released with zkp_buffer_free
+///
+/// # Safety
+/// `params` must come from this library, and `p`, `q` and `g` must be valid for writing a buffer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn zkp_params_to_bytes(
+    params: *const ZkpParams,
+    p: *mut ZkpBuffer,
+    q: *mut ZkpBuffer,
+    g: *mut ZkpBuffer,
+) -> ZkpStatus {
+    if params.is_null() || p.is_null() || q.is_null() || g.is_null() {
+        return ZkpStatus::NullPointer;
+    }
+    let params = unsafe { &(*params).0 };
+    guard(|| {
+        let [p_bytes, q_bytes, g_bytes] = [params.p(), params.q(), params.g()].map(BigUint::to_bytes_be);
+        unsafe {
+            p.write(ZkpBuffer::new(p_bytes));
+            q.write(ZkpBuffer::new(q_bytes));
+            g.write(ZkpBuffer::new(g_bytes));
+        }
+        ZkpStatus::Ok
+    })
+}
+
+/// # Safety
+/// `params` must be null or come from this library, and must not be used afterwards.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn zkp_params_free(params: *mut ZkpParams) {
+    if !params.is_null() {
+        drop(unsafe { Box::from_raw(params) });
+    }
+}
+
+/// Proves knowledge of the secrets a and b (big-endian, each in [1, q)) behind the commitment
+/// (g^a, g^b, g^ab). The proof carries that commitment, so a verifier should also check it
+/// matches the one registered for the user (see zkp_proof_commitment).
+///
+/// # Safety
+/// `params` must come from this library, each secret must point to `len` readable bytes, and
+/// `proof` must be valid for writing a buffer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn zkp_create_proof(
+    params: *const ZkpParams,
+    secret_a: *const u8,
+    secret_a_len: usize,
+    secret_b: *const u8,
+    secret_b_len: usize,
+    proof: *mut ZkpBuffer,
+) -> ZkpStatus {
+    let (Some(a), Some(b)) = (unsafe { input(secret_a, secret_a_len) }, unsafe { input(secret_b, secret_b_len) }) else {
+        return ZkpStatus::NullPointer;
+    };
+    if params.is_null() || proof.is_null() {
+        return ZkpStatus::NullPointer;
+    }
+    let params = unsafe { &(*params).0 };
+    guard(|| {
+        let [a, b] = [a, b].map(BigUint::from_bytes_be);
+        let in_range = |secret: &BigUint| secret.bits() > 0 && secret < params.q();
+        if !in_range(&a) || !in_range(&b) {
+            return ZkpStatus::InvalidSecret;
+        }
+        let prover = Prover::builder(params.clone()).secrets(a, b).build();
+        let Ok(prover) = prover else {
+            return ZkpStatus::InvalidParameters;
+        };
+        unsafe { proof.write(ZkpBuffer::new(prover.create_proof().to_bytes())) };
+        ZkpStatus::Ok
+    })
+}
+
+/// Checks an encoded proof against the parameters and sets `verified`. ZKP_STATUS_OK with
+/// `verified` false means a well-formed proof that does not hold.
+///
+/// # Safety
+/// `params` must come from this library, `proof` must point to `proof_len` readable bytes, and
+/// `verified` must be valid for writing a bool.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn zkp_verify_proof(
+    params: *const ZkpParams,
+    proof: *const u8,
+    proof_len: usize,
+    verified: *mut bool,
+) -> ZkpStatus {
+    let Some(proof) = (unsafe { input(proof, proof_len) }) else {
+        return ZkpStatus::NullPointer;
+    };
+    if params.is_null() || verified.is_null() {
+        return ZkpStatus::NullPointer;
+    }
+    let params = unsafe { &(*params).0 };
+    guard(|| {
+        let Ok(proof) = ZKProof::from_bytes(proof) else {
+            return ZkpStatus::InvalidProof;
+        };
+        let result = Verifier::new(params.clone()).verify_proof(&proof);
+        unsafe { verified.write(result) };
+        ZkpStatus::Ok
+    })
+}
+
+/// Writes the commitment (a1, b1, c1) an encoded proof was made for, as big-endian byte strings
+/// each to be released with zkp_buffer_free
+///
+/// # Safety
+/// `proof` must point to `proof_len` readable bytes, and `a1`, `b1` and `c1` must be valid for
+/// writing a buffer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn zkp_proof_commitment(
+    proof: *const u8,
+    proof_len: usize,
+    a1: *mut ZkpBuffer,
+    b1: *mut ZkpBuffer,
+    c1: *mut ZkpBuffer,
+) -> ZkpStatus {
+    let Some(proof) = (unsafe { input(proof, proof_len) }) else {
+        return ZkpStatus::NullPointer;
+    };
+    if a1.is_null() || b1.is_null() || c1.is_null() {
+        return ZkpStatus::NullPointer;
+    }
+    guard(|| {
+        let Ok(proof) = ZKProof::from_bytes(proof) else {
+            return ZkpStatus::InvalidProof;
+        };
+        let commitment = proof.commitment;
+        unsafe {
+            a1.write(ZkpBuffer::new(commitment.a1.to_bytes_be()));
+            b1.write(ZkpBuffer::new(commitment.b1.to_bytes_be()));
+            c1.write(ZkpBuffer::new(commitment.c1.to_bytes_be()));
+        }
+        ZkpStatus::Ok
+    })
+}
+
+/// Releases a buffer returned by this library; a buffer with a null `data` is ignored
+///
+/// # Safety
+/// `buffer` must come from this library and must not be freed twice.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn zkp_buffer_free(buffer: ZkpBuffer) {
+    if !buffer.data.is_null() {
+        drop(unsafe { Box::from_raw(ptr::slice_from_raw_parts_mut(buffer.data, buffer.len)) });
+    }
+}
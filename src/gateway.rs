@@ -0,0 +1,321 @@
+use std::future::Future;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use axum::extract::rejection::JsonRejection;
+use axum::extract::{ConnectInfo, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::post;
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+use tonic::metadata::MetadataMap;
+use tonic::transport::server::TcpConnectInfo;
+use tonic::{Code, Request, Status};
+
+use crate::auth::Authenticator;
+use crate::service::ChaumPedersenServer;
+use crate::zkp::chaum_pedersen_service_server::ChaumPedersenService;
+use crate::zkp::{self, ChallengeMode, GroupKind};
+
+// HTTP/JSON front for clients that cannot speak gRPC. Each route runs the matching v1 RPC on the
+// same ChaumPedersenServer the gRPC listeners use, so sessions started on one can finish on the other.
+// Bodies mirror the proto messages field for field; bytes fields are lowercase hex strings and
+// enums travel by their proto names, e.g. "CHALLENGE_INTERACTIVE".
+
+pub const INITIALIZE_PATH: &str = "/v1/initialize";
+pub const CHALLENGE_PATH: &str = "/v1/challenge";
+pub const VERIFY_PATH: &str = "/v1/verify";
+
+mod hex_bytes {
+    use serde::{de, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&hex::encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        hex::decode(text).map_err(|_| de::Error::custom("invalid hex string"))
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct InitializeRequest {
+    pub bit_size: u32,
+    pub group: Option<String>,
+    pub challenge_mode: Option<String>,
+    pub user_id: String,
+    pub recovery: bool,
+    pub compressed_elements: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InitializeResponse {
+    pub session_id: String,
+    pub params: Option<PublicParameters>,
+    pub group: String,
+    pub challenge_mode: String,
+    #[serde(with = "hex_bytes")]
+    pub params_fingerprint: Vec<u8>,
+    pub commitment_origin: String,
+    pub compressed_elements: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PublicParameters {
+    #[serde(with = "hex_bytes")]
+    pub p: Vec<u8>,
+    #[serde(with = "hex_bytes")]
+    pub q: Vec<u8>,
+    #[serde(with = "hex_bytes")]
+    pub g: Vec<u8>,
+    #[serde(with = "hex_bytes")]
+    pub h: Vec<u8>,
+    #[serde(with = "hex_bytes")]
+    pub h_seed: Vec<u8>,
+    #[serde(with = "hex_bytes")]
+    pub seed: Vec<u8>,
+    pub seed_counter: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Commitment {
+    #[serde(with = "hex_bytes")]
+    pub a1: Vec<u8>,
+    #[serde(with = "hex_bytes")]
+    pub b1: Vec<u8>,
+    #[serde(with = "hex_bytes")]
+    pub c1: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProofChallenge {
+    #[serde(with = "hex_bytes")]
+    pub y1: Vec<u8>,
+    #[serde(with = "hex_bytes")]
+    pub y2: Vec<u8>,
+}
+
+// `commitment` may be left out of a user's session, which then uses the registered one
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ChallengeRequest {
+    pub session_id: String,
+    #[serde(default)]
+    pub commitment: Option<Commitment>,
+    pub challenge_values: ProofChallenge,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChallengeResponse {
+    #[serde(with = "hex_bytes")]
+    pub challenge: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct VerifyRequest {
+    pub session_id: String,
+    #[serde(with = "hex_bytes")]
+    pub z: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VerifyResponse {
+    pub verified: bool,
+    pub message: String,
+}
+
+// Error body in the shape of google.rpc.Status: the numeric gRPC code and its message
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErrorBody {
+    pub code: i32,
+    pub message: String,
+}
+
+// A failed RPC as an HTTP response. The status code follows the usual gRPC-to-HTTP mapping and
+// the status metadata, such as a load-shedding retry-after, is passed on as headers.
+#[derive(Debug)]
+pub struct GatewayError(pub Status);
+
+impl From<Status> for GatewayError {
+    fn from(status: Status) -> Self {
+        Self(status)
+    }
+}
+
+impl From<JsonRejection> for GatewayError {
+    fn from(rejection: JsonRejection) -> Self {
+        Self(Status::invalid_argument(rejection.body_text()))
+    }
+}
+
+impl IntoResponse for GatewayError {
+    fn into_response(self) -> Response {
+        let status = self.0;
+        let body = ErrorBody {
+            code: status.code() as i32,
+            message: status.message().to_string(),
+        };
+        (http_status(status.code()), status.metadata().clone().into_headers(), Json(body)).into_response()
+    }
+}
+
+pub fn http_status(code: Code) -> StatusCode {
+    match code {
+        Code::Ok => StatusCode::OK,
+        Code::InvalidArgument | Code::FailedPrecondition | Code::OutOfRange => StatusCode::BAD_REQUEST,
+        Code::Unauthenticated => StatusCode::UNAUTHORIZED,
+        Code::PermissionDenied => StatusCode::FORBIDDEN,
+        Code::NotFound => StatusCode::NOT_FOUND,
+        Code::AlreadyExists | Code::Aborted => StatusCode::CONFLICT,
+        Code::ResourceExhausted => StatusCode::TOO_MANY_REQUESTS,
+        Code::Cancelled => StatusCode::REQUEST_TIMEOUT,
+        Code::Unimplemented => StatusCode::NOT_IMPLEMENTED,
+        Code::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+        Code::DeadlineExceeded => StatusCode::GATEWAY_TIMEOUT,
+        Code::Unknown | Code::Internal | Code::DataLoss => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Gateway {
+    server: Arc<ChaumPedersenServer>,
+    authenticator: Authenticator,
+}
+
+impl Gateway {
+    // Wraps a message the way the gRPC listener would: headers as metadata, the peer address for
+    // lockouts and the authenticated principal in the extensions
+    fn request<T>(&self, peer: SocketAddr, headers: HeaderMap, message: T) -> Result<Request<T>, Status> {
+        let mut request = Request::new(message);
+        *request.metadata_mut() = MetadataMap::from_headers(headers);
+        request.extensions_mut().insert(TcpConnectInfo {
+            local_addr: None,
+            remote_addr: Some(peer),
+        });
+        self.authenticator.authenticate(&mut request)?;
+        Ok(request)
+    }
+}
+
+// The gateway routes, checking API keys against `authenticator`. Client certificates are a TLS
+// matter and the gateway serves plain HTTP, so with only CNs configured every request is refused.
+pub fn router(server: Arc<ChaumPedersenServer>, authenticator: Authenticator) -> Router {
+    Router::new()
+        .route(INITIALIZE_PATH, post(initialize))
+        .route(CHALLENGE_PATH, post(challenge))
+        .route(VERIFY_PATH, post(verify))
+        .with_state(Gateway { server, authenticator })
+}
+
+// Serves `router` on a bound listener until `shutdown` completes; handlers need the peer address,
+// so routers served any other way must provide `ConnectInfo<SocketAddr>` themselves
+pub async fn serve(listener: std::net::TcpListener, router: Router, shutdown: impl Future<Output = ()>) -> io::Result<()> {
+    listener.set_nonblocking(true)?;
+    axum::Server::from_tcp(listener)
+        .map_err(io::Error::other)?
+        .serve(router.into_make_service_with_connect_info::<SocketAddr>())
+        .with_graceful_shutdown(shutdown)
+        .await
+        .map_err(io::Error::other)
+}
+
+fn enum_value<E>(field: &str, name: Option<&str>, parse: impl Fn(&str) -> Option<E>) -> Result<i32, Status>
+where
+    E: Into<i32> + Default,
+{
+    match name {
+        None => Ok(E::default().into()),
+        Some(name) => parse(name)
+            .map(Into::into)
+            .ok_or_else(|| Status::invalid_argument(format!("Unknown {} {:?}", field, name))),
+    }
+}
+
+async fn initialize(
+    State(gateway): State<Gateway>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    body: Result<Json<InitializeRequest>, JsonRejection>,
+) -> Result<Json<InitializeResponse>, GatewayError> {
+    let Json(body) = body?;
+    let message = zkp::InitializeRequest {
+        bit_size: body.bit_size,
+        group: enum_value("group", body.group.as_deref(), GroupKind::from_str_name)?,
+        challenge_mode: enum_value("challenge_mode", body.challenge_mode.as_deref(), ChallengeMode::from_str_name)?,
+        user_id: body.user_id,
+        recovery: body.recovery,
+        compressed_elements: body.compressed_elements,
+    };
+    let request = gateway.request(peer, headers, message)?;
+    let response = gateway.server.initialize_protocol(request).await?.into_inner();
+    let (group, challenge_mode, origin) = (response.group(), response.challenge_mode(), response.commitment_origin());
+    Ok(Json(InitializeResponse {
+        session_id: response.session_id,
+        params: response.params.map(|params| PublicParameters {
+            p: params.p.into(),
+            q: params.q.into(),
+            g: params.g.into(),
+            h: params.h.into(),
+            h_seed: params.h_seed.into(),
+            seed: params.seed.into(),
+            seed_counter: params.seed_counter,
+        }),
+        group: group.as_str_name().to_string(),
+        challenge_mode: challenge_mode.as_str_name().to_string(),
+        params_fingerprint: response.params_fingerprint,
+        commitment_origin: origin.as_str_name().to_string(),
+        compressed_elements: response.compressed_elements,
+    }))
+}
+
+async fn challenge(
+    State(gateway): State<Gateway>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    body: Result<Json<ChallengeRequest>, JsonRejection>,
+) -> Result<Json<ChallengeResponse>, GatewayError> {
+    let Json(body) = body?;
+    let message = zkp::CommitmentRequest {
+        session_id: body.session_id,
+        commitment: body.commitment.map(|commitment| zkp::Commitment {
+            a1: commitment.a1.into(),
+            b1: commitment.b1.into(),
+            c1: commitment.c1.into(),
+        }),
+        challenge_values: Some(zkp::ProofChallenge {
+            y1: body.challenge_values.y1.into(),
+            y2: body.challenge_values.y2.into(),
+        }),
+    };
+    let request = gateway.request(peer, headers, message)?;
+    let response = gateway.server.send_commitment(request).await?.into_inner();
+    Ok(Json(ChallengeResponse {
+        challenge: response.challenge.into(),
+    }))
+}
+
+async fn verify(
+    State(gateway): State<Gateway>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    body: Result<Json<VerifyRequest>, JsonRejection>,
+) -> Result<Json<VerifyResponse>, GatewayError> {
+    let Json(body) = body?;
+    let message = zkp::VerifyProofRequest {
+        session_id: body.session_id,
+        z: body.z.into(),
+    };
+    let request = gateway.request(peer, headers, message)?;
+    let response = gateway.server.verify_proof(request).await?.into_inner();
+    Ok(Json(VerifyResponse {
+        verified: response.verified,
+        message: response.message,
+    }))
+}
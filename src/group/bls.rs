@@ -0,0 +1,104 @@
+use bls12_381::{G1Affine, G1Projective, Scalar};
+use num_bigint::BigUint;
+
+use super::{expand_message, Group};
+use crate::chaum_pedersen::SecureRng;
+
+// r, the order of G1 and of the BLS12-381 scalar field
+const ORDER: &str = "73eda753299d7d483339d80809a1d80553bda402fffe5bfeffffffff00000001";
+
+// The prime-order subgroup G1 of BLS12-381, the group BLS public keys with short signatures and
+// drand-style beacons live in. Elements travel as the 48-byte compressed points those use.
+#[derive(Debug, Clone)]
+pub struct Bls12381G1 {
+    order: BigUint,
+}
+
+impl Bls12381G1 {
+    pub fn new() -> Self {
+        Self {
+            order: BigUint::parse_bytes(ORDER.as_bytes(), 16).expect("valid hex"),
+        }
+    }
+}
+
+impl Default for Bls12381G1 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Group for Bls12381G1 {
+    type Scalar = Scalar;
+    type Element = G1Projective;
+
+    const NAME: &'static [u8] = b"BLS12-381-G1";
+
+    fn order(&self) -> &BigUint {
+        &self.order
+    }
+
+    fn generator(&self) -> G1Projective {
+        G1Projective::generator()
+    }
+
+    // 512 random bits reduced mod r, so the bias is negligible
+    fn random_scalar(&self, rng: &mut dyn SecureRng) -> Scalar {
+        loop {
+            let mut wide = [0u8; 64];
+            rng.fill_bytes(&mut wide);
+            let k = Scalar::from_bytes_wide(&wide);
+            if k != Scalar::zero() {
+                return k;
+            }
+        }
+    }
+
+    fn scalar_from_integer(&self, value: &BigUint) -> Scalar {
+        let mut repr = [0u8; 32];
+        let bytes = (value % &self.order).to_bytes_le();
+        repr[..bytes.len()].copy_from_slice(&bytes);
+        Scalar::from_bytes(&repr).expect("reduced below r")
+    }
+
+    fn scalar_to_integer(&self, scalar: &Scalar) -> BigUint {
+        BigUint::from_bytes_le(&scalar.to_bytes())
+    }
+
+    fn mul_add(&self, x: &Scalar, a: &Scalar, c: &Scalar) -> Scalar {
+        x + a * c
+    }
+
+    fn exp(&self, base: &G1Projective, exponent: &Scalar) -> G1Projective {
+        base * exponent
+    }
+
+    fn mul(&self, a: &G1Projective, b: &G1Projective) -> G1Projective {
+        a + b
+    }
+
+    fn encode_element(&self, element: &G1Projective) -> Vec<u8> {
+        G1Affine::from(element).to_compressed().to_vec()
+    }
+
+    // from_compressed rejects points outside the prime-order subgroup
+    fn decode_element(&self, bytes: &[u8]) -> Option<G1Projective> {
+        let bytes: &[u8; 48] = bytes.try_into().ok()?;
+        let affine = Option::<G1Affine>::from(G1Affine::from_compressed(bytes))?;
+        (!bool::from(affine.is_identity())).then(|| G1Projective::from(affine))
+    }
+
+    // Try-and-increment onto the curve, then cofactor clearing into G1
+    fn hash_to_element(&self, dst: &[u8], msg: &[u8]) -> G1Projective {
+        (0u32..)
+            .find_map(|counter| {
+                let mut candidate: [u8; 48] = expand_message(dst, msg, counter, 48).try_into().ok()?;
+                // compressed, not infinity, random sign
+                candidate[0] = 0x80 | (candidate[0] & 0x3f);
+                let point = Option::<G1Affine>::from(G1Affine::from_compressed_unchecked(&candidate))?;
+                let point = G1Projective::from(point).clear_cofactor();
+                (!bool::from(point.is_identity())).then_some(point)
+            })
+            .expect("a candidate is found long before the counter wraps")
+    }
+}
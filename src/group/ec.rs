@@ -0,0 +1,151 @@
+use std::fmt;
+use std::marker::PhantomData;
+
+use elliptic_curve::bigint::ArrayEncoding;
+use elliptic_curve::group::{Curve as _, Group as _};
+use elliptic_curve::ops::Reduce;
+use elliptic_curve::sec1::{EncodedPoint, FromEncodedPoint, ModulusSize, ToEncodedPoint};
+use elliptic_curve::{Curve, CurveArithmetic, Field, FieldBytes, FieldBytesSize, PrimeField};
+use num_bigint::BigUint;
+
+use super::{expand_message, Group};
+use crate::chaum_pedersen::SecureRng;
+
+// Curves with a group implementation, and the name their transcripts are bound to
+pub trait NamedCurve: CurveArithmetic {
+    const NAME: &'static [u8];
+}
+
+#[cfg(feature = "secp256k1")]
+impl NamedCurve for k256::Secp256k1 {
+    const NAME: &'static [u8] = b"secp256k1";
+}
+
+#[cfg(feature = "p256")]
+impl NamedCurve for p256::NistP256 {
+    const NAME: &'static [u8] = b"P-256";
+}
+
+#[cfg(feature = "secp256k1")]
+pub type Secp256k1 = EcGroup<k256::Secp256k1>;
+
+#[cfg(feature = "p256")]
+pub type P256 = EcGroup<p256::NistP256>;
+
+// The points of a prime-order short Weierstrass curve. Elements travel as compressed SEC1 points,
+// so keys taken from other software can be used as they are.
+pub struct EcGroup<C> {
+    order: BigUint,
+    curve: PhantomData<C>,
+}
+
+impl<C: Curve> EcGroup<C> {
+    pub fn new() -> Self {
+        Self {
+            order: BigUint::from_bytes_be(&C::ORDER.to_be_byte_array()),
+            curve: PhantomData,
+        }
+    }
+}
+
+impl<C: Curve> Default for EcGroup<C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C> Clone for EcGroup<C> {
+    fn clone(&self) -> Self {
+        Self {
+            order: self.order.clone(),
+            curve: PhantomData,
+        }
+    }
+}
+
+impl<C> fmt::Debug for EcGroup<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EcGroup").field("order", &self.order).finish()
+    }
+}
+
+impl<C> Group for EcGroup<C>
+where
+    C: NamedCurve,
+    C::AffinePoint: FromEncodedPoint<C> + ToEncodedPoint<C>,
+    FieldBytesSize<C>: ModulusSize,
+{
+    type Scalar = C::Scalar;
+    type Element = C::ProjectivePoint;
+
+    const NAME: &'static [u8] = C::NAME;
+
+    fn order(&self) -> &BigUint {
+        &self.order
+    }
+
+    fn generator(&self) -> C::ProjectivePoint {
+        C::ProjectivePoint::generator()
+    }
+
+    fn random_scalar(&self, rng: &mut dyn SecureRng) -> C::Scalar {
+        loop {
+            let k = C::Scalar::random(&mut *rng);
+            if !bool::from(k.is_zero()) {
+                return k;
+            }
+        }
+    }
+
+    fn scalar_from_integer(&self, value: &BigUint) -> C::Scalar {
+        let bytes = value.to_bytes_be();
+        let mut repr = FieldBytes::<C>::default();
+        let width = repr.len();
+        assert!(bytes.len() <= width, "integer is wider than a scalar");
+        repr[width - bytes.len()..].copy_from_slice(&bytes);
+        <C::Scalar as Reduce<C::Uint>>::reduce_bytes(&repr)
+    }
+
+    fn scalar_to_integer(&self, scalar: &C::Scalar) -> BigUint {
+        BigUint::from_bytes_be(&scalar.to_repr())
+    }
+
+    fn mul_add(&self, x: &C::Scalar, a: &C::Scalar, c: &C::Scalar) -> C::Scalar {
+        *x + *a * *c
+    }
+
+    fn exp(&self, base: &C::ProjectivePoint, exponent: &C::Scalar) -> C::ProjectivePoint {
+        *base * *exponent
+    }
+
+    fn mul(&self, a: &C::ProjectivePoint, b: &C::ProjectivePoint) -> C::ProjectivePoint {
+        *a + *b
+    }
+
+    fn encode_element(&self, element: &C::ProjectivePoint) -> Vec<u8> {
+        element.to_affine().to_encoded_point(true).as_bytes().to_vec()
+    }
+
+    // Only the compressed form is canonical
+    fn decode_element(&self, bytes: &[u8]) -> Option<C::ProjectivePoint> {
+        let encoded = EncodedPoint::<C>::from_bytes(bytes).ok()?;
+        if !encoded.is_compressed() {
+            return None;
+        }
+        let affine = Option::<C::AffinePoint>::from(C::AffinePoint::from_encoded_point(&encoded))?;
+        let point = C::ProjectivePoint::from(affine);
+        (!bool::from(point.is_identity())).then_some(point)
+    }
+
+    // Try-and-increment: a hashed x coordinate is on the curve about half the time
+    fn hash_to_element(&self, dst: &[u8], msg: &[u8]) -> C::ProjectivePoint {
+        let width = FieldBytes::<C>::default().len();
+        (0u32..)
+            .find_map(|counter| {
+                let mut candidate = expand_message(dst, msg, counter, width + 1);
+                candidate[0] = 0x02 | (candidate[0] & 1);
+                self.decode_element(&candidate)
+            })
+            .expect("a candidate is found long before the counter wraps")
+    }
+}
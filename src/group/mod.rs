@@ -0,0 +1,335 @@
+#[cfg(feature = "bls12_381")]
+pub mod bls;
+#[cfg(any(feature = "secp256k1", feature = "p256"))]
+pub mod ec;
+#[cfg(feature = "ristretto")]
+pub mod ristretto;
+
+use std::fmt;
+
+use num_bigint::BigUint;
+use serde::{Deserialize, Serialize};
+#[cfg(any(feature = "secp256k1", feature = "p256", feature = "bls12_381", feature = "ristretto"))]
+use sha2::{Digest, Sha256};
+use subtle::ConstantTimeEq;
+
+use crate::chaum_pedersen::{
+    compute_z, derive_generator, generate_prover_secret_with_rng, modpow, secret_fixed_pow, secret_modpow,
+    to_fixed_bytes, PublicParameters, Scalar, SecureRng, Transcript,
+};
+use crate::sigma::{self, SigmaProof, SigmaProtocol};
+
+#[cfg(feature = "bls12_381")]
+pub use bls::Bls12381G1;
+#[cfg(any(feature = "secp256k1", feature = "p256"))]
+pub use ec::EcGroup;
+#[cfg(feature = "p256")]
+pub use ec::P256;
+#[cfg(feature = "secp256k1")]
+pub use ec::Secp256k1;
+#[cfg(feature = "ristretto")]
+pub use ristretto::Ristretto255;
+
+// The groups a session can run over. Curve groups need their cargo feature; `is_available` says
+// whether this build has it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum GroupKind {
+    #[default]
+    Modp,  // order-q subgroup of generated PublicParameters
+    Secp256k1,
+    P256,
+    Bls12381G1,
+    Ristretto255,
+}
+
+impl GroupKind {
+    pub const ALL: [GroupKind; 5] = [
+        GroupKind::Modp,
+        GroupKind::Secp256k1,
+        GroupKind::P256,
+        GroupKind::Bls12381G1,
+        GroupKind::Ristretto255,
+    ];
+
+    pub fn is_available(self) -> bool {
+        match self {
+            GroupKind::Modp => true,
+            GroupKind::Secp256k1 => cfg!(feature = "secp256k1"),
+            GroupKind::P256 => cfg!(feature = "p256"),
+            GroupKind::Bls12381G1 => cfg!(feature = "bls12_381"),
+            GroupKind::Ristretto255 => cfg!(feature = "ristretto"),
+        }
+    }
+}
+
+impl fmt::Display for GroupKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            GroupKind::Modp => "modp",
+            GroupKind::Secp256k1 => "secp256k1",
+            GroupKind::P256 => "P-256",
+            GroupKind::Bls12381G1 => "BLS12-381 G1",
+            GroupKind::Ristretto255 => "Ristretto255",
+        };
+        f.write_str(name)
+    }
+}
+
+// A cyclic group of prime order, written multiplicatively. The modp subgroup of `PublicParameters`
+// is one; elliptic-curve groups are available behind cargo features.
+pub trait Group {
+    type Scalar: Clone + fmt::Debug;
+    type Element: Clone + fmt::Debug;
+
+    // Absorbed into every transcript, so a proof over one group never verifies over another
+    const NAME: &'static [u8];
+
+    fn order(&self) -> &BigUint;
+    fn generator(&self) -> Self::Element;
+    // Uniform and nonzero
+    fn random_scalar(&self, rng: &mut dyn SecureRng) -> Self::Scalar;
+    // The scalar for an integer below the order, e.g. a Fiat-Shamir challenge
+    fn scalar_from_integer(&self, value: &BigUint) -> Self::Scalar;
+    fn scalar_to_integer(&self, scalar: &Self::Scalar) -> BigUint;
+    // x + a*c mod the order
+    fn mul_add(&self, x: &Self::Scalar, a: &Self::Scalar, c: &Self::Scalar) -> Self::Scalar;
+    // base^exponent; the exponent may be secret
+    fn exp(&self, base: &Self::Element, exponent: &Self::Scalar) -> Self::Element;
+    fn mul(&self, a: &Self::Element, b: &Self::Element) -> Self::Element;
+    // Canonical, fixed-length encoding
+    fn encode_element(&self, element: &Self::Element) -> Vec<u8>;
+    // None unless `bytes` is the canonical encoding of a group element other than the identity
+    fn decode_element(&self, bytes: &[u8]) -> Option<Self::Element>;
+    // An element with unknown discrete log, derived from `msg` under the domain label `dst`. Not
+    // constant time, so only for public inputs.
+    fn hash_to_element(&self, dst: &[u8], msg: &[u8]) -> Self::Element;
+}
+
+// SHA-256(len(dst) || dst || msg || counter || block), expanded to `len` bytes. Try-and-increment
+// hashing to a curve draws one candidate per counter.
+#[cfg(any(feature = "secp256k1", feature = "p256", feature = "bls12_381", feature = "ristretto"))]
+pub(crate) fn expand_message(dst: &[u8], msg: &[u8], counter: u32, len: usize) -> Vec<u8> {
+    let mut expanded = Vec::with_capacity(len + 32);
+    let mut block: u32 = 0;
+    while expanded.len() < len {
+        let mut hasher = Sha256::new();
+        hasher.update((dst.len() as u64).to_be_bytes());
+        hasher.update(dst);
+        hasher.update(msg);
+        hasher.update(counter.to_be_bytes());
+        hasher.update(block.to_be_bytes());
+        expanded.extend_from_slice(&hasher.finalize());
+        block += 1;
+    }
+    expanded.truncate(len);
+    expanded
+}
+
+impl Group for PublicParameters {
+    type Scalar = BigUint;
+    type Element = BigUint;
+
+    const NAME: &'static [u8] = b"modp";
+
+    fn order(&self) -> &BigUint {
+        self.q()
+    }
+
+    fn generator(&self) -> BigUint {
+        self.g().clone()
+    }
+
+    fn random_scalar(&self, rng: &mut dyn SecureRng) -> BigUint {
+        generate_prover_secret_with_rng(self.q(), rng)
+    }
+
+    fn scalar_from_integer(&self, value: &BigUint) -> BigUint {
+        value % self.q()
+    }
+
+    fn scalar_to_integer(&self, scalar: &BigUint) -> BigUint {
+        scalar.clone()
+    }
+
+    fn mul_add(&self, x: &BigUint, a: &BigUint, c: &BigUint) -> BigUint {
+        compute_z(self, &Scalar::new(x, self), &Scalar::new(a, self), &Scalar::new(c, self)).into_biguint()
+    }
+
+    fn exp(&self, base: &BigUint, exponent: &BigUint) -> BigUint {
+        if base == self.g() {
+            secret_fixed_pow(self.g_table(), exponent, self.q())
+        } else {
+            secret_modpow(base, exponent, self.p())
+        }
+    }
+
+    fn mul(&self, a: &BigUint, b: &BigUint) -> BigUint {
+        a * b % self.p()
+    }
+
+    fn encode_element(&self, element: &BigUint) -> Vec<u8> {
+        to_fixed_bytes(element, self.p())
+    }
+
+    // Also checks membership of the order-q subgroup, which costs an exponentiation
+    fn decode_element(&self, bytes: &[u8]) -> Option<BigUint> {
+        if bytes.len() != self.p().to_bytes_be().len() {
+            return None;
+        }
+        let x = BigUint::from_bytes_be(bytes);
+        let in_subgroup = self.is_element(&x) && x != BigUint::from(1u32) && modpow(&x, self.q(), self.p()) == 1u32.into();
+        in_subgroup.then_some(x)
+    }
+
+    // The same hash-to-subgroup map that derives the second generator h
+    fn hash_to_element(&self, dst: &[u8], msg: &[u8]) -> BigUint {
+        let seed = [&(dst.len() as u64).to_be_bytes()[..], dst, msg].concat();
+        derive_generator(&seed, self.p(), self.q())
+    }
+}
+
+// y1 = g^x and y2 = h^x for one secret x
+#[derive(Debug, Clone)]
+pub struct DleqStatement<E> {
+    pub g: E,
+    pub h: E,
+    pub y1: E,
+    pub y2: E,
+}
+
+#[derive(Debug, Clone)]
+pub struct DleqCommitment<E> {
+    pub t1: E,  // g^k
+    pub t2: E,  // h^k
+}
+
+pub type DleqProof<G> = SigmaProof<Dleq<G>>;
+
+// Equality of discrete logs, log_g(y1) = log_h(y2), over any group. Chaum-Pedersen over the modp
+// group is the case g = g, h = b1, y1 = a1, y2 = c1.
+#[derive(Debug, Clone)]
+pub struct Dleq<G> {
+    pub group: G,
+}
+
+impl<G: Group> Dleq<G> {
+    pub fn new(group: G) -> Self {
+        Self { group }
+    }
+
+    // The statement for secret x over the bases g and h
+    pub fn statement(&self, g: G::Element, h: G::Element, x: &G::Scalar) -> DleqStatement<G::Element> {
+        let y1 = self.group.exp(&g, x);
+        let y2 = self.group.exp(&h, x);
+        DleqStatement { g, h, y1, y2 }
+    }
+
+    pub fn prove(&self, statement: &DleqStatement<G::Element>, x: &G::Scalar) -> DleqProof<G> {
+        sigma::prove(self, statement, x)
+    }
+
+    pub fn verify_proof(&self, statement: &DleqStatement<G::Element>, proof: &DleqProof<G>) -> bool {
+        sigma::verify(self, statement, proof)
+    }
+
+    // Proofs sent to the server's VerifyDleqProof are bound to a session, so one made for one session
+    // is rejected in every other
+    pub fn prove_for_session(
+        &self,
+        session_id: &str,
+        statement: &DleqStatement<G::Element>,
+        x: &G::Scalar,
+    ) -> DleqProof<G> {
+        sigma::prove_with_transcript(self, &mut session_transcript(session_id), statement, x)
+    }
+
+    pub fn verify_for_session(
+        &self,
+        session_id: &str,
+        statement: &DleqStatement<G::Element>,
+        proof: &DleqProof<G>,
+    ) -> bool {
+        sigma::verify_with_transcript(self, &mut session_transcript(session_id), statement, proof)
+    }
+
+    fn elements_equal(&self, a: &G::Element, b: &G::Element) -> bool {
+        self.group.encode_element(a).ct_eq(&self.group.encode_element(b)).into()
+    }
+}
+
+fn session_transcript(session_id: &str) -> Transcript {
+    let mut transcript = Transcript::new(b"dleq");
+    transcript.append_message(b"session", session_id.as_bytes());
+    transcript
+}
+
+impl<G: Group> SigmaProtocol for Dleq<G> {
+    type Statement = DleqStatement<G::Element>;
+    type Witness = G::Scalar;
+    type Commitment = DleqCommitment<G::Element>;
+    type Nonce = G::Scalar;
+    type Response = G::Scalar;
+
+    const LABEL: &'static [u8] = b"dleq";
+
+    fn challenge_modulus(&self) -> &BigUint {
+        self.group.order()
+    }
+
+    fn commit(
+        &self,
+        statement: &DleqStatement<G::Element>,
+        _witness: &G::Scalar,
+        rng: &mut dyn SecureRng,
+    ) -> (DleqCommitment<G::Element>, G::Scalar) {
+        let k = self.group.random_scalar(rng);
+        let t1 = self.group.exp(&statement.g, &k);
+        let t2 = self.group.exp(&statement.h, &k);
+        (DleqCommitment { t1, t2 }, k)
+    }
+
+    fn respond(&self, witness: &G::Scalar, nonce: G::Scalar, challenge: &BigUint) -> G::Scalar {
+        self.group.mul_add(&nonce, witness, &self.group.scalar_from_integer(challenge))
+    }
+
+    fn verify(
+        &self,
+        statement: &DleqStatement<G::Element>,
+        commitment: &DleqCommitment<G::Element>,
+        challenge: &BigUint,
+        response: &G::Scalar,
+    ) -> bool {
+        let group = &self.group;
+        let c = group.scalar_from_integer(challenge);
+        // Check: g^z = t1 * y1^c and h^z = t2 * y2^c
+        let first = self.elements_equal(
+            &group.exp(&statement.g, response),
+            &group.mul(&commitment.t1, &group.exp(&statement.y1, &c)),
+        );
+        let second = self.elements_equal(
+            &group.exp(&statement.h, response),
+            &group.mul(&commitment.t2, &group.exp(&statement.y2, &c)),
+        );
+        first & second
+    }
+
+    fn append_transcript(
+        &self,
+        transcript: &mut Transcript,
+        statement: &DleqStatement<G::Element>,
+        commitment: &DleqCommitment<G::Element>,
+    ) {
+        transcript.append_message(b"group", G::NAME);
+        let values = [
+            (&b"g"[..], &statement.g),
+            (b"h", &statement.h),
+            (b"y1", &statement.y1),
+            (b"y2", &statement.y2),
+            (b"t1", &commitment.t1),
+            (b"t2", &commitment.t2),
+        ];
+        for (label, value) in values {
+            transcript.append_message(label, &self.group.encode_element(value));
+        }
+    }
+}
@@ -0,0 +1,103 @@
+use curve25519_dalek::constants::{RISTRETTO_BASEPOINT_POINT, RISTRETTO_BASEPOINT_TABLE};
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::traits::IsIdentity;
+use curve25519_dalek::Scalar;
+use num_bigint::BigUint;
+
+use super::{expand_message, Group};
+use crate::chaum_pedersen::SecureRng;
+
+// l = 2^252 + 27742317777372353535851937790883648493, the order of Ristretto255
+const ORDER: &str = "1000000000000000000000000000000014def9dea2f79cd65812631a5cf5d3ed";
+
+// The prime-order Ristretto255 group over Curve25519, as curve25519-dalek implements it. Elements
+// travel as 32-byte compressed Ristretto points and scalars as 32 little-endian bytes, the
+// encoding of the dalek-based DLEQ and VOPRF crates; see `interop` for their proofs.
+#[derive(Debug, Clone)]
+pub struct Ristretto255 {
+    order: BigUint,
+}
+
+impl Ristretto255 {
+    pub fn new() -> Self {
+        Self {
+            order: BigUint::parse_bytes(ORDER.as_bytes(), 16).expect("valid hex"),
+        }
+    }
+}
+
+impl Default for Ristretto255 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Group for Ristretto255 {
+    type Scalar = Scalar;
+    type Element = RistrettoPoint;
+
+    const NAME: &'static [u8] = b"ristretto255";
+
+    fn order(&self) -> &BigUint {
+        &self.order
+    }
+
+    fn generator(&self) -> RistrettoPoint {
+        RISTRETTO_BASEPOINT_POINT
+    }
+
+    // 512 random bits reduced mod l, so the bias is negligible
+    fn random_scalar(&self, rng: &mut dyn SecureRng) -> Scalar {
+        loop {
+            let mut wide = [0u8; 64];
+            rng.fill_bytes(&mut wide);
+            let k = Scalar::from_bytes_mod_order_wide(&wide);
+            if k != Scalar::ZERO {
+                return k;
+            }
+        }
+    }
+
+    fn scalar_from_integer(&self, value: &BigUint) -> Scalar {
+        let mut repr = [0u8; 32];
+        let bytes = (value % &self.order).to_bytes_le();
+        repr[..bytes.len()].copy_from_slice(&bytes);
+        Option::from(Scalar::from_canonical_bytes(repr)).expect("reduced below l")
+    }
+
+    fn scalar_to_integer(&self, scalar: &Scalar) -> BigUint {
+        BigUint::from_bytes_le(scalar.as_bytes())
+    }
+
+    fn mul_add(&self, x: &Scalar, a: &Scalar, c: &Scalar) -> Scalar {
+        x + a * c
+    }
+
+    fn exp(&self, base: &RistrettoPoint, exponent: &Scalar) -> RistrettoPoint {
+        if base == &RISTRETTO_BASEPOINT_POINT {
+            RISTRETTO_BASEPOINT_TABLE * exponent
+        } else {
+            base * exponent
+        }
+    }
+
+    fn mul(&self, a: &RistrettoPoint, b: &RistrettoPoint) -> RistrettoPoint {
+        a + b
+    }
+
+    fn encode_element(&self, element: &RistrettoPoint) -> Vec<u8> {
+        element.compress().to_bytes().to_vec()
+    }
+
+    // Ristretto decoding only accepts the canonical encoding of a group element
+    fn decode_element(&self, bytes: &[u8]) -> Option<RistrettoPoint> {
+        let point = CompressedRistretto::from_slice(bytes).ok()?.decompress()?;
+        (!point.is_identity()).then_some(point)
+    }
+
+    // The Elligator-based one-way map from 64 uniform bytes
+    fn hash_to_element(&self, dst: &[u8], msg: &[u8]) -> RistrettoPoint {
+        let uniform: [u8; 64] = expand_message(dst, msg, 0, 64).try_into().expect("64 bytes requested");
+        RistrettoPoint::from_uniform_bytes(&uniform)
+    }
+}
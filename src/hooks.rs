@@ -0,0 +1,130 @@
+use std::fmt;
+use std::io;
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::audit::{AuditEntry, AuditLog};
+
+// What a hook learns about a concluded verification: the audit entry for the attempt, which
+// carries the session id, parameters digest, transcript, verdict, time and peer, plus the user
+// the session was opened for
+#[derive(Debug, Clone, Serialize)]
+pub struct VerificationEvent {
+    #[serde(flatten)]
+    pub entry: AuditEntry,
+    pub user_id: Option<String>,
+}
+
+impl VerificationEvent {
+    pub fn new(entry: AuditEntry, user_id: Option<String>) -> Self {
+        Self { entry, user_id }
+    }
+
+    pub fn verified(&self) -> bool {
+        self.entry.verified
+    }
+}
+
+// Called by the server after every proof it checks, before the verdict is returned, so a
+// deployment can provision access on success or raise an alert on failure. Errors are logged and
+// never change the verdict. Both methods default to doing nothing.
+#[tonic::async_trait]
+pub trait VerificationHook: Send + Sync + fmt::Debug {
+    async fn on_success(&self, _event: &VerificationEvent) -> io::Result<()> {
+        Ok(())
+    }
+
+    async fn on_failure(&self, _event: &VerificationEvent) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+// Appends every attempt to an audit log; this is how the server's `audit_log` is written
+#[derive(Debug)]
+pub struct AuditLogHook {
+    log: AuditLog,
+}
+
+impl AuditLogHook {
+    pub fn new(log: AuditLog) -> Self {
+        Self { log }
+    }
+}
+
+#[tonic::async_trait]
+impl VerificationHook for AuditLogHook {
+    async fn on_success(&self, event: &VerificationEvent) -> io::Result<()> {
+        self.log.record(event.entry.clone())
+    }
+
+    async fn on_failure(&self, event: &VerificationEvent) -> io::Result<()> {
+        self.log.record(event.entry.clone())
+    }
+}
+
+// Which outcomes a webhook is sent for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WebhookEvents {
+    #[default]
+    All,
+    Success,
+    Failure,
+}
+
+// POSTs each event as JSON to `url`. A response outside 2xx, or none within the timeout, is an error.
+#[derive(Debug, Clone)]
+pub struct WebhookHook {
+    client: reqwest::Client,
+    url: String,
+    events: WebhookEvents,
+}
+
+impl WebhookHook {
+    pub fn new(url: impl Into<String>, timeout: Duration) -> io::Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(timeout)
+            .build()
+            .map_err(io::Error::other)?;
+        Ok(Self {
+            client,
+            url: url.into(),
+            events: WebhookEvents::All,
+        })
+    }
+
+    pub fn with_events(mut self, events: WebhookEvents) -> Self {
+        self.events = events;
+        self
+    }
+
+    async fn post(&self, event: &VerificationEvent) -> io::Result<()> {
+        let body = serde_json::to_vec(event)?;
+        self.client
+            .post(&self.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(body)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(io::Error::other)?;
+        Ok(())
+    }
+}
+
+#[tonic::async_trait]
+impl VerificationHook for WebhookHook {
+    async fn on_success(&self, event: &VerificationEvent) -> io::Result<()> {
+        match self.events {
+            WebhookEvents::Failure => Ok(()),
+            _ => self.post(event).await,
+        }
+    }
+
+    async fn on_failure(&self, event: &VerificationEvent) -> io::Result<()> {
+        match self.events {
+            WebhookEvents::Success => Ok(()),
+            _ => self.post(event).await,
+        }
+    }
+}
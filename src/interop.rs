@@ -0,0 +1,129 @@
+use core::fmt;
+
+use num_bigint::BigUint;
+use rand::rngs::OsRng;
+use sha2::{Digest, Sha512};
+use subtle::ConstantTimeEq;
+
+use crate::chaum_pedersen::SecureRng;
+use crate::group::{DleqStatement, Group};
+
+// DLEQ proofs as the dalek-based Rust crates encode them, so proofs from their clients can be
+// checked here. A proof is c || s, two little-endian scalars of the group order's width, with
+// s = k - c*x and c = SHA-512(domain || g || h || y1 || y2 || g^k || h^k) reduced mod the order;
+// elements are hashed in the group's encoding, compressed points for `group::Ristretto255`.
+// Unlike `group::Dleq`, nothing but `domain` is bound into the challenge, so a proof is tied to a
+// session only through its domain: the server's VerifyCompactDleqProof requires one ending in the
+// session id, as `session_domain` builds.
+pub struct CompactDleqProof<G: Group> {
+    pub c: G::Scalar,
+    pub s: G::Scalar,
+}
+
+impl<G: Group> Clone for CompactDleqProof<G> {
+    fn clone(&self) -> Self {
+        Self {
+            c: self.c.clone(),
+            s: self.s.clone(),
+        }
+    }
+}
+
+impl<G: Group> fmt::Debug for CompactDleqProof<G> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CompactDleqProof").field("c", &self.c).field("s", &self.s).finish()
+    }
+}
+
+// Bytes in one encoded scalar
+pub fn scalar_len<G: Group>(group: &G) -> usize {
+    group.order().bits().div_ceil(8) as usize
+}
+
+impl<G: Group> CompactDleqProof<G> {
+    // None unless `bytes` holds exactly two scalars, each reduced mod the order
+    pub fn from_bytes(group: &G, bytes: &[u8]) -> Option<Self> {
+        let len = scalar_len(group);
+        if bytes.len() != 2 * len {
+            return None;
+        }
+        let (c, s) = bytes.split_at(len);
+        Some(Self {
+            c: decode_scalar(group, c)?,
+            s: decode_scalar(group, s)?,
+        })
+    }
+
+    pub fn to_bytes(&self, group: &G) -> Vec<u8> {
+        [encode_scalar(group, &self.c), encode_scalar(group, &self.s)].concat()
+    }
+}
+
+fn decode_scalar<G: Group>(group: &G, bytes: &[u8]) -> Option<G::Scalar> {
+    let value = BigUint::from_bytes_le(bytes);
+    (&value < group.order()).then(|| group.scalar_from_integer(&value))
+}
+
+fn encode_scalar<G: Group>(group: &G, scalar: &G::Scalar) -> Vec<u8> {
+    let mut bytes = group.scalar_to_integer(scalar).to_bytes_le();
+    bytes.resize(scalar_len(group), 0);
+    bytes
+}
+
+fn challenge<G: Group>(
+    group: &G,
+    domain: &[u8],
+    statement: &DleqStatement<G::Element>,
+    t1: &G::Element,
+    t2: &G::Element,
+) -> G::Scalar {
+    let mut hasher = Sha512::new();
+    hasher.update(domain);
+    for element in [&statement.g, &statement.h, &statement.y1, &statement.y2, t1, t2] {
+        hasher.update(group.encode_element(element));
+    }
+    group.scalar_from_integer(&BigUint::from_bytes_le(&hasher.finalize()))
+}
+
+// `domain` followed by "/" and the server session id, which VerifyCompactDleqProof requires
+pub fn session_domain(domain: &[u8], session_id: &str) -> Vec<u8> {
+    [domain, b"/", session_id.as_bytes()].concat()
+}
+
+pub fn prove<G: Group>(
+    group: &G,
+    domain: &[u8],
+    statement: &DleqStatement<G::Element>,
+    x: &G::Scalar,
+) -> CompactDleqProof<G> {
+    prove_with_rng(group, domain, statement, x, &mut OsRng)
+}
+
+pub fn prove_with_rng<G: Group>(
+    group: &G,
+    domain: &[u8],
+    statement: &DleqStatement<G::Element>,
+    x: &G::Scalar,
+    rng: &mut dyn SecureRng,
+) -> CompactDleqProof<G> {
+    let k = group.random_scalar(rng);
+    let (t1, t2) = (group.exp(&statement.g, &k), group.exp(&statement.h, &k));
+    let c = challenge(group, domain, statement, &t1, &t2);
+    // s = k + x*(order - c) = k - c*x
+    let minus_c = group.scalar_from_integer(&(group.order() - group.scalar_to_integer(&c)));
+    let s = group.mul_add(&k, x, &minus_c);
+    CompactDleqProof { c, s }
+}
+
+// Rebuilds g^k = g^s * y1^c and h^k = h^s * y2^c and checks they hash back to c
+pub fn verify<G: Group>(
+    group: &G,
+    domain: &[u8],
+    statement: &DleqStatement<G::Element>,
+    proof: &CompactDleqProof<G>,
+) -> bool {
+    let t1 = group.mul(&group.exp(&statement.g, &proof.s), &group.exp(&statement.y1, &proof.c));
+    let t2 = group.mul(&group.exp(&statement.h, &proof.s), &group.exp(&statement.y2, &proof.c));
+    let expected = challenge(group, domain, statement, &t1, &t2);
+    encode_scalar(group, &expected).ct_eq(&encode_scalar(group, &proof.c)).into()
+}
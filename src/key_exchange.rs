@@ -0,0 +1,72 @@
+use rand::rngs::OsRng;
+
+use crate::chaum_pedersen::{SecureRng, Transcript};
+use crate::group::{Dleq, DleqProof, DleqStatement, Group};
+use crate::sigma;
+
+// Proof that a Diffie-Hellman share A = g^a and a value B = h^a derived from it use the same a,
+// e.g. that B is the shared secret computed against the peer's share h, without revealing a. The
+// proof is bound to a context such as a handshake or session id, so it cannot be replayed in
+// another exchange.
+
+#[derive(Debug, Clone)]
+pub struct DhShare<E> {
+    pub share: E,    // A = g^a
+    pub base: E,     // h, usually the peer's share
+    pub derived: E,  // B = h^a
+}
+
+impl<E: Clone> DhShare<E> {
+    fn statement<G: Group<Element = E>>(&self, group: &G) -> DleqStatement<E> {
+        DleqStatement {
+            g: group.generator(),
+            h: self.base.clone(),
+            y1: self.share.clone(),
+            y2: self.derived.clone(),
+        }
+    }
+}
+
+pub type DhShareProof<G> = DleqProof<G>;
+
+fn transcript(context: &[u8]) -> Transcript {
+    let mut transcript = Transcript::new(b"dh-share");
+    transcript.append_message(b"context", context);
+    transcript
+}
+
+pub fn prove_share<G: Group + Clone>(
+    group: &G,
+    base: &G::Element,
+    a: &G::Scalar,
+    context: &[u8],
+) -> (DhShare<G::Element>, DhShareProof<G>) {
+    prove_share_with_rng(group, base, a, context, &mut OsRng)
+}
+
+pub fn prove_share_with_rng<G: Group + Clone>(
+    group: &G,
+    base: &G::Element,
+    a: &G::Scalar,
+    context: &[u8],
+    rng: &mut dyn SecureRng,
+) -> (DhShare<G::Element>, DhShareProof<G>) {
+    let share = DhShare {
+        share: group.exp(&group.generator(), a),
+        base: base.clone(),
+        derived: group.exp(base, a),
+    };
+    let statement = share.statement(group);
+    let proof = sigma::prove_with_rng(&Dleq::new(group.clone()), &mut transcript(context), &statement, a, rng);
+    (share, proof)
+}
+
+pub fn verify_share<G: Group + Clone>(
+    group: &G,
+    share: &DhShare<G::Element>,
+    context: &[u8],
+    proof: &DhShareProof<G>,
+) -> bool {
+    let statement = share.statement(group);
+    sigma::verify_with_transcript(&Dleq::new(group.clone()), &mut transcript(context), &statement, proof)
+}
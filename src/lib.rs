@@ -1 +1,118 @@
-pub mod chaum_pedersen;
\ No newline at end of file
+// tonic::Status is the error type of every RPC-facing function in this crate
+#![allow(clippy::result_large_err)]
+// Without `std` only the proof core remains; see the `std` feature
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "std")]
+pub mod batch;
+#[cfg(feature = "std")]
+pub mod audit;
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+pub mod backend;
+#[cfg(all(any(feature = "grpc", feature = "client"), not(target_arch = "wasm32")))]
+pub mod auth;
+pub mod chaum_pedersen;
+#[cfg(all(feature = "client", not(target_arch = "wasm32")))]
+pub mod checkpoint;
+#[cfg(all(feature = "client", not(target_arch = "wasm32")))]
+pub mod client_api;
+#[cfg(all(feature = "client", not(target_arch = "wasm32")))]
+pub mod client_lib;
+#[cfg(feature = "std")]
+pub mod config;
+#[cfg(all(any(feature = "grpc", feature = "client"), not(target_arch = "wasm32")))]
+pub mod convert;
+#[cfg(feature = "std")]
+pub mod dleq;
+#[cfg(feature = "std")]
+pub mod elgamal;
+#[cfg(all(any(feature = "grpc", feature = "client"), not(target_arch = "wasm32")))]
+pub mod evidence;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(all(feature = "rest", not(target_arch = "wasm32")))]
+pub mod gateway;
+#[cfg(feature = "std")]
+pub mod group;
+#[cfg(all(feature = "grpc", not(target_arch = "wasm32")))]
+pub mod hooks;
+#[cfg(feature = "std")]
+pub mod interop;
+#[cfg(feature = "std")]
+pub mod key_exchange;
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+pub mod params_cache;
+#[cfg(feature = "std")]
+pub mod pedersen;
+#[cfg(all(feature = "grpc", not(target_arch = "wasm32")))]
+pub mod policy;
+#[cfg(feature = "std")]
+pub mod proof_file;
+#[cfg(feature = "std")]
+pub mod range;
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+pub mod registry;
+#[cfg(feature = "std")]
+pub mod schnorr;
+#[cfg(all(feature = "grpc", not(target_arch = "wasm32")))]
+pub mod service;
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+pub mod session;
+pub mod sigma;
+#[cfg(feature = "std")]
+pub mod simulator;
+#[cfg(all(feature = "otel", not(target_arch = "wasm32")))]
+pub mod telemetry;
+#[cfg(feature = "proptest")]
+pub mod strategies;
+#[cfg(all(feature = "grpc", feature = "client", not(target_arch = "wasm32")))]
+pub mod testing;
+#[cfg(feature = "test-vectors")]
+pub mod test_vectors;
+#[cfg(feature = "std")]
+pub mod threshold;
+#[cfg(feature = "std")]
+pub mod voprf;
+#[cfg(feature = "std")]
+pub mod vrf;
+
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+// The one implementation of the protocol arithmetic, also reachable as `chaum_pedersen::*`
+pub use chaum_pedersen::crypto;
+
+// What most users need, at the crate root: the proof types, the client and the generated
+// protocol. The modules stay public for everything else.
+pub use chaum_pedersen::{Commitment, ProofChallenge, ProofResponse, Prover, PublicParameters, Verifier, ZKProof};
+#[cfg(all(any(feature = "grpc", feature = "client"), not(target_arch = "wasm32")))]
+pub use zkp as proto;
+
+#[cfg(all(feature = "client", not(target_arch = "wasm32")))]
+pub mod client {
+    pub use crate::client_api::{MockZkpClient, ZkpClientApi};
+    pub use crate::client_lib::{
+        ChaumPedersenClient as ZkpClient, ClientError, ClientOptions, ParamsPin, ProtocolSession, RetryPolicy,
+        Verification,
+    };
+}
+
+// Generated gRPC messages, client and server for proto/zkp.proto, for proto/zkp_v2.proto as
+// `zkp::v2` and for the prover role in proto/zkp_prover.proto as `zkp::prover`
+#[cfg(all(any(feature = "grpc", feature = "client"), not(target_arch = "wasm32")))]
+// the Bytes-typed parameters (see build.rs) make InitializeProgress's Done variant the large one;
+// streams send it once, so it is not worth boxing in generated code
+#[allow(clippy::large_enum_variant)]
+pub mod zkp {
+    tonic::include_proto!("zkp");
+
+    pub mod v2 {
+        tonic::include_proto!("zkp.v2");
+    }
+
+    pub mod prover {
+        tonic::include_proto!("zkp.prover");
+    }
+}
@@ -0,0 +1,184 @@
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use hmac::{Hmac, Mac};
+use rand::rngs::OsRng;
+use rand::seq::SliceRandom;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+use crate::chaum_pedersen::PublicParameters;
+
+type HmacSha256 = Hmac<Sha256>;
+
+const CACHE_FILE: &str = "params.json";
+const KEY_FILE: &str = "cache.key";
+const KEY_LEN: usize = 32;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheEntry {
+    bits: u64,
+    params: PublicParameters,
+    mac: String,  // hex HMAC-SHA256 over the JSON of (bits, params)
+}
+
+// Generated parameter sets persisted to a directory, keyed by bit size, so a restart does not
+// have to search for safe primes again. Every entry carries a MAC; entries that fail it are
+// dropped on load rather than served.
+pub struct ParamsCache {
+    path: PathBuf,
+    key: Vec<u8>,
+    max_per_bits: usize,
+    entries: Mutex<Vec<CacheEntry>>,
+}
+
+impl ParamsCache {
+    // Uses the MAC key stored in the directory, creating the directory and a fresh key if needed
+    pub fn open(dir: impl AsRef<Path>, max_per_bits: usize) -> io::Result<Self> {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir)?;
+        let key = load_or_create_key(&dir.join(KEY_FILE))?;
+        Self::open_with_key(dir, key, max_per_bits)
+    }
+
+    pub fn open_with_key(dir: impl AsRef<Path>, key: Vec<u8>, max_per_bits: usize) -> io::Result<Self> {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir)?;
+        let path = dir.join(CACHE_FILE);
+
+        let stored: Vec<CacheEntry> = match fs::read(&path) {
+            // an unreadable file is treated like a cache full of bad entries
+            Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_default(),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e),
+        };
+
+        let cache = Self {
+            path,
+            key,
+            max_per_bits,
+            entries: Mutex::new(Vec::new()),
+        };
+
+        let total = stored.len();
+        let valid: Vec<CacheEntry> = stored.into_iter().filter(|entry| cache.entry_valid(entry)).collect();
+        let rewrite = valid.len() != total;
+        *cache.entries.lock().unwrap() = valid;
+        if rewrite {
+            cache.persist(&cache.entries.lock().unwrap())?;
+        }
+
+        Ok(cache)
+    }
+
+    fn mac(&self, bits: u64, params: &PublicParameters) -> HmacSha256 {
+        let payload = serde_json::to_vec(&(bits, params)).expect("parameters always serialize");
+        let mut mac = HmacSha256::new_from_slice(&self.key).expect("HMAC accepts keys of any length");
+        mac.update(&payload);
+        mac
+    }
+
+    fn entry_valid(&self, entry: &CacheEntry) -> bool {
+        match hex::decode(&entry.mac) {
+            Ok(tag) => self.mac(entry.bits, &entry.params).verify_slice(&tag).is_ok(),
+            Err(_) => false,
+        }
+    }
+
+    // Written to a temporary file first so a crash never leaves a half-written cache behind
+    fn persist(&self, entries: &[CacheEntry]) -> io::Result<()> {
+        let tmp = self.path.with_extension("json.tmp");
+        let mut file = fs::File::create(&tmp)?;
+        file.write_all(&serde_json::to_vec(entries)?)?;
+        file.sync_all()?;
+        fs::rename(tmp, &self.path)
+    }
+
+    // A random cached parameter set of this size, if there is one
+    pub fn get(&self, bits: u64) -> Option<PublicParameters> {
+        let entries = self.entries.lock().unwrap();
+        let matching: Vec<&CacheEntry> = entries.iter().filter(|entry| entry.bits == bits).collect();
+        matching.choose(&mut OsRng).map(|entry| entry.params.clone())
+    }
+
+    // Stores a parameter set, evicting the oldest one of the same size beyond the limit
+    pub fn insert(&self, bits: u64, params: PublicParameters) -> io::Result<()> {
+        let mut entries = self.entries.lock().unwrap();
+        let mac = hex::encode(self.mac(bits, &params).finalize().into_bytes());
+        entries.push(CacheEntry { bits, params, mac });
+
+        let count = entries.iter().filter(|entry| entry.bits == bits).count();
+        if count > self.max_per_bits {
+            let mut excess = count - self.max_per_bits;
+            entries.retain(|entry| {
+                let evict = excess > 0 && entry.bits == bits;
+                if evict {
+                    excess -= 1;
+                }
+                !evict
+            });
+        }
+
+        self.persist(&entries)
+    }
+
+    // Drops every cached set of this size, returning how many were removed
+    pub fn invalidate(&self, bits: u64) -> io::Result<usize> {
+        let mut entries = self.entries.lock().unwrap();
+        let before = entries.len();
+        entries.retain(|entry| entry.bits != bits);
+        let removed = before - entries.len();
+        if removed > 0 {
+            self.persist(&entries)?;
+        }
+        Ok(removed)
+    }
+
+    pub fn clear(&self) -> io::Result<()> {
+        let mut entries = self.entries.lock().unwrap();
+        entries.clear();
+        self.persist(&entries)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl std::fmt::Debug for ParamsCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ParamsCache")
+            .field("path", &self.path)
+            .field("max_per_bits", &self.max_per_bits)
+            .field("entries", &self.len())
+            .finish()
+    }
+}
+
+fn load_or_create_key(path: &Path) -> io::Result<Vec<u8>> {
+    match fs::read(path) {
+        Ok(key) => return Ok(key),
+        Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+        Err(_) => {}
+    }
+
+    let mut key = vec![0u8; KEY_LEN];
+    OsRng.fill_bytes(&mut key);
+
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)?.write_all(&key)?;
+    Ok(key)
+}
@@ -0,0 +1,172 @@
+use num_bigint::BigUint;
+use rand::rngs::OsRng;
+use serde::{Deserialize, Serialize};
+
+use crate::chaum_pedersen::{
+    ct_eq, generate_prover_secret_with_rng, modpow, secret_fixed_pow, secret_modpow, wire, PublicParameters,
+    SecureRng, Transcript,
+};
+use crate::group::Group;
+use crate::sigma::{self, SigmaProof, SigmaProtocol};
+
+// Pedersen commitments C = g^m h^r mod p. Hiding for uniform r, and binding as long as nobody
+// knows log_g(h), which is why h must be the seed-derived second generator.
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PedersenCommitment {
+    #[serde(with = "wire::biguint")]
+    pub c: BigUint,  // g^m h^r mod p
+}
+
+// Everything needed to open a commitment
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Opening {
+    #[serde(with = "wire::biguint")]
+    pub m: BigUint,
+    #[serde(with = "wire::biguint")]
+    pub r: BigUint,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpeningCommitment {
+    #[serde(with = "wire::biguint")]
+    pub t: BigUint,  // g^k1 h^k2 mod p
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpeningResponse {
+    #[serde(with = "wire::biguint")]
+    pub s1: BigUint,  // k1 + m*c mod q
+    #[serde(with = "wire::biguint")]
+    pub s2: BigUint,  // k2 + r*c mod q
+}
+
+pub type OpeningProof = SigmaProof<Pedersen>;
+
+#[derive(Debug, Clone)]
+pub struct Pedersen {
+    pub params: PublicParameters,
+    h: BigUint,
+}
+
+impl Pedersen {
+    // Returns None unless the parameters carry a second generator h
+    pub fn new(params: PublicParameters) -> Option<Self> {
+        let h = params.h.as_ref()?.h.clone();
+        Some(Self { params, h })
+    }
+
+    pub fn h(&self) -> &BigUint {
+        &self.h
+    }
+
+    pub fn commit(&self, m: &BigUint, r: &BigUint) -> PedersenCommitment {
+        let p = self.params.p();
+        let gm = secret_fixed_pow(self.params.g_table(), m, self.params.q());
+        let c = (gm * secret_modpow(&self.h, r, p)) % p;
+        PedersenCommitment { c }
+    }
+
+    // Commits to m under fresh randomness, returning the commitment and its opening
+    pub fn commit_random(&self, m: &BigUint) -> (PedersenCommitment, Opening) {
+        self.commit_random_with_rng(m, &mut OsRng)
+    }
+
+    pub fn commit_random_with_rng<R: SecureRng + ?Sized>(
+        &self,
+        m: &BigUint,
+        rng: &mut R,
+    ) -> (PedersenCommitment, Opening) {
+        let r = generate_prover_secret_with_rng(self.params.q(), rng);
+        let commitment = self.commit(m, &r);
+        (commitment, Opening { m: m % self.params.q(), r })
+    }
+
+    pub fn verify_opening(&self, commitment: &PedersenCommitment, opening: &Opening) -> bool {
+        ct_eq(&self.commit(&opening.m, &opening.r).c, &commitment.c, self.params.p())
+    }
+
+    // Commitment to m1 + m2 under r1 + r2
+    pub fn add(&self, a: &PedersenCommitment, b: &PedersenCommitment) -> PedersenCommitment {
+        PedersenCommitment {
+            c: (&a.c * &b.c) % self.params.p(),
+        }
+    }
+
+    // Opening matching `add` on the corresponding commitments
+    pub fn add_openings(&self, a: &Opening, b: &Opening) -> Opening {
+        let q = self.params.q();
+        Opening {
+            m: (&a.m + &b.m) % q,
+            r: (&a.r + &b.r) % q,
+        }
+    }
+
+    // Proves knowledge of an opening without revealing m or r
+    pub fn prove_opening(&self, commitment: &PedersenCommitment, opening: &Opening) -> OpeningProof {
+        sigma::prove(self, commitment, opening)
+    }
+
+    pub fn verify_opening_proof(&self, commitment: &PedersenCommitment, proof: &OpeningProof) -> bool {
+        sigma::verify(self, commitment, proof)
+    }
+}
+
+impl SigmaProtocol for Pedersen {
+    type Statement = PedersenCommitment;
+    type Witness = Opening;
+    type Commitment = OpeningCommitment;
+    type Nonce = (BigUint, BigUint);
+    type Response = OpeningResponse;
+
+    const LABEL: &'static [u8] = b"pedersen-opening";
+
+    fn challenge_modulus(&self) -> &BigUint {
+        self.params.q()
+    }
+
+    fn commit(
+        &self,
+        _statement: &PedersenCommitment,
+        _witness: &Opening,
+        rng: &mut dyn SecureRng,
+    ) -> (OpeningCommitment, (BigUint, BigUint)) {
+        let k1 = generate_prover_secret_with_rng(self.params.q(), rng);
+        let k2 = generate_prover_secret_with_rng(self.params.q(), rng);
+        let t = Pedersen::commit(self, &k1, &k2).c;
+        (OpeningCommitment { t }, (k1, k2))
+    }
+
+    fn respond(&self, witness: &Opening, nonce: (BigUint, BigUint), challenge: &BigUint) -> OpeningResponse {
+        OpeningResponse {
+            s1: self.params.mul_add(&nonce.0, &witness.m, challenge),
+            s2: self.params.mul_add(&nonce.1, &witness.r, challenge),
+        }
+    }
+
+    fn verify(
+        &self,
+        statement: &PedersenCommitment,
+        commitment: &OpeningCommitment,
+        challenge: &BigUint,
+        response: &OpeningResponse,
+    ) -> bool {
+        let p = self.params.p();
+        // Check: g^s1 h^s2 mod p = t * C^c mod p
+        let left = (modpow(self.params.g(), &response.s1, p) * modpow(&self.h, &response.s2, p)) % p;
+        let right = (&commitment.t * modpow(&statement.c, challenge, p)) % p;
+        ct_eq(&left, &right, p)
+    }
+
+    fn append_transcript(
+        &self,
+        transcript: &mut Transcript,
+        statement: &PedersenCommitment,
+        commitment: &OpeningCommitment,
+    ) {
+        transcript.append_integer(b"g", self.params.g());
+        transcript.append_integer(b"h", &self.h);
+        transcript.append_integer(b"C", &statement.c);
+        transcript.append_integer(b"t", &commitment.t);
+    }
+}
@@ -0,0 +1,135 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::Duration;
+
+use prost::Message;
+use tonic::{Code, Status};
+
+use crate::config::ServerConfig;
+use crate::zkp;
+use crate::zkp::policy_violation::Rule;
+
+// Limits on parameter generation an operator sets to keep expensive sizes off small instances
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GenerationPolicy {
+    pub min_bits: u32,
+    pub max_bits: u32,
+    pub max_concurrent_generations: usize,
+    // A timeout applies to its bit size and every larger one up to the next entry
+    pub timeouts: BTreeMap<u32, Duration>,
+}
+
+impl GenerationPolicy {
+    pub fn from_config(config: &ServerConfig) -> Self {
+        Self {
+            min_bits: config.min_bits,
+            max_bits: config.max_bits,
+            max_concurrent_generations: config.max_concurrent_generations,
+            timeouts: config
+                .generation_timeout_secs
+                .iter()
+                .map(|(&bits, &secs)| (bits, Duration::from_secs(secs)))
+                .collect(),
+        }
+    }
+
+    pub fn check_bits(&self, bits: u32) -> Result<(), PolicyViolation> {
+        if bits < self.min_bits {
+            return Err(PolicyViolation::BitsTooSmall { requested: bits, min: self.min_bits });
+        }
+        if bits > self.max_bits {
+            return Err(PolicyViolation::BitsTooLarge { requested: bits, max: self.max_bits });
+        }
+        Ok(())
+    }
+
+    pub fn timeout_for(&self, bits: u32) -> Option<Duration> {
+        self.timeouts.range(..=bits).next_back().map(|(_, &timeout)| timeout)
+    }
+}
+
+impl Default for GenerationPolicy {
+    fn default() -> Self {
+        Self::from_config(&ServerConfig::default())
+    }
+}
+
+// A request the policy refused. It travels as a zkp.PolicyViolation in the status details, so
+// clients can tell which limit they hit without parsing the message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PolicyViolation {
+    BitsTooSmall { requested: u32, min: u32 },
+    BitsTooLarge { requested: u32, max: u32 },
+    TooManyGenerations { limit: usize },
+    GenerationTimeout { bits: u32, limit: Duration },
+}
+
+impl fmt::Display for PolicyViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PolicyViolation::BitsTooSmall { requested, min } => {
+                write!(f, "bit size {} is below the minimum of {}", requested, min)
+            }
+            PolicyViolation::BitsTooLarge { requested, max } => {
+                write!(f, "bit size {} is above the maximum of {}", requested, max)
+            }
+            PolicyViolation::TooManyGenerations { limit } => {
+                write!(f, "too many parameter generations in progress (limit {})", limit)
+            }
+            PolicyViolation::GenerationTimeout { bits, limit } => {
+                write!(f, "{}-bit parameter generation exceeded the {}s limit", bits, limit.as_secs_f64())
+            }
+        }
+    }
+}
+
+impl std::error::Error for PolicyViolation {}
+
+impl PolicyViolation {
+    fn to_proto(self) -> zkp::PolicyViolation {
+        let (rule, requested, limit) = match self {
+            PolicyViolation::BitsTooSmall { requested, min } => (Rule::MinBits, requested.into(), min.into()),
+            PolicyViolation::BitsTooLarge { requested, max } => (Rule::MaxBits, requested.into(), max.into()),
+            PolicyViolation::TooManyGenerations { limit } => (Rule::MaxConcurrentGenerations, 0, limit as u64),
+            PolicyViolation::GenerationTimeout { bits, limit } => {
+                (Rule::GenerationTimeout, bits.into(), limit.as_millis() as u64)
+            }
+        };
+        zkp::PolicyViolation {
+            rule: rule.into(),
+            requested,
+            limit,
+        }
+    }
+
+    // Recovers the violation from a status returned by the server, if that is what it carries
+    pub fn from_status(status: &Status) -> Option<Self> {
+        let proto = zkp::PolicyViolation::decode(status.details()).ok()?;
+        let requested = u32::try_from(proto.requested).ok();
+        let bits = || u32::try_from(proto.limit).ok();
+        match Rule::try_from(proto.rule).ok()? {
+            Rule::MinBits => Some(PolicyViolation::BitsTooSmall { requested: requested?, min: bits()? }),
+            Rule::MaxBits => Some(PolicyViolation::BitsTooLarge { requested: requested?, max: bits()? }),
+            Rule::MaxConcurrentGenerations => Some(PolicyViolation::TooManyGenerations {
+                limit: usize::try_from(proto.limit).ok()?,
+            }),
+            Rule::GenerationTimeout => Some(PolicyViolation::GenerationTimeout {
+                bits: requested?,
+                limit: Duration::from_millis(proto.limit),
+            }),
+            Rule::Unspecified => None,
+        }
+    }
+}
+
+impl From<PolicyViolation> for Status {
+    fn from(violation: PolicyViolation) -> Self {
+        let code = match violation {
+            PolicyViolation::BitsTooSmall { .. } | PolicyViolation::BitsTooLarge { .. } => Code::InvalidArgument,
+            PolicyViolation::TooManyGenerations { .. } => Code::ResourceExhausted,
+            PolicyViolation::GenerationTimeout { .. } => Code::DeadlineExceeded,
+        };
+        Status::with_details(code, violation.to_string(), violation.to_proto().encode_to_vec().into())
+    }
+}
@@ -0,0 +1,143 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use num_bigint::BigUint;
+use rand::rngs::OsRng;
+use serde::{Deserialize, Serialize};
+
+use crate::chaum_pedersen::{
+    wire, ChaumPedersen, Commitment, ParameterError, ProofChallenge, ProofResponse, Prover, PublicParameters,
+    SecureRng, Transcript,
+};
+use crate::sigma::{self, SigmaProof};
+
+// Extension conventionally used for proof files
+pub const PROOF_FILE_EXTENSION: &str = "zkproof";
+
+const PROOF_FILE_VERSION: u32 = 1;
+
+// Domain label for the Fiat-Shamir transcript of a proof file
+const PROOF_FILE_LABEL: &[u8] = b"zkp-chaum-pedersen-grpc/zkproof";
+
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ProofFileError {
+    Io(io::Error),
+    Format(serde_json::Error),
+    UnsupportedVersion(u32),
+    InvalidParameters(ParameterError),
+}
+
+impl fmt::Display for ProofFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProofFileError::Io(e) => write!(f, "cannot access proof file: {}", e),
+            ProofFileError::Format(e) => write!(f, "malformed proof file: {}", e),
+            ProofFileError::UnsupportedVersion(v) => write!(f, "unsupported proof file version {}", v),
+            ProofFileError::InvalidParameters(e) => write!(f, "invalid parameters: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ProofFileError {}
+
+impl From<io::Error> for ProofFileError {
+    fn from(e: io::Error) -> Self {
+        ProofFileError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for ProofFileError {
+    fn from(e: serde_json::Error) -> Self {
+        ProofFileError::Format(e)
+    }
+}
+
+// A self-contained non-interactive Chaum-Pedersen proof: the parameters, the statement (a1, b1, c1)
+// and a Fiat-Shamir proof that log_g(a1) = log_b1(c1). It is stored as JSON, so a prover
+// without network access can hand it to a verifier as a file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProofFile {
+    pub version: u32,
+    pub params: PublicParameters,
+    pub statement: Commitment,
+    pub commitment: ProofChallenge,
+    #[serde(with = "wire::biguint")]
+    pub challenge: BigUint,
+    pub response: ProofResponse,
+}
+
+// Binds the challenge to the whole group, not just g, since the verifier takes p and q from the file
+fn transcript(params: &PublicParameters) -> Transcript {
+    let mut transcript = Transcript::new(PROOF_FILE_LABEL);
+    transcript.append_integer(b"p", params.p());
+    transcript.append_integer(b"q", params.q());
+    transcript
+}
+
+impl ProofFile {
+    // Proves knowledge of the prover's secret a behind a fresh statement
+    pub fn create(prover: &Prover) -> Self {
+        Self::create_with_rng(prover, &mut OsRng)
+    }
+
+    // Same, with the proof nonce drawn from `rng`
+    pub fn create_with_rng(prover: &Prover, rng: &mut dyn SecureRng) -> Self {
+        let params = prover.params.clone();
+        let statement = prover.generate_commitment();
+        let protocol = ChaumPedersen::new(params.clone());
+        let proof = sigma::prove_with_rng(&protocol, &mut transcript(&params), &statement, &prover.secret_a, rng);
+
+        Self {
+            version: PROOF_FILE_VERSION,
+            params,
+            statement,
+            commitment: proof.commitment,
+            challenge: proof.challenge,
+            response: proof.response,
+        }
+    }
+
+    pub fn read(path: impl AsRef<Path>) -> Result<Self, ProofFileError> {
+        let file: Self = serde_json::from_slice(&fs::read(path)?)?;
+        if file.version != PROOF_FILE_VERSION {
+            return Err(ProofFileError::UnsupportedVersion(file.version));
+        }
+        Ok(file)
+    }
+
+    pub fn write(&self, path: impl AsRef<Path>) -> Result<(), ProofFileError> {
+        let mut json = serde_json::to_vec_pretty(self)?;
+        json.push(b'\n');
+        fs::write(path, json)?;
+        Ok(())
+    }
+
+    // Checks the parameters from scratch, then the proof. Ok(false) means a well-formed file whose
+    // proof does not hold.
+    pub fn verify(&self) -> Result<bool, ProofFileError> {
+        self.params.validate().map_err(ProofFileError::InvalidParameters)?;
+
+        let params = &self.params;
+        let elements = [
+            &self.statement.a1,
+            &self.statement.b1,
+            &self.statement.c1,
+            &self.commitment.y1,
+            &self.commitment.y2,
+        ];
+        if !elements.into_iter().all(|x| params.is_element(x)) || self.response.z >= *params.q() {
+            return Ok(false);
+        }
+
+        let proof = SigmaProof::<ChaumPedersen> {
+            commitment: self.commitment.clone(),
+            challenge: self.challenge.clone(),
+            response: self.response.clone(),
+        };
+        let protocol = ChaumPedersen::new(params.clone());
+        Ok(sigma::verify_with_transcript(&protocol, &mut transcript(params), &self.statement, &proof))
+    }
+}
@@ -0,0 +1,475 @@
+// tonic::Status is the error type of the key helpers as well as the RPCs
+#![allow(clippy::result_large_err)]
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+
+use num_bigint::BigUint;
+use tokio::sync::watch;
+use tokio::task::JoinSet;
+use tonic::codec::CompressionEncoding;
+use tonic::service::interceptor::InterceptedService;
+use tonic::transport::server::TcpIncoming;
+use tonic::transport::{Certificate, Identity, Server, ServerTlsConfig};
+use tonic::{Request, Response, Status};
+use tracing::{error, info, info_span, warn};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use uuid::Uuid;
+use zkp_chaum_pedersen_grpc::auth::Authenticator;
+use zkp_chaum_pedersen_grpc::backend::{self, BackendError, NonceHandle, SecretBackend, SoftwareBackend};
+use zkp_chaum_pedersen_grpc::chaum_pedersen::{Commitment, ParameterPolicy, Prover, PublicParameters};
+use zkp_chaum_pedersen_grpc::config::ProverConfig;
+use zkp_chaum_pedersen_grpc::convert::FieldLimits;
+use zkp_chaum_pedersen_grpc::zkp;
+
+use zkp::prover::prover_service_server::{ProverService, ProverServiceServer};
+use zkp::prover::*;
+
+// Longest key id accepted, so ids stay usable as log fields and file keys
+const MAX_KEY_ID_LEN: usize = 128;
+
+// A key's secrets, the commitment to them and the nonces drawn for it but not yet answered
+#[derive(Debug)]
+struct Key {
+    backend: Box<dyn SecretBackend>,
+    commitment: Commitment,
+    nonces: Mutex<HashMap<String, NonceHandle>>,
+}
+
+// Where the secrets of a new key are generated
+#[derive(Debug)]
+enum KeyFactory {
+    Software,
+    #[cfg(feature = "pkcs11")]
+    Pkcs11 { module: PathBuf, slot: u64, pin: String },
+}
+
+impl KeyFactory {
+    fn from_config(config: &ProverConfig) -> Self {
+        match config.backend.as_str() {
+            #[cfg(feature = "pkcs11")]
+            "pkcs11" => KeyFactory::Pkcs11 {
+                module: config.pkcs11_module.clone().expect("validated with the backend"),
+                slot: config.pkcs11_slot,
+                pin: config.pkcs11_pin.clone().expect("validated with the backend"),
+            },
+            _ => KeyFactory::Software,
+        }
+    }
+
+    // The new backend, and the prover to save if its secrets can be kept in the key store
+    fn generate(&self, params: PublicParameters) -> Result<(Box<dyn SecretBackend>, Option<Prover>), BackendError> {
+        match self {
+            KeyFactory::Software => {
+                let prover = Prover::new(params);
+                Ok((Box::new(SoftwareBackend::new(prover.clone())), Some(prover)))
+            }
+            #[cfg(feature = "pkcs11")]
+            KeyFactory::Pkcs11 { module, slot, pin } => {
+                let slot = (*slot)
+                    .try_into()
+                    .map_err(|_| BackendError::Token(format!("slot {} out of range", slot)))?;
+                let backend = backend::Pkcs11Backend::generate(module, slot, pin, params)?;
+                Ok((Box::new(backend), None))
+            }
+        }
+    }
+}
+
+// JSON file of software keys by id. It holds the raw secrets, so it is created readable by the
+// owner only and replaced as a whole on every change.
+#[derive(Debug)]
+struct KeyStore {
+    path: PathBuf,
+    lock: Mutex<()>,
+}
+
+impl KeyStore {
+    fn new(path: PathBuf) -> Self {
+        Self { path, lock: Mutex::new(()) }
+    }
+
+    fn load(&self) -> std::io::Result<BTreeMap<String, Prover>> {
+        match std::fs::read(&self.path) {
+            Ok(bytes) => serde_json::from_slice(&bytes).map_err(std::io::Error::other),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(BTreeMap::new()),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn insert(&self, key_id: &str, prover: &Prover) -> std::io::Result<()> {
+        let _guard = self.lock.lock().unwrap();
+        let mut keys = self.load()?;
+        keys.insert(key_id.to_string(), prover.clone());
+        let json = serde_json::to_vec_pretty(&keys).map_err(std::io::Error::other)?;
+
+        let tmp = self.path.with_extension("tmp");
+        write_private(&tmp, &json)?;
+        std::fs::rename(&tmp, &self.path)
+    }
+}
+
+#[cfg(unix)]
+fn write_private(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    use std::io::Write;
+    use std::os::unix::fs::OpenOptionsExt;
+
+    let mut file = std::fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)?;
+    file.write_all(contents)?;
+    file.sync_all()
+}
+
+#[cfg(not(unix))]
+fn write_private(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    std::fs::write(path, contents)
+}
+
+// Serves ProverService: holds secrets on behalf of applications and hands out only commitments,
+// nonce commitments and responses
+#[derive(Debug)]
+struct ProverDaemon {
+    keys: RwLock<HashMap<String, Arc<Key>>>,
+    factory: Arc<KeyFactory>,
+    store: Option<KeyStore>,
+    policy: ParameterPolicy,
+    max_keys: usize,
+    max_pending_nonces: usize,
+}
+
+impl ProverDaemon {
+    fn from_config(config: &ProverConfig) -> Self {
+        Self {
+            keys: RwLock::new(HashMap::new()),
+            factory: Arc::new(KeyFactory::from_config(config)),
+            store: config.key_store.clone().map(KeyStore::new),
+            policy: ParameterPolicy {
+                min_bits: config.min_bits.into(),
+                max_bits: config.max_bits.into(),
+                ..ParameterPolicy::default()
+            },
+            max_keys: config.max_keys,
+            max_pending_nonces: config.max_pending_nonces,
+        }
+    }
+
+    // Reopens the keys saved in the key store, if there is one
+    fn load_keys(&self) -> Result<usize, Box<dyn std::error::Error>> {
+        let Some(store) = &self.store else {
+            return Ok(0);
+        };
+        let saved = store.load()?;
+        let mut keys = self.keys.write().unwrap();
+        for (key_id, prover) in saved {
+            let backend = SoftwareBackend::new(prover);
+            let commitment = backend.commitment()?;
+            keys.insert(key_id, Arc::new(Key::new(Box::new(backend), commitment)));
+        }
+        Ok(keys.len())
+    }
+
+    fn key(&self, key_id: &str) -> Result<Arc<Key>, Status> {
+        self.keys
+            .read()
+            .unwrap()
+            .get(key_id)
+            .cloned()
+            .ok_or_else(|| Status::not_found(format!("No key {}", key_id)))
+    }
+}
+
+impl Key {
+    fn new(backend: Box<dyn SecretBackend>, commitment: Commitment) -> Self {
+        Self {
+            backend,
+            commitment,
+            nonces: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn info(&self, key_id: &str) -> KeyInfo {
+        KeyInfo {
+            key_id: key_id.to_string(),
+            params: Some(zkp::PublicParameters::from(self.backend.params())),
+            commitment: Some(zkp::Commitment::from(&self.commitment)),
+        }
+    }
+}
+
+fn check_key_id(key_id: &str) -> Result<(), Status> {
+    if key_id.is_empty() || key_id.len() > MAX_KEY_ID_LEN {
+        return Err(Status::invalid_argument(format!("key_id must be 1 to {} bytes", MAX_KEY_ID_LEN)));
+    }
+    Ok(())
+}
+
+fn backend_error(e: BackendError) -> Status {
+    match e {
+        BackendError::UnknownNonce => Status::not_found(e.to_string()),
+        BackendError::Unsupported(_) => Status::failed_precondition(e.to_string()),
+        _ => {
+            error!("Secret backend error: {}", e);
+            Status::internal("Secret backend unavailable")
+        }
+    }
+}
+
+// Runs modpows and token calls on the blocking thread pool so they never stall the async executor
+async fn run_blocking<T: Send + 'static>(
+    phase: &'static str,
+    work: impl FnOnce() -> T + Send + 'static,
+) -> Result<T, Status> {
+    let span = info_span!("crypto", otel.name = phase);
+    tokio::task::spawn_blocking(move || span.in_scope(work)).await.map_err(|e| {
+        error!("Blocking task failed: {}", e);
+        Status::internal("Internal error")
+    })
+}
+
+#[tonic::async_trait]
+impl ProverService for ProverDaemon {
+    async fn create_key(&self, request: Request<CreateKeyRequest>) -> Result<Response<KeyInfo>, Status> {
+        let req = request.into_inner();
+        check_key_id(&req.key_id)?;
+        let params = PublicParameters::try_from(req.params.ok_or_else(|| Status::invalid_argument("missing params"))?)?;
+        {
+            let keys = self.keys.read().unwrap();
+            if keys.contains_key(&req.key_id) {
+                return Err(Status::already_exists(format!("Key {} already exists", req.key_id)));
+            }
+            if keys.len() >= self.max_keys {
+                return Err(Status::resource_exhausted("Key limit reached"));
+            }
+        }
+
+        // primality of p and q is only checked here, off the executor
+        let (policy, factory) = (self.policy.clone(), self.factory.clone());
+        let (backend, commitment, prover) = run_blocking("create_key", move || {
+            params
+                .validate_with(&policy)
+                .map_err(|e| Status::invalid_argument(format!("Rejected parameters: {}", e)))?;
+            let (backend, prover) = factory.generate(params).map_err(backend_error)?;
+            let commitment = backend.commitment().map_err(backend_error)?;
+            Ok::<_, Status>((backend, commitment, prover))
+        })
+        .await??;
+
+        let key = Arc::new(Key::new(backend, commitment));
+        {
+            let mut keys = self.keys.write().unwrap();
+            if keys.contains_key(&req.key_id) {
+                return Err(Status::already_exists(format!("Key {} already exists", req.key_id)));
+            }
+            if keys.len() >= self.max_keys {
+                return Err(Status::resource_exhausted("Key limit reached"));
+            }
+            if let (Some(store), Some(prover)) = (&self.store, &prover) {
+                store.insert(&req.key_id, prover).map_err(|e| {
+                    error!("Key store error: {}", e);
+                    Status::internal("Key store unavailable")
+                })?;
+            }
+            keys.insert(req.key_id.clone(), key.clone());
+        }
+
+        info!(key_id = %req.key_id, bits = key.backend.params().p().bits(), "Created key");
+        Ok(Response::new(key.info(&req.key_id)))
+    }
+
+    async fn get_key(&self, request: Request<KeyRequest>) -> Result<Response<KeyInfo>, Status> {
+        let key_id = request.into_inner().key_id;
+        Ok(Response::new(self.key(&key_id)?.info(&key_id)))
+    }
+
+    async fn list_keys(&self, _request: Request<ListKeysRequest>) -> Result<Response<KeyList>, Status> {
+        let mut key_ids: Vec<String> = self.keys.read().unwrap().keys().cloned().collect();
+        key_ids.sort();
+        Ok(Response::new(KeyList { key_ids }))
+    }
+
+    async fn commit(&self, request: Request<KeyRequest>) -> Result<Response<NonceCommitment>, Status> {
+        let key = self.key(&request.into_inner().key_id)?;
+        if key.nonces.lock().unwrap().len() >= self.max_pending_nonces {
+            return Err(Status::resource_exhausted("Too many unanswered nonces for this key"));
+        }
+
+        let (values, handle) = run_blocking("commit", {
+            let key = key.clone();
+            move || key.backend.commit_nonce(&key.commitment.b1)
+        })
+        .await?
+        .map_err(backend_error)?;
+
+        let nonce_id = Uuid::new_v4().to_string();
+        key.nonces.lock().unwrap().insert(nonce_id.clone(), handle);
+        Ok(Response::new(NonceCommitment {
+            nonce_id,
+            challenge_values: Some(zkp::ProofChallenge::from(&values)),
+        }))
+    }
+
+    async fn respond(&self, request: Request<RespondRequest>) -> Result<Response<ProverResponse>, Status> {
+        let req = request.into_inner();
+        let key = self.key(&req.key_id)?;
+        // taken before anything can fail, so a nonce never answers twice
+        let handle = key
+            .nonces
+            .lock()
+            .unwrap()
+            .remove(&req.nonce_id)
+            .ok_or_else(|| Status::not_found("Nonce is unknown or already answered"))?;
+        let challenge = FieldLimits::for_params(key.backend.params()).scalar("challenge", &req.challenge);
+
+        let z = run_blocking("respond", move || match challenge {
+            Ok(challenge) => key.backend.respond(handle, &challenge).map_err(backend_error),
+            Err(e) => {
+                // releases the nonce inside the backend; the response is dropped unseen
+                let _ = key.backend.respond(handle, &BigUint::default());
+                Err(e.into())
+            }
+        })
+        .await??;
+        Ok(Response::new(ProverResponse { z: z.to_bytes_be() }))
+    }
+
+    async fn create_proof(&self, request: Request<KeyRequest>) -> Result<Response<zkp::ZkProofMessage>, Status> {
+        let key = self.key(&request.into_inner().key_id)?;
+        let proof = run_blocking("create_proof", move || backend::create_proof(key.backend.as_ref()))
+            .await?
+            .map_err(backend_error)?;
+        Ok(Response::new(zkp::ZkProofMessage::from(&proof)))
+    }
+}
+
+// Client certificates stay optional at the TLS layer so API-key clients can still connect; the interceptor decides
+fn tls_from_config(config: &ProverConfig) -> Result<Option<ServerTlsConfig>, Box<dyn std::error::Error>> {
+    let (cert, key) = match (&config.tls_cert, &config.tls_key) {
+        (Some(cert), Some(key)) => (std::fs::read(cert)?, std::fs::read(key)?),
+        _ => return Ok(None),
+    };
+
+    let mut tls = ServerTlsConfig::new().identity(Identity::from_pem(cert, key));
+    if let Some(ca) = &config.tls_client_ca {
+        tls = tls
+            .client_ca_root(Certificate::from_pem(std::fs::read(ca)?))
+            .client_auth_optional(true);
+    }
+    Ok(Some(tls))
+}
+
+// Resolves on Ctrl+C, or on SIGTERM when running on Unix
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c().await.expect("failed to listen for Ctrl+C");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("failed to listen for SIGTERM")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+
+    info!("Shutdown signal received, draining in-flight requests...");
+}
+
+// Headless prover: holds secrets for applications and serves ProverService only. It should sit
+// next to the applications that drive it, behind TLS and an allowlist, never next to a verifier.
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let config = match ProverConfig::load(std::env::args().skip(1)) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Configuration error: {}", e);
+            std::process::exit(2);
+        }
+    };
+
+    let level: tracing::Level = config.log_level.parse()?;
+    tracing_subscriber::registry()
+        .with(tracing_subscriber::filter::LevelFilter::from_level(level))
+        .with(tracing_subscriber::fmt::layer())
+        .init();
+
+    let daemon = Arc::new(ProverDaemon::from_config(&config));
+    if let Some(path) = &config.key_store {
+        info!("Loaded {} keys from {}", daemon.load_keys()?, path.display());
+    }
+    info!("Generating new secrets with the {} backend", config.backend);
+
+    let authenticator = Authenticator::new(
+        config.api_keys.clone(),
+        config.allowed_client_cns.iter().cloned().collect(),
+    );
+    if !authenticator.is_enabled() {
+        warn!("No API keys or client CNs configured, anyone who can connect can use the held secrets");
+    }
+
+    let mut builder = Server::builder();
+    if let Some(tls) = tls_from_config(&config)? {
+        builder = builder.tls_config(tls)?;
+    }
+
+    let grpc = ProverServiceServer::from_arc(daemon)
+        .accept_compressed(CompressionEncoding::Gzip)
+        .accept_compressed(CompressionEncoding::Zstd);
+    let service = InterceptedService::new(grpc, authenticator.into_interceptor());
+    let router = || builder.clone().add_service(service.clone());
+
+    // dropping `stop` tells every listener to shut down
+    let (stop, stopped) = watch::channel(());
+    let until_stopped = || {
+        let mut stopped = stopped.clone();
+        async move {
+            let _ = stopped.changed().await;
+        }
+    };
+
+    let mut listeners = JoinSet::new();
+    for &addr in &config.listen_addr {
+        let incoming = TcpIncoming::new(addr, false, None).map_err(|e| format!("cannot listen on {}: {}", addr, e))?;
+        info!("Prover listening on {}", addr);
+        listeners.spawn(router().serve_with_incoming_shutdown(incoming, until_stopped()));
+    }
+    #[cfg(unix)]
+    if let Some(path) = &config.listen_socket {
+        let listener =
+            tokio::net::UnixListener::bind(path).map_err(|e| format!("cannot listen on {}: {}", path.display(), e))?;
+        let incoming = tokio_stream::wrappers::UnixListenerStream::new(listener);
+        info!("Prover listening on {}", path.display());
+        listeners.spawn(router().serve_with_incoming_shutdown(incoming, until_stopped()));
+    }
+
+    let mut outcomes = Vec::new();
+    tokio::select! {
+        _ = shutdown_signal() => {}
+        Some(outcome) = listeners.join_next() => outcomes.push(outcome),
+    }
+    drop(stop);
+    while let Some(outcome) = listeners.join_next().await {
+        outcomes.push(outcome);
+    }
+    if let Some(path) = &config.listen_socket {
+        let _ = std::fs::remove_file(path);
+    }
+
+    for outcome in outcomes {
+        outcome??;
+    }
+    Ok(())
+}
@@ -0,0 +1,290 @@
+use num_bigint::BigUint;
+use rand::rngs::OsRng;
+
+use crate::chaum_pedersen::{
+    ct_eq, generate_prover_secret_with_rng, modpow, secret_modpow, PublicParameters, SecureRng, Transcript,
+};
+use crate::group::Group;
+use crate::pedersen::{Opening, Pedersen, PedersenCommitment};
+use crate::sigma::{self, SigmaProof, SigmaProtocol};
+
+// Range proof for a Pedersen-committed value: m lies in [0, 2^n). The prover commits to each bit as
+// C_i = g^b_i h^r_i, with the r_i chosen so that the product of C_i^(2^i) is C, and proves for each
+// C_i that it opens to 0 or to 1 with an OR of two Schnorr proofs over h (Cramer-Damgard-
+// Schoenmakers). Proof size grows linearly in n, so this is meant for small exponents.
+
+// Widest range a proof may cover
+pub const MAX_BITS: u32 = 64;
+
+#[derive(Debug, Clone)]
+pub struct RangeStatement {
+    pub commitment: PedersenCommitment,
+    pub bits: u32,
+}
+
+#[derive(Debug, Clone)]
+pub struct BitCommitment {
+    pub c: BigUint,   // g^b h^r
+    pub t0: BigUint,  // commitment of the branch "C_i = h^r"
+    pub t1: BigUint,  // commitment of the branch "C_i / g = h^r"
+}
+
+// c1 is the challenge minus c0, so only c0 is sent
+#[derive(Debug, Clone)]
+pub struct BitResponse {
+    pub c0: BigUint,
+    pub s0: BigUint,
+    pub s1: BigUint,
+}
+
+// One bit's real nonce and its simulated branch
+pub struct BitNonce {
+    bit: bool,
+    r: BigUint,
+    k: BigUint,
+    simulated_c: BigUint,
+    simulated_s: BigUint,
+}
+
+pub type RangeProof = SigmaProof<Range>;
+
+#[derive(Debug, Clone)]
+pub struct Range {
+    pub pedersen: Pedersen,
+}
+
+impl Range {
+    pub fn new(pedersen: Pedersen) -> Self {
+        Self { pedersen }
+    }
+
+    fn params(&self) -> &PublicParameters {
+        &self.pedersen.params
+    }
+
+    // C_i for b = 0 and C_i / g for b = 1, the values each branch proves to be a power of h
+    fn branch_targets(&self, c: &BigUint) -> [BigUint; 2] {
+        let params = self.params();
+        let g_inverse = modpow(params.g(), &(params.q() - 1u32), params.p());
+        [c.clone(), c * g_inverse % params.p()]
+    }
+
+    // h^s * target^(-c), the commitment a branch with challenge c and response s must have had
+    fn simulate(&self, target: &BigUint, c: &BigUint, s: &BigUint) -> BigUint {
+        let params = self.params();
+        let negated = (params.q() - c % params.q()) % params.q();
+        modpow(self.pedersen.h(), s, params.p()) * modpow(target, &negated, params.p()) % params.p()
+    }
+}
+
+// The range must also stay below q, or values would wrap around
+fn in_bounds(pedersen: &Pedersen, bits: u32) -> bool {
+    bits > 0 && bits <= MAX_BITS && u64::from(bits) < pedersen.params.q().bits()
+}
+
+// Proves that `opening` commits to a value below 2^bits. None if it does not or bits is out of range.
+pub fn prove(pedersen: &Pedersen, commitment: &PedersenCommitment, opening: &Opening, bits: u32) -> Option<RangeProof> {
+    prove_with_rng(pedersen, commitment, opening, bits, &mut OsRng)
+}
+
+pub fn prove_with_rng(
+    pedersen: &Pedersen,
+    commitment: &PedersenCommitment,
+    opening: &Opening,
+    bits: u32,
+    rng: &mut dyn SecureRng,
+) -> Option<RangeProof> {
+    if !in_bounds(pedersen, bits) || opening.m.bits() > u64::from(bits) {
+        return None;
+    }
+    let statement = RangeStatement {
+        commitment: commitment.clone(),
+        bits,
+    };
+    let range = Range::new(pedersen.clone());
+    Some(sigma::prove_with_rng(&range, &mut Transcript::new(Range::LABEL), &statement, opening, rng))
+}
+
+pub fn verify(pedersen: &Pedersen, commitment: &PedersenCommitment, bits: u32, proof: &RangeProof) -> bool {
+    verify_with_transcript(pedersen, &mut Transcript::new(Range::LABEL), commitment, bits, proof)
+}
+
+// Proofs sent to the server's VerifyRangeProof are bound to the session they were made for
+pub fn prove_for_session(
+    pedersen: &Pedersen,
+    session_id: &str,
+    commitment: &PedersenCommitment,
+    opening: &Opening,
+    bits: u32,
+) -> Option<RangeProof> {
+    if !in_bounds(pedersen, bits) || opening.m.bits() > u64::from(bits) {
+        return None;
+    }
+    let statement = RangeStatement {
+        commitment: commitment.clone(),
+        bits,
+    };
+    let range = Range::new(pedersen.clone());
+    Some(sigma::prove_with_transcript(&range, &mut session_transcript(session_id), &statement, opening))
+}
+
+pub fn verify_for_session(
+    pedersen: &Pedersen,
+    session_id: &str,
+    commitment: &PedersenCommitment,
+    bits: u32,
+    proof: &RangeProof,
+) -> bool {
+    verify_with_transcript(pedersen, &mut session_transcript(session_id), commitment, bits, proof)
+}
+
+fn session_transcript(session_id: &str) -> Transcript {
+    let mut transcript = Transcript::new(Range::LABEL);
+    transcript.append_message(b"session", session_id.as_bytes());
+    transcript
+}
+
+fn verify_with_transcript(
+    pedersen: &Pedersen,
+    transcript: &mut Transcript,
+    commitment: &PedersenCommitment,
+    bits: u32,
+    proof: &RangeProof,
+) -> bool {
+    let statement = RangeStatement {
+        commitment: commitment.clone(),
+        bits,
+    };
+    sigma::verify_with_transcript(&Range::new(pedersen.clone()), transcript, &statement, proof)
+}
+
+impl SigmaProtocol for Range {
+    type Statement = RangeStatement;
+    type Witness = Opening;
+    type Commitment = Vec<BitCommitment>;
+    type Nonce = Vec<BitNonce>;
+    type Response = Vec<BitResponse>;
+
+    const LABEL: &'static [u8] = b"range";
+
+    fn challenge_modulus(&self) -> &BigUint {
+        self.params().q()
+    }
+
+    fn commit(
+        &self,
+        statement: &RangeStatement,
+        witness: &Opening,
+        rng: &mut dyn SecureRng,
+    ) -> (Vec<BitCommitment>, Vec<BitNonce>) {
+        let (p, q) = (self.params().p(), self.params().q());
+
+        // r_1.. are random and r_0 absorbs the difference, so that sum(2^i r_i) = r
+        let mut rs: Vec<BigUint> = (0..statement.bits).map(|_| generate_prover_secret_with_rng(q, rng)).collect();
+        let weighted = rs.iter().enumerate().skip(1).fold(BigUint::from(0u32), |acc, (i, r)| (acc + (r << i)) % q);
+        rs[0] = (&witness.r % q + q - weighted) % q;
+
+        rs.into_iter()
+            .enumerate()
+            .map(|(i, r)| {
+                let bit = witness.m.bit(i as u64);
+                let c = self.pedersen.commit(&BigUint::from(u32::from(bit)), &r).c;
+                let k = generate_prover_secret_with_rng(q, rng);
+                let simulated_c = generate_prover_secret_with_rng(q, rng);
+                let simulated_s = generate_prover_secret_with_rng(q, rng);
+
+                let real = secret_modpow(self.pedersen.h(), &k, p);
+                let targets = self.branch_targets(&c);
+                let simulated = self.simulate(&targets[usize::from(!bit)], &simulated_c, &simulated_s);
+                let (t0, t1) = if bit { (simulated, real) } else { (real, simulated) };
+
+                let nonce = BitNonce {
+                    bit,
+                    r,
+                    k,
+                    simulated_c,
+                    simulated_s,
+                };
+                (BitCommitment { c, t0, t1 }, nonce)
+            })
+            .unzip()
+    }
+
+    fn respond(&self, _witness: &Opening, nonce: Vec<BitNonce>, challenge: &BigUint) -> Vec<BitResponse> {
+        let q = self.params().q();
+        nonce
+            .into_iter()
+            .map(|bit| {
+                let real_c = (challenge + q - &bit.simulated_c) % q;
+                let real_s = self.params().mul_add(&bit.k, &bit.r, &real_c);
+                if bit.bit {
+                    BitResponse {
+                        c0: bit.simulated_c,
+                        s0: bit.simulated_s,
+                        s1: real_s,
+                    }
+                } else {
+                    BitResponse {
+                        c0: real_c,
+                        s0: real_s,
+                        s1: bit.simulated_s,
+                    }
+                }
+            })
+            .collect()
+    }
+
+    fn verify(
+        &self,
+        statement: &RangeStatement,
+        commitment: &Vec<BitCommitment>,
+        challenge: &BigUint,
+        response: &Vec<BitResponse>,
+    ) -> bool {
+        let (p, q) = (self.params().p(), self.params().q());
+        if !in_bounds(&self.pedersen, statement.bits)
+            || commitment.len() != statement.bits as usize
+            || response.len() != commitment.len()
+        {
+            return false;
+        }
+
+        // Check: the bit commitments recombine to C
+        let recombined = commitment
+            .iter()
+            .enumerate()
+            .fold(BigUint::from(1u32), |acc, (i, bit)| acc * modpow(&bit.c, &(BigUint::from(1u32) << i), p) % p);
+        if !ct_eq(&recombined, &statement.commitment.c, p) {
+            return false;
+        }
+
+        // Check per bit: h^s0 = t0 * C_i^c0 and h^s1 = t1 * (C_i / g)^c1, with c0 + c1 = challenge
+        commitment.iter().zip(response).all(|(bit, answer)| {
+            if !self.params().is_element(&bit.c) || answer.c0 >= *q || answer.s0 >= *q || answer.s1 >= *q {
+                return false;
+            }
+            let c1 = (challenge + q - &answer.c0) % q;
+            let targets = self.branch_targets(&bit.c);
+            let first = ct_eq(&self.simulate(&targets[0], &answer.c0, &answer.s0), &bit.t0, p);
+            let second = ct_eq(&self.simulate(&targets[1], &c1, &answer.s1), &bit.t1, p);
+            first & second
+        })
+    }
+
+    fn append_transcript(
+        &self,
+        transcript: &mut Transcript,
+        statement: &RangeStatement,
+        commitment: &Vec<BitCommitment>,
+    ) {
+        transcript.append_integer(b"g", self.params().g());
+        transcript.append_integer(b"h", self.pedersen.h());
+        transcript.append_integer(b"C", &statement.commitment.c);
+        transcript.append_u64(b"bits", u64::from(statement.bits));
+        for bit in commitment {
+            transcript.append_integer(b"C_i", &bit.c);
+            transcript.append_integer(b"t0", &bit.t0);
+            transcript.append_integer(b"t1", &bit.t1);
+        }
+    }
+}
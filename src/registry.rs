@@ -0,0 +1,234 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::chaum_pedersen::{Commitment, PublicParameters};
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+// A user's registered commitment (a1, b1, c1) and the parameters it was made over. Sessions opened
+// for the user run over these parameters and are verified against this commitment.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Registration {
+    pub params: PublicParameters,
+    pub commitment: Commitment,
+    pub created_at: u64,  // seconds since the Unix epoch
+    pub updated_at: u64,
+    pub version: u64,  // bumped by every change, so two concurrent updates cannot both apply
+    pub revoked: bool,
+    #[serde(default)]
+    pub recovery: Option<Recovery>,
+}
+
+// A secondary commitment, escrowed at registration, that can re-bind the account when the
+// primary secret is lost
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Recovery {
+    pub params: PublicParameters,
+    pub commitment: Commitment,
+}
+
+fn same_commitment(
+    (params, commitment): (&PublicParameters, &Commitment),
+    other_params: &PublicParameters,
+    other: &Commitment,
+) -> bool {
+    params.digest() == other_params.digest()
+        && commitment.a1 == other.a1
+        && commitment.b1 == other.b1
+        && commitment.c1 == other.c1
+}
+
+impl Registration {
+    pub fn new(params: PublicParameters, commitment: Commitment) -> Self {
+        let now = now_secs();
+        Self {
+            params,
+            commitment,
+            created_at: now,
+            updated_at: now,
+            version: 1,
+            revoked: false,
+            recovery: None,
+        }
+    }
+
+    pub fn with_recovery(mut self, recovery: Recovery) -> Self {
+        self.recovery = Some(recovery);
+        self
+    }
+
+    // The registration with `commitment` over `params` in place of the current one
+    pub fn rotated(&self, params: PublicParameters, commitment: Commitment) -> Self {
+        Self {
+            params,
+            commitment,
+            updated_at: now_secs(),
+            version: self.version + 1,
+            ..self.clone()
+        }
+    }
+
+    pub fn revoked(&self) -> Self {
+        Self {
+            revoked: true,
+            updated_at: now_secs(),
+            version: self.version + 1,
+            ..self.clone()
+        }
+    }
+
+    // Whether `commitment` is the one registered, over the same parameters
+    pub fn matches(&self, params: &PublicParameters, commitment: &Commitment) -> bool {
+        same_commitment((&self.params, &self.commitment), params, commitment)
+    }
+
+    // The same for the recovery commitment; false if there is none
+    pub fn matches_recovery(&self, params: &PublicParameters, commitment: &Commitment) -> bool {
+        self.recovery
+            .as_ref()
+            .is_some_and(|recovery| same_commitment((&recovery.params, &recovery.commitment), params, commitment))
+    }
+}
+
+// Where registrations live. Writes are conditional so that racing RPCs for one user cannot
+// overwrite each other: `insert` only creates, `replace` only succeeds over the version it read.
+pub trait RegistryStore: Send + Sync + fmt::Debug {
+    fn get(&self, user_id: &str) -> io::Result<Option<Registration>>;
+    // Returns false if the user already has a registration
+    fn insert(&self, user_id: &str, registration: &Registration) -> io::Result<bool>;
+    // Returns false if the stored registration is no longer at version `expected`
+    fn replace(&self, user_id: &str, expected: u64, registration: &Registration) -> io::Result<bool>;
+    fn len(&self) -> io::Result<usize>;
+    fn is_empty(&self) -> io::Result<bool> {
+        Ok(self.len()? == 0)
+    }
+    fn is_durable(&self) -> bool;
+}
+
+#[derive(Debug, Default)]
+pub struct MemoryRegistryStore {
+    registrations: Mutex<HashMap<String, Registration>>,
+}
+
+impl MemoryRegistryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl RegistryStore for MemoryRegistryStore {
+    fn get(&self, user_id: &str) -> io::Result<Option<Registration>> {
+        Ok(self.registrations.lock().unwrap().get(user_id).cloned())
+    }
+
+    fn insert(&self, user_id: &str, registration: &Registration) -> io::Result<bool> {
+        let mut registrations = self.registrations.lock().unwrap();
+        if registrations.contains_key(user_id) {
+            return Ok(false);
+        }
+        registrations.insert(user_id.to_string(), registration.clone());
+        Ok(true)
+    }
+
+    fn replace(&self, user_id: &str, expected: u64, registration: &Registration) -> io::Result<bool> {
+        let mut registrations = self.registrations.lock().unwrap();
+        match registrations.get_mut(user_id) {
+            Some(current) if current.version == expected => {
+                *current = registration.clone();
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    fn len(&self) -> io::Result<usize> {
+        Ok(self.registrations.lock().unwrap().len())
+    }
+
+    fn is_durable(&self) -> bool {
+        false
+    }
+}
+
+// Registrations as JSON rows in SQLite, with the version as a column for the conditional update
+#[cfg(feature = "registry-sqlite")]
+#[derive(Debug)]
+pub struct SqliteRegistryStore {
+    conn: Mutex<rusqlite::Connection>,
+}
+
+#[cfg(feature = "registry-sqlite")]
+impl SqliteRegistryStore {
+    pub fn open(path: impl AsRef<std::path::Path>) -> rusqlite::Result<Self> {
+        let conn = rusqlite::Connection::open(path)?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS registrations (
+                user_id TEXT PRIMARY KEY,
+                version INTEGER NOT NULL,
+                registration TEXT NOT NULL
+            )",
+        )?;
+        Ok(Self { conn: Mutex::new(conn) })
+    }
+}
+
+#[cfg(feature = "registry-sqlite")]
+impl RegistryStore for SqliteRegistryStore {
+    fn get(&self, user_id: &str) -> io::Result<Option<Registration>> {
+        use rusqlite::OptionalExtension;
+
+        let conn = self.conn.lock().unwrap();
+        let registration: Option<String> = conn
+            .query_row("SELECT registration FROM registrations WHERE user_id = ?1", [user_id], |row| row.get(0))
+            .optional()
+            .map_err(io::Error::other)?;
+        registration
+            .map(|registration| serde_json::from_str(&registration).map_err(io::Error::from))
+            .transpose()
+    }
+
+    fn insert(&self, user_id: &str, registration: &Registration) -> io::Result<bool> {
+        let json = serde_json::to_string(registration)?;
+        let conn = self.conn.lock().unwrap();
+        let inserted = conn
+            .execute(
+                "INSERT OR IGNORE INTO registrations (user_id, version, registration) VALUES (?1, ?2, ?3)",
+                rusqlite::params![user_id, registration.version as i64, json],
+            )
+            .map_err(io::Error::other)?;
+        Ok(inserted == 1)
+    }
+
+    fn replace(&self, user_id: &str, expected: u64, registration: &Registration) -> io::Result<bool> {
+        let json = serde_json::to_string(registration)?;
+        let conn = self.conn.lock().unwrap();
+        let updated = conn
+            .execute(
+                "UPDATE registrations SET version = ?1, registration = ?2 WHERE user_id = ?3 AND version = ?4",
+                rusqlite::params![registration.version as i64, json, user_id, expected as i64],
+            )
+            .map_err(io::Error::other)?;
+        Ok(updated == 1)
+    }
+
+    fn len(&self) -> io::Result<usize> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row("SELECT COUNT(*) FROM registrations", [], |row| row.get::<_, i64>(0))
+            .map(|count| count as usize)
+            .map_err(io::Error::other)
+    }
+
+    fn is_durable(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,130 @@
+use num_bigint::BigUint;
+use rand::rngs::OsRng;
+use serde::{Deserialize, Serialize};
+
+use crate::chaum_pedersen::{
+    ct_eq, generate_prover_secret_with_rng, modpow, secret_fixed_pow, wire, PublicParameters, SecureRng, Transcript,
+};
+use crate::group::Group;
+use crate::sigma::{self, SigmaProof, SigmaProtocol};
+
+// Schnorr proof of knowledge of x with y = g^x, over the same parameters as Chaum-Pedersen
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchnorrStatement {
+    #[serde(with = "wire::biguint")]
+    pub y: BigUint,  // g^x mod p
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchnorrCommitment {
+    #[serde(with = "wire::biguint")]
+    pub t: BigUint,  // g^k mod p
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchnorrResponse {
+    #[serde(with = "wire::biguint")]
+    pub s: BigUint,  // k + x*c mod q
+}
+
+pub type SchnorrProof = SigmaProof<Schnorr>;
+
+#[derive(Debug, Clone)]
+pub struct Schnorr {
+    pub params: PublicParameters,
+}
+
+impl Schnorr {
+    pub fn new(params: PublicParameters) -> Self {
+        Self { params }
+    }
+
+    // Fresh secret x and its public statement y = g^x
+    pub fn generate_keypair(&self) -> (BigUint, SchnorrStatement) {
+        self.generate_keypair_with_rng(&mut OsRng)
+    }
+
+    pub fn generate_keypair_with_rng<R: SecureRng + ?Sized>(&self, rng: &mut R) -> (BigUint, SchnorrStatement) {
+        let x = generate_prover_secret_with_rng(self.params.q(), rng);
+        let y = secret_fixed_pow(self.params.g_table(), &x, self.params.q());
+        (x, SchnorrStatement { y })
+    }
+
+    pub fn prove(&self, statement: &SchnorrStatement, x: &BigUint) -> SchnorrProof {
+        sigma::prove(self, statement, x)
+    }
+
+    pub fn verify_proof(&self, statement: &SchnorrStatement, proof: &SchnorrProof) -> bool {
+        sigma::verify(self, statement, proof)
+    }
+
+    // The challenge SendSchnorrCommitment issues for (y, t) in the session `session_id`. It covers the
+    // session id, so a recorded (t, s) answers no challenge in any other session.
+    pub fn session_challenge(
+        &self,
+        session_id: &str,
+        statement: &SchnorrStatement,
+        commitment: &SchnorrCommitment,
+    ) -> BigUint {
+        let mut transcript = Transcript::new(Self::LABEL);
+        transcript.append_message(b"session", session_id.as_bytes());
+        sigma::challenge_with(self, &mut transcript, statement, commitment)
+    }
+}
+
+impl SigmaProtocol for Schnorr {
+    type Statement = SchnorrStatement;
+    type Witness = BigUint;
+    type Commitment = SchnorrCommitment;
+    type Nonce = BigUint;
+    type Response = SchnorrResponse;
+
+    const LABEL: &'static [u8] = b"schnorr";
+
+    fn challenge_modulus(&self) -> &BigUint {
+        self.params.q()
+    }
+
+    fn commit(
+        &self,
+        _statement: &SchnorrStatement,
+        _witness: &BigUint,
+        rng: &mut dyn SecureRng,
+    ) -> (SchnorrCommitment, BigUint) {
+        let k = generate_prover_secret_with_rng(self.params.q(), rng);
+        let t = secret_fixed_pow(self.params.g_table(), &k, self.params.q());
+        (SchnorrCommitment { t }, k)
+    }
+
+    fn respond(&self, witness: &BigUint, nonce: BigUint, challenge: &BigUint) -> SchnorrResponse {
+        SchnorrResponse {
+            s: self.params.mul_add(&nonce, witness, challenge),
+        }
+    }
+
+    fn verify(
+        &self,
+        statement: &SchnorrStatement,
+        commitment: &SchnorrCommitment,
+        challenge: &BigUint,
+        response: &SchnorrResponse,
+    ) -> bool {
+        let p = self.params.p();
+        // Check: g^s mod p = t * y^c mod p
+        let left = modpow(self.params.g(), &response.s, p);
+        let right = (&commitment.t * modpow(&statement.y, challenge, p)) % p;
+        ct_eq(&left, &right, p)
+    }
+
+    fn append_transcript(
+        &self,
+        transcript: &mut Transcript,
+        statement: &SchnorrStatement,
+        commitment: &SchnorrCommitment,
+    ) {
+        transcript.append_integer(b"g", self.params.g());
+        transcript.append_integer(b"y", &statement.y);
+        transcript.append_integer(b"t", &commitment.t);
+    }
+}
@@ -1,211 +1,652 @@
-use tonic::{transport::Server, Request, Response, Status};
-use tokio;
-use zkp_chaum_pedersen_grpc::chaum_pedersen;
+// tonic::Status is the error type of the session helpers as well as the RPCs
+#![allow(clippy::result_large_err)]
+
+use tonic::{transport::Server, Status};
+use tonic::body::BoxBody;
+use tonic::codec::CompressionEncoding;
+use tonic::codegen::{http, Body, BoxFuture, Service};
+use tonic::service::interceptor::InterceptedService;
+use tonic::transport::server::{TcpConnectInfo, TcpIncoming};
+use tonic::transport::{Certificate, Identity, ServerTlsConfig};
+use tonic_web::GrpcWebLayer;
+use tokio::sync::{watch, Semaphore};
+use tokio::task::JoinSet;
+use tower::Layer;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tower_http::cors::{AllowOrigin, CorsLayer};
+use zkp_chaum_pedersen_grpc::auth::Authenticator;
+use zkp_chaum_pedersen_grpc::audit::{AuditLog, FileAuditSink};
+use zkp_chaum_pedersen_grpc::config::ServerConfig;
+use zkp_chaum_pedersen_grpc::evidence::{FileTranscriptSink, TranscriptArchive, TranscriptSigner, TranscriptSink};
+use zkp_chaum_pedersen_grpc::hooks::{WebhookEvents, WebhookHook};
+use zkp_chaum_pedersen_grpc::params_cache::ParamsCache;
+use zkp_chaum_pedersen_grpc::policy::PolicyViolation;
+use zkp_chaum_pedersen_grpc::registry::RegistryStore;
+use zkp_chaum_pedersen_grpc::service::ChaumPedersenServer;
+use zkp_chaum_pedersen_grpc::session::SessionStore;
+use zkp_chaum_pedersen_grpc::voprf::EvaluationKey;
 use std::collections::HashMap;
+use std::net::IpAddr;
 use std::sync::{Arc, Mutex};
-use num_bigint::BigUint;
-use uuid::Uuid;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+use tracing::{info, info_span, warn, Instrument};
+
+const INITIALIZE_PATH: &str = "/zkp.ChaumPedersenService/InitializeProtocol";
+const INITIALIZE_STREAM_PATH: &str = "/zkp.ChaumPedersenService/InitializeProtocolStream";
+const V2_INITIALIZE_PATH: &str = "/zkp.v2.ChaumPedersenService/InitializeProtocol";
+const RATE_LIMIT_WINDOW: Duration = Duration::from_secs(60);
 
-pub mod zkp {
-    tonic::include_proto!("zkp");
+// Fixed-window counter of initialize requests per client IP plus a global cap on parameter generations
+#[derive(Debug)]
+struct RateLimiter {
+    max_per_window: u32,
+    window: Duration,
+    history: Mutex<HashMap<IpAddr, (Instant, u32)>>,
+    max_generations: usize,
+    generations: Arc<Semaphore>,
 }
 
-use zkp::chaum_pedersen_service_server::{ChaumPedersenService, ChaumPedersenServiceServer};
-use zkp::*;
+impl RateLimiter {
+    // `generations` holds `max_generations` slots, shared with the server's parameter pool
+    fn new(max_per_window: u32, window: Duration, max_generations: usize, generations: Arc<Semaphore>) -> Self {
+        Self {
+            max_per_window,
+            window,
+            history: Mutex::new(HashMap::new()),
+            max_generations,
+            generations,
+        }
+    }
 
-use chaum_pedersen::{
-    PublicParameters as CryptoPublicParameters,
-    Commitment as CryptoCommitment,
-    generate_challenge, verify_proof
-};
+    // Records the request and returns false if the IP has used up its window
+    fn check(&self, ip: IpAddr) -> bool {
+        let now = Instant::now();
+        let mut history = self.history.lock().unwrap();
+        history.retain(|_, (started, _)| now.duration_since(*started) < self.window);
 
-#[derive(Debug, Clone)]
-struct VerifierSession {
-    params: CryptoPublicParameters,
-    commitment: Option<CryptoCommitment>,
-    y1: Option<BigUint>,
-    y2: Option<BigUint>,
-    challenge: Option<BigUint>,
+        let (_, count) = history.entry(ip).or_insert((now, 0));
+        if *count >= self.max_per_window {
+            return false;
+        }
+        *count += 1;
+        true
+    }
 }
 
-#[derive(Debug)]
-pub struct ChaumPedersenServer {
-    // shared state across requests with thread-safe access
-    sessions: Arc<Mutex<HashMap<String, VerifierSession>>>,
+#[derive(Debug, Clone)]
+struct RateLimitLayer {
+    limiter: Arc<RateLimiter>,
 }
 
-impl ChaumPedersenServer {
-    pub fn new() -> Self {
-        Self {
-            sessions: Arc::new(Mutex::new(HashMap::new())),
+impl<S> Layer<S> for RateLimitLayer {
+    type Service = RateLimit<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RateLimit {
+            inner,
+            limiter: self.limiter.clone(),
         }
     }
+}
 
-    fn generate_session_id(&self) -> String {
-        Uuid::new_v4().to_string()
-    }
+#[derive(Debug, Clone)]
+struct RateLimit<S> {
+    inner: S,
+    limiter: Arc<RateLimiter>,
 }
 
-#[tonic::async_trait]
-impl ChaumPedersenService for ChaumPedersenServer {
-    async fn initialize_protocol(
-        &self,
-        request: Request<InitializeRequest>,
-    ) -> Result<Response<InitializeResponse>, Status> {
-        let req = request.into_inner();
-        let bit_size = req.bit_size as u64;
+impl<S, ReqBody> Service<http::Request<ReqBody>> for RateLimit<S>
+where
+    S: Service<http::Request<ReqBody>, Response = http::Response<BoxBody>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    ReqBody: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<Self::Response, Self::Error>;
 
-        if bit_size < 256 || bit_size > 4096 {
-            return Err(Status::invalid_argument("Bit size must be between 256 and 4096"));
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: http::Request<ReqBody>) -> Self::Future {
+        // only parameter generation is expensive enough to need limiting
+        if !matches!(req.uri().path(), INITIALIZE_PATH | INITIALIZE_STREAM_PATH | V2_INITIALIZE_PATH) {
+            return Box::pin(self.inner.call(req));
         }
 
-        let params = CryptoPublicParameters::new(bit_size);
-        let session_id = self.generate_session_id();
-        
-        let session = VerifierSession {
-            params: params.clone(),
-            commitment: None,
-            y1: None,
-            y2: None,
-            challenge: None,
-        };
+        let peer_ip = req
+            .extensions()
+            .get::<TcpConnectInfo>()
+            .and_then(|info| info.remote_addr())
+            .map(|addr| addr.ip());
 
-        {
-            let mut sessions = self.sessions.lock().unwrap();
-            sessions.insert(session_id.clone(), session);
+        if let Some(ip) = peer_ip && !self.limiter.check(ip) {
+            let status = Status::resource_exhausted(format!(
+                "Too many initialize requests from {}, try again later",
+                ip
+            ));
+            return Box::pin(async move { Ok(status.to_http()) });
         }
 
-        let proto_params = PublicParameters {
-            p: params.p.to_bytes_be(),
-            q: params.q.to_bytes_be(),
-            g: params.g.to_bytes_be(),
+        let permit = match self.limiter.generations.clone().try_acquire_owned() {
+            Ok(permit) => permit,
+            Err(_) => {
+                let limit = self.limiter.max_generations;
+                let status = Status::from(PolicyViolation::TooManyGenerations { limit });
+                return Box::pin(async move { Ok(status.to_http()) });
+            }
         };
 
-        let response = InitializeResponse {
-            session_id: session_id.clone(),
-            params: Some(proto_params),
-        };
+        // take the service that was driven to readiness and leave a fresh clone behind
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        // the permit goes with the body, so a streamed generation holds it until the stream ends
+        Box::pin(async move {
+            let response = inner.call(req).await?;
+            Ok(response.map(|body| {
+                body.map_data(move |data| {
+                    let _ = &permit;
+                    data
+                })
+                .boxed_unsync()
+            }))
+        })
+    }
+}
+
+// The gateway's initialize counts against the same per-IP window and generation cap as the gRPC one.
+// Nothing is streamed, so the permit is simply held until the response is ready.
+#[cfg(feature = "rest")]
+async fn limit_gateway<B>(
+    axum::extract::State(limiter): axum::extract::State<Arc<RateLimiter>>,
+    axum::extract::ConnectInfo(peer): axum::extract::ConnectInfo<std::net::SocketAddr>,
+    request: http::Request<B>,
+    next: axum::middleware::Next<B>,
+) -> axum::response::Response {
+    use axum::response::IntoResponse;
+    use zkp_chaum_pedersen_grpc::gateway::{GatewayError, INITIALIZE_PATH};
+
+    if request.uri().path() != INITIALIZE_PATH {
+        return next.run(request).await;
+    }
+    if !limiter.check(peer.ip()) {
+        let status = Status::resource_exhausted(format!("Too many initialize requests from {}, try again later", peer.ip()));
+        return GatewayError(status).into_response();
+    }
+    let Ok(_permit) = limiter.generations.clone().try_acquire_owned() else {
+        let limit = limiter.max_generations;
+        return GatewayError(Status::from(PolicyViolation::TooManyGenerations { limit })).into_response();
+    };
+    next.run(request).await
+}
+
+// Opens a span per RPC, continuing the caller's trace when it sent one, so everything the handler
+// logs or times lands under it
+#[derive(Debug, Clone, Default)]
+struct RpcSpanLayer;
+
+impl<S> Layer<S> for RpcSpanLayer {
+    type Service = RpcSpan<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RpcSpan { inner }
+    }
+}
 
-        println!("Protocol initialized with session ID: {}", session_id);
-        Ok(Response::new(response))
-    }
-
-    async fn send_commitment(
-        &self,
-        request: Request<CommitmentRequest>,
-    ) -> Result<Response<ChallengeResponse>, Status> {
-        let req = request.into_inner();
-        let session_id = req.session_id;
-        
-        let commitment_proto = req.commitment.ok_or_else(|| {
-            Status::invalid_argument("Missing commitment")
-        })?;
-        
-        let challenge_proto = req.challenge_values.ok_or_else(|| {
-            Status::invalid_argument("Missing challenge values")
-        })?;
-
-        let commitment = CryptoCommitment {
-            a1: BigUint::from_bytes_be(&commitment_proto.a1),
-            b1: BigUint::from_bytes_be(&commitment_proto.b1),
-            c1: BigUint::from_bytes_be(&commitment_proto.c1),
+#[derive(Debug, Clone)]
+struct RpcSpan<S> {
+    inner: S,
+}
+
+impl<S, ReqBody> Service<http::Request<ReqBody>> for RpcSpan<S>
+where
+    S: Service<http::Request<ReqBody>>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: http::Request<ReqBody>) -> Self::Future {
+        let path = req.uri().path();
+        let (service, method) = path.trim_start_matches('/').split_once('/').unwrap_or(("", path));
+        let span = info_span!(
+            "rpc",
+            otel.name = path,
+            otel.kind = "server",
+            rpc.system = "grpc",
+            rpc.service = service,
+            rpc.method = method,
+        );
+        #[cfg(feature = "otel")]
+        zkp_chaum_pedersen_grpc::telemetry::set_remote_parent(&span, req.headers());
+
+        Box::pin(self.inner.call(req).instrument(span))
+    }
+}
+
+const CORS_MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+// CORS policy for grpc-web clients, which need the grpc-status trailers exposed to read call results.
+// With no allowed origins configured any origin is mirrored back.
+#[derive(Debug, Clone, Default)]
+struct CorsOptions {
+    allowed_origins: Vec<String>,
+}
+
+impl CorsOptions {
+    fn layer(&self) -> Result<CorsLayer, http::header::InvalidHeaderValue> {
+        let allow_origin = if self.allowed_origins.is_empty() {
+            AllowOrigin::mirror_request()
+        } else {
+            let origins = self
+                .allowed_origins
+                .iter()
+                .map(|origin| origin.parse())
+                .collect::<Result<Vec<http::HeaderValue>, _>>()?;
+            AllowOrigin::list(origins)
         };
 
-        let y1 = BigUint::from_bytes_be(&challenge_proto.y1);
-        let y2 = BigUint::from_bytes_be(&challenge_proto.y2);
-
-        {
-            let mut sessions = self.sessions.lock().unwrap();
-            if let Some(session) = sessions.get_mut(&session_id) {
-                session.commitment = Some(commitment);
-                session.y1 = Some(y1.clone());
-                session.y2 = Some(y2.clone());
-                
-                let challenge = generate_challenge(&y1, &y2, &session.params.q);
-                session.challenge = Some(challenge.clone());
-                
-                let response = ChallengeResponse {
-                    challenge: challenge.to_bytes_be(),
-                };
-
-                println!("Generated challenge for session: {}", session_id);
-                return Ok(Response::new(response));
-            }
+        Ok(CorsLayer::new()
+            .allow_origin(allow_origin)
+            .allow_credentials(true)
+            .max_age(CORS_MAX_AGE)
+            .expose_headers([
+                http::HeaderName::from_static("grpc-status"),
+                http::HeaderName::from_static("grpc-message"),
+                http::HeaderName::from_static("grpc-status-details-bin"),
+            ])
+            .allow_headers([
+                http::HeaderName::from_static("x-grpc-web"),
+                http::header::CONTENT_TYPE,
+                http::HeaderName::from_static("x-user-agent"),
+                http::HeaderName::from_static("grpc-timeout"),
+            ]))
+    }
+}
+
+fn params_cache_from_config(config: &ServerConfig) -> Result<Option<ParamsCache>, Box<dyn std::error::Error>> {
+    let dir = match &config.params_cache_dir {
+        Some(dir) => dir,
+        None => return Ok(None),
+    };
+
+    let max = config.params_cache_max_per_bits;
+    let cache = match &config.params_cache_key {
+        Some(key) => ParamsCache::open_with_key(dir, hex::decode(key.trim())?, max)?,
+        None => ParamsCache::open(dir, max)?,
+    };
+
+    info!("Parameter cache: {} ({} cached sets)", dir.display(), cache.len());
+    Ok(Some(cache))
+}
+
+#[cfg(feature = "session-sqlite")]
+fn session_store_from_config(
+    config: &ServerConfig,
+) -> Result<Option<Box<dyn SessionStore>>, Box<dyn std::error::Error>> {
+    let path = match &config.session_store {
+        Some(path) => path,
+        None => return Ok(None),
+    };
+
+    let store = zkp_chaum_pedersen_grpc::session::SqliteSessionStore::open(path)?;
+    info!("Session store: {}", path.display());
+    Ok(Some(Box::new(store)))
+}
+
+// Config validation rejects session_store when the feature is off
+#[cfg(not(feature = "session-sqlite"))]
+fn session_store_from_config(
+    _config: &ServerConfig,
+) -> Result<Option<Box<dyn SessionStore>>, Box<dyn std::error::Error>> {
+    Ok(None)
+}
+
+#[cfg(feature = "registry-sqlite")]
+fn registry_store_from_config(
+    config: &ServerConfig,
+) -> Result<Option<Box<dyn RegistryStore>>, Box<dyn std::error::Error>> {
+    let path = match &config.registry_store {
+        Some(path) => path,
+        None => return Ok(None),
+    };
+
+    let store = zkp_chaum_pedersen_grpc::registry::SqliteRegistryStore::open(path)?;
+    info!("Registry store: {}", path.display());
+    Ok(Some(Box::new(store)))
+}
+
+// Config validation rejects registry_store when the feature is off
+#[cfg(not(feature = "registry-sqlite"))]
+fn registry_store_from_config(
+    _config: &ServerConfig,
+) -> Result<Option<Box<dyn RegistryStore>>, Box<dyn std::error::Error>> {
+    Ok(None)
+}
+
+fn audit_log_from_config(config: &ServerConfig) -> Result<Option<AuditLog>, Box<dyn std::error::Error>> {
+    let path = match &config.audit_log {
+        Some(path) => path,
+        None => return Ok(None),
+    };
+
+    let mut audit = AuditLog::new(Box::new(FileAuditSink::open(path)?));
+    if let Some(key) = &config.audit_key {
+        audit = audit.with_signing_key(hex::decode(key.trim())?);
+    }
+
+    info!("Audit log: {}", path.display());
+    Ok(Some(audit))
+}
+
+fn transcript_archive_from_config(config: &ServerConfig) -> Result<Option<TranscriptArchive>, Box<dyn std::error::Error>> {
+    let sink: Box<dyn TranscriptSink> = match (&config.transcript_dir, &config.transcript_db) {
+        (Some(dir), _) => {
+            info!("Transcript archive: {}", dir.display());
+            Box::new(FileTranscriptSink::open(dir)?)
         }
+        #[cfg(feature = "transcript-sqlite")]
+        (None, Some(path)) => {
+            info!("Transcript archive: {} (SQLite)", path.display());
+            Box::new(zkp_chaum_pedersen_grpc::evidence::SqliteTranscriptSink::open(path)?)
+        }
+        // config validation rejects transcript_db when the feature is off
+        _ => return Ok(None),
+    };
+    let archive = TranscriptArchive::new(sink);
+    Ok(Some(match config.transcript_retention() {
+        Some(retention) => archive.with_retention(retention),
+        None => archive,
+    }))
+}
 
-        Err(Status::not_found("Session not found"))
-    }
-
-    async fn verify_proof(
-        &self,
-        request: Request<VerifyProofRequest>,
-    ) -> Result<Response<VerifyProofResponse>, Status> {
-        let req = request.into_inner();
-        let session_id = req.session_id;
-        let z = BigUint::from_bytes_be(&req.z);
-
-        let verification_result = {
-            let sessions = self.sessions.lock().unwrap();
-            if let Some(session) = sessions.get(&session_id) {
-                if let (Some(commitment), Some(y1), Some(y2), Some(challenge)) = 
-                    (&session.commitment, &session.y1, &session.y2, &session.challenge) {
-                    
-                    let verification = verify_proof(
-                        &session.params.g,
-                        &commitment.b1,
-                        y1,
-                        y2,
-                        &commitment.a1,
-                        &commitment.c1,
-                        challenge,
-                        &z,
-                        &session.params.p,
-                    );
-                    
-                    Some(verification)
-                } else {
-                    None
-                }
-            } else {
-                None
-            }
-        };
+fn webhook_from_config(config: &ServerConfig) -> Result<Option<WebhookHook>, Box<dyn std::error::Error>> {
+    let Some(url) = &config.webhook_url else {
+        return Ok(None);
+    };
+    let events = match config.webhook_events.as_str() {
+        "success" => WebhookEvents::Success,
+        "failure" => WebhookEvents::Failure,
+        _ => WebhookEvents::All,
+    };
+    let hook = WebhookHook::new(url, Duration::from_secs(config.webhook_timeout_secs))?.with_events(events);
 
-        match verification_result {
-            Some(true) => {
-                println!("Proof verified successfully for session: {}", session_id);
-                {
-                    let mut sessions = self.sessions.lock().unwrap();
-                    sessions.remove(&session_id);
-                }
-                Ok(Response::new(VerifyProofResponse {
-                    verified: true,
-                    message: "Zero-knowledge proof verified successfully!".to_string(),
-                }))
-            }
-            Some(false) => {
-                println!("Proof verification failed for session: {}", session_id);
-                Ok(Response::new(VerifyProofResponse {
-                    verified: false,
-                    message: "Zero-knowledge proof verification failed!".to_string(),
-                }))
-            }
-            None => Err(Status::invalid_argument("Invalid session state or session not found")),
+    info!("Verification webhook enabled for {} events", config.webhook_events);
+    Ok(Some(hook))
+}
+
+// A configured key keeps transcripts checkable across restarts; otherwise each run signs with a fresh one
+fn transcript_signer_from_config(config: &ServerConfig) -> Result<TranscriptSigner, Box<dyn std::error::Error>> {
+    let signer = match &config.transcript_key {
+        Some(key) => {
+            let seed: [u8; 32] = hex::decode(key.trim())?
+                .try_into()
+                .map_err(|_| "transcript_key must be 32 bytes")?;
+            TranscriptSigner::from_seed(&seed)
+        }
+        None => TranscriptSigner::generate(),
+    };
+    info!("Signing session transcripts with Ed25519 key {}", hex::encode(signer.public_key()));
+    Ok(signer)
+}
+
+// Like the transcript key: a configured seed keeps VOPRF public keys and outputs stable across restarts
+fn oprf_key_from_config(config: &ServerConfig) -> Result<EvaluationKey, Box<dyn std::error::Error>> {
+    match &config.oprf_key {
+        Some(key) => {
+            let seed: [u8; 32] = hex::decode(key.trim())?
+                .try_into()
+                .map_err(|_| "oprf_key must be 32 bytes")?;
+            Ok(EvaluationKey::from_seed(&seed))
+        }
+        None => {
+            info!("No oprf_key configured; VOPRF outputs will change on restart");
+            Ok(EvaluationKey::generate())
         }
     }
 }
 
+// Client certificates stay optional at the TLS layer so API-key clients can still connect; the interceptor decides
+fn tls_from_config(config: &ServerConfig) -> Result<Option<ServerTlsConfig>, Box<dyn std::error::Error>> {
+    let (cert, key) = match (&config.tls_cert, &config.tls_key) {
+        (Some(cert), Some(key)) => (std::fs::read(cert)?, std::fs::read(key)?),
+        _ => return Ok(None),
+    };
+
+    let mut tls = ServerTlsConfig::new().identity(Identity::from_pem(cert, key));
+    if let Some(ca) = &config.tls_client_ca {
+        tls = tls
+            .client_ca_root(Certificate::from_pem(std::fs::read(ca)?))
+            .client_auth_optional(true);
+    }
+    Ok(Some(tls))
+}
+
+// Resolves on Ctrl+C, or on SIGTERM when running on Unix
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c().await.expect("failed to listen for Ctrl+C");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("failed to listen for SIGTERM")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+
+    info!("Shutdown signal received, draining in-flight requests...");
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let addr = "[::1]:50051".parse()?;
-    let server = ChaumPedersenServer::new();
+    let config = match ServerConfig::load(std::env::args().skip(1)) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Configuration error: {}", e);
+            std::process::exit(2);
+        }
+    };
 
-    println!("Listening on {}", addr);
-    // starting the gRPC server listening for requests
-    Server::builder()
-        .add_service(ChaumPedersenServiceServer::new(server))
-        .serve(addr)
-        .await?;
+    let level: tracing::Level = config.log_level.parse()?;
+    #[cfg(feature = "otel")]
+    let otel = config
+        .otlp_endpoint
+        .as_deref()
+        .map(|endpoint| zkp_chaum_pedersen_grpc::telemetry::layer("grpc-zkp-server", endpoint))
+        .transpose()?;
+    #[cfg(not(feature = "otel"))]
+    let otel: Option<tracing_subscriber::layer::Identity> = None;
+    tracing_subscriber::registry()
+        .with(tracing_subscriber::filter::LevelFilter::from_level(level))
+        .with(tracing_subscriber::fmt::layer())
+        .with(otel)
+        .init();
 
+    let mut server = ChaumPedersenServer::from_config(&config)
+        .with_transcript_signer(transcript_signer_from_config(&config)?)
+        .with_oprf_key(oprf_key_from_config(&config)?);
+    if let Some(audit) = audit_log_from_config(&config)? {
+        server = server.with_audit_log(audit);
+    }
+    if let Some(archive) = transcript_archive_from_config(&config)? {
+        server = server.with_transcript_archive(archive);
+    }
+    if let Some(webhook) = webhook_from_config(&config)? {
+        server = server.with_hook(Box::new(webhook));
+    }
+    if let Some(cache) = params_cache_from_config(&config)? {
+        server = server.with_params_cache(cache);
+    }
+    if let Some(store) = session_store_from_config(&config)? {
+        server = server.with_session_store(store);
+    }
+    if let Some(store) = registry_store_from_config(&config)? {
+        server = server.with_registry_store(store);
+    }
+    let server = Arc::new(server);
+
+    if config.param_pool_size > 0 {
+        info!("Pre-generating {} parameter sets for bit sizes {:?}", config.param_pool_size, config.param_pool_bits);
+        tokio::spawn(server.refill_param_pool());
+    }
+
+    let cors = CorsOptions {
+        allowed_origins: config.cors_allowed_origins.clone(),
+    }
+    .layer()?;
+    let rate_limit = RateLimitLayer {
+        limiter: Arc::new(RateLimiter::new(
+            config.init_requests_per_minute,
+            RATE_LIMIT_WINDOW,
+            server.policy().max_concurrent_generations,
+            server.generation_permits(),
+        )),
+    };
+
+    let authenticator = Authenticator::new(
+        config.api_keys.clone(),
+        config.allowed_client_cns.iter().cloned().collect(),
+    )
+    .with_operators(
+        config.operator_api_keys.clone(),
+        config.operator_client_cns.iter().cloned().collect(),
+    );
+    if !authenticator.is_enabled() {
+        warn!("No API keys or client CNs configured, RPCs are unauthenticated");
+    }
+
+    let mut builder = Server::builder()
+        .max_concurrent_streams(config.max_concurrent_streams)
+        .initial_stream_window_size(config.initial_stream_window_size)
+        .initial_connection_window_size(config.initial_connection_window_size)
+        .http2_keepalive_interval(config.keepalive_interval_secs.map(Duration::from_secs))
+        .http2_keepalive_timeout(Some(Duration::from_secs(config.keepalive_timeout_secs)));
+    if let Some(tls) = tls_from_config(&config)? {
+        builder = builder.tls_config(tls)?;
+    }
+
+    // compressed requests are always accepted; responses are compressed only if configured and the client accepts it
+    let (mut grpc, mut grpc_v2) = server.clone().services();
+    for encoding in &config.compression {
+        grpc = grpc.send_compressed(compression_encoding(encoding));
+        grpc_v2 = grpc_v2.send_compressed(compression_encoding(encoding));
+    }
+    #[cfg(feature = "rest")]
+    let gateway = zkp_chaum_pedersen_grpc::gateway::router(server.clone(), authenticator.clone())
+        .layer(axum::middleware::from_fn_with_state(rate_limit.limiter.clone(), limit_gateway))
+        .layer(cors.clone());
+    let interceptor = authenticator.into_interceptor();
+    let service = InterceptedService::new(grpc, interceptor.clone());
+    let service_v2 = InterceptedService::new(grpc_v2, interceptor);
+
+    // one gRPC server per listener, all sharing the service state.
+    // HTTP/1.1 is accepted so browsers can reach the service over grpc-web without a proxy
+    let router = || {
+        builder
+            .clone()
+            .accept_http1(true)
+            .layer(RpcSpanLayer)
+            .layer(cors.clone())
+            .layer(GrpcWebLayer::new())
+            .layer(rate_limit.clone())
+            .add_service(service.clone())
+            .add_service(service_v2.clone())
+    };
+    // dropping `stop` tells every listener to shut down
+    let (stop, stopped) = watch::channel(());
+    let until_stopped = || {
+        let mut stopped = stopped.clone();
+        async move {
+            let _ = stopped.changed().await;
+        }
+    };
+
+    let mut listeners = JoinSet::<Result<(), Box<dyn std::error::Error + Send + Sync>>>::new();
+    for &addr in &config.listen_addr {
+        let incoming = TcpIncoming::new(addr, false, None).map_err(|e| format!("cannot listen on {}: {}", addr, e))?;
+        info!("Listening on {} (gRPC and gRPC-web)", addr);
+        let serving = router().serve_with_incoming_shutdown(incoming, until_stopped());
+        listeners.spawn(async move { Ok(serving.await?) });
+    }
+    #[cfg(unix)]
+    if let Some(path) = &config.listen_socket {
+        let incoming = unix_incoming(path).map_err(|e| format!("cannot listen on {}: {}", path.display(), e))?;
+        info!("Listening on {} (gRPC and gRPC-web)", path.display());
+        let serving = router().serve_with_incoming_shutdown(incoming, until_stopped());
+        listeners.spawn(async move { Ok(serving.await?) });
+    }
+    #[cfg(feature = "rest")]
+    for &addr in &config.rest_listen_addr {
+        let listener = std::net::TcpListener::bind(addr).map_err(|e| format!("cannot listen on {}: {}", addr, e))?;
+        info!("Listening on {} (HTTP/JSON gateway)", addr);
+        let serving = zkp_chaum_pedersen_grpc::gateway::serve(listener, gateway.clone(), until_stopped());
+        listeners.spawn(async move { Ok(serving.await?) });
+    }
+
+    // in-flight RPCs finish before a listener returns; one that fails takes the others down with it
+    let mut outcomes = Vec::new();
+    tokio::select! {
+        _ = shutdown_signal() => {}
+        Some(outcome) = listeners.join_next() => outcomes.push(outcome),
+    }
+    drop(stop);
+    while let Some(outcome) = listeners.join_next().await {
+        outcomes.push(outcome);
+    }
+    if let Some(path) = &config.listen_socket {
+        let _ = std::fs::remove_file(path);
+    }
+
+    server.log_shutdown_summary();
+    #[cfg(feature = "otel")]
+    zkp_chaum_pedersen_grpc::telemetry::shutdown();
+
+    for outcome in outcomes {
+        outcome?.map_err(|e| -> Box<dyn std::error::Error> { e })?;
+    }
     Ok(())
-}
\ No newline at end of file
+}
+
+// Names are checked against COMPRESSION_ENCODINGS when the config is validated
+fn compression_encoding(name: &str) -> CompressionEncoding {
+    match name {
+        "gzip" => CompressionEncoding::Gzip,
+        "zstd" => CompressionEncoding::Zstd,
+        _ => unreachable!("unvalidated compression encoding {}", name),
+    }
+}
+
+// Binds the socket, replacing a file an earlier run left behind but never one still being served
+#[cfg(unix)]
+fn unix_incoming(path: &std::path::Path) -> std::io::Result<tokio_stream::wrappers::UnixListenerStream> {
+    use std::os::unix::fs::FileTypeExt;
+
+    if let Ok(meta) = std::fs::symlink_metadata(path)
+        && meta.file_type().is_socket()
+    {
+        if std::os::unix::net::UnixStream::connect(path).is_ok() {
+            return Err(std::io::Error::new(std::io::ErrorKind::AddrInUse, "another server is listening"));
+        }
+        std::fs::remove_file(path)?;
+    }
+    let listener = tokio::net::UnixListener::bind(path)?;
+    Ok(tokio_stream::wrappers::UnixListenerStream::new(listener))
+}
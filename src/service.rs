@@ -0,0 +1,2802 @@
+use tonic::{Code, Request, Response, Status};
+use tonic::codec::CompressionEncoding;
+use tokio::sync::{mpsc, Semaphore};
+use tokio_stream::wrappers::ReceiverStream;
+use crate::batch::{self, ProofBatch};
+use crate::auth::{Operator, Principal};
+use crate::audit::{AuditEntry, AuditLog};
+use crate::chaum_pedersen;
+use crate::config::ServerConfig;
+use crate::convert::{
+    compress_commitment, decode_blinded_elements, decode_compact_dleq, decode_dh_share, decode_dleq, decode_integer, decode_range,
+    decompress_challenge_values, decompress_commitment, encode_dleq, encode_integer, ConversionError, FieldLimits,
+};
+use crate::evidence::{self, TranscriptArchive, TranscriptSigner};
+use crate::group::{self, Dleq, DleqStatement, Group};
+use crate::hooks::{AuditLogHook, VerificationEvent, VerificationHook};
+use crate::interop;
+use crate::key_exchange;
+use crate::pedersen::Pedersen;
+use crate::range;
+use crate::registry::{
+    MemoryRegistryStore, Recovery, Registration as UserRegistration, RegistryStore,
+};
+use crate::params_cache::ParamsCache;
+use crate::policy::{GenerationPolicy, PolicyViolation};
+use crate::session::{
+    ChallengeMode as SessionChallengeMode, Exchange, InvalidSessionId, MemorySessionStore, Outcome, PendingStatement,
+    SchnorrExchange, Session, SessionId, SessionState, SessionStore, CHALLENGE_EXPIRED, now_secs,
+};
+use crate::zkp;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::future::Future;
+use std::time::{Duration, Instant};
+use tracing::{error, info, info_span, warn, Instrument};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+
+use zkp::chaum_pedersen_service_server::{ChaumPedersenService, ChaumPedersenServiceServer};
+use zkp::v2::chaum_pedersen_service_server::ChaumPedersenServiceServer as ChaumPedersenServiceV2Server;
+use zkp::*;
+
+use chaum_pedersen::{
+    PublicParameters as CryptoPublicParameters,
+    Commitment as CryptoCommitment,
+    ProofChallenge as CryptoProofChallenge,
+    ProofResponse as CryptoProofResponse,
+    ChaumPedersen, MultiZKProof, PrimeSearch, Verifier, ZKProof,
+    generate_interactive_challenge, generate_session_challenge
+};
+use crate::schnorr::{Schnorr, SchnorrCommitment, SchnorrResponse, SchnorrStatement};
+use crate::sigma::SigmaProtocol;
+use crate::threshold::{Coordinator, PartialCommitment, PublicShare, ThresholdError};
+use crate::voprf::{self, EvaluationKey};
+
+// Public seed for the second generator h, published so clients can re-derive it
+const SECOND_GENERATOR_SEED: &[u8] = b"zkp-chaum-pedersen-grpc/second-generator";
+
+// Fresh parameters, derived from a random published seed when verifiable generation is on
+fn generate_params(bits: u64, verifiable: bool, search: &PrimeSearch) -> Option<CryptoPublicParameters> {
+    let params = if verifiable {
+        let mut seed = [0u8; 32];
+        OsRng.fill_bytes(&mut seed);
+        CryptoPublicParameters::from_seed_cancellable(bits, &seed, search)?
+    } else {
+        CryptoPublicParameters::new_cancellable(bits, search)?
+    };
+    Some(params.with_second_generator(SECOND_GENERATOR_SEED))
+}
+
+// Parameter sets generated ahead of time for the configured bit sizes, so initialize can skip prime search.
+// Its searches count as generations: they show in `generating` and each holds a `permits` slot.
+#[derive(Debug)]
+struct ParamsPool {
+    target_size: usize,
+    bit_sizes: Vec<u64>,
+    verifiable: bool,
+    ready: Mutex<HashMap<u64, Vec<CryptoPublicParameters>>>,
+    refilling: AtomicBool,  // one refill at a time, however many takes asked for one
+    generating: Arc<AtomicUsize>,
+    permits: Arc<Semaphore>,
+}
+
+// Clears ParamsPool::refilling when a refill ends, however it ends
+struct Refilling<'a>(&'a AtomicBool);
+
+impl Drop for Refilling<'_> {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::Release);
+    }
+}
+
+impl ParamsPool {
+    fn new(
+        target_size: usize,
+        bit_sizes: Vec<u64>,
+        verifiable: bool,
+        generating: Arc<AtomicUsize>,
+        permits: Arc<Semaphore>,
+    ) -> Self {
+        Self {
+            target_size,
+            bit_sizes,
+            verifiable,
+            ready: Mutex::new(HashMap::new()),
+            refilling: AtomicBool::new(false),
+            generating,
+            permits,
+        }
+    }
+
+    fn take(&self, bits: u64) -> Option<CryptoPublicParameters> {
+        self.ready.lock().unwrap().get_mut(&bits)?.pop()
+    }
+
+    fn missing(&self, bits: u64) -> usize {
+        let ready = self.ready.lock().unwrap();
+        self.target_size.saturating_sub(ready.get(&bits).map_or(0, Vec::len))
+    }
+
+    // Tops every pooled bit size back up to the target, generating on the blocking thread pool one set
+    // at a time. Returns at once if another refill is already running, which will top up for this one.
+    async fn refill(self: Arc<Self>) {
+        if self.refilling.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        let _refilling = Refilling(&self.refilling);
+        for bits in self.bit_sizes.clone() {
+            for _ in 0..self.missing(bits) {
+                // waits for a slot, so a refill never pushes the server past its generation cap
+                let Ok(_permit) = self.permits.clone().acquire_owned().await else {
+                    return;
+                };
+                let _generating = Generating::start(&self.generating);
+                let verifiable = self.verifiable;
+                let params = tokio::task::spawn_blocking(move || {
+                    generate_params(bits, verifiable, &PrimeSearch::new()).expect("generation is never cancelled")
+                })
+                .await;
+
+                match params {
+                    Ok(params) => self.ready.lock().unwrap().entry(bits).or_default().push(params),
+                    Err(e) => {
+                        error!("Parameter pool generation failed: {}", e);
+                        return;
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Failed verifications per key (a session or a client). Reaching the threshold locks the key for
+// the cooldown; a failure more than a cooldown after the previous one starts the count afresh.
+#[derive(Debug)]
+struct FailureTracker {
+    threshold: u32,
+    cooldown: Duration,
+    failures: Mutex<HashMap<String, Failures>>,
+}
+
+#[derive(Debug)]
+struct Failures {
+    count: u32,
+    last: Instant,
+    locked_until: Option<Instant>,
+}
+
+impl FailureTracker {
+    fn new(threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            threshold,
+            cooldown,
+            failures: Mutex::new(HashMap::new()),
+        }
+    }
+
+    // Longest remaining lockout among the keys, if any of them is locked
+    fn locked_for(&self, keys: &[String]) -> Option<Duration> {
+        let now = Instant::now();
+        let failures = self.failures.lock().unwrap();
+        keys.iter()
+            .filter_map(|key| failures.get(key)?.locked_until)
+            .filter(|until| *until > now)
+            .map(|until| until - now)
+            .max()
+    }
+
+    // Counts a failure against every key and returns the keys it locked out
+    fn record_failure(&self, keys: &[String]) -> Vec<String> {
+        let now = Instant::now();
+        let mut failures = self.failures.lock().unwrap();
+        failures.retain(|_, f| {
+            now.duration_since(f.last) < self.cooldown || f.locked_until.is_some_and(|until| until > now)
+        });
+
+        let mut locked = Vec::new();
+        for key in keys {
+            let entry = failures.entry(key.clone()).or_insert(Failures {
+                count: 0,
+                last: now,
+                locked_until: None,
+            });
+            entry.count += 1;
+            entry.last = now;
+            if entry.count >= self.threshold {
+                entry.count = 0;
+                entry.locked_until = Some(now + self.cooldown);
+                locked.push(key.clone());
+            }
+        }
+        locked
+    }
+
+    fn clear(&self, keys: &[String]) {
+        let mut failures = self.failures.lock().unwrap();
+        for key in keys {
+            failures.remove(key);
+        }
+    }
+}
+
+// Recently seen (y1, y2) pairs and multi-statement proof statements, keyed together with their
+// parameter set and remembering which session sent each. Session, one-shot, batch and
+// multi-statement challenges cover the session id, so a recorded z answers nothing in another
+// session whether or not the cache remembers its values; for those the cache is defence in depth,
+// refusing reused nonces before anything is verified. A threshold run's joint challenge covers only
+// the statement, so for assembled partial commitments the cache is what keeps a recorded run from
+// being replayed into another session. Resends within the same session are fine. The least
+// recently seen entries are forgotten once `capacity` is reached.
+#[derive(Debug)]
+struct ReplayCache {
+    capacity: usize,
+    state: Mutex<ReplayState>,
+}
+
+#[derive(Debug, Default)]
+struct ReplayState {
+    tick: u64,
+    seen: HashMap<[u8; 32], (SessionId, u64)>,  // pair digest -> (session, last seen)
+    order: BTreeMap<u64, [u8; 32]>,
+}
+
+impl ReplayCache {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            state: Mutex::new(ReplayState::default()),
+        }
+    }
+
+    fn digest(params: &CryptoPublicParameters, values: &[&num_bigint::BigUint]) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(params.digest());
+        hasher.update((values.len() as u64).to_be_bytes());
+        for value in values {
+            let bytes = value.to_bytes_be();
+            hasher.update((bytes.len() as u64).to_be_bytes());
+            hasher.update(bytes);
+        }
+        hasher.finalize().into()
+    }
+
+    // Records the values, a (y1, y2) pair or a multi-statement proof's statement and its t, for
+    // `session_id`; false if another session has already used them
+    fn check(&self, params: &CryptoPublicParameters, values: &[&num_bigint::BigUint], session_id: &SessionId) -> bool {
+        if self.capacity == 0 {
+            return true;
+        }
+        let digest = Self::digest(params, values);
+        let mut state = self.state.lock().unwrap();
+        state.tick += 1;
+        let tick = state.tick;
+
+        match state.seen.get_mut(&digest) {
+            Some((owner, _)) if owner != session_id => return false,
+            Some((_, last)) => {
+                let previous = std::mem::replace(last, tick);
+                state.order.remove(&previous);
+            }
+            None => {
+                state.seen.insert(digest, (*session_id, tick));
+                if state.seen.len() > self.capacity
+                    && let Some((_, oldest)) = state.order.pop_first()
+                {
+                    state.seen.remove(&oldest);
+                }
+            }
+        }
+        state.order.insert(tick, digest);
+        true
+    }
+}
+
+// Decodes a commitment request after checking its fields fit the session's group, expanding
+// compressed elements if the session asked for them
+fn decode_commitment(
+    req: CommitmentRequest,
+    session: &Session,
+    params: &CryptoPublicParameters,
+) -> Result<(CryptoCommitment, CryptoProofChallenge), Status> {
+    let commitment = req.commitment.ok_or_else(|| Status::invalid_argument("Missing commitment"))?;
+    let challenge_values = req
+        .challenge_values
+        .ok_or_else(|| Status::invalid_argument("Missing challenge values"))?;
+    let limits = FieldLimits::for_params(params);
+    limits.check_commitment(&commitment)?;
+    limits.check_challenge_values(&challenge_values)?;
+    let commitment = CryptoCommitment::try_from(commitment)?;
+    let challenge_values = CryptoProofChallenge::try_from(challenge_values)?;
+    if !session.compressed_elements {
+        return Ok((commitment, challenge_values));
+    }
+    Ok((
+        decompress_commitment(commitment, params)?,
+        decompress_challenge_values(challenge_values, params)?,
+    ))
+}
+
+// Lockout keys for a verification: the session itself, and the authenticated principal or else the peer IP
+fn lockout_keys<T>(request: &Request<T>, session_id: &str) -> Vec<String> {
+    let mut keys = vec![format!("session:{}", session_id)];
+    if let Some(principal) = request.extensions().get::<Principal>() {
+        keys.push(format!("client:{}", principal));
+    } else if let Some(addr) = request.remote_addr() {
+        keys.push(format!("client:ip:{}", addr.ip()));
+    }
+    keys
+}
+
+// The operator behind a request that changes a registration without the user's proof
+fn require_operator<T>(request: &Request<T>) -> Result<Operator, Status> {
+    request
+        .extensions()
+        .get::<Operator>()
+        .cloned()
+        .ok_or_else(|| Status::permission_denied("Only an operator can change or revoke a user's registration"))
+}
+
+// Cancels the search when dropped, so blocking work outlives neither a timeout nor a cancelled RPC
+#[derive(Debug)]
+struct CancelOnDrop(Arc<PrimeSearch>);
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        self.0.cancel();
+    }
+}
+
+// Deadline the client set with the grpc-timeout header, e.g. "300S" or "1500m"
+fn request_deadline<T>(request: &Request<T>) -> Option<Instant> {
+    let timeout = request.metadata().get("grpc-timeout")?.to_str().ok()?;
+    let (value, unit) = timeout.split_at(timeout.len().checked_sub(1)?);
+    let value: u64 = value.parse().ok()?;
+    let timeout = match unit {
+        "H" => Duration::from_secs(value.saturating_mul(60 * 60)),
+        "M" => Duration::from_secs(value.saturating_mul(60)),
+        "S" => Duration::from_secs(value),
+        "m" => Duration::from_millis(value),
+        "u" => Duration::from_micros(value),
+        "n" => Duration::from_nanos(value),
+        _ => return None,
+    };
+    Instant::now().checked_add(timeout)
+}
+
+// Protocol outcome counters, reported in the shutdown summary
+#[derive(Debug, Default)]
+struct ServerStats {
+    initialized: AtomicU64,
+    verified: AtomicU64,
+    failed: AtomicU64,
+    lockouts: AtomicU64,            // keys locked out after too many failures
+    locked_out_attempts: AtomicU64, // verifications refused during a lockout
+    replays: AtomicU64,             // commitments refused for reusing another session's (y1, y2)
+    shed: AtomicU64,                // initialize requests refused by load shedding
+}
+
+// Where initialize gets its parameters from; cheap to clone into a streaming task
+#[derive(Debug, Clone)]
+struct ParamsSource {
+    pool: Arc<ParamsPool>,
+    cache: Option<Arc<ParamsCache>>,
+    verifiable: bool,
+    generating: Arc<AtomicUsize>,  // searches running now, the pool's included; pool and cache hits are not counted
+    permits: Arc<Semaphore>,  // max_concurrent_generations slots, shared with the initialize rate limiter
+}
+
+// Counts a search in ParamsSource::generating for as long as it runs
+struct Generating(Arc<AtomicUsize>);
+
+impl Generating {
+    fn start(count: &Arc<AtomicUsize>) -> Self {
+        count.fetch_add(1, Ordering::Relaxed);
+        Self(count.clone())
+    }
+}
+
+impl Drop for Generating {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+// Largest request and response either service handles, reported by GetCapabilities
+#[derive(Debug, Clone, Copy)]
+struct MessageSizes {
+    decoding: usize,
+    encoding: usize,
+}
+
+// Thresholds past which initialize is refused with UNAVAILABLE, so that a burst of new sessions
+// cannot slow down protocols already in flight; 0 turns a threshold off
+#[derive(Debug, Clone, Copy)]
+struct LoadShedding {
+    max_sessions: usize,
+    max_generations: usize,
+    retry_after: Duration,
+}
+
+impl LoadShedding {
+    fn from_config(config: &ServerConfig) -> Self {
+        Self {
+            max_sessions: config.shed_max_sessions,
+            max_generations: config.shed_max_generations,
+            retry_after: Duration::from_secs(config.shed_retry_after_secs),
+        }
+    }
+}
+
+impl ParamsSource {
+    // Pool first, then the on-disk cache, and only then a fresh search, which is cached for next time.
+    // The search runs on the blocking pool and stops if the deadline passes or this future is dropped;
+    // `search` reports its progress meanwhile.
+    async fn obtain(
+        &self,
+        bit_size: u64,
+        deadline: GenerationDeadline,
+        search: Arc<PrimeSearch>,
+    ) -> Result<CryptoPublicParameters, Status> {
+        if let Some(params) = self.pool.take(bit_size) {
+            tokio::spawn(self.pool.clone().refill());
+            return Ok(params);
+        }
+
+        // sets cached before verifiable generation was switched on are not served
+        if let Some(params) = self.cache.as_ref().and_then(|cache| cache.get(bit_size))
+            && (!self.verifiable || params.provenance.is_some())
+        {
+            return Ok(params);
+        }
+
+        let _cancel = CancelOnDrop(search.clone());
+        let _generating = Generating::start(&self.generating);
+        let verifiable = self.verifiable;
+        let span = info_span!("crypto", otel.name = "generate_params", bits = bit_size);
+        let search = tokio::task::spawn_blocking(move || {
+            span.in_scope(|| generate_params(bit_size, verifiable, &search))
+        });
+        let generated = match deadline.at {
+            Some(at) => tokio::time::timeout_at(at.into(), search).await.map_err(|_| match deadline.policy {
+                Some(violation) => Status::from(violation),
+                None => Status::deadline_exceeded("Parameter generation did not finish before the deadline"),
+            })?,
+            None => search.await,
+        };
+
+        let params = generated
+            .map_err(|e| {
+                error!("Parameter generation failed: {}", e);
+                Status::internal("Parameter generation failed")
+            })?
+            .ok_or_else(|| Status::cancelled("Parameter generation was cancelled"))?;
+
+        if let Some(cache) = &self.cache
+            && let Err(e) = cache.insert(bit_size, params.clone())
+        {
+            error!("Failed to cache parameters: {}", e);
+        }
+        Ok(params)
+    }
+}
+
+// When a generation has to stop: the client's deadline or the policy timeout, whichever comes first.
+// `policy` is set when it is the policy's, so running out of time reports the violation.
+#[derive(Debug, Clone, Copy)]
+struct GenerationDeadline {
+    at: Option<Instant>,
+    policy: Option<PolicyViolation>,
+}
+
+impl GenerationDeadline {
+    fn new(policy: &GenerationPolicy, bits: u32, client: Option<Instant>) -> Self {
+        let limit = policy
+            .timeout_for(bits)
+            .and_then(|limit| Some((Instant::now().checked_add(limit)?, limit)));
+        match (client, limit) {
+            (Some(client), Some((at, _))) if client <= at => Self { at: Some(client), policy: None },
+            (_, Some((at, limit))) => Self {
+                at: Some(at),
+                policy: Some(PolicyViolation::GenerationTimeout { bits, limit }),
+            },
+            (client, None) => Self { at: client, policy: None },
+        }
+    }
+}
+
+// Stores a new session over `params` and builds the response that hands it to the client
+fn open_session(sessions: &dyn SessionStore, stats: &ServerStats, session: Session) -> Result<InitializeResponse, Status> {
+    let session_id = SessionId::generate();
+    sessions.put(&session_id, &session).map_err(session_store_error)?;
+
+    stats.initialized.fetch_add(1, Ordering::Relaxed);
+    info!("Protocol initialized over {} with session ID: {}", session.group, session_id);
+    Ok(InitializeResponse {
+        session_id: session_id.to_string(),
+        params: session.params.as_ref().map(PublicParameters::from),
+        group: GroupKind::from(session.group).into(),
+        challenge_mode: ChallengeMode::from(session.challenge_mode).into(),
+        params_fingerprint: session.params.as_ref().map(|params| params.digest().to_vec()).unwrap_or_default(),
+        commitment_origin: CommitmentOrigin::from(session.commitment_origin()).into(),
+        compressed_elements: session.compressed_elements,
+    })
+}
+
+// Session IDs are parsed where a request arrives, so nothing past it handles a malformed one.
+// Every RPC after InitializeProtocol names its session, so an empty ID is never a default.
+fn parse_session_id(id: &str) -> Result<SessionId, Status> {
+    if id.is_empty() {
+        return Err(Status::invalid_argument("Missing session ID"));
+    }
+    id.parse()
+        .map_err(|e: InvalidSessionId| Status::invalid_argument(format!("Invalid session ID: {}", e)))
+}
+
+fn cancelled_session() -> Status {
+    Status::aborted("Session was cancelled")
+}
+
+fn concluded_session() -> Status {
+    Status::failed_precondition("This session's proof was already checked")
+}
+
+// Statements, threshold shares, batches and the other proof kinds are over values the clients pick,
+// not the registered commitment, so their verdicts must not speak for a user
+fn check_anonymous(session: &Session) -> Result<(), Status> {
+    if session.user_id.is_some() {
+        return Err(Status::failed_precondition(
+            "A registered user's session ends with VerifyProof, ProveOneShot, RotateSecret or RecoverAccount",
+        ));
+    }
+    Ok(())
+}
+
+// Marked so clients can tell it from their own deadline and not retry: only fresh (y1, y2) help
+fn expired_challenge() -> Status {
+    let mut status = Status::deadline_exceeded("The challenge expired; send fresh (y1, y2) for a new one");
+    status.metadata_mut().insert(CHALLENGE_EXPIRED, "true".parse().expect("valid metadata value"));
+    status
+}
+
+fn verdict(verified: bool) -> VerifyProofResponse {
+    let message = if verified {
+        "Zero-knowledge proof verified successfully!"
+    } else {
+        "Zero-knowledge proof verification failed!"
+    };
+    VerifyProofResponse {
+        verified,
+        message: message.to_string(),
+    }
+}
+
+fn requested_challenge_mode(request: &InitializeRequest) -> Result<SessionChallengeMode, Status> {
+    ChallengeMode::try_from(request.challenge_mode)
+        .map(SessionChallengeMode::from)
+        .map_err(|_| Status::invalid_argument("Unknown challenge mode"))
+}
+
+// The challenge for a commitment and its (y1, y2) in the session's mode, and the randomness an
+// interactive one was drawn with
+fn issue_challenge(
+    session_id: &SessionId,
+    mode: SessionChallengeMode,
+    commitment: &CryptoCommitment,
+    challenge_values: &CryptoProofChallenge,
+    q: &num_bigint::BigUint,
+) -> (num_bigint::BigUint, Option<Vec<u8>>) {
+    let (y1, y2) = (&challenge_values.y1, &challenge_values.y2);
+    match mode {
+        SessionChallengeMode::FiatShamir => {
+            (generate_session_challenge(session_id.to_string().as_bytes(), commitment, y1, y2, q), None)
+        }
+        SessionChallengeMode::Interactive => {
+            let mut randomness = [0u8; 32];
+            OsRng.fill_bytes(&mut randomness);
+            (generate_interactive_challenge(&randomness, commitment, y1, y2, q), Some(randomness.to_vec()))
+        }
+    }
+}
+
+// Whether `challenge` is the one issue_challenge derived for exactly this session, commitment and
+// (y1, y2). A run is only checked against the commitment its challenge was issued for, so one
+// swapped in afterwards, e.g. through a tampered session store, fails here rather than being verified.
+fn challenge_is_bound(
+    session_id: &SessionId,
+    mode: SessionChallengeMode,
+    commitment: &CryptoCommitment,
+    challenge_values: &CryptoProofChallenge,
+    challenge: &num_bigint::BigUint,
+    server_randomness: Option<&[u8]>,
+    q: &num_bigint::BigUint,
+) -> bool {
+    let (y1, y2) = (&challenge_values.y1, &challenge_values.y2);
+    let expected = match (mode, server_randomness) {
+        (SessionChallengeMode::FiatShamir, None) => {
+            generate_session_challenge(session_id.to_string().as_bytes(), commitment, y1, y2, q)
+        }
+        (SessionChallengeMode::Interactive, Some(randomness)) => {
+            generate_interactive_challenge(randomness, commitment, y1, y2, q)
+        }
+        _ => return false,
+    };
+    expected == *challenge
+}
+
+// The groups this build was compiled with
+fn available_groups() -> impl Iterator<Item = group::GroupKind> {
+    group::GroupKind::ALL.into_iter().filter(|kind| kind.is_available())
+}
+
+// The group an InitializeRequest asks for, if this build supports it
+fn requested_group(request: &InitializeRequest) -> Result<group::GroupKind, Status> {
+    let kind = GroupKind::try_from(request.group)
+        .map(group::GroupKind::from)
+        .map_err(|_| Status::invalid_argument("Unknown group"))?;
+    if !kind.is_available() {
+        return Err(Status::unimplemented(format!("This server was built without {} support", kind)));
+    }
+    Ok(kind)
+}
+
+// The curve group an OPRF request names. Modp has no parameters fixed for the server's lifetime to
+// hold a published key, so it is refused.
+fn oprf_group(value: i32) -> Result<group::GroupKind, Status> {
+    let kind = GroupKind::try_from(value)
+        .map(group::GroupKind::from)
+        .map_err(|_| Status::invalid_argument("Unknown group"))?;
+    match kind {
+        group::GroupKind::Modp => Err(Status::failed_precondition("OPRF evaluation needs a curve group")),
+        kind if !kind.is_available() => {
+            Err(Status::unimplemented(format!("This server was built without {} support", kind)))
+        }
+        kind => Ok(kind),
+    }
+}
+
+// A proof check, or other group work, that runs over whichever group a session or request uses
+trait GroupCheck: Send + 'static {
+    const PHASE: &'static str;
+    type Output: Send + 'static;
+    fn check<G: Group + Clone>(self, group: G) -> Result<Self::Output, ConversionError>;
+}
+
+// Runs `check` over the session's group on the blocking pool
+async fn check_in_group<C: GroupCheck>(session: Session, check: C) -> Result<C::Output, Status> {
+    run_in_group(session.group, session.params, check).await
+}
+
+// `params` are only needed, and only looked at, for modp
+async fn run_in_group<C: GroupCheck>(
+    kind: group::GroupKind,
+    params: Option<CryptoPublicParameters>,
+    check: C,
+) -> Result<C::Output, Status> {
+    match kind {
+        group::GroupKind::Modp => {
+            let params = params.ok_or_else(|| Status::internal("Modp session without parameters"))?;
+            run_check(params, check).await
+        }
+        #[cfg(feature = "secp256k1")]
+        group::GroupKind::Secp256k1 => run_check(group::Secp256k1::new(), check).await,
+        #[cfg(feature = "p256")]
+        group::GroupKind::P256 => run_check(group::P256::new(), check).await,
+        #[cfg(feature = "bls12_381")]
+        group::GroupKind::Bls12381G1 => run_check(group::Bls12381G1::new(), check).await,
+        #[cfg(feature = "ristretto")]
+        group::GroupKind::Ristretto255 => run_check(group::Ristretto255::new(), check).await,
+        // a group this build lacks, asked for directly or through a session stored by another build
+        #[allow(unreachable_patterns)]
+        kind => Err(Status::unimplemented(format!("This server was built without {} support", kind))),
+    }
+}
+
+async fn run_check<G, C>(group: G, check: C) -> Result<C::Output, Status>
+where
+    G: Group + Clone + Send + 'static,
+    C: GroupCheck,
+{
+    Ok(run_blocking(C::PHASE, move || check.check(group)).await??)
+}
+
+struct DleqCheck {
+    session_id: String,
+    proof: DleqProof,
+}
+
+impl GroupCheck for DleqCheck {
+    const PHASE: &'static str = "verify_dleq_proof";
+    type Output = bool;
+
+    fn check<G: Group + Clone>(self, group: G) -> Result<bool, ConversionError> {
+        let (statement, proof) = decode_dleq(&group, &self.proof)?;
+        Ok(Dleq::new(group).verify_for_session(&self.session_id, &statement, &proof))
+    }
+}
+
+// Only the domain is bound into the proof, so the session just picks the group
+struct CompactDleqCheck {
+    request: CompactDleqProofRequest,
+}
+
+impl GroupCheck for CompactDleqCheck {
+    const PHASE: &'static str = "verify_compact_dleq_proof";
+    type Output = bool;
+
+    fn check<G: Group + Clone>(self, group: G) -> Result<bool, ConversionError> {
+        let (statement, proof) = decode_compact_dleq(&group, &self.request)?;
+        Ok(interop::verify(&group, &self.request.domain, &statement, &proof))
+    }
+}
+
+// The server's VOPRF public key in one group
+struct OprfKeyCheck {
+    key: EvaluationKey,
+}
+
+impl GroupCheck for OprfKeyCheck {
+    const PHASE: &'static str = "oprf_public_key";
+    type Output = Vec<u8>;
+
+    fn check<G: Group + Clone>(self, group: G) -> Result<Vec<u8>, ConversionError> {
+        Ok(group.encode_element(&self.key.public_key(&group)))
+    }
+}
+
+// Evaluates each blinded element, each with its own proof
+struct OprfEvaluationCheck {
+    key: EvaluationKey,
+    request: OprfEvaluationRequest,
+}
+
+impl GroupCheck for OprfEvaluationCheck {
+    const PHASE: &'static str = "evaluate_oprf";
+    type Output = Vec<DleqProof>;
+
+    fn check<G: Group + Clone>(self, group: G) -> Result<Vec<DleqProof>, ConversionError> {
+        let blinded_elements = decode_blinded_elements(&group, &self.request)?;
+        let k = self.key.secret(&group);
+        let pk = voprf::public_key(&group, &k);
+        let evaluations = blinded_elements
+            .into_iter()
+            .map(|blinded| {
+                let (evaluated, proof) = voprf::evaluate(&group, &k, &blinded);
+                let statement = DleqStatement {
+                    g: group.generator(),
+                    h: blinded,
+                    y1: pk.clone(),
+                    y2: evaluated,
+                };
+                encode_dleq(&group, &statement, &proof)
+            })
+            .collect();
+        Ok(evaluations)
+    }
+}
+
+// The session id is the proof's context
+struct DhShareCheck {
+    session_id: String,
+    proof: DhShareProof,
+}
+
+impl GroupCheck for DhShareCheck {
+    const PHASE: &'static str = "verify_dh_share";
+    type Output = bool;
+
+    fn check<G: Group + Clone>(self, group: G) -> Result<bool, ConversionError> {
+        let (share, proof) = decode_dh_share(&group, &self.proof)?;
+        Ok(key_exchange::verify_share(&group, &share, self.session_id.as_bytes(), &proof))
+    }
+}
+
+// How often InitializeProtocolStream reports on a running search
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Debug)]
+pub struct ChaumPedersenServer {
+    // shared state across requests with thread-safe access
+    sessions: Arc<dyn SessionStore>,
+    registry: Arc<dyn RegistryStore>,
+    stats: Arc<ServerStats>,
+    failures: FailureTracker,
+    replay: ReplayCache,
+    hooks: Vec<Box<dyn VerificationHook>>,  // run in order after every verification
+    signer: TranscriptSigner,
+    transcripts: TranscriptArchive,  // where concluded runs' signed transcripts outlive their sessions
+    oprf_key: EvaluationKey,
+    params: ParamsSource,
+    policy: GenerationPolicy,
+    shedding: LoadShedding,
+    message_sizes: MessageSizes,
+    session_ttl: Duration,
+    challenge_ttl: Duration,
+    max_statements: usize,
+    max_parties: u32,
+    max_batched: usize,
+}
+
+impl ChaumPedersenServer {
+    pub fn new() -> Self {
+        Self::from_config(&ServerConfig::default())
+    }
+
+    pub fn from_config(config: &ServerConfig) -> Self {
+        let pool_bits = config.param_pool_bits.iter().map(|&bits| bits as u64).collect();
+        let generating = Arc::new(AtomicUsize::new(0));
+        let permits = Arc::new(Semaphore::new(config.max_concurrent_generations));
+        let pool = ParamsPool::new(
+            config.param_pool_size,
+            pool_bits,
+            config.verifiable_params,
+            generating.clone(),
+            permits.clone(),
+        );
+        Self {
+            sessions: Arc::new(MemorySessionStore::new()),
+            registry: Arc::new(MemoryRegistryStore::new()),
+            stats: Arc::new(ServerStats::default()),
+            failures: FailureTracker::new(config.max_failed_verifications, config.lockout()),
+            replay: ReplayCache::new(config.replay_cache_size),
+            hooks: Vec::new(),
+            signer: TranscriptSigner::generate(),
+            transcripts: TranscriptArchive::default(),
+            oprf_key: EvaluationKey::generate(),
+            params: ParamsSource {
+                pool: Arc::new(pool),
+                cache: None,
+                verifiable: config.verifiable_params,
+                generating,
+                permits,
+            },
+            shedding: LoadShedding::from_config(config),
+            message_sizes: MessageSizes {
+                decoding: config.max_decoding_message_size,
+                encoding: config.max_encoding_message_size,
+            },
+            policy: GenerationPolicy::from_config(config),
+            session_ttl: config.session_ttl(),
+            challenge_ttl: config.challenge_ttl(),
+            max_statements: config.max_statements_per_session,
+            max_parties: config.max_threshold_parties,
+            max_batched: config.max_batched_proofs,
+        }
+    }
+
+    fn sweep_expired_sessions(&self) {
+        match self.sessions.sweep(self.session_ttl) {
+            Ok(0) => {}
+            Ok(expired) => info!("Expired {} stale sessions", expired),
+            Err(e) => error!("Failed to sweep sessions: {}", e),
+        }
+        match self.transcripts.prune_if_due() {
+            Ok(0) => {}
+            Ok(pruned) => info!("Pruned {} archived transcripts past their retention", pruned),
+            Err(e) => error!("Failed to prune archived transcripts: {}", e),
+        }
+    }
+
+    // Loads a live session, treating expired ones as missing
+    fn load_session(&self, session_id: &SessionId) -> Result<Session, Status> {
+        let session = self
+            .sessions
+            .get(session_id)
+            .map_err(session_store_error)?
+            .filter(|session| !session.is_expired(self.session_ttl))
+            .ok_or_else(|| Status::not_found("Session not found"))?;
+        if session.cancelled {
+            return Err(cancelled_session());
+        }
+        Ok(session)
+    }
+
+    // Loads a session over the modp group along with its parameters
+    fn load_modp_session(&self, session_id: &SessionId) -> Result<(Session, CryptoPublicParameters), Status> {
+        let session = self.load_session(session_id)?;
+        let params = session
+            .params
+            .clone()
+            .ok_or_else(|| Status::failed_precondition("This RPC needs a session over the modp group"))?;
+        Ok((session, params))
+    }
+
+    fn store_session(&self, session_id: &SessionId, session: &Session) -> Result<(), Status> {
+        self.sessions.put(session_id, session).map_err(session_store_error)
+    }
+
+    // Stores a concluded Chaum-Pedersen run and archives its signed transcript. The verdict stands
+    // whether or not the archive takes it, so a failure there is only logged.
+    fn complete_run(&self, session_id: &SessionId, session: Session, exchange: Exchange, outcome: Outcome) -> Result<(), Status> {
+        if let Some(params) = &session.params {
+            let transcript = evidence::transcript(
+                &session_id.to_string(),
+                params,
+                &exchange.commitment,
+                &exchange.challenge_values,
+                &exchange.challenge,
+                &outcome,
+            );
+            if let Err(e) = self.transcripts.store(session_id, &self.signer.sign(&transcript), outcome.at) {
+                error!("Failed to archive the transcript of session {}: {}", session_id, e);
+            }
+        }
+        self.store_session(session_id, &Session {
+            state: SessionState::Completed { exchange, outcome },
+            ..session
+        })
+    }
+
+    // Called once the protocol has concluded; a leftover row just expires later
+    fn finish_session(&self, session_id: &SessionId) {
+        if let Err(e) = self.sessions.remove(session_id) {
+            error!("Failed to remove session {}: {}", session_id, e);
+        }
+    }
+
+    pub fn with_audit_log(self, audit: AuditLog) -> Self {
+        self.with_hook(Box::new(AuditLogHook::new(audit)))
+    }
+
+    pub fn with_hook(mut self, hook: Box<dyn VerificationHook>) -> Self {
+        self.hooks.push(hook);
+        self
+    }
+
+    pub fn with_transcript_signer(mut self, signer: TranscriptSigner) -> Self {
+        self.signer = signer;
+        self
+    }
+
+    pub fn with_oprf_key(mut self, key: EvaluationKey) -> Self {
+        self.oprf_key = key;
+        self
+    }
+
+    // Archives each concluded run's signed transcript, pruning what is past the archive's retention
+    pub fn with_transcript_archive(mut self, archive: TranscriptArchive) -> Self {
+        self.transcripts = archive;
+        self.sweep_expired_sessions();
+        self
+    }
+
+    pub fn with_params_cache(mut self, cache: ParamsCache) -> Self {
+        self.params.cache = Some(Arc::new(cache));
+        self
+    }
+
+    // Swaps in another session store, dropping expired sessions it still holds from a previous run
+    pub fn with_session_store(mut self, store: Box<dyn SessionStore>) -> Self {
+        self.sessions = store.into();
+        self.sweep_expired_sessions();
+        match self.sessions.len() {
+            Ok(0) => {}
+            Ok(recovered) => info!("Recovered {} in-flight sessions", recovered),
+            Err(e) => error!("Failed to count recovered sessions: {}", e),
+        }
+        self
+    }
+
+    pub fn with_registry_store(mut self, store: Box<dyn RegistryStore>) -> Self {
+        self.registry = store.into();
+        match self.registry.len() {
+            Ok(0) => {}
+            Ok(users) => info!("Loaded {} registered users", users),
+            Err(e) => error!("Failed to count registered users: {}", e),
+        }
+        self
+    }
+
+    // Both protocol versions served by this server, accepting compressed requests and bounded by the
+    // configured message sizes
+    pub fn services(
+        self: Arc<Self>,
+    ) -> (ChaumPedersenServiceServer<Self>, ChaumPedersenServiceV2Server<Self>) {
+        let sizes = self.message_sizes;
+        let v1 = ChaumPedersenServiceServer::from_arc(self.clone())
+            .accept_compressed(CompressionEncoding::Gzip)
+            .accept_compressed(CompressionEncoding::Zstd)
+            .max_decoding_message_size(sizes.decoding)
+            .max_encoding_message_size(sizes.encoding);
+        let v2 = ChaumPedersenServiceV2Server::from_arc(self)
+            .accept_compressed(CompressionEncoding::Gzip)
+            .accept_compressed(CompressionEncoding::Zstd)
+            .max_decoding_message_size(sizes.decoding)
+            .max_encoding_message_size(sizes.encoding);
+        (v1, v2)
+    }
+
+    // Fills the parameter pool up to its configured size; meant to be spawned at startup
+    pub fn refill_param_pool(&self) -> impl Future<Output = ()> + Send + 'static {
+        self.params.pool.clone().refill()
+    }
+
+    // The max_concurrent_generations slots. The initialize rate limiter holds one for every request
+    // it lets through and pool refills one per search, so both count against the same cap.
+    pub fn generation_permits(&self) -> Arc<Semaphore> {
+        self.params.permits.clone()
+    }
+
+    pub fn policy(&self) -> &GenerationPolicy {
+        &self.policy
+    }
+
+    // The user's registration, if it exists and has not been revoked
+    fn active_registration(&self, user_id: &str) -> Result<UserRegistration, Status> {
+        let registration = self
+            .registry
+            .get(user_id)
+            .map_err(registry_store_error)?
+            .ok_or_else(|| Status::not_found("No commitment is registered for this user"))?;
+        if registration.revoked {
+            return Err(Status::failed_precondition("This user's registration was revoked"));
+        }
+        Ok(registration)
+    }
+
+    // A session over the user's registered parameters, to be checked against their commitment, or
+    // over those of their recovery commitment
+    fn user_session(
+        &self,
+        user_id: String,
+        recovery: bool,
+        group: group::GroupKind,
+        mode: SessionChallengeMode,
+    ) -> Result<Session, Status> {
+        if group != group::GroupKind::Modp {
+            return Err(Status::invalid_argument("Registered commitments are over the modp group"));
+        }
+        let registration = self.active_registration(&user_id)?;
+        let params = if recovery {
+            registration
+                .recovery
+                .ok_or_else(|| Status::failed_precondition("This user registered no recovery commitment"))?
+                .params
+        } else {
+            registration.params
+        };
+        let session = Session::new(params).with_challenge_mode(mode).for_user(user_id);
+        Ok(Session { recovery, ..session })
+    }
+
+    // The commitment a user's session runs against
+    fn registered_commitment(&self, user_id: &str, recovery: bool) -> Result<CryptoCommitment, Status> {
+        let registration = self.active_registration(user_id)?;
+        if !recovery {
+            return Ok(registration.commitment);
+        }
+        registration
+            .recovery
+            .map(|recovery| recovery.commitment)
+            .ok_or_else(|| Status::failed_precondition("This user registered no recovery commitment"))
+    }
+
+    // In a user's session the commitment must be the registered one, still, when it is checked
+    fn check_registered(
+        &self,
+        session: &Session,
+        params: &CryptoPublicParameters,
+        commitment: &CryptoCommitment,
+    ) -> Result<(), Status> {
+        let Some(user_id) = &session.user_id else {
+            return Ok(());
+        };
+        let registration = self.active_registration(user_id)?;
+        let matches = match session.recovery {
+            true => registration.matches_recovery(params, commitment),
+            false => registration.matches(params, commitment),
+        };
+        if !matches {
+            return Err(Status::failed_precondition("The commitment does not match this user's registration"));
+        }
+        Ok(())
+    }
+
+    // Failures in a user's sessions also count against the user, since each session is a fresh key
+    fn check_user_lockout(&self, keys: &mut Vec<String>, session: &Session) -> Result<(), Status> {
+        if let Some(user_id) = &session.user_id {
+            keys.push(format!("user:{}", user_id));
+            self.check_lockout(keys)?;
+        }
+        Ok(())
+    }
+
+    // Decodes a registration request, taking the parameters from its session
+    fn requested_registration(
+        &self,
+        req: RegisterRequest,
+    ) -> Result<(String, CryptoPublicParameters, CryptoCommitment, Option<Recovery>), Status> {
+        if req.user_id.is_empty() {
+            return Err(Status::invalid_argument("Missing user id"));
+        }
+        let session_id = parse_session_id(&req.session_id)?;
+        let (params, commitment) = self.commitment_to_register(&session_id, req.commitment)?;
+        let recovery = match req.recovery_commitment {
+            Some(recovery) => {
+                let (params, commitment) = self.commitment_to_register(&session_id, Some(recovery))?;
+                Some(Recovery { params, commitment })
+            }
+            None => None,
+        };
+        Ok((req.user_id, params, commitment, recovery))
+    }
+
+    // A commitment to register, over the parameters of the session it names
+    fn commitment_to_register(
+        &self,
+        session_id: &SessionId,
+        commitment: Option<zkp::Commitment>,
+    ) -> Result<(CryptoPublicParameters, CryptoCommitment), Status> {
+        let commitment =
+            CryptoCommitment::try_from(commitment.ok_or_else(|| Status::invalid_argument("Missing commitment"))?)?;
+        let (_, params) = self.load_modp_session(session_id)?;
+        if ![&commitment.a1, &commitment.b1, &commitment.c1].into_iter().all(|x| params.is_element(x)) {
+            return Err(Status::invalid_argument("Commitment values must be elements of the group"));
+        }
+        Ok((params, commitment))
+    }
+
+    // Checks z against the challenge issued in `exchange` and concludes the session's run with the
+    // verdict. Shared by VerifyProof, RotateSecret and RecoverAccount.
+    #[allow(clippy::too_many_arguments)]
+    async fn conclude_run(
+        &self,
+        session_id: &SessionId,
+        session: Session,
+        params: CryptoPublicParameters,
+        exchange: Exchange,
+        z: num_bigint::BigUint,
+        lockout: &[String],
+        peer: Option<String>,
+    ) -> Result<bool, Status> {
+        if z >= *params.q() {
+            return Err(Status::invalid_argument("Response z must be reduced mod q"));
+        }
+        if exchange.is_expired(self.challenge_ttl) {
+            info!("Response for session {} came after its challenge expired", session_id);
+            return Err(expired_challenge());
+        }
+        self.check_registered(&session, &params, &exchange.commitment)?;
+        let bound = challenge_is_bound(
+            session_id,
+            session.challenge_mode,
+            &exchange.commitment,
+            &exchange.challenge_values,
+            &exchange.challenge,
+            exchange.server_randomness.as_deref(),
+            params.q(),
+        );
+        if !bound {
+            warn!("Challenge of session {} was not issued for its commitment", session_id);
+            return Err(Status::failed_precondition("The challenge was not issued for this commitment"));
+        }
+
+        let verified = {
+            let (params, exchange, z) = (params.clone(), exchange.clone(), z.clone());
+            run_blocking("verify_proof", move || {
+                let response = CryptoProofResponse { z };
+                ChaumPedersen::new(params).verify(
+                    &exchange.commitment,
+                    &exchange.challenge_values,
+                    &exchange.challenge,
+                    &response,
+                )
+            })
+            .await?
+        };
+
+        self.notify_hooks(session.user_id.clone(), AuditEntry::new(
+            &session_id.to_string(),
+            &params,
+            Some(exchange.commitment.clone()),
+            exchange.challenge.clone(),
+            z.clone(),
+            verified,
+            peer,
+        )).await;
+        self.record_outcome(lockout, verified);
+        self.complete_run(session_id, session, exchange, Outcome::new(z, verified))?;
+
+        if verified {
+            info!("Proof verified successfully for session: {}", session_id);
+        } else {
+            info!("Proof verification failed for session: {}", session_id);
+        }
+        Ok(verified)
+    }
+
+    // Concludes a registered user's run and, if it verified, registers the new commitment as their
+    // primary one, and the new recovery commitment if one was given. `recovery` says which kind of session the run must be in.
+    async fn rebind_user(
+        &self,
+        request: Request<RotateSecretRequest>,
+        recovery: bool,
+    ) -> Result<Response<RotateSecretResponse>, Status> {
+        let peer = request.remote_addr().map(|addr| addr.to_string());
+        let mut lockout = lockout_keys(&request, &request.get_ref().session_id);
+        self.check_lockout(&lockout)?;
+        let req = request.into_inner();
+        let session_id = parse_session_id(&req.session_id)?;
+
+        let (session, params) = self.load_modp_session(&session_id)?;
+        let z = FieldLimits::for_params(&params).scalar("z", &req.z)?;
+        let user_id = session
+            .user_id
+            .clone()
+            .ok_or_else(|| Status::failed_precondition("Only a registered user's session can change their secret"))?;
+        match (session.recovery, recovery) {
+            (true, false) => return Err(Status::failed_precondition("A recovery session ends with RecoverAccount")),
+            (false, true) => return Err(Status::failed_precondition("RecoverAccount needs a recovery session")),
+            _ => {}
+        }
+        self.check_user_lockout(&mut lockout, &session)?;
+        let exchange = match &session.state {
+            SessionState::AwaitingCommitment => {
+                return Err(Status::failed_precondition("No challenge has been issued for this session"));
+            }
+            SessionState::AwaitingResponse(exchange) => exchange.clone(),
+            SessionState::Completed { .. } => return Err(concluded_session()),
+        };
+        let params_session_id = match req.params_session_id.as_str() {
+            "" => session_id,
+            other => parse_session_id(other)?,
+        };
+        let (new_params, new_commitment) = self.commitment_to_register(&params_session_id, req.new_commitment)?;
+        let new_recovery = match req.new_recovery_commitment {
+            Some(recovery) => {
+                let (params, commitment) = self.commitment_to_register(&params_session_id, Some(recovery))?;
+                Some(Recovery { params, commitment })
+            }
+            None => None,
+        };
+        // read before the proof is checked, so a change made meanwhile makes the update fail
+        let current = self.active_registration(&user_id)?;
+
+        let verified = self.conclude_run(&session_id, session, params, exchange, z, &lockout, peer).await?;
+        if !verified {
+            return Ok(Response::new(RotateSecretResponse {
+                verified,
+                message: verdict(verified).message,
+                registration: None,
+            }));
+        }
+        let mut registration = current.rotated(new_params, new_commitment);
+        if new_recovery.is_some() {
+            registration.recovery = new_recovery;
+        }
+        if !self
+            .registry
+            .replace(&user_id, current.version, &registration)
+            .map_err(registry_store_error)?
+        {
+            return Err(concurrent_registration());
+        }
+
+        if recovery {
+            warn!("Recovered the account of user {} in session {}", user_id, session_id);
+        } else {
+            info!("Rotated the secret of user {} in session {}", user_id, session_id);
+        }
+        Ok(Response::new(RotateSecretResponse {
+            verified,
+            message: verdict(verified).message,
+            registration: Some(registration_info(&user_id, &registration)),
+        }))
+    }
+
+    // Runs every hook in turn; a failing hook is reported but never fails the RPC itself
+    async fn notify_hooks(&self, user_id: Option<String>, entry: AuditEntry) {
+        let event = VerificationEvent::new(entry, user_id);
+        for hook in &self.hooks {
+            let result = if event.verified() {
+                hook.on_success(&event).await
+            } else {
+                hook.on_failure(&event).await
+            };
+            if let Err(e) = result {
+                error!("Verification hook {:?} failed for session {}: {}", hook, event.entry.session_id, e);
+            }
+        }
+    }
+
+    // Returns how many sessions are still mid-protocol; a durable store keeps them for the next run
+    fn flush_sessions(&self) -> usize {
+        let pending = self.sessions.len().unwrap_or_default();
+        if !self.sessions.is_durable()
+            && let Err(e) = self.sessions.clear()
+        {
+            error!("Failed to clear sessions: {}", e);
+        }
+        pending
+    }
+
+    pub fn log_shutdown_summary(&self) {
+        let pending = self.flush_sessions();
+        info!(
+            initialized = self.stats.initialized.load(Ordering::Relaxed),
+            verified = self.stats.verified.load(Ordering::Relaxed),
+            failed = self.stats.failed.load(Ordering::Relaxed),
+            lockouts = self.stats.lockouts.load(Ordering::Relaxed),
+            locked_out_attempts = self.stats.locked_out_attempts.load(Ordering::Relaxed),
+            replays = self.stats.replays.load(Ordering::Relaxed),
+            shed = self.stats.shed.load(Ordering::Relaxed),
+            pending,
+            persisted = self.sessions.is_durable(),
+            "Shutdown summary"
+        );
+    }
+
+    // Refuses a new session while the server is past a load-shedding threshold. The status carries
+    // a retry-after hint in seconds.
+    fn check_load(&self) -> Result<(), Status> {
+        let shedding = self.shedding;
+        let reason = if shedding.max_generations > 0
+            && self.params.generating.load(Ordering::Relaxed) >= shedding.max_generations
+        {
+            "parameter generations"
+        } else if shedding.max_sessions > 0 && self.sessions.len().map_err(session_store_error)? >= shedding.max_sessions {
+            "active sessions"
+        } else {
+            return Ok(());
+        };
+
+        self.stats.shed.fetch_add(1, Ordering::Relaxed);
+        let retry_after = shedding.retry_after.as_secs();
+        let mut status = Status::unavailable(format!("Too many {}, try again in {}s", reason, retry_after));
+        status.metadata_mut().insert("retry-after", retry_after.into());
+        Err(status)
+    }
+
+    // Refuses a verification while its session or client is locked out
+    fn check_lockout(&self, keys: &[String]) -> Result<(), Status> {
+        match self.failures.locked_for(keys) {
+            Some(remaining) => {
+                self.stats.locked_out_attempts.fetch_add(1, Ordering::Relaxed);
+                Err(Status::permission_denied(format!(
+                    "Too many failed verifications, try again in {}s",
+                    remaining.as_secs().max(1)
+                )))
+            }
+            None => Ok(()),
+        }
+    }
+
+    // Checks a commitment and its (y1, y2) before a challenge is issued for them
+    fn check_commitment(
+        &self,
+        session_id: &SessionId,
+        params: &CryptoPublicParameters,
+        commitment: &CryptoCommitment,
+        challenge_values: &CryptoProofChallenge,
+    ) -> Result<(), Status> {
+        let values = [
+            &commitment.a1,
+            &commitment.b1,
+            &commitment.c1,
+            &challenge_values.y1,
+            &challenge_values.y2,
+        ];
+        if !values.into_iter().all(|x| params.is_element(x)) {
+            return Err(Status::invalid_argument("Commitment values must be elements of the group"));
+        }
+        if !self.replay.check(params, &[&challenge_values.y1, &challenge_values.y2], session_id) {
+            self.stats.replays.fetch_add(1, Ordering::Relaxed);
+            warn!("Rejected replayed (y1, y2) in session: {}", session_id);
+            return Err(Status::already_exists("These (y1, y2) values were already used in another session"));
+        }
+        Ok(())
+    }
+
+    // Updates the outcome counters and the lockout state after a verification
+    fn record_outcome(&self, keys: &[String], verified: bool) {
+        if verified {
+            self.stats.verified.fetch_add(1, Ordering::Relaxed);
+            self.failures.clear(keys);
+            return;
+        }
+
+        self.stats.failed.fetch_add(1, Ordering::Relaxed);
+        for key in self.failures.record_failure(keys) {
+            self.stats.lockouts.fetch_add(1, Ordering::Relaxed);
+            warn!("Locked out {} after {} failed verifications", key, self.failures.threshold);
+        }
+    }
+
+}
+
+impl Default for ChaumPedersenServer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[tonic::async_trait]
+impl ChaumPedersenService for ChaumPedersenServer {
+    async fn initialize_protocol(
+        &self,
+        mut request: Request<InitializeRequest>,
+    ) -> Result<Response<InitializeResponse>, Status> {
+        let client_deadline = request_deadline(&request);
+        let group = requested_group(request.get_ref())?;
+        let mode = requested_challenge_mode(request.get_ref())?;
+        let compressed = request.get_ref().compressed_elements;
+        self.sweep_expired_sessions();
+        self.check_load()?;
+        let user_id = std::mem::take(&mut request.get_mut().user_id);
+        if !user_id.is_empty() {
+            let session = self.user_session(user_id, request.get_ref().recovery, group, mode)?;
+            let response = open_session(self.sessions.as_ref(), &self.stats, session.with_compressed_elements(compressed))?;
+            return Ok(Response::new(response));
+        }
+        if group != group::GroupKind::Modp {
+            let session = Session::over(group).with_challenge_mode(mode);
+            let response = open_session(self.sessions.as_ref(), &self.stats, session)?;
+            return Ok(Response::new(response));
+        }
+
+        let bits = request.into_inner().bit_size;
+        self.policy.check_bits(bits)?;
+        let deadline = GenerationDeadline::new(&self.policy, bits, client_deadline);
+
+        let params = self.params.obtain(bits.into(), deadline, Arc::new(PrimeSearch::new())).await?;
+        let session = Session::new(params).with_challenge_mode(mode).with_compressed_elements(compressed);
+        let response = open_session(self.sessions.as_ref(), &self.stats, session)?;
+        Ok(Response::new(response))
+    }
+
+    type InitializeProtocolStreamStream = ReceiverStream<Result<InitializeProgress, Status>>;
+
+    // Like initialize_protocol, but reports progress while parameters are generated. A client that
+    // hangs up cancels the search.
+    async fn initialize_protocol_stream(
+        &self,
+        mut request: Request<InitializeRequest>,
+    ) -> Result<Response<Self::InitializeProtocolStreamStream>, Status> {
+        let client_deadline = request_deadline(&request);
+        let group = requested_group(request.get_ref())?;
+        let mode = requested_challenge_mode(request.get_ref())?;
+        let compressed = request.get_ref().compressed_elements;
+        self.sweep_expired_sessions();
+        self.check_load()?;
+        let (tx, rx) = mpsc::channel(4);
+        // nothing to generate for a curve group or a registered user, so the stream is just the result
+        let user_id = std::mem::take(&mut request.get_mut().user_id);
+        if !user_id.is_empty() || group != group::GroupKind::Modp {
+            let session = if user_id.is_empty() {
+                Session::over(group).with_challenge_mode(mode)
+            } else {
+                self.user_session(user_id, request.get_ref().recovery, group, mode)?
+            };
+            let response = open_session(self.sessions.as_ref(), &self.stats, session.with_compressed_elements(compressed))?;
+            let event = Some(initialize_progress::Event::Done(response));
+            let _ = tx.send(Ok(InitializeProgress { event })).await;
+            return Ok(Response::new(ReceiverStream::new(rx)));
+        }
+
+        let bits = request.into_inner().bit_size;
+        self.policy.check_bits(bits)?;
+        let deadline = GenerationDeadline::new(&self.policy, bits, client_deadline);
+
+        let source = self.params.clone();
+        let sessions = self.sessions.clone();
+        let stats = self.stats.clone();
+        let task = async move {
+            let started = Instant::now();
+            let search = Arc::new(PrimeSearch::new());
+            let obtain = source.obtain(bits.into(), deadline, search.clone());
+            tokio::pin!(obtain);
+
+            let mut ticker = tokio::time::interval_at((started + PROGRESS_INTERVAL).into(), PROGRESS_INTERVAL);
+            let params = loop {
+                tokio::select! {
+                    params = &mut obtain => break params,
+                    _ = ticker.tick() => {
+                        let progress = GenerationProgress {
+                            candidates_tested: search.candidates(),
+                            elapsed_ms: started.elapsed().as_millis() as u64,
+                        };
+                        let event = Some(initialize_progress::Event::Progress(progress));
+                        // the receiver is gone once the client hangs up; dropping `obtain` cancels the search
+                        if tx.send(Ok(InitializeProgress { event })).await.is_err() {
+                            return;
+                        }
+                    }
+                }
+            };
+
+            let done = params.and_then(|params| {
+                let session = Session::new(params).with_challenge_mode(mode).with_compressed_elements(compressed);
+                open_session(sessions.as_ref(), &stats, session)
+            });
+            let event = done.map(|response| InitializeProgress {
+                event: Some(initialize_progress::Event::Done(response)),
+            });
+            let _ = tx.send(event).await;
+        };
+        tokio::spawn(task.instrument(tracing::Span::current()));
+
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+
+    async fn send_commitment(
+        &self,
+        request: Request<CommitmentRequest>,
+    ) -> Result<Response<ChallengeResponse>, Status> {
+        let mut req = request.into_inner();
+        let session_id = parse_session_id(&req.session_id)?;
+        let (mut session, params) = self.load_modp_session(&session_id)?;
+        // a user's session may leave the commitment out and use the registered one
+        if req.commitment.is_none()
+            && let Some(user_id) = &session.user_id
+        {
+            let registered = self.registered_commitment(user_id, session.recovery)?;
+            req.commitment = Some(match session.compressed_elements {
+                true => compress_commitment(&registered, &params),
+                false => zkp::Commitment::from(&registered),
+            });
+        }
+        let (commitment, challenge_values) = decode_commitment(req, &session, &params)?;
+        self.check_registered(&session, &params, &commitment)?;
+        self.check_commitment(&session_id, &params, &commitment, &challenge_values)?;
+
+        // a resent (y1, y2) gets the challenge it was already given, so retries stay idempotent and
+        // an interactive prover cannot draw fresh challenges for the same values. The commitment kept
+        // with them is the one verified, so a resend may not swap it.
+        // values whose challenge expired are not given another, which would let a late z and a
+        // timely one to the same (y1, y2) be combined
+        let issued = match &session.state {
+            SessionState::AwaitingCommitment => None,
+            SessionState::AwaitingResponse(exchange) => {
+                let values = &exchange.challenge_values;
+                let resent = values.y1 == challenge_values.y1 && values.y2 == challenge_values.y2;
+                if resent && exchange.commitment.digest() != commitment.digest() {
+                    return Err(Status::failed_precondition("These (y1, y2) were sent with another commitment"));
+                }
+                if resent && exchange.is_expired(self.challenge_ttl) {
+                    return Err(expired_challenge());
+                }
+                resent.then(|| (exchange.challenge.clone(), exchange.remaining_secs(self.challenge_ttl)))
+            }
+            SessionState::Completed { .. } => return Err(concluded_session()),
+        };
+        let (challenge, expires_in_secs) = match issued {
+            Some(issued) => issued,
+            None => {
+                let (challenge, server_randomness) = info_span!("crypto", otel.name = "generate_challenge")
+                    .in_scope(|| issue_challenge(&session_id, session.challenge_mode, &commitment, &challenge_values, params.q()));
+                session.state = SessionState::AwaitingResponse(Exchange {
+                    commitment,
+                    challenge_values,
+                    challenge: challenge.clone(),
+                    server_randomness,
+                    issued_at: now_secs(),
+                });
+                self.store_session(&session_id, &session)?;
+                (challenge, self.challenge_ttl.as_secs())
+            }
+        };
+
+        let response = ChallengeResponse {
+            challenge: encode_integer(&challenge),
+            expires_in_secs,
+        };
+
+        info!("Generated challenge for session: {}", session_id);
+        Ok(Response::new(response))
+    }
+
+    async fn verify_proof(
+        &self,
+        request: Request<VerifyProofRequest>,
+    ) -> Result<Response<VerifyProofResponse>, Status> {
+        let peer = request.remote_addr().map(|addr| addr.to_string());
+        let mut lockout = lockout_keys(&request, &request.get_ref().session_id);
+        self.check_lockout(&lockout)?;
+        let req = request.into_inner();
+        let session_id = parse_session_id(&req.session_id)?;
+
+        let (session, params) = self.load_modp_session(&session_id)?;
+        let z = FieldLimits::for_params(&params).scalar("z", &req.z)?;
+        // a recovery secret only ever re-binds the account, it never signs the user in
+        if session.recovery {
+            return Err(Status::failed_precondition("A recovery session ends with RecoverAccount"));
+        }
+        self.check_user_lockout(&mut lockout, &session)?;
+        let exchange = match session.state {
+            SessionState::AwaitingCommitment => {
+                return Err(Status::failed_precondition("No challenge has been issued for this session"));
+            }
+            SessionState::AwaitingResponse(ref exchange) => exchange.clone(),
+            // the session stays around only so its transcript can be fetched, and so a client resuming
+            // after a lost response can resend the same z and learn the verdict
+            SessionState::Completed { ref outcome, .. } => {
+                if outcome.z != z {
+                    return Err(concluded_session());
+                }
+                info!("Repeated response for session: {}", session_id);
+                return Ok(Response::new(verdict(outcome.verified)));
+            }
+        };
+        let verified = self.conclude_run(&session_id, session, params, exchange, z, &lockout, peer).await?;
+        Ok(Response::new(verdict(verified)))
+    }
+
+    // A whole Fiat-Shamir proof in one call, its challenge bound to this session's id. The session
+    // ends up as if SendCommitment and VerifyProof had run, so the transcript covers it and a resent
+    // proof gets the same verdict.
+    async fn prove_one_shot(
+        &self,
+        request: Request<OneShotProofRequest>,
+    ) -> Result<Response<VerifyProofResponse>, Status> {
+        let peer = request.remote_addr().map(|addr| addr.to_string());
+        let mut lockout = lockout_keys(&request, &request.get_ref().session_id);
+        self.check_lockout(&lockout)?;
+        let req = request.into_inner();
+        let session_id = parse_session_id(&req.session_id)?;
+        let proof = req.proof.ok_or_else(|| Status::invalid_argument("Missing proof"))?;
+
+        let (session, params) = self.load_modp_session(&session_id)?;
+        FieldLimits::for_params(&params).check_proof(&proof)?;
+        let proof = ZKProof::try_from(proof)?;
+        // a recovery secret only ever re-binds the account, it never signs the user in
+        if session.recovery {
+            return Err(Status::failed_precondition("A recovery session ends with RecoverAccount"));
+        }
+        self.check_user_lockout(&mut lockout, &session)?;
+        match &session.state {
+            SessionState::AwaitingCommitment => {}
+            SessionState::AwaitingResponse(_) => {
+                return Err(Status::failed_precondition("This session is in the middle of an interactive run"));
+            }
+            SessionState::Completed { outcome, .. } => {
+                if outcome.z != proof.response.z {
+                    return Err(concluded_session());
+                }
+                return Ok(Response::new(verdict(outcome.verified)));
+            }
+        }
+        self.check_registered(&session, &params, &proof.commitment)?;
+        self.check_commitment(&session_id, &params, &proof.commitment, &proof.challenge)?;
+        if proof.response.z >= *params.q() || proof.challenge_hash >= *params.q() {
+            return Err(Status::invalid_argument("Challenge and response must be reduced mod q"));
+        }
+
+        let verified = {
+            let (params, proof) = (params.clone(), proof.clone());
+            let session_id = session_id.to_string();
+            run_blocking("verify_proof", move || Verifier::new(params).verify_session_proof(&session_id, &proof)).await?
+        };
+
+        self.notify_hooks(session.user_id.clone(), AuditEntry::new(
+            &session_id.to_string(),
+            &params,
+            Some(proof.commitment.clone()),
+            proof.challenge_hash.clone(),
+            proof.response.z.clone(),
+            verified,
+            peer,
+        )).await;
+        self.record_outcome(&lockout, verified);
+        let ZKProof {
+            commitment,
+            challenge,
+            response,
+            challenge_hash,
+        } = proof;
+        let exchange = Exchange {
+            commitment,
+            challenge_values: challenge,
+            challenge: challenge_hash,
+            server_randomness: None,
+            issued_at: now_secs(),
+        };
+        self.complete_run(&session_id, session, exchange, Outcome::new(response.z, verified))?;
+
+        info!("One-shot proof for session {}: verified = {}", session_id, verified);
+        Ok(Response::new(verdict(verified)))
+    }
+
+    async fn verify_multi_proof(
+        &self,
+        request: Request<MultiProofRequest>,
+    ) -> Result<Response<VerifyProofResponse>, Status> {
+        let lockout = lockout_keys(&request, &request.get_ref().session_id);
+        self.check_lockout(&lockout)?;
+        let req = request.into_inner();
+        let session_id = parse_session_id(&req.session_id)?;
+        let (session, params) = self.load_modp_session(&session_id)?;
+        check_anonymous(&session)?;
+
+        let proof = MultiZKProof::try_from(req.proof.ok_or_else(|| {
+            Status::invalid_argument("Missing proof")
+        })?)?;
+        if proof.statements.len() > self.max_statements {
+            return Err(Status::resource_exhausted(format!(
+                "A multi-statement proof carries at most {} statements",
+                self.max_statements
+            )));
+        }
+        // each statement and its t count against replay like a (y1, y2) pair
+        for (statement, t) in proof.statements.iter().zip(&proof.commitments) {
+            if !self.replay.check(&params, &[&statement.base, &statement.value, t], &session_id) {
+                self.stats.replays.fetch_add(1, Ordering::Relaxed);
+                warn!("Rejected a replayed multi-statement proof in session: {}", session_id);
+                return Err(Status::already_exists("This statement was already proven in another session"));
+            }
+        }
+
+        let statements = proof.statements.len();
+        let id = session_id.to_string();
+        let verified = run_blocking("verify_multi_proof", move || {
+            Verifier::new(params).verify_multi_session_proof(&id, &proof)
+        })
+        .await?;
+
+        if verified {
+            self.record_outcome(&lockout, true);
+            info!("Multi-statement proof verified for session: {}", session_id);
+            self.finish_session(&session_id);
+        } else {
+            self.record_outcome(&lockout, false);
+            info!("Multi-statement proof verification failed for session: {}", session_id);
+        }
+
+        Ok(Response::new(VerifyProofResponse {
+            verified,
+            message: if verified {
+                format!("All {} statements verified successfully!", statements)
+            } else {
+                "Multi-statement proof verification failed!".to_string()
+            },
+        }))
+    }
+
+    // Verifies many Chaum-Pedersen proofs sent as one message, e.g. logins batched by a gateway. Each
+    // proof must be bound to this session like a one-shot proof, each (y1, y2) counts against replay
+    // like a single proof, and the batch passes or fails as a whole.
+    async fn verify_proof_batch(
+        &self,
+        request: Request<ProofBatchRequest>,
+    ) -> Result<Response<VerifyProofResponse>, Status> {
+        let lockout = lockout_keys(&request, &request.get_ref().session_id);
+        self.check_lockout(&lockout)?;
+        let req = request.into_inner();
+        let session_id = parse_session_id(&req.session_id)?;
+        let (session, params) = self.load_modp_session(&session_id)?;
+        check_anonymous(&session)?;
+
+        let proof = ProofBatch::try_from(req.proof.ok_or_else(|| Status::invalid_argument("Missing proof"))?)?;
+        if proof.entries.len() > self.max_batched {
+            return Err(Status::resource_exhausted(format!(
+                "A proof batch carries at most {} proofs",
+                self.max_batched
+            )));
+        }
+
+        for entry in &proof.entries {
+            self.check_commitment(&session_id, &params, &entry.commitment, &entry.challenge)?;
+        }
+
+        let proofs = proof.entries.len();
+        let id = session_id.to_string();
+        let verified =
+            run_blocking("verify_proof_batch", move || batch::verify_for_session(&params, &id, &proof)).await?;
+
+        self.record_outcome(&lockout, verified);
+        if verified {
+            info!("Batch of {} proofs verified for session: {}", proofs, session_id);
+            self.finish_session(&session_id);
+        } else {
+            info!("Proof batch verification failed for session: {}", session_id);
+        }
+
+        Ok(Response::new(VerifyProofResponse {
+            verified,
+            message: if verified {
+                format!("All {} proofs verified successfully!", proofs)
+            } else {
+                "Proof batch verification failed!".to_string()
+            },
+        }))
+    }
+
+    // Adds one statement to a multi-proof session and returns its challenge. Statements are only
+    // checked, all together, by finalize_session.
+    async fn add_statement(
+        &self,
+        request: Request<CommitmentRequest>,
+    ) -> Result<Response<StatementChallenge>, Status> {
+        let session_id = parse_session_id(&request.get_ref().session_id)?;
+        let (mut session, params) = self.load_modp_session(&session_id)?;
+        check_anonymous(&session)?;
+        let (commitment, challenge_values) = decode_commitment(request.into_inner(), &session, &params)?;
+        if session.statements.len() >= self.max_statements {
+            return Err(Status::resource_exhausted(format!(
+                "A session holds at most {} statements",
+                self.max_statements
+            )));
+        }
+        self.check_commitment(&session_id, &params, &commitment, &challenge_values)?;
+
+        let (challenge, server_randomness) = info_span!("crypto", otel.name = "generate_challenge")
+            .in_scope(|| issue_challenge(&session_id, session.challenge_mode, &commitment, &challenge_values, params.q()));
+        let index = session.statements.len() as u32;
+        session.statements.push(PendingStatement {
+            commitment,
+            challenge_values,
+            challenge: challenge.clone(),
+            z: None,
+            server_randomness,
+        });
+        self.store_session(&session_id, &session)?;
+
+        info!("Added statement {} to session: {}", index, session_id);
+        Ok(Response::new(StatementChallenge {
+            index,
+            challenge: challenge.to_bytes_be(),
+        }))
+    }
+
+    async fn answer_statement(
+        &self,
+        request: Request<StatementAnswer>,
+    ) -> Result<Response<StatementAnswerResponse>, Status> {
+        let req = request.into_inner();
+        let session_id = parse_session_id(&req.session_id)?;
+
+        let (mut session, params) = self.load_modp_session(&session_id)?;
+        let z = FieldLimits::for_params(&params).scalar("z", &req.z)?;
+        if z >= *params.q() {
+            return Err(Status::invalid_argument("Response z must be reduced mod q"));
+        }
+        let statement = session
+            .statements
+            .get_mut(req.index as usize)
+            .ok_or_else(|| Status::not_found(format!("No statement {} in this session", req.index)))?;
+        // answers are final; a wrong one shows up as a failed statement when the session is finalized
+        if statement.z.is_some() {
+            return Err(Status::failed_precondition(format!("Statement {} was already answered", req.index)));
+        }
+        statement.z = Some(z);
+        self.store_session(&session_id, &session)?;
+
+        Ok(Response::new(StatementAnswerResponse {
+            answered: session.statements.iter().filter(|statement| statement.z.is_some()).count() as u32,
+            statements: session.statements.len() as u32,
+        }))
+    }
+
+    // Verifies every statement of a multi-proof session and ends it. The session counts as one
+    // verification for the lockout, and passes only if every statement was answered and verified.
+    async fn finalize_session(
+        &self,
+        request: Request<FinalizeRequest>,
+    ) -> Result<Response<FinalizeResponse>, Status> {
+        let peer = request.remote_addr().map(|addr| addr.to_string());
+        let lockout = lockout_keys(&request, &request.get_ref().session_id);
+        self.check_lockout(&lockout)?;
+        let session_id = parse_session_id(&request.into_inner().session_id)?;
+
+        let (session, params) = self.load_modp_session(&session_id)?;
+        check_anonymous(&session)?;
+        if session.statements.is_empty() {
+            return Err(Status::failed_precondition("No statements were added to this session"));
+        }
+
+        let statements = session.statements.clone();
+        let (mode, q) = (session.challenge_mode, params.q().clone());
+        let protocol = ChaumPedersen::new(params.clone());
+        let checked = run_blocking("verify_statements", move || {
+            statements
+                .iter()
+                .map(|statement| {
+                    let bound = challenge_is_bound(
+                        &session_id,
+                        mode,
+                        &statement.commitment,
+                        &statement.challenge_values,
+                        &statement.challenge,
+                        statement.server_randomness.as_deref(),
+                        &q,
+                    );
+                    bound && statement.z.as_ref().is_some_and(|z| {
+                        let response = CryptoProofResponse { z: z.clone() };
+                        protocol.verify(
+                            &statement.commitment,
+                            &statement.challenge_values,
+                            &statement.challenge,
+                            &response,
+                        )
+                    })
+                })
+                .collect::<Vec<_>>()
+        })
+        .await?;
+
+        for (statement, &verified) in session.statements.iter().zip(&checked) {
+            if let Some(z) = &statement.z {
+                self.notify_hooks(session.user_id.clone(), AuditEntry::new(
+                    &session_id.to_string(),
+                    &params,
+                    Some(statement.commitment.clone()),
+                    statement.challenge.clone(),
+                    z.clone(),
+                    verified,
+                    peer.clone(),
+                )).await;
+            }
+        }
+
+        let results: Vec<_> = session
+            .statements
+            .iter()
+            .zip(&checked)
+            .enumerate()
+            .map(|(index, (statement, &verified))| StatementResult {
+                index: index as u32,
+                answered: statement.z.is_some(),
+                verified,
+            })
+            .collect();
+        let verified_count = checked.iter().filter(|&&verified| verified).count();
+        let verified = verified_count == checked.len();
+
+        self.record_outcome(&lockout, verified);
+        self.finish_session(&session_id);
+        info!("Finalized session {}: {}/{} statements verified", session_id, verified_count, checked.len());
+
+        Ok(Response::new(FinalizeResponse {
+            results,
+            verified,
+            verified_count: verified_count as u32,
+            message: if verified {
+                format!("All {} statements verified successfully!", checked.len())
+            } else {
+                format!("{} of {} statements verified", verified_count, checked.len())
+            },
+        }))
+    }
+
+    // Collects one prover's share of a jointly produced proof. The first submission fixes the number
+    // of parties and b1; the party that commits last gets the joint challenge straight away.
+    async fn submit_partial_commitment(
+        &self,
+        request: Request<PartialCommitmentRequest>,
+    ) -> Result<Response<PartialCommitmentResponse>, Status> {
+        let req = request.into_inner();
+        let session_id = parse_session_id(&req.session_id)?;
+
+        let (mut session, params) = self.load_modp_session(&session_id)?;
+        check_anonymous(&session)?;
+        let limits = FieldLimits::for_params(&params);
+        let b1 = limits.element("b1", &req.b1)?;
+        let values = req
+            .challenge_values
+            .ok_or_else(|| Status::invalid_argument("Missing challenge values"))?;
+        limits.check_challenge_values(&values)?;
+        let commitment = PartialCommitment {
+            share: PublicShare {
+                a1: limits.element("a1", &req.a1)?,
+                c1: limits.element("c1", &req.c1)?,
+            },
+            values: CryptoProofChallenge::try_from(values)?,
+        };
+        let coordinator = match &mut session.threshold {
+            Some(coordinator) => {
+                if coordinator.parties != req.parties || coordinator.b1 != b1 {
+                    return Err(Status::invalid_argument("parties and b1 must match the first submission"));
+                }
+                coordinator
+            }
+            None => {
+                if req.parties == 0 || req.parties > self.max_parties {
+                    return Err(Status::invalid_argument(format!(
+                        "parties must be between 1 and {}",
+                        self.max_parties
+                    )));
+                }
+                if !params.is_element(&b1) {
+                    return Err(Status::invalid_argument("b1 must be an element of the group"));
+                }
+                session.threshold.insert(Coordinator::new(params.clone(), b1, req.parties))
+            }
+        };
+        coordinator.add_commitment(req.party, commitment).map_err(threshold_error)?;
+
+        let (received, parties) = (coordinator.commitments_received(), coordinator.parties);
+        let challenge = coordinator.challenge();
+        if let (Some(commitment), Some(values)) = (coordinator.commitment(), coordinator.challenge_values()) {
+            self.check_commitment(&session_id, &params, &commitment, &values)?;
+        }
+        self.store_session(&session_id, &session)?;
+
+        info!("Party {} committed in session {} ({}/{})", req.party, session_id, received, parties);
+        Ok(Response::new(PartialCommitmentResponse {
+            received,
+            parties,
+            challenge: challenge.map(|s| s.to_bytes_be()).unwrap_or_default(),
+        }))
+    }
+
+    async fn get_threshold_challenge(
+        &self,
+        request: Request<ThresholdChallengeRequest>,
+    ) -> Result<Response<ChallengeResponse>, Status> {
+        let (session, _) = self.load_modp_session(&parse_session_id(&request.into_inner().session_id)?)?;
+        let coordinator = session
+            .threshold
+            .ok_or_else(|| Status::failed_precondition("No threshold proof in this session"))?;
+        let challenge = coordinator.challenge().ok_or_else(|| {
+            threshold_error(ThresholdError::CommitmentsPending {
+                received: coordinator.commitments_received(),
+                parties: coordinator.parties,
+            })
+        })?;
+
+        Ok(Response::new(ChallengeResponse {
+            challenge: encode_integer(&challenge),
+            expires_in_secs: 0,
+        }))
+    }
+
+    // Checks one party's response against its own share, and once every party has answered,
+    // assembles and verifies the joint proof. A response that does not match its share counts as a
+    // failed verification for the lockout.
+    async fn submit_partial_response(
+        &self,
+        request: Request<PartialResponseRequest>,
+    ) -> Result<Response<PartialResponseResult>, Status> {
+        let peer = request.remote_addr().map(|addr| addr.to_string());
+        let lockout = lockout_keys(&request, &request.get_ref().session_id);
+        self.check_lockout(&lockout)?;
+        let req = request.into_inner();
+        let session_id = parse_session_id(&req.session_id)?;
+        let party = req.party;
+
+        let (mut session, params) = self.load_modp_session(&session_id)?;
+        check_anonymous(&session)?;
+        let response = CryptoProofResponse {
+            z: FieldLimits::for_params(&params).scalar("z", &req.z)?,
+        };
+        let coordinator = session
+            .threshold
+            .take()
+            .ok_or_else(|| Status::failed_precondition("No threshold proof in this session"))?;
+        let (coordinator, added) = run_blocking("verify_partial", move || {
+            let mut coordinator = coordinator;
+            let added = coordinator.add_response(party, response);
+            (coordinator, added)
+        })
+        .await?;
+        if let Err(e) = added {
+            if let ThresholdError::InvalidResponse(_) = e {
+                self.record_outcome(&lockout, false);
+                warn!("Rejected response of party {} in session: {}", party, session_id);
+            }
+            return Err(threshold_error(e));
+        }
+
+        let (received, parties) = (coordinator.responses_received(), coordinator.parties);
+        let assembled = coordinator.assemble();
+        session.threshold = Some(coordinator);
+        let Some(proof) = assembled else {
+            self.store_session(&session_id, &session)?;
+            return Ok(Response::new(PartialResponseResult {
+                received,
+                parties,
+                proof: None,
+                verified: false,
+            }));
+        };
+
+        let verified = {
+            let (params, proof) = (params.clone(), proof.clone());
+            run_blocking("verify_proof", move || Verifier::new(params).verify_proof(&proof)).await?
+        };
+
+        self.notify_hooks(session.user_id.clone(), AuditEntry::new(
+            &session_id.to_string(),
+            &params,
+            Some(proof.commitment.clone()),
+            proof.challenge_hash.clone(),
+            proof.response.z.clone(),
+            verified,
+            peer,
+        )).await;
+        self.record_outcome(&lockout, verified);
+        // stored like a single prover's run, so GetSessionTranscript covers the assembled proof
+        let exchange = Exchange {
+            commitment: proof.commitment.clone(),
+            challenge_values: proof.challenge.clone(),
+            challenge: proof.challenge_hash.clone(),
+            server_randomness: None,
+            issued_at: now_secs(),
+        };
+        self.complete_run(&session_id, session, exchange, Outcome::new(proof.response.z.clone(), verified))?;
+
+        info!("Assembled threshold proof for session {} from {} parties: verified = {}", session_id, parties, verified);
+        Ok(Response::new(PartialResponseResult {
+            received,
+            parties,
+            proof: Some(ThresholdProof {
+                commitment: Some(zkp::Commitment::from(&proof.commitment)),
+                challenge_values: Some(zkp::ProofChallenge::from(&proof.challenge)),
+                challenge: proof.challenge_hash.to_bytes_be(),
+                z: proof.response.z.to_bytes_be(),
+            }),
+            verified,
+        }))
+    }
+
+    async fn send_schnorr_commitment(
+        &self,
+        request: Request<SchnorrCommitmentRequest>,
+    ) -> Result<Response<ChallengeResponse>, Status> {
+        let req = request.into_inner();
+        let session_id = parse_session_id(&req.session_id)?;
+        let (mut session, params) = self.load_modp_session(&session_id)?;
+        check_anonymous(&session)?;
+
+        let statement = SchnorrStatement { y: decode_integer("y", &req.y)? };
+        let commitment = SchnorrCommitment { t: decode_integer("t", &req.t)? };
+
+        if !params.is_element(&statement.y) || !params.is_element(&commitment.t) {
+            return Err(Status::invalid_argument("Schnorr values must be elements of the group"));
+        }
+
+        let challenge = info_span!("crypto", otel.name = "generate_challenge").in_scope(|| {
+            Schnorr::new(params.clone()).session_challenge(&session_id.to_string(), &statement, &commitment)
+        });
+        session.schnorr = Some(SchnorrExchange {
+            statement,
+            commitment,
+            challenge: challenge.clone(),
+        });
+        self.store_session(&session_id, &session)?;
+
+        info!("Generated Schnorr challenge for session: {}", session_id);
+        Ok(Response::new(ChallengeResponse {
+            challenge: encode_integer(&challenge),
+            expires_in_secs: 0,
+        }))
+    }
+
+    async fn verify_schnorr_proof(
+        &self,
+        request: Request<SchnorrVerifyRequest>,
+    ) -> Result<Response<VerifyProofResponse>, Status> {
+        let lockout = lockout_keys(&request, &request.get_ref().session_id);
+        self.check_lockout(&lockout)?;
+        let req = request.into_inner();
+        let session_id = parse_session_id(&req.session_id)?;
+        let (session, params) = self.load_modp_session(&session_id)?;
+        check_anonymous(&session)?;
+        let response = SchnorrResponse { s: decode_integer("s", &req.s)? };
+
+        let SchnorrExchange { statement, commitment, challenge } = session
+            .schnorr
+            .ok_or_else(|| Status::failed_precondition("No Schnorr commitment for this session"))?;
+
+        if response.s >= *params.q() {
+            return Err(Status::invalid_argument("Response s must be reduced mod q"));
+        }
+
+        let verified = run_blocking("verify_schnorr_proof", move || {
+            Schnorr::new(params).verify(&statement, &commitment, &challenge, &response)
+        })
+        .await?;
+
+        if verified {
+            self.record_outcome(&lockout, true);
+            info!("Schnorr proof verified for session: {}", session_id);
+            self.finish_session(&session_id);
+        } else {
+            self.record_outcome(&lockout, false);
+            info!("Schnorr proof verification failed for session: {}", session_id);
+        }
+
+        Ok(Response::new(VerifyProofResponse {
+            verified,
+            message: if verified {
+                "Schnorr proof verified successfully!".to_string()
+            } else {
+                "Schnorr proof verification failed!".to_string()
+            },
+        }))
+    }
+
+    // Checks a non-interactive DLEQ proof over the session's group. Works for modp and curve sessions.
+    async fn verify_dleq_proof(
+        &self,
+        request: Request<DleqProofRequest>,
+    ) -> Result<Response<VerifyProofResponse>, Status> {
+        let lockout = lockout_keys(&request, &request.get_ref().session_id);
+        self.check_lockout(&lockout)?;
+        let req = request.into_inner();
+        let session_id = parse_session_id(&req.session_id)?;
+        let session = self.load_session(&session_id)?;
+        check_anonymous(&session)?;
+        let proof = req.proof.ok_or_else(|| Status::invalid_argument("Missing proof"))?;
+
+        let check = DleqCheck {
+            session_id: session_id.to_string(),
+            proof,
+        };
+        let verified = check_in_group(session, check).await?;
+
+        self.record_outcome(&lockout, verified);
+        if verified {
+            info!("DLEQ proof verified for session: {}", session_id);
+            self.finish_session(&session_id);
+        } else {
+            info!("DLEQ proof verification failed for session: {}", session_id);
+        }
+
+        Ok(Response::new(VerifyProofResponse {
+            verified,
+            message: if verified {
+                "DLEQ proof verified successfully!".to_string()
+            } else {
+                "DLEQ proof verification failed!".to_string()
+            },
+        }))
+    }
+
+    // Checks a DLEQ proof made by another implementation, in the (c, s) encoding of dalek-based
+    // crates, over the session's group and under a domain ending in the session id. Consumes the
+    // session when it verifies, like VerifyDleqProof.
+    async fn verify_compact_dleq_proof(
+        &self,
+        request: Request<CompactDleqProofRequest>,
+    ) -> Result<Response<VerifyProofResponse>, Status> {
+        let lockout = lockout_keys(&request, &request.get_ref().session_id);
+        self.check_lockout(&lockout)?;
+        let req = request.into_inner();
+        let session_id = parse_session_id(&req.session_id)?;
+
+        let session = self.load_session(&session_id)?;
+        check_anonymous(&session)?;
+        // the domain is all the challenge covers, so it has to name the session
+        if !req.domain.ends_with(session_id.to_string().as_bytes()) {
+            return Err(Status::invalid_argument("The proof's domain must end with the session id"));
+        }
+        let verified = check_in_group(session, CompactDleqCheck { request: req }).await?;
+
+        self.record_outcome(&lockout, verified);
+        if verified {
+            info!("Compact DLEQ proof verified for session: {}", session_id);
+            self.finish_session(&session_id);
+        } else {
+            info!("Compact DLEQ proof verification failed for session: {}", session_id);
+        }
+
+        Ok(Response::new(VerifyProofResponse {
+            verified,
+            message: if verified {
+                "DLEQ proof verified successfully!".to_string()
+            } else {
+                "DLEQ proof verification failed!".to_string()
+            },
+        }))
+    }
+
+    async fn get_oprf_public_key(
+        &self,
+        request: Request<OprfPublicKeyRequest>,
+    ) -> Result<Response<OprfPublicKey>, Status> {
+        let group = oprf_group(request.get_ref().group)?;
+        let check = OprfKeyCheck {
+            key: self.oprf_key.clone(),
+        };
+        let public_key = run_in_group(group, None, check).await?;
+        Ok(Response::new(OprfPublicKey {
+            group: GroupKind::from(group).into(),
+            public_key,
+        }))
+    }
+
+    // Evaluates blinded inputs under the server's key, for token issuance and the like. The server
+    // never sees the inputs; the proofs let the client check each evaluation against the public key.
+    async fn evaluate_oprf(
+        &self,
+        request: Request<OprfEvaluationRequest>,
+    ) -> Result<Response<OprfEvaluation>, Status> {
+        let req = request.into_inner();
+        let group = oprf_group(req.group)?;
+        if req.blinded_elements.is_empty() {
+            return Err(Status::invalid_argument("No blinded elements"));
+        }
+        if req.blinded_elements.len() > self.max_statements {
+            return Err(Status::invalid_argument(format!(
+                "At most {} blinded elements per request",
+                self.max_statements
+            )));
+        }
+        let count = req.blinded_elements.len();
+        let check = OprfEvaluationCheck {
+            key: self.oprf_key.clone(),
+            request: req,
+        };
+        let evaluations = run_in_group(group, None, check).await?;
+        info!("Evaluated {} blinded OPRF inputs over {}", count, group);
+        Ok(Response::new(OprfEvaluation { evaluations }))
+    }
+
+    // Checks that a Diffie-Hellman share and the value derived from it use the same exponent, so the
+    // server can act as the verifier in an authenticated key exchange
+    async fn verify_dh_share(
+        &self,
+        request: Request<DhShareRequest>,
+    ) -> Result<Response<VerifyProofResponse>, Status> {
+        let lockout = lockout_keys(&request, &request.get_ref().session_id);
+        self.check_lockout(&lockout)?;
+        let req = request.into_inner();
+        let session_id = parse_session_id(&req.session_id)?;
+        let session = self.load_session(&session_id)?;
+        check_anonymous(&session)?;
+        let proof = req.proof.ok_or_else(|| Status::invalid_argument("Missing proof"))?;
+
+        let check = DhShareCheck {
+            session_id: session_id.to_string(),
+            proof,
+        };
+        let verified = check_in_group(session, check).await?;
+
+        self.record_outcome(&lockout, verified);
+        if verified {
+            info!("DH share proof verified for session: {}", session_id);
+            self.finish_session(&session_id);
+        } else {
+            info!("DH share proof verification failed for session: {}", session_id);
+        }
+
+        Ok(Response::new(VerifyProofResponse {
+            verified,
+            message: if verified {
+                "DH share proof verified successfully!".to_string()
+            } else {
+                "DH share proof verification failed!".to_string()
+            },
+        }))
+    }
+
+    // Checks that a Pedersen commitment opens to a value in [0, 2^bits), over a modp session whose
+    // parameters carry the second generator h
+    async fn verify_range_proof(
+        &self,
+        request: Request<RangeProofRequest>,
+    ) -> Result<Response<VerifyProofResponse>, Status> {
+        let lockout = lockout_keys(&request, &request.get_ref().session_id);
+        self.check_lockout(&lockout)?;
+        let req = request.into_inner();
+        let session_id = parse_session_id(&req.session_id)?;
+        let (session, params) = self.load_modp_session(&session_id)?;
+        check_anonymous(&session)?;
+        let (commitment, bits, proof) =
+            decode_range(req.proof.as_ref().ok_or_else(|| Status::invalid_argument("Missing proof"))?)?;
+
+        let pedersen = Pedersen::new(params)
+            .ok_or_else(|| Status::failed_precondition("Session parameters have no second generator"))?;
+        if !pedersen.params.is_element(&commitment.c) {
+            return Err(Status::invalid_argument("Commitment must be an element of the group"));
+        }
+
+        let id = session_id.to_string();
+        let verified = run_blocking("verify_range_proof", move || {
+            range::verify_for_session(&pedersen, &id, &commitment, bits, &proof)
+        })
+        .await?;
+
+        self.record_outcome(&lockout, verified);
+        if verified {
+            info!("Range proof verified for session: {}", session_id);
+            self.finish_session(&session_id);
+        } else {
+            info!("Range proof verification failed for session: {}", session_id);
+        }
+
+        Ok(Response::new(VerifyProofResponse {
+            verified,
+            message: if verified {
+                "Range proof verified successfully!".to_string()
+            } else {
+                "Range proof verification failed!".to_string()
+            },
+        }))
+    }
+
+    async fn get_session_transcript(
+        &self,
+        request: Request<TranscriptRequest>,
+    ) -> Result<Response<SignedTranscript>, Status> {
+        let session_id = parse_session_id(&request.into_inner().session_id)?;
+        // once the session has expired, the archive may still hold its transcript
+        let (session, params) = match self.load_modp_session(&session_id) {
+            Err(status) if status.code() == Code::NotFound => {
+                let archived = self.transcripts.load(&session_id).map_err(|e| {
+                    error!("Failed to read the archived transcript of session {}: {}", session_id, e);
+                    Status::internal("Transcript archive unavailable")
+                })?;
+                return archived.map(Response::new).ok_or(status);
+            }
+            loaded => loaded?,
+        };
+
+        let SessionState::Completed { exchange, outcome } = &session.state else {
+            return Err(Status::failed_precondition("No Chaum-Pedersen verification for this session yet"));
+        };
+
+        let transcript = evidence::transcript(
+            &session_id.to_string(),
+            &params,
+            &exchange.commitment,
+            &exchange.challenge_values,
+            &exchange.challenge,
+            outcome,
+        );
+        Ok(Response::new(self.signer.sign(&transcript)))
+    }
+
+    // Cancelling twice is fine; the tombstone keeps the session's creation time, so it expires on schedule
+    async fn cancel_session(
+        &self,
+        request: Request<CancelRequest>,
+    ) -> Result<Response<CancelResponse>, Status> {
+        let session_id = parse_session_id(&request.into_inner().session_id)?;
+        let session = self
+            .sessions
+            .get(&session_id)
+            .map_err(session_store_error)?
+            .filter(|session| !session.is_expired(self.session_ttl))
+            .ok_or_else(|| Status::not_found("Session not found"))?;
+        if !session.cancelled {
+            self.store_session(&session_id, &session.tombstone())?;
+            info!("Cancelled session: {}", session_id);
+        }
+        Ok(Response::new(CancelResponse {}))
+    }
+
+    // Reads the session without the expiry and cancellation checks, so those stages can be reported
+    async fn get_session_status(
+        &self,
+        request: Request<SessionStatusRequest>,
+    ) -> Result<Response<SessionStatus>, Status> {
+        let session_id = parse_session_id(&request.into_inner().session_id)?;
+        let session = self
+            .sessions
+            .get(&session_id)
+            .map_err(session_store_error)?
+            .ok_or_else(|| Status::not_found("Session not found"))?;
+
+        Ok(Response::new(SessionStatus {
+            stage: SessionStage::from(session.stage(self.session_ttl)).into(),
+            group: GroupKind::from(session.group).into(),
+            params_digest: session.params.as_ref().map(|params| params.digest().to_vec()).unwrap_or_default(),
+            created_at: session.created_at,
+            expires_at: session.created_at + self.session_ttl.as_secs(),
+            concluded_at: session.state.outcome().map(|outcome| outcome.at).unwrap_or_default(),
+        }))
+    }
+
+    async fn resume_session(
+        &self,
+        request: Request<ResumeRequest>,
+    ) -> Result<Response<ResumeResponse>, Status> {
+        let session_id = parse_session_id(&request.into_inner().session_id)?;
+        let (session, params) = self.load_modp_session(&session_id)?;
+
+        info!("Resuming session: {}", session_id);
+        Ok(Response::new(ResumeResponse {
+            params_digest: params.digest().to_vec(),
+            challenge: session
+                .state
+                .exchange()
+                .map(|exchange| exchange.challenge.to_bytes_be())
+                .unwrap_or_default(),
+            verified: session.state.outcome().is_some_and(|outcome| outcome.verified),
+        }))
+    }
+
+    async fn get_capabilities(
+        &self,
+        _request: Request<CapabilitiesRequest>,
+    ) -> Result<Response<Capabilities>, Status> {
+        let pooled_bits = match self.params.pool.target_size {
+            0 => Vec::new(),
+            _ => self.params.pool.bit_sizes.iter().map(|&bits| bits as u32).collect(),
+        };
+        Ok(Response::new(Capabilities {
+            min_bits: self.policy.min_bits,
+            max_bits: self.policy.max_bits,
+            pooled_bits,
+            groups: available_groups().map(|kind| GroupKind::from(kind).into()).collect(),
+            hash_functions: vec![HashFunction::HashSha256.into()],
+            max_decoding_message_size: self.message_sizes.decoding as u64,
+            max_encoding_message_size: self.message_sizes.encoding as u64,
+            interactive_challenges: true,
+            batching: true,
+            registration: true,
+            max_statements_per_session: self.max_statements as u32,
+            max_batched_proofs: self.max_batched as u32,
+            max_threshold_parties: self.max_parties,
+        }))
+    }
+
+    // Creates a registration, or, for an operator, replaces one that was revoked
+    async fn register_commitment(
+        &self,
+        request: Request<RegisterRequest>,
+    ) -> Result<Response<Registration>, Status> {
+        let operator = require_operator(&request).ok();
+        let (user_id, params, commitment, recovery) = self.requested_registration(request.into_inner())?;
+        let (registration, stored) = match self.registry.get(&user_id).map_err(registry_store_error)? {
+            None => {
+                let registration = UserRegistration {
+                    recovery,
+                    ..UserRegistration::new(params, commitment)
+                };
+                let stored = self.registry.insert(&user_id, &registration).map_err(registry_store_error)?;
+                (registration, stored)
+            }
+            Some(existing) if existing.revoked => {
+                // a revoked id stays taken until an operator hands it out again
+                if operator.is_none() {
+                    return Err(Status::permission_denied("Only an operator can re-register a revoked user"));
+                }
+                let registration = UserRegistration {
+                    version: existing.version + 1,
+                    recovery,
+                    ..UserRegistration::new(params, commitment)
+                };
+                let stored = self
+                    .registry
+                    .replace(&user_id, existing.version, &registration)
+                    .map_err(registry_store_error)?;
+                (registration, stored)
+            }
+            Some(_) => return Err(Status::already_exists("This user already has a registered commitment")),
+        };
+        if !stored {
+            return Err(concurrent_registration());
+        }
+
+        match operator {
+            Some(Operator(principal)) => info!("Operator {} registered a commitment for user: {}", principal, user_id),
+            None => info!("Registered a commitment for user: {}", user_id),
+        }
+        Ok(Response::new(registration_info(&user_id, &registration)))
+    }
+
+    // Operators only; a user rotates their own secret with RotateSecret
+    async fn rotate_commitment(
+        &self,
+        request: Request<RegisterRequest>,
+    ) -> Result<Response<Registration>, Status> {
+        let Operator(principal) = require_operator(&request)?;
+        let (user_id, params, commitment, recovery) = self.requested_registration(request.into_inner())?;
+        // the recovery commitment only changes with a proof, through RotateSecret or RecoverAccount
+        if recovery.is_some() {
+            return Err(Status::invalid_argument("The recovery commitment can only be changed with RotateSecret"));
+        }
+        let existing = self.active_registration(&user_id)?;
+        let registration = existing.rotated(params, commitment);
+        if !self
+            .registry
+            .replace(&user_id, existing.version, &registration)
+            .map_err(registry_store_error)?
+        {
+            return Err(concurrent_registration());
+        }
+
+        info!("Operator {} rotated the commitment of user: {}", principal, user_id);
+        Ok(Response::new(registration_info(&user_id, &registration)))
+    }
+
+    // Operators only. Revoking twice is not an error.
+    async fn revoke_commitment(
+        &self,
+        request: Request<UserRequest>,
+    ) -> Result<Response<Registration>, Status> {
+        let Operator(principal) = require_operator(&request)?;
+        let user_id = request.into_inner().user_id;
+        let existing = self
+            .registry
+            .get(&user_id)
+            .map_err(registry_store_error)?
+            .ok_or_else(|| Status::not_found("No commitment is registered for this user"))?;
+        if existing.revoked {
+            return Ok(Response::new(registration_info(&user_id, &existing)));
+        }
+        let registration = existing.revoked();
+        if !self
+            .registry
+            .replace(&user_id, existing.version, &registration)
+            .map_err(registry_store_error)?
+        {
+            return Err(concurrent_registration());
+        }
+
+        info!("Operator {} revoked the commitment of user: {}", principal, user_id);
+        Ok(Response::new(registration_info(&user_id, &registration)))
+    }
+
+    // VerifyProof for a registered user, which on success swaps in the new commitment. Unlike
+    // VerifyProof it cannot be repeated: the run must still be waiting for its response.
+    async fn rotate_secret(
+        &self,
+        request: Request<RotateSecretRequest>,
+    ) -> Result<Response<RotateSecretResponse>, Status> {
+        self.rebind_user(request, false).await
+    }
+
+    // RotateSecret for a recovery session, proving the escrowed recovery secret instead
+    async fn recover_account(
+        &self,
+        request: Request<RotateSecretRequest>,
+    ) -> Result<Response<RotateSecretResponse>, Status> {
+        self.rebind_user(request, true).await
+    }
+
+    async fn get_registration(
+        &self,
+        request: Request<UserRequest>,
+    ) -> Result<Response<Registration>, Status> {
+        let user_id = request.into_inner().user_id;
+        let registration = self
+            .registry
+            .get(&user_id)
+            .map_err(registry_store_error)?
+            .ok_or_else(|| Status::not_found("No commitment is registered for this user"))?;
+        Ok(Response::new(registration_info(&user_id, &registration)))
+    }
+}
+
+// Proto packages this server answers, oldest first
+const PROTOCOL_VERSIONS: [&str; 2] = ["zkp", "zkp.v2"];
+
+// zkp.v2 runs on the v1 handlers, so sessions, lockouts and the replay cache are shared between
+// versions and a session opened over one can be finished over the other
+#[tonic::async_trait]
+impl v2::chaum_pedersen_service_server::ChaumPedersenService for ChaumPedersenServer {
+    async fn get_server_capabilities(
+        &self,
+        _request: Request<v2::CapabilitiesRequest>,
+    ) -> Result<Response<v2::ServerCapabilities>, Status> {
+        Ok(Response::new(v2::ServerCapabilities {
+            versions: PROTOCOL_VERSIONS.iter().map(|version| version.to_string()).collect(),
+            groups: available_groups().map(|kind| v2::GroupKind::from(kind).into()).collect(),
+            hash_functions: vec![v2::HashFunction::HashSha256.into()],
+        }))
+    }
+
+    async fn initialize_protocol(
+        &self,
+        request: Request<v2::InitializeRequest>,
+    ) -> Result<Response<v2::Session>, Status> {
+        let response = ChaumPedersenService::initialize_protocol(self, request.map(Into::into)).await?;
+        Ok(response.map(|response| v2::Session {
+            session_id: response.session_id,
+            group: response.group,
+            params: response.params.map(Into::into),
+            challenge_mode: response.challenge_mode,
+            params_fingerprint: response.params_fingerprint,
+            commitment_origin: response.commitment_origin,
+            compressed_elements: response.compressed_elements,
+        }))
+    }
+
+    async fn send_commitment(
+        &self,
+        request: Request<v2::CommitmentRequest>,
+    ) -> Result<Response<v2::Challenge>, Status> {
+        let session_id = request.get_ref().session_id.clone();
+        let response = ChaumPedersenService::send_commitment(self, request.map(Into::into)).await?;
+        Ok(response.map(|response| v2::Challenge {
+            session_id,
+            challenge: response.challenge,
+            expires_in_secs: response.expires_in_secs,
+        }))
+    }
+
+    async fn verify_proof(
+        &self,
+        request: Request<v2::ResponseRequest>,
+    ) -> Result<Response<v2::Verdict>, Status> {
+        let session_id = request.get_ref().session_id.clone();
+        let response = ChaumPedersenService::verify_proof(self, request.map(Into::into)).await?;
+        Ok(response.map(|response| v2::Verdict {
+            session_id,
+            verified: response.verified,
+            message: response.message,
+        }))
+    }
+
+    async fn prove_one_shot(
+        &self,
+        request: Request<v2::ProofRequest>,
+    ) -> Result<Response<v2::Verdict>, Status> {
+        let session_id = request.get_ref().session_id.clone();
+        let response = ChaumPedersenService::prove_one_shot(self, request.map(Into::into)).await?;
+        Ok(response.map(|response| v2::Verdict {
+            session_id,
+            verified: response.verified,
+            message: response.message,
+        }))
+    }
+
+    async fn get_session_status(
+        &self,
+        request: Request<v2::SessionRequest>,
+    ) -> Result<Response<v2::SessionStatus>, Status> {
+        let session_id = request.get_ref().session_id.clone();
+        let response = ChaumPedersenService::get_session_status(self, request.map(Into::into)).await?;
+        Ok(response.map(|status| v2::SessionStatus {
+            session_id,
+            stage: status.stage,
+            group: status.group,
+            params_digest: status.params_digest,
+            created_at: status.created_at,
+            expires_at: status.expires_at,
+            concluded_at: status.concluded_at,
+        }))
+    }
+
+    async fn cancel_session(
+        &self,
+        request: Request<v2::SessionRequest>,
+    ) -> Result<Response<v2::CancelResponse>, Status> {
+        let session_id = request.get_ref().session_id.clone();
+        let response = ChaumPedersenService::cancel_session(self, request.map(Into::into)).await?;
+        Ok(response.map(|_| v2::CancelResponse { session_id }))
+    }
+}
+
+// Runs BigUint-heavy work on the blocking thread pool so it never stalls the async executor. The
+// work gets a span named after `phase` under the RPC's own.
+async fn run_blocking<T: Send + 'static>(
+    phase: &'static str,
+    work: impl FnOnce() -> T + Send + 'static,
+) -> Result<T, Status> {
+    let span = info_span!("crypto", otel.name = phase);
+    tokio::task::spawn_blocking(move || span.in_scope(work)).await.map_err(|e| {
+        error!("Blocking task failed: {}", e);
+        Status::internal("Internal error")
+    })
+}
+
+fn threshold_error(e: ThresholdError) -> Status {
+    match e {
+        ThresholdError::DuplicateCommitment(_)
+        | ThresholdError::DuplicateResponse(_)
+        | ThresholdError::CommitmentsPending { .. } => Status::failed_precondition(e.to_string()),
+        _ => Status::invalid_argument(e.to_string()),
+    }
+}
+
+fn registry_store_error(e: std::io::Error) -> Status {
+    error!("Registry store error: {}", e);
+    Status::internal("Registry store unavailable")
+}
+
+fn concurrent_registration() -> Status {
+    Status::aborted("The registration changed concurrently, try again")
+}
+
+fn registration_info(user_id: &str, registration: &UserRegistration) -> Registration {
+    Registration {
+        user_id: user_id.to_string(),
+        commitment: Some(zkp::Commitment::from(&registration.commitment)),
+        params_digest: registration.params.digest().to_vec(),
+        created_at: registration.created_at,
+        updated_at: registration.updated_at,
+        revoked: registration.revoked,
+        has_recovery: registration.recovery.is_some(),
+    }
+}
+
+fn session_store_error(e: std::io::Error) -> Status {
+    error!("Session store error: {}", e);
+    Status::internal("Session store unavailable")
+}
@@ -0,0 +1,499 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use num_bigint::BigUint;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use subtle::ConstantTimeEq;
+
+use crate::chaum_pedersen::{wire, Commitment, ProofChallenge, PublicParameters};
+use crate::group::GroupKind;
+use crate::schnorr::{SchnorrCommitment, SchnorrStatement};
+use crate::threshold::Coordinator;
+
+// A session's handle: 128 bits from the OS CSPRNG, shown as 32 lowercase hex digits. Comparison
+// is constant time, since knowing an ID is what lets a caller drive the session.
+#[derive(Debug, Clone, Copy, Eq)]
+pub struct SessionId([u8; 16]);
+
+impl SessionId {
+    pub fn generate() -> Self {
+        let mut bytes = [0u8; 16];
+        OsRng.fill_bytes(&mut bytes);
+        Self(bytes)
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 16] {
+        &self.0
+    }
+}
+
+impl PartialEq for SessionId {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.ct_eq(&other.0).into()
+    }
+}
+
+impl Hash for SessionId {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state);
+    }
+}
+
+impl fmt::Display for SessionId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&hex::encode(self.0))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidSessionId;
+
+impl fmt::Display for InvalidSessionId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a session ID is 32 lowercase hex digits")
+    }
+}
+
+impl std::error::Error for InvalidSessionId {}
+
+impl FromStr for SessionId {
+    type Err = InvalidSessionId;
+
+    fn from_str(s: &str) -> Result<Self, InvalidSessionId> {
+        // one spelling per ID, so the string form can key a store
+        if s.len() != 32 || !s.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f')) {
+            return Err(InvalidSessionId);
+        }
+        let mut bytes = [0u8; 16];
+        hex::decode_to_slice(s, &mut bytes).map_err(|_| InvalidSessionId)?;
+        Ok(Self(bytes))
+    }
+}
+
+pub(crate) fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+// Verifier-side state of one protocol run, from InitializeProtocol until the proof is checked
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Session {
+    pub created_at: u64,  // seconds since the Unix epoch, so it stays meaningful across restarts
+    pub params: Option<PublicParameters>,  // None for a session over an elliptic-curve group
+    #[serde(default)]
+    pub group: GroupKind,
+    #[serde(default)]
+    pub state: SessionState,  // the Chaum-Pedersen run of SendCommitment and VerifyProof
+    #[serde(default)]
+    pub schnorr: Option<SchnorrExchange>,
+    #[serde(default)]
+    pub statements: Vec<PendingStatement>,  // multi-proof statements, checked together by FinalizeSession
+    #[serde(default)]
+    pub threshold: Option<Coordinator>,  // partial commitments and responses of a jointly produced proof
+    #[serde(default)]
+    pub challenge_mode: ChallengeMode,
+    #[serde(default)]
+    pub cancelled: bool,
+    #[serde(default)]
+    pub user_id: Option<String>,  // set when the run is checked against the user's registered commitment
+    #[serde(default)]
+    pub recovery: bool,  // the user's recovery commitment rather than the primary one
+    #[serde(default)]
+    pub compressed_elements: bool,  // commitments and (y1, y2) arrive as representatives in [1, q]
+}
+
+// Where a session's Chaum-Pedersen run stands. Each RPC accepts only the states it can follow
+// from, so e.g. a response before any challenge is rejected rather than checked against nothing.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub enum SessionState {
+    #[default]
+    AwaitingCommitment,
+    AwaitingResponse(Exchange),
+    // Terminal; kept so the transcript can be fetched and a repeated response gets the same verdict
+    Completed { exchange: Exchange, outcome: Outcome },
+}
+
+impl SessionState {
+    // The exchange a challenge was issued in, if there is one yet
+    pub fn exchange(&self) -> Option<&Exchange> {
+        match self {
+            SessionState::AwaitingCommitment => None,
+            SessionState::AwaitingResponse(exchange) | SessionState::Completed { exchange, .. } => Some(exchange),
+        }
+    }
+
+    pub fn outcome(&self) -> Option<&Outcome> {
+        match self {
+            SessionState::Completed { outcome, .. } => Some(outcome),
+            _ => None,
+        }
+    }
+}
+
+// The prover's commitment and (y1, y2), and the challenge the verifier answered them with
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Exchange {
+    pub commitment: Commitment,
+    pub challenge_values: ProofChallenge,
+    #[serde(with = "wire::biguint")]
+    pub challenge: BigUint,
+    #[serde(default)]
+    pub server_randomness: Option<Vec<u8>>,  // drawn for the challenge in interactive mode
+    #[serde(default = "now_secs")]
+    pub issued_at: u64,  // seconds since the Unix epoch on the server's clock; exchanges stored before it restart now
+}
+
+// Metadata key the server sets on the DEADLINE_EXCEEDED of an expired challenge
+pub const CHALLENGE_EXPIRED: &str = "challenge-expired";
+
+// Only the server's clock is read, when the challenge is issued and when it is answered, so a
+// client clock that is off changes nothing, and a server clock stepping back only extends the window
+impl Exchange {
+    // Whole seconds left to answer the challenge, as sent with it
+    pub fn remaining_secs(&self, ttl: Duration) -> u64 {
+        ttl.as_secs().saturating_sub(now_secs().saturating_sub(self.issued_at))
+    }
+
+    // Like session expiry, counts whole seconds past the TTL
+    pub fn is_expired(&self, ttl: Duration) -> bool {
+        now_secs().saturating_sub(self.issued_at) > ttl.as_secs()
+    }
+}
+
+// A Schnorr run: the prover's statement and commitment and the challenge they were given
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchnorrExchange {
+    pub statement: SchnorrStatement,
+    pub commitment: SchnorrCommitment,
+    #[serde(with = "wire::biguint")]
+    pub challenge: BigUint,
+}
+
+// How the verifier derives challenges for a session
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ChallengeMode {
+    #[default]
+    FiatShamir,   // a hash of the prover's values, which the prover can compute itself
+    Interactive,  // also hashes fresh server randomness, so the prover learns it only from the verifier
+}
+
+impl fmt::Display for ChallengeMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChallengeMode::FiatShamir => f.write_str("Fiat-Shamir"),
+            ChallengeMode::Interactive => f.write_str("interactive"),
+        }
+    }
+}
+
+// Where the commitment a session's run is checked against comes from. Either way the server only
+// ever sees the commitment, never the secrets behind it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum CommitmentOrigin {
+    #[default]
+    Client,      // sent by the prover with SendCommitment and kept in the session
+    Registered,  // the commitment the session's user registered
+}
+
+impl fmt::Display for CommitmentOrigin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CommitmentOrigin::Client => f.write_str("client"),
+            CommitmentOrigin::Registered => f.write_str("registered"),
+        }
+    }
+}
+
+// One statement of a multi-proof session: the prover's commitment and (y1, y2), the challenge they
+// were given and, once answered, their response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingStatement {
+    pub commitment: Commitment,
+    pub challenge_values: ProofChallenge,
+    #[serde(with = "wire::biguint")]
+    pub challenge: BigUint,
+    #[serde(default, with = "optional_biguint")]
+    pub z: Option<BigUint>,
+    #[serde(default)]
+    pub server_randomness: Option<Vec<u8>>,
+}
+
+// The response the prover sent and what the verifier made of it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Outcome {
+    #[serde(with = "wire::biguint")]
+    pub z: BigUint,
+    pub verified: bool,
+    pub at: u64,  // seconds since the Unix epoch
+}
+
+impl Outcome {
+    pub fn new(z: BigUint, verified: bool) -> Self {
+        Self {
+            z,
+            verified,
+            at: now_secs(),
+        }
+    }
+}
+
+impl Session {
+    pub fn new(params: PublicParameters) -> Self {
+        Self {
+            params: Some(params),
+            ..Self::over(GroupKind::Modp)
+        }
+    }
+
+    // A session over a fixed group that needs no generated parameters; `new` opens modp sessions
+    pub fn over(group: GroupKind) -> Self {
+        Self {
+            created_at: now_secs(),
+            params: None,
+            group,
+            state: SessionState::AwaitingCommitment,
+            schnorr: None,
+            statements: Vec::new(),
+            threshold: None,
+            challenge_mode: ChallengeMode::FiatShamir,
+            cancelled: false,
+            user_id: None,
+            recovery: false,
+            compressed_elements: false,
+        }
+    }
+
+    // What is kept of a cancelled session: only enough to tell later RPCs so, until it expires
+    pub fn tombstone(&self) -> Self {
+        Self {
+            created_at: self.created_at,
+            cancelled: true,
+            ..Self::over(self.group)
+        }
+    }
+
+    pub fn with_challenge_mode(mut self, mode: ChallengeMode) -> Self {
+        self.challenge_mode = mode;
+        self
+    }
+
+    // Only modp sessions compress; curve groups keep their own point encodings
+    pub fn with_compressed_elements(mut self, compressed: bool) -> Self {
+        self.compressed_elements = compressed && self.params.is_some();
+        self
+    }
+
+    pub fn for_user(mut self, user_id: String) -> Self {
+        self.user_id = Some(user_id);
+        self
+    }
+
+    pub fn commitment_origin(&self) -> CommitmentOrigin {
+        match self.user_id {
+            Some(_) => CommitmentOrigin::Registered,
+            None => CommitmentOrigin::Client,
+        }
+    }
+
+    pub fn is_expired(&self, ttl: Duration) -> bool {
+        now_secs().saturating_sub(self.created_at) > ttl.as_secs()
+    }
+
+    pub fn stage(&self, ttl: Duration) -> Stage {
+        if self.cancelled {
+            return Stage::Cancelled;
+        }
+        if self.is_expired(ttl) {
+            return Stage::Expired;
+        }
+        match &self.state {
+            SessionState::Completed { outcome, .. } if outcome.verified => Stage::Verified,
+            SessionState::Completed { .. } => Stage::Failed,
+            SessionState::AwaitingResponse(_) => Stage::ChallengeIssued,
+            SessionState::AwaitingCommitment if self.schnorr.is_some() || !self.statements.is_empty() => {
+                Stage::ChallengeIssued
+            }
+            SessionState::AwaitingCommitment => Stage::Initialized,
+        }
+    }
+}
+
+// How far a session has come, as reported by GetSessionStatus
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    Initialized,
+    ChallengeIssued,
+    Verified,
+    Failed,
+    Expired,
+    Cancelled,
+}
+
+mod optional_biguint {
+    use num_bigint::BigUint;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    #[derive(Serialize, Deserialize)]
+    struct Wrapped(#[serde(with = "super::wire::biguint")] BigUint);
+
+    pub fn serialize<S: Serializer>(value: &Option<BigUint>, serializer: S) -> Result<S::Ok, S::Error> {
+        value.clone().map(Wrapped).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<BigUint>, D::Error> {
+        Ok(Option::<Wrapped>::deserialize(deserializer)?.map(|w| w.0))
+    }
+}
+
+// Where sessions live between RPCs. A durable store keeps in-flight protocols across restarts.
+pub trait SessionStore: Send + Sync + fmt::Debug {
+    fn get(&self, id: &SessionId) -> io::Result<Option<Session>>;
+    fn put(&self, id: &SessionId, session: &Session) -> io::Result<()>;
+    fn remove(&self, id: &SessionId) -> io::Result<Option<Session>>;
+    // Drops sessions older than `ttl`, returning how many were removed
+    fn sweep(&self, ttl: Duration) -> io::Result<usize>;
+    fn len(&self) -> io::Result<usize>;
+    fn is_empty(&self) -> io::Result<bool> {
+        Ok(self.len()? == 0)
+    }
+    fn clear(&self) -> io::Result<()>;
+    fn is_durable(&self) -> bool;
+}
+
+#[derive(Debug, Default)]
+pub struct MemorySessionStore {
+    sessions: Mutex<HashMap<SessionId, Session>>,
+}
+
+impl MemorySessionStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl SessionStore for MemorySessionStore {
+    fn get(&self, id: &SessionId) -> io::Result<Option<Session>> {
+        Ok(self.sessions.lock().unwrap().get(id).cloned())
+    }
+
+    fn put(&self, id: &SessionId, session: &Session) -> io::Result<()> {
+        self.sessions.lock().unwrap().insert(*id, session.clone());
+        Ok(())
+    }
+
+    fn remove(&self, id: &SessionId) -> io::Result<Option<Session>> {
+        Ok(self.sessions.lock().unwrap().remove(id))
+    }
+
+    fn sweep(&self, ttl: Duration) -> io::Result<usize> {
+        let mut sessions = self.sessions.lock().unwrap();
+        let before = sessions.len();
+        sessions.retain(|_, session| !session.is_expired(ttl));
+        Ok(before - sessions.len())
+    }
+
+    fn len(&self) -> io::Result<usize> {
+        Ok(self.sessions.lock().unwrap().len())
+    }
+
+    fn clear(&self) -> io::Result<()> {
+        self.sessions.lock().unwrap().clear();
+        Ok(())
+    }
+
+    fn is_durable(&self) -> bool {
+        false
+    }
+}
+
+// Sessions as JSON rows in SQLite, so a restarted server can finish protocols begun before it stopped
+#[cfg(feature = "session-sqlite")]
+#[derive(Debug)]
+pub struct SqliteSessionStore {
+    conn: Mutex<rusqlite::Connection>,
+}
+
+#[cfg(feature = "session-sqlite")]
+impl SqliteSessionStore {
+    pub fn open(path: impl AsRef<std::path::Path>) -> rusqlite::Result<Self> {
+        let conn = rusqlite::Connection::open(path)?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS sessions (
+                id TEXT PRIMARY KEY,
+                created_at INTEGER NOT NULL,
+                state TEXT NOT NULL
+            )",
+        )?;
+        Ok(Self { conn: Mutex::new(conn) })
+    }
+}
+
+#[cfg(feature = "session-sqlite")]
+impl SessionStore for SqliteSessionStore {
+    fn get(&self, id: &SessionId) -> io::Result<Option<Session>> {
+        use rusqlite::OptionalExtension;
+
+        let conn = self.conn.lock().unwrap();
+        let state: Option<String> = conn
+            .query_row("SELECT state FROM sessions WHERE id = ?1", [id.to_string()], |row| row.get(0))
+            .optional()
+            .map_err(io::Error::other)?;
+        state
+            .map(|state| serde_json::from_str(&state).map_err(io::Error::from))
+            .transpose()
+    }
+
+    fn put(&self, id: &SessionId, session: &Session) -> io::Result<()> {
+        let state = serde_json::to_string(session)?;
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO sessions (id, created_at, state) VALUES (?1, ?2, ?3)",
+            rusqlite::params![id.to_string(), session.created_at as i64, state],
+        )
+        .map_err(io::Error::other)?;
+        Ok(())
+    }
+
+    fn remove(&self, id: &SessionId) -> io::Result<Option<Session>> {
+        let session = self.get(id)?;
+        if session.is_some() {
+            let conn = self.conn.lock().unwrap();
+            conn.execute("DELETE FROM sessions WHERE id = ?1", [id.to_string()])
+                .map_err(io::Error::other)?;
+        }
+        Ok(session)
+    }
+
+    fn sweep(&self, ttl: Duration) -> io::Result<usize> {
+        let cutoff = now_secs().saturating_sub(ttl.as_secs()) as i64;
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM sessions WHERE created_at < ?1", [cutoff])
+            .map_err(io::Error::other)
+    }
+
+    fn len(&self) -> io::Result<usize> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row("SELECT COUNT(*) FROM sessions", [], |row| row.get::<_, i64>(0))
+            .map(|count| count as usize)
+            .map_err(io::Error::other)
+    }
+
+    fn clear(&self) -> io::Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM sessions", []).map_err(io::Error::other)?;
+        Ok(())
+    }
+
+    fn is_durable(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,148 @@
+use core::fmt;
+
+use num_bigint::BigUint;
+#[cfg(feature = "std")]
+use rand::rngs::OsRng;
+
+use crate::chaum_pedersen::{ct_eq, SecureRng, Transcript};
+
+// A three-move public-coin proof of knowledge: the prover commits, the verifier sends a random
+// challenge, the prover responds. Implementations only describe the moves; the Fiat-Shamir
+// transform below and the gRPC session flow work with any of them.
+pub trait SigmaProtocol {
+    type Statement;
+    type Witness;
+    type Commitment: Clone + fmt::Debug;
+    type Nonce;  // prover randomness behind the commitment, never sent
+    type Response: Clone + fmt::Debug;
+
+    // Transcript label for Fiat-Shamir challenges, unique per protocol
+    const LABEL: &'static [u8];
+
+    // Challenges are reduced modulo this value, normally the group order
+    fn challenge_modulus(&self) -> &BigUint;
+
+    // The nonce must come from `rng`, so a seeded generator reproduces the whole proof
+    fn commit(
+        &self,
+        statement: &Self::Statement,
+        witness: &Self::Witness,
+        rng: &mut dyn SecureRng,
+    ) -> (Self::Commitment, Self::Nonce);
+
+    fn respond(&self, witness: &Self::Witness, nonce: Self::Nonce, challenge: &BigUint) -> Self::Response;
+
+    fn verify(
+        &self,
+        statement: &Self::Statement,
+        commitment: &Self::Commitment,
+        challenge: &BigUint,
+        response: &Self::Response,
+    ) -> bool;
+
+    // Absorbs every public value the challenge must be bound to, statement before commitment
+    fn append_transcript(
+        &self,
+        transcript: &mut Transcript,
+        statement: &Self::Statement,
+        commitment: &Self::Commitment,
+    );
+}
+
+// Non-interactive proof produced by the Fiat-Shamir transform
+pub struct SigmaProof<P: SigmaProtocol> {
+    pub commitment: P::Commitment,
+    pub challenge: BigUint,
+    pub response: P::Response,
+}
+
+impl<P: SigmaProtocol> Clone for SigmaProof<P> {
+    fn clone(&self) -> Self {
+        Self {
+            commitment: self.commitment.clone(),
+            challenge: self.challenge.clone(),
+            response: self.response.clone(),
+        }
+    }
+}
+
+impl<P: SigmaProtocol> fmt::Debug for SigmaProof<P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SigmaProof")
+            .field("commitment", &self.commitment)
+            .field("challenge", &self.challenge)
+            .field("response", &self.response)
+            .finish()
+    }
+}
+
+pub fn challenge_for<P: SigmaProtocol>(
+    protocol: &P,
+    statement: &P::Statement,
+    commitment: &P::Commitment,
+) -> BigUint {
+    challenge_with(protocol, &mut Transcript::new(P::LABEL), statement, commitment)
+}
+
+// Challenge drawn from a caller-supplied transcript, for proofs composed with other messages
+pub fn challenge_with<P: SigmaProtocol>(
+    protocol: &P,
+    transcript: &mut Transcript,
+    statement: &P::Statement,
+    commitment: &P::Commitment,
+) -> BigUint {
+    transcript.append_message(b"protocol", P::LABEL);
+    protocol.append_transcript(transcript, statement, commitment);
+    transcript.challenge_scalar(b"challenge", protocol.challenge_modulus())
+}
+
+#[cfg(feature = "std")]
+pub fn prove<P: SigmaProtocol>(protocol: &P, statement: &P::Statement, witness: &P::Witness) -> SigmaProof<P> {
+    prove_with_transcript(protocol, &mut Transcript::new(P::LABEL), statement, witness)
+}
+
+#[cfg(feature = "std")]
+pub fn prove_with_transcript<P: SigmaProtocol>(
+    protocol: &P,
+    transcript: &mut Transcript,
+    statement: &P::Statement,
+    witness: &P::Witness,
+) -> SigmaProof<P> {
+    prove_with_rng(protocol, transcript, statement, witness, &mut OsRng)
+}
+
+pub fn prove_with_rng<P: SigmaProtocol>(
+    protocol: &P,
+    transcript: &mut Transcript,
+    statement: &P::Statement,
+    witness: &P::Witness,
+    rng: &mut dyn SecureRng,
+) -> SigmaProof<P> {
+    let (commitment, nonce) = protocol.commit(statement, witness, rng);
+    let challenge = challenge_with(protocol, transcript, statement, &commitment);
+    let response = protocol.respond(witness, nonce, &challenge);
+
+    SigmaProof {
+        commitment,
+        challenge,
+        response,
+    }
+}
+
+pub fn verify<P: SigmaProtocol>(protocol: &P, statement: &P::Statement, proof: &SigmaProof<P>) -> bool {
+    verify_with_transcript(protocol, &mut Transcript::new(P::LABEL), statement, proof)
+}
+
+// The verifier's transcript must have absorbed the same messages as the prover's before this proof
+pub fn verify_with_transcript<P: SigmaProtocol>(
+    protocol: &P,
+    transcript: &mut Transcript,
+    statement: &P::Statement,
+    proof: &SigmaProof<P>,
+) -> bool {
+    let expected = challenge_with(protocol, transcript, statement, &proof.commitment);
+    if !ct_eq(&expected, &proof.challenge, protocol.challenge_modulus()) {
+        return false;
+    }
+    protocol.verify(statement, &proof.commitment, &proof.challenge, &proof.response)
+}
@@ -0,0 +1,41 @@
+use num_bigint::RandBigInt;
+use rand::rngs::OsRng;
+
+use crate::chaum_pedersen::{
+    modpow, ChaumPedersen, Commitment, ProofChallenge, ProofResponse, PublicParameters, SecureRng,
+};
+use crate::sigma::SigmaProof;
+
+// Honest-verifier simulator for Chaum-Pedersen. It picks the challenge s and response z first and
+// solves for the prover's move, y1 = g^z a1^-s and y2 = b1^z c1^-s, so it needs no witness. Real
+// transcripts have the same distribution, which is what makes the protocol zero-knowledge. The
+// challenge is not derived from a transcript, so a simulated proof passes `SigmaProtocol::verify`
+// but never `sigma::verify`.
+pub struct Simulator;
+
+impl Simulator {
+    pub fn simulate(params: &PublicParameters, statement: &Commitment) -> SigmaProof<ChaumPedersen> {
+        Self::simulate_with_rng(params, statement, &mut OsRng)
+    }
+
+    pub fn simulate_with_rng<R: SecureRng + ?Sized>(
+        params: &PublicParameters,
+        statement: &Commitment,
+        rng: &mut R,
+    ) -> SigmaProof<ChaumPedersen> {
+        let (p, q) = (params.p(), params.q());
+        let challenge = rng.gen_biguint_below(q);
+        let z = rng.gen_biguint_below(q);
+
+        // x^-s = x^(p - 1 - s) for any x coprime to p
+        let negated = p - 1u32 - &challenge;
+        let y1 = modpow(params.g(), &z, p) * modpow(&statement.a1, &negated, p) % p;
+        let y2 = modpow(&statement.b1, &z, p) * modpow(&statement.c1, &negated, p) % p;
+
+        SigmaProof {
+            commitment: ProofChallenge { y1, y2 },
+            challenge,
+            response: ProofResponse { z },
+        }
+    }
+}
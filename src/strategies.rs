@@ -0,0 +1,101 @@
+use num_bigint::BigUint;
+use proptest::prelude::*;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use crate::chaum_pedersen::{ProofOptions, Prover, PublicParameters, ZKProof};
+
+// proptest strategies for property tests against this crate
+
+// Sizes small enough to generate a group per case; far too small for anything but tests
+pub const SMALL_BITS: [u64; 3] = [32, 48, 64];
+
+// Parameters derived from a random seed, so a failing case is reproduced from the seed alone
+pub fn params() -> impl Strategy<Value = PublicParameters> {
+    (prop::sample::select(SMALL_BITS.to_vec()), any::<[u8; 16]>())
+        .prop_map(|(bits, seed)| PublicParameters::from_seed(bits, &seed))
+}
+
+// Scalar in [1, q); the extra bytes keep the reduction close to uniform
+pub fn secret(q: &BigUint) -> impl Strategy<Value = BigUint> + use<> {
+    let q_minus_1 = q - 1u32;
+    let width = q.to_bytes_be().len() + 8;
+    prop::collection::vec(any::<u8>(), width)
+        .prop_map(move |bytes| BigUint::from_bytes_be(&bytes) % &q_minus_1 + 1u32)
+}
+
+pub fn prover() -> impl Strategy<Value = Prover> {
+    params()
+        .prop_flat_map(|params| {
+            let q = params.q().clone();
+            (Just(params), secret(&q), secret(&q))
+        })
+        .prop_map(|(params, secret_a, secret_b)| Prover {
+            params,
+            secret_a,
+            secret_b,
+            options: ProofOptions::default(),
+        })
+}
+
+// A prover and a valid proof from it, with the nonce drawn from a seeded generator
+pub fn proof() -> impl Strategy<Value = (Prover, ZKProof)> {
+    (prover(), any::<u64>()).prop_map(|(prover, seed)| {
+        let proof = prover.create_proof_with_rng(&mut StdRng::seed_from_u64(seed));
+        (prover, proof)
+    })
+}
+
+// Fields of a ZKProof that a mutation can target
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProofField {
+    A1,
+    B1,
+    C1,
+    Y1,
+    Y2,
+    Z,
+    ChallengeHash,
+}
+
+impl ProofField {
+    pub const ALL: [ProofField; 7] = [
+        ProofField::A1,
+        ProofField::B1,
+        ProofField::C1,
+        ProofField::Y1,
+        ProofField::Y2,
+        ProofField::Z,
+        ProofField::ChallengeHash,
+    ];
+
+    // Adds `delta` to the field, mod p for group elements and mod q for scalars. The field changes
+    // unless `delta` is a multiple of that modulus.
+    pub fn mutate(self, proof: &mut ZKProof, delta: &BigUint, params: &PublicParameters) {
+        let (value, modulus) = match self {
+            ProofField::A1 => (&mut proof.commitment.a1, params.p()),
+            ProofField::B1 => (&mut proof.commitment.b1, params.p()),
+            ProofField::C1 => (&mut proof.commitment.c1, params.p()),
+            ProofField::Y1 => (&mut proof.challenge.y1, params.p()),
+            ProofField::Y2 => (&mut proof.challenge.y2, params.p()),
+            ProofField::Z => (&mut proof.response.z, params.q()),
+            ProofField::ChallengeHash => (&mut proof.challenge_hash, params.q()),
+        };
+        *value = (&*value + delta) % modulus;
+    }
+}
+
+// A valid proof with one field shifted by a nonzero amount, returned with the field that changed
+pub fn mutated_proof() -> impl Strategy<Value = (Prover, ZKProof, ProofField)> {
+    (proof(), prop::sample::select(ProofField::ALL.to_vec()), any::<u64>()).prop_map(
+        |((prover, mut proof), field, delta)| {
+            let modulus = match field {
+                ProofField::Z | ProofField::ChallengeHash => prover.params.q(),
+                _ => prover.params.p(),
+            };
+            let delta = BigUint::from(delta) % (modulus - 1u32) + 1u32;
+            field.mutate(&mut proof, &delta, &prover.params);
+            (prover, proof, field)
+        },
+    )
+}
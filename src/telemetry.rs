@@ -0,0 +1,72 @@
+use opentelemetry::propagation::{Extractor, Injector};
+use opentelemetry::trace::TraceError;
+use opentelemetry::{global, KeyValue};
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::{runtime, trace as sdktrace, Resource};
+use tonic::codegen::http::HeaderMap;
+use tonic::metadata::{MetadataKey, MetadataMap, MetadataValue};
+use tracing::Subscriber;
+use tracing_opentelemetry::{OpenTelemetryLayer, OpenTelemetrySpanExt};
+use tracing_subscriber::registry::LookupSpan;
+
+// OpenTelemetry export for tracing spans. Trace context travels between client and server as W3C
+// `traceparent` metadata, so one prove/verify flow shows up as a single trace.
+
+// Layer exporting spans over OTLP/gRPC to `endpoint`, e.g. http://localhost:4317 for a Jaeger
+// collector. Call `shutdown` before exiting so buffered spans are flushed.
+pub fn layer<S>(
+    service_name: &'static str,
+    endpoint: &str,
+) -> Result<OpenTelemetryLayer<S, sdktrace::Tracer>, TraceError>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    global::set_text_map_propagator(TraceContextPropagator::new());
+    let tracer = opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(opentelemetry_otlp::new_exporter().tonic().with_endpoint(endpoint))
+        .with_trace_config(
+            sdktrace::config().with_resource(Resource::new([KeyValue::new("service.name", service_name)])),
+        )
+        .install_batch(runtime::Tokio)?;
+    Ok(tracing_opentelemetry::layer().with_tracer(tracer))
+}
+
+pub fn shutdown() {
+    global::shutdown_tracer_provider();
+}
+
+struct MetadataInjector<'a>(&'a mut MetadataMap);
+
+impl Injector for MetadataInjector<'_> {
+    fn set(&mut self, key: &str, value: String) {
+        if let (Ok(key), Ok(value)) = (MetadataKey::from_bytes(key.as_bytes()), MetadataValue::try_from(value)) {
+            self.0.insert(key, value);
+        }
+    }
+}
+
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|key| key.as_str()).collect()
+    }
+}
+
+// Adds the current span's trace context to an outgoing request
+pub fn inject_context(metadata: &mut MetadataMap) {
+    let context = tracing::Span::current().context();
+    global::get_text_map_propagator(|propagator| propagator.inject_context(&context, &mut MetadataInjector(metadata)));
+}
+
+// Makes `span` a child of the trace the caller sent in `headers`, if any
+pub fn set_remote_parent(span: &tracing::Span, headers: &HeaderMap) {
+    let parent = global::get_text_map_propagator(|propagator| propagator.extract(&HeaderExtractor(headers)));
+    span.set_parent(parent);
+}
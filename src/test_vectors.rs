@@ -0,0 +1,142 @@
+use num_bigint::BigUint;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde::{Deserialize, Serialize};
+
+use crate::chaum_pedersen::{
+    generate_session_challenge, wire, Commitment, ProofChallenge, ProofResponse, Prover, PublicParameters, ZKProof,
+};
+
+// Fixed inputs and the outputs this crate computes from them, for checking another implementation
+// against it. Parameters are derived from public seeds and the secrets and nonces drawn from a
+// seeded generator, so `vectors()` gives the same set on every run. vectors/chaum_pedersen.json
+// holds it as `to_json` writes it; implementations in other languages read that file, and the
+// test suite fails if this module stops reproducing it.
+
+pub const VECTORS_VERSION: u32 = 3;
+
+// How one vector is produced
+#[derive(Debug, Clone, Copy)]
+pub struct Case {
+    pub name: &'static str,
+    pub bits: u64,
+    pub params_seed: &'static [u8],  // for PublicParameters::from_seed
+    pub rng_seed: u64,               // for the StdRng the secrets and the nonce are drawn from
+    pub label: &'static [u8],        // Fiat-Shamir transcript label of the proof
+    pub session_id: &'static str,    // of the Fiat-Shamir session the session challenge is issued in
+}
+
+pub const CASES: [Case; 3] = [
+    Case {
+        name: "modp-256",
+        bits: 256,
+        params_seed: b"zkp-chaum-pedersen-grpc/test-vectors/256",
+        rng_seed: 1,
+        label: b"chaum-pedersen/commitment",
+        session_id: "00000000-0000-4000-8000-000000000001",
+    },
+    Case {
+        name: "modp-256-labeled",
+        bits: 256,
+        params_seed: b"zkp-chaum-pedersen-grpc/test-vectors/256",
+        rng_seed: 2,
+        label: b"integrator/login",
+        session_id: "00000000-0000-4000-8000-000000000002",
+    },
+    Case {
+        name: "modp-512",
+        bits: 512,
+        params_seed: b"zkp-chaum-pedersen-grpc/test-vectors/512",
+        rng_seed: 3,
+        label: b"chaum-pedersen/commitment",
+        session_id: "00000000-0000-4000-8000-000000000003",
+    },
+];
+
+// One run of the protocol. `challenge` is the Fiat-Shamir challenge of a non-interactive proof
+// under `label`; `session_challenge` is the one a server derives for the same commitment and
+// (y1, y2) in the Fiat-Shamir session `session_id`, which also covers the session and the commitment.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestVector {
+    pub name: String,
+    pub params: PublicParameters,
+    #[serde(with = "wire::biguint")]
+    pub secret_a: BigUint,
+    #[serde(with = "wire::biguint")]
+    pub secret_b: BigUint,
+    pub commitment: Commitment,
+    #[serde(with = "wire::biguint")]
+    pub nonce: BigUint,  // x
+    pub challenge_values: ProofChallenge,
+    pub label: String,
+    #[serde(with = "wire::biguint")]
+    pub challenge: BigUint,
+    #[serde(with = "wire::biguint")]
+    pub z: BigUint,
+    pub session_id: String,
+    #[serde(with = "wire::biguint")]
+    pub session_challenge: BigUint,
+    #[serde(with = "wire::biguint")]
+    pub session_z: BigUint,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestVectors {
+    pub version: u32,
+    pub vectors: Vec<TestVector>,
+}
+
+impl Case {
+    pub fn generate(&self) -> TestVector {
+        let params = PublicParameters::from_seed(self.bits, self.params_seed);
+        let mut rng = StdRng::seed_from_u64(self.rng_seed);
+        let prover = Prover::builder(params)
+            .label(self.label)
+            .build_with_rng(&mut rng)
+            .expect("lenient builds never fail");
+        let commitment = prover.generate_commitment();
+        let (values, x) = prover.generate_proof_challenge_with_rng(&commitment, &mut rng);
+        let q = prover.params.q();
+        let challenge = prover.options.challenge(&prover.params, &commitment, &values);
+        let session_challenge = generate_session_challenge(self.session_id.as_bytes(), &commitment, &values.y1, &values.y2, q);
+        TestVector {
+            name: self.name.to_string(),
+            z: prover.generate_response(&x, &challenge).z,
+            session_z: prover.generate_response(&x, &session_challenge).z,
+            secret_a: prover.secret_a,
+            secret_b: prover.secret_b,
+            commitment,
+            nonce: x,
+            challenge_values: values,
+            label: String::from_utf8_lossy(self.label).into_owned(),
+            challenge,
+            session_id: self.session_id.to_string(),
+            session_challenge,
+            params: prover.params,
+        }
+    }
+}
+
+impl TestVector {
+    // The non-interactive proof the vector describes
+    pub fn proof(&self) -> ZKProof {
+        ZKProof {
+            commitment: self.commitment.clone(),
+            challenge: self.challenge_values.clone(),
+            response: ProofResponse { z: self.z.clone() },
+            challenge_hash: self.challenge.clone(),
+        }
+    }
+}
+
+pub fn vectors() -> TestVectors {
+    TestVectors {
+        version: VECTORS_VERSION,
+        vectors: CASES.iter().map(Case::generate).collect(),
+    }
+}
+
+// The vectors as pretty-printed JSON, integers in the hex of `wire::biguint`
+pub fn to_json() -> String {
+    serde_json::to_string_pretty(&vectors()).expect("vectors always serialize") + "\n"
+}
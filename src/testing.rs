@@ -0,0 +1,120 @@
+use std::io;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use tokio::net::TcpListener;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::service::interceptor::InterceptedService;
+use tonic::transport::Server;
+
+use crate::auth::Authenticator;
+use crate::client_lib::{ChaumPedersenClient, ClientError, ClientOptions};
+use crate::config::ServerConfig;
+use crate::service::ChaumPedersenServer;
+
+// Server settings for tests: the smallest groups a server may allow, so parameter generation
+// takes a fraction of a second, and no rate limit worth hitting
+pub fn test_config() -> ServerConfig {
+    ServerConfig {
+        min_bits: 256,
+        max_bits: 512,
+        init_requests_per_minute: u32::MAX,
+        ..ServerConfig::default()
+    }
+}
+
+// A ChaumPedersenServer serving both protocol versions on an ephemeral loopback port, inside the
+// test's own runtime, with the configured API keys and client CNs enforced. Dropping it stops the
+// server; `shutdown` does the same and waits for in-flight RPCs.
+#[derive(Debug)]
+pub struct TestServer {
+    addr: SocketAddr,
+    server: Arc<ChaumPedersenServer>,
+    stop: Option<oneshot::Sender<()>>,
+    task: JoinHandle<Result<(), tonic::transport::Error>>,
+}
+
+impl TestServer {
+    pub async fn start() -> io::Result<Self> {
+        Self::start_with(test_config()).await
+    }
+
+    pub async fn start_with(config: ServerConfig) -> io::Result<Self> {
+        Self::serve(ChaumPedersenServer::from_config(&config), &config).await
+    }
+
+    // Serves a server that was set up beforehand, e.g. with hooks or another session store;
+    // `config` only supplies the authentication allowlists
+    pub async fn serve(server: ChaumPedersenServer, config: &ServerConfig) -> io::Result<Self> {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).await?;
+        let addr = listener.local_addr()?;
+
+        let server = Arc::new(server);
+        let (grpc, grpc_v2) = server.clone().services();
+        let interceptor = Authenticator::new(
+            config.api_keys.clone(),
+            config.allowed_client_cns.iter().cloned().collect(),
+        )
+        .with_operators(
+            config.operator_api_keys.clone(),
+            config.operator_client_cns.iter().cloned().collect(),
+        )
+        .into_interceptor();
+        let router = Server::builder()
+            .add_service(InterceptedService::new(grpc, interceptor.clone()))
+            .add_service(InterceptedService::new(grpc_v2, interceptor));
+
+        let (stop, stopped) = oneshot::channel();
+        let task = tokio::spawn(router.serve_with_incoming_shutdown(TcpListenerStream::new(listener), async {
+            let _ = stopped.await;
+        }));
+        Ok(Self {
+            addr,
+            server,
+            stop: Some(stop),
+            task,
+        })
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    // The URL clients connect to, e.g. http://127.0.0.1:49152
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    // The served instance, to inspect or drive it alongside the RPCs
+    pub fn server(&self) -> &ChaumPedersenServer {
+        &self.server
+    }
+
+    pub async fn client(&self) -> Result<ChaumPedersenClient, ClientError> {
+        self.client_with(ClientOptions::default()).await
+    }
+
+    pub async fn client_with(&self, options: ClientOptions) -> Result<ChaumPedersenClient, ClientError> {
+        ChaumPedersenClient::connect_with(&self.url(), options).await
+    }
+
+    pub async fn shutdown(mut self) -> Result<(), tonic::transport::Error> {
+        if let Some(stop) = self.stop.take() {
+            let _ = stop.send(());
+        }
+        match (&mut self.task).await {
+            Ok(result) => result,
+            Err(e) => std::panic::resume_unwind(e.into_panic()),
+        }
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        if self.stop.is_some() {
+            self.task.abort();
+        }
+    }
+}
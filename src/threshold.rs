@@ -0,0 +1,298 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use num_bigint::{BigUint, RandBigInt};
+use rand::rngs::OsRng;
+use serde::{Deserialize, Serialize};
+
+use crate::chaum_pedersen::{
+    compute_y1y2, compute_z, generate_challenge, verify_values, wire, Commitment, GroupElement, ProofChallenge,
+    ProofResponse, PublicParameters, Scalar, SecureRng, ZKProof,
+};
+
+// Chaum-Pedersen proofs produced jointly by n provers, each holding an additive share a_i of the
+// secret a = a_1 + ... + a_n mod q. The parties' public shares (g^a_i, b1^a_i) and nonce commitments
+// (g^x_i, b1^x_i) multiply into the usual commitment and (y1, y2), and their responses add up to z,
+// so the assembled proof is an ordinary ZKProof. Every one of the n shares is needed.
+
+// Splits `secret` into `parties` random shares that sum to it mod q
+pub fn split_secret(secret: &BigUint, parties: usize, q: &BigUint) -> Vec<BigUint> {
+    split_secret_with_rng(secret, parties, q, &mut OsRng)
+}
+
+pub fn split_secret_with_rng<R: SecureRng + ?Sized>(
+    secret: &BigUint,
+    parties: usize,
+    q: &BigUint,
+    rng: &mut R,
+) -> Vec<BigUint> {
+    assert!(parties > 0, "a secret needs at least one share");
+    let mut shares: Vec<BigUint> = (1..parties).map(|_| rng.gen_biguint_below(q)).collect();
+    let sum = shares.iter().fold(BigUint::from(0u32), |acc, share| (acc + share) % q);
+    shares.push((secret % q + q - sum) % q);
+    shares
+}
+
+// One party's part of the commitment (a1, c1)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PublicShare {
+    #[serde(with = "wire::biguint")]
+    pub a1: BigUint,  // g^a_i mod p
+    #[serde(with = "wire::biguint")]
+    pub c1: BigUint,  // b1^a_i mod p
+}
+
+// What a party sends the coordinator before the challenge: its public share and (g^x_i, b1^x_i)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PartialCommitment {
+    pub share: PublicShare,
+    pub values: ProofChallenge,
+}
+
+// A prover holding one share a_i. All parties use the same b1 = g^b, which may be public.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShareProver {
+    pub params: PublicParameters,
+    #[serde(with = "wire::biguint")]
+    pub b1: BigUint,
+    #[serde(with = "wire::biguint")]
+    pub share: BigUint,
+}
+
+impl ShareProver {
+    pub fn new(params: PublicParameters, b1: BigUint, share: BigUint) -> Self {
+        Self { params, b1, share }
+    }
+
+    pub fn public_share(&self) -> PublicShare {
+        let params = &self.params;
+        let (a1, c1) = compute_y1y2(params, &Scalar::new(&self.share, params), &GroupElement::unchecked(self.b1.clone()));
+        PublicShare {
+            a1: a1.into_biguint(),
+            c1: c1.into_biguint(),
+        }
+    }
+
+    // Draws this party's nonce x_i; keep it until the challenge arrives, and use it only once
+    pub fn commit(&self) -> (PartialCommitment, BigUint) {
+        self.commit_with_rng(&mut OsRng)
+    }
+
+    pub fn commit_with_rng<R: SecureRng + ?Sized>(&self, rng: &mut R) -> (PartialCommitment, BigUint) {
+        let params = &self.params;
+        let x = Scalar::random_with_rng(params, rng);
+        let (y1, y2) = compute_y1y2(params, &x, &GroupElement::unchecked(self.b1.clone()));
+        let commitment = PartialCommitment {
+            share: self.public_share(),
+            values: ProofChallenge {
+                y1: y1.into_biguint(),
+                y2: y2.into_biguint(),
+            },
+        };
+        (commitment, x.into_biguint())
+    }
+
+    pub fn respond(&self, x: &BigUint, challenge: &BigUint) -> ProofResponse {
+        let params = &self.params;
+        let z = compute_z(
+            params,
+            &Scalar::new(x, params),
+            &Scalar::new(&self.share, params),
+            &Scalar::new(challenge, params),
+        );
+        ProofResponse { z: z.into_biguint() }
+    }
+}
+
+fn product<'a>(values: impl IntoIterator<Item = &'a BigUint>, p: &BigUint) -> BigUint {
+    values.into_iter().fold(BigUint::from(1u32), |acc, value| acc * value % p)
+}
+
+pub fn aggregate_commitment(params: &PublicParameters, b1: &BigUint, shares: &[PublicShare]) -> Commitment {
+    Commitment {
+        a1: product(shares.iter().map(|share| &share.a1), params.p()),
+        b1: b1.clone(),
+        c1: product(shares.iter().map(|share| &share.c1), params.p()),
+    }
+}
+
+pub fn aggregate_challenge_values(params: &PublicParameters, values: &[ProofChallenge]) -> ProofChallenge {
+    ProofChallenge {
+        y1: product(values.iter().map(|v| &v.y1), params.p()),
+        y2: product(values.iter().map(|v| &v.y2), params.p()),
+    }
+}
+
+pub fn aggregate_responses(params: &PublicParameters, responses: &[ProofResponse]) -> ProofResponse {
+    let z = responses
+        .iter()
+        .fold(BigUint::from(0u32), |acc, response| (acc + &response.z) % params.q());
+    ProofResponse { z }
+}
+
+// Checks one party's response against its own share and nonce commitment, so a bad share can be
+// blamed on the party that sent it rather than only failing the assembled proof
+pub fn verify_partial(
+    params: &PublicParameters,
+    b1: &BigUint,
+    commitment: &PartialCommitment,
+    challenge: &BigUint,
+    response: &ProofResponse,
+) -> bool {
+    verify_values(
+        params,
+        [b1, &commitment.values.y1, &commitment.values.y2, &commitment.share.a1, &commitment.share.c1],
+        challenge,
+        &response.z,
+    )
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ThresholdError {
+    UnknownParty { party: u32, parties: u32 },
+    DuplicateCommitment(u32),
+    DuplicateResponse(u32),
+    NotAnElement(u32),  // a value the party sent is outside the group
+    CommitmentsPending { received: u32, parties: u32 },
+    ResponseNotReduced(u32),
+    InvalidResponse(u32),  // the party's response does not match its share
+}
+
+impl fmt::Display for ThresholdError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ThresholdError::UnknownParty { party, parties } => {
+                write!(f, "party {} is out of range for {} parties", party, parties)
+            }
+            ThresholdError::DuplicateCommitment(party) => write!(f, "party {} has already committed", party),
+            ThresholdError::DuplicateResponse(party) => write!(f, "party {} has already responded", party),
+            ThresholdError::NotAnElement(party) => write!(f, "party {} sent values outside the group", party),
+            ThresholdError::CommitmentsPending { received, parties } => {
+                write!(f, "only {} of {} parties have committed", received, parties)
+            }
+            ThresholdError::ResponseNotReduced(party) => write!(f, "response of party {} is not reduced mod q", party),
+            ThresholdError::InvalidResponse(party) => write!(f, "response of party {} does not match its share", party),
+        }
+    }
+}
+
+impl std::error::Error for ThresholdError {}
+
+// Collects the parties' commitments, hands out the joint challenge once all are in, then checks each
+// partial response and assembles the final proof
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Coordinator {
+    pub params: PublicParameters,
+    #[serde(with = "wire::biguint")]
+    pub b1: BigUint,
+    pub parties: u32,
+    commitments: BTreeMap<u32, PartialCommitment>,
+    responses: BTreeMap<u32, ProofResponse>,
+}
+
+impl Coordinator {
+    pub fn new(params: PublicParameters, b1: BigUint, parties: u32) -> Self {
+        Self {
+            params,
+            b1,
+            parties,
+            commitments: BTreeMap::new(),
+            responses: BTreeMap::new(),
+        }
+    }
+
+    pub fn add_commitment(&mut self, party: u32, commitment: PartialCommitment) -> Result<(), ThresholdError> {
+        self.check_party(party)?;
+        if self.commitments.contains_key(&party) {
+            return Err(ThresholdError::DuplicateCommitment(party));
+        }
+        let values = [
+            &commitment.share.a1,
+            &commitment.share.c1,
+            &commitment.values.y1,
+            &commitment.values.y2,
+        ];
+        if !values.into_iter().all(|x| self.params.is_element(x)) {
+            return Err(ThresholdError::NotAnElement(party));
+        }
+        self.commitments.insert(party, commitment);
+        Ok(())
+    }
+
+    pub fn commitments_received(&self) -> u32 {
+        self.commitments.len() as u32
+    }
+
+    pub fn responses_received(&self) -> u32 {
+        self.responses.len() as u32
+    }
+
+    // The joint (a1, b1, c1), once every party has committed
+    pub fn commitment(&self) -> Option<Commitment> {
+        self.all_committed().then(|| {
+            let shares: Vec<_> = self.commitments.values().map(|c| c.share.clone()).collect();
+            aggregate_commitment(&self.params, &self.b1, &shares)
+        })
+    }
+
+    // The joint (y1, y2), once every party has committed
+    pub fn challenge_values(&self) -> Option<ProofChallenge> {
+        self.all_committed().then(|| {
+            let values: Vec<_> = self.commitments.values().map(|c| c.values.clone()).collect();
+            aggregate_challenge_values(&self.params, &values)
+        })
+    }
+
+    // The challenge every party answers, derived from the joint commitment and (y1, y2) like a
+    // single prover's
+    pub fn challenge(&self) -> Option<BigUint> {
+        let (commitment, values) = (self.commitment()?, self.challenge_values()?);
+        Some(generate_challenge(&self.params, &commitment, &values.y1, &values.y2))
+    }
+
+    // Accepts a party's response only if it checks out against that party's own commitment
+    pub fn add_response(&mut self, party: u32, response: ProofResponse) -> Result<(), ThresholdError> {
+        self.check_party(party)?;
+        let challenge = self.challenge().ok_or(ThresholdError::CommitmentsPending {
+            received: self.commitments_received(),
+            parties: self.parties,
+        })?;
+        if self.responses.contains_key(&party) {
+            return Err(ThresholdError::DuplicateResponse(party));
+        }
+        if response.z >= *self.params.q() {
+            return Err(ThresholdError::ResponseNotReduced(party));
+        }
+        if !verify_partial(&self.params, &self.b1, &self.commitments[&party], &challenge, &response) {
+            return Err(ThresholdError::InvalidResponse(party));
+        }
+        self.responses.insert(party, response);
+        Ok(())
+    }
+
+    // The assembled proof, once every party has responded
+    pub fn assemble(&self) -> Option<ZKProof> {
+        if self.responses_received() != self.parties {
+            return None;
+        }
+        let responses: Vec<_> = self.responses.values().cloned().collect();
+        Some(ZKProof {
+            commitment: self.commitment()?,
+            challenge: self.challenge_values()?,
+            response: aggregate_responses(&self.params, &responses),
+            challenge_hash: self.challenge()?,
+        })
+    }
+
+    fn all_committed(&self) -> bool {
+        self.commitments_received() == self.parties
+    }
+
+    fn check_party(&self, party: u32) -> Result<(), ThresholdError> {
+        if party >= self.parties {
+            return Err(ThresholdError::UnknownParty { party, parties: self.parties });
+        }
+        Ok(())
+    }
+}
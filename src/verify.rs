@@ -0,0 +1,59 @@
+use zkp_chaum_pedersen_grpc::proof_file::ProofFile;
+
+// Offline verifier for .zkproof files: checks each file named on the command line without any network.
+// With --params-digest HEX, the file's parameters must also match the expected digest.
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let mut expected_digest = None;
+    let mut paths = Vec::new();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--params-digest" => expected_digest = args.next(),
+            _ => paths.push(arg),
+        }
+    }
+
+    if paths.is_empty() {
+        eprintln!("usage: zkp-verify [--params-digest HEX] FILE.zkproof...");
+        std::process::exit(2);
+    }
+
+    let mut all_verified = true;
+    for path in &paths {
+        let file = match ProofFile::read(path) {
+            Ok(file) => file,
+            Err(e) => {
+                println!("{}: ERROR {}", path, e);
+                all_verified = false;
+                continue;
+            }
+        };
+
+        let digest = hex::encode(file.params.digest());
+        if let Some(expected) = &expected_digest
+            && !expected.eq_ignore_ascii_case(&digest)
+        {
+            println!("{}: REJECTED parameters digest {} does not match", path, digest);
+            all_verified = false;
+            continue;
+        }
+
+        match file.verify() {
+            Ok(true) => {
+                println!("{}: VERIFIED ({}-bit group, parameters digest {})", path, file.params.p().bits(), digest);
+            }
+            Ok(false) => {
+                println!("{}: FAILED proof does not verify", path);
+                all_verified = false;
+            }
+            Err(e) => {
+                println!("{}: ERROR {}", path, e);
+                all_verified = false;
+            }
+        }
+    }
+
+    if !all_verified {
+        std::process::exit(1);
+    }
+}
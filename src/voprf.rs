@@ -0,0 +1,161 @@
+use std::fmt;
+
+use num_bigint::BigUint;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use sha2::{Digest, Sha256, Sha512};
+
+use crate::chaum_pedersen::{SecureRng, Transcript};
+use crate::group::{Dleq, DleqProof, DleqStatement, Group};
+use crate::sigma;
+
+// A verifiable oblivious PRF from DLEQ (2HashDH). The client blinds H(input) with a random r and
+// sends H(input)^r; the key holder raises it to k and proves log_g(pk) = log_blinded(evaluated); the
+// client checks the proof, unblinds to H(input)^k and hashes that into the output. The evaluator
+// never sees the input, and the proof ties every evaluation to the one published pk, so it cannot
+// single clients out with keys of their own. Works over any `Group`; `EvaluateOprf` serves it over
+// the curve groups.
+
+const VOPRF_INPUT_LABEL: &[u8] = b"zkp-chaum-pedersen-grpc/voprf-input";
+const VOPRF_OUTPUT_LABEL: &[u8] = b"zkp-chaum-pedersen-grpc/voprf-output";
+const VOPRF_KEY_LABEL: &[u8] = b"zkp-chaum-pedersen-grpc/voprf-key";
+
+pub type EvaluationProof<G> = DleqProof<G>;
+
+// The factor a client blinded its input with, needed again to unblind the evaluation
+pub struct Blind<G: Group> {
+    r: G::Scalar,
+}
+
+pub fn public_key<G: Group>(group: &G, k: &G::Scalar) -> G::Element {
+    group.exp(&group.generator(), k)
+}
+
+pub fn blind<G: Group>(group: &G, input: &[u8]) -> (Blind<G>, G::Element) {
+    blind_with_rng(group, input, &mut OsRng)
+}
+
+pub fn blind_with_rng<G: Group>(group: &G, input: &[u8], rng: &mut dyn SecureRng) -> (Blind<G>, G::Element) {
+    let r = group.random_scalar(rng);
+    let blinded = group.exp(&group.hash_to_element(VOPRF_INPUT_LABEL, input), &r);
+    (Blind { r }, blinded)
+}
+
+// The key holder's side: blinded^k and the proof that k is the key behind pk
+pub fn evaluate<G: Group + Clone>(group: &G, k: &G::Scalar, blinded: &G::Element) -> (G::Element, EvaluationProof<G>) {
+    evaluate_with_rng(group, k, blinded, &mut OsRng)
+}
+
+pub fn evaluate_with_rng<G: Group + Clone>(
+    group: &G,
+    k: &G::Scalar,
+    blinded: &G::Element,
+    rng: &mut dyn SecureRng,
+) -> (G::Element, EvaluationProof<G>) {
+    let dleq = Dleq::new(group.clone());
+    let statement = dleq.statement(group.generator(), blinded.clone(), k);
+    let proof = sigma::prove_with_rng(&dleq, &mut Transcript::new(b"voprf"), &statement, k, rng);
+    (statement.y2, proof)
+}
+
+pub fn verify_evaluation<G: Group + Clone>(
+    group: &G,
+    pk: &G::Element,
+    blinded: &G::Element,
+    evaluated: &G::Element,
+    proof: &EvaluationProof<G>,
+) -> bool {
+    let statement = DleqStatement {
+        g: group.generator(),
+        h: blinded.clone(),
+        y1: pk.clone(),
+        y2: evaluated.clone(),
+    };
+    sigma::verify_with_transcript(&Dleq::new(group.clone()), &mut Transcript::new(b"voprf"), &statement, proof)
+}
+
+// The client's output for `input`, or None if the evaluation was not made with the key behind pk
+pub fn finalize<G: Group + Clone>(
+    group: &G,
+    pk: &G::Element,
+    input: &[u8],
+    blind: &Blind<G>,
+    blinded: &G::Element,
+    evaluated: &G::Element,
+    proof: &EvaluationProof<G>,
+) -> Option<[u8; 32]> {
+    if !verify_evaluation(group, pk, blinded, evaluated, proof) {
+        return None;
+    }
+    // r^-1 = r^(order - 2), the order being prime
+    let order = group.order();
+    let r = group.scalar_to_integer(&blind.r);
+    let r_inv = group.scalar_from_integer(&r.modpow(&(order - 2u32), order));
+    Some(output(group, input, &group.exp(evaluated, &r_inv)))
+}
+
+// The output `finalize` gives for `input`, computed by the key holder without blinding, e.g. to
+// check a token a client redeems
+pub fn evaluate_unblinded<G: Group>(group: &G, k: &G::Scalar, input: &[u8]) -> [u8; 32] {
+    let unblinded = group.exp(&group.hash_to_element(VOPRF_INPUT_LABEL, input), k);
+    output(group, input, &unblinded)
+}
+
+fn output<G: Group>(group: &G, input: &[u8], unblinded: &G::Element) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(VOPRF_OUTPUT_LABEL);
+    hasher.update(G::NAME);
+    hasher.update((input.len() as u64).to_be_bytes());
+    hasher.update(input);
+    hasher.update(group.encode_element(unblinded));
+    hasher.finalize().into()
+}
+
+// A server's evaluation keys, one per group, all derived from one 32-byte seed, so a configured
+// seed keeps public keys and outputs stable across restarts
+#[derive(Clone)]
+pub struct EvaluationKey {
+    seed: [u8; 32],
+}
+
+impl EvaluationKey {
+    // A fresh seed, good for one server run; outputs change with every restart
+    pub fn generate() -> Self {
+        let mut seed = [0u8; 32];
+        OsRng.fill_bytes(&mut seed);
+        Self { seed }
+    }
+
+    pub fn from_seed(seed: &[u8; 32]) -> Self {
+        Self { seed: *seed }
+    }
+
+    // The key for `group`: 128 bits more than the order hashed from the seed, reduced mod the order
+    pub fn secret<G: Group>(&self, group: &G) -> G::Scalar {
+        let len = group.order().bits().div_ceil(8) as usize + 16;
+        let mut wide = Vec::with_capacity(len + 64);
+        let mut block: u32 = 0;
+        while wide.len() < len {
+            let mut hasher = Sha512::new();
+            hasher.update(VOPRF_KEY_LABEL);
+            hasher.update(G::NAME);
+            hasher.update(group.order().to_bytes_be());
+            hasher.update(self.seed);
+            hasher.update(block.to_be_bytes());
+            wide.extend_from_slice(&hasher.finalize());
+            block += 1;
+        }
+        wide.truncate(len);
+        group.scalar_from_integer(&BigUint::from_bytes_be(&wide))
+    }
+
+    pub fn public_key<G: Group>(&self, group: &G) -> G::Element {
+        public_key(group, &self.secret(group))
+    }
+}
+
+impl fmt::Debug for EvaluationKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("EvaluationKey(<redacted>)")
+    }
+}
@@ -0,0 +1,60 @@
+use rand::rngs::OsRng;
+use sha2::{Digest, Sha256};
+
+use crate::chaum_pedersen::{SecureRng, Transcript};
+use crate::group::{Dleq, DleqProof, DleqStatement, Group};
+use crate::sigma;
+
+// A verifiable random function from DLEQ: the output for `input` is H(input)^sk, and the proof shows
+// log_g(pk) = log_H(input)(output), so only the holder of sk can compute it and anyone can check it.
+// Works over any `Group`; hashing to the group is not constant time, so inputs are public.
+
+const VRF_INPUT_LABEL: &[u8] = b"zkp-chaum-pedersen-grpc/vrf-input";
+const VRF_OUTPUT_LABEL: &[u8] = b"zkp-chaum-pedersen-grpc/vrf-output";
+
+pub type VrfProof<G> = DleqProof<G>;
+
+pub fn public_key<G: Group>(group: &G, sk: &G::Scalar) -> G::Element {
+    group.exp(&group.generator(), sk)
+}
+
+pub fn eval<G: Group + Clone>(group: &G, sk: &G::Scalar, input: &[u8]) -> (G::Element, VrfProof<G>) {
+    eval_with_rng(group, sk, input, &mut OsRng)
+}
+
+pub fn eval_with_rng<G: Group + Clone>(
+    group: &G,
+    sk: &G::Scalar,
+    input: &[u8],
+    rng: &mut dyn SecureRng,
+) -> (G::Element, VrfProof<G>) {
+    let dleq = Dleq::new(group.clone());
+    let statement = dleq.statement(group.generator(), group.hash_to_element(VRF_INPUT_LABEL, input), sk);
+    let proof = sigma::prove_with_rng(&dleq, &mut Transcript::new(b"vrf"), &statement, sk, rng);
+    (statement.y2, proof)
+}
+
+pub fn verify<G: Group + Clone>(
+    group: &G,
+    pk: &G::Element,
+    input: &[u8],
+    output: &G::Element,
+    proof: &VrfProof<G>,
+) -> bool {
+    let statement = DleqStatement {
+        g: group.generator(),
+        h: group.hash_to_element(VRF_INPUT_LABEL, input),
+        y1: pk.clone(),
+        y2: output.clone(),
+    };
+    sigma::verify_with_transcript(&Dleq::new(group.clone()), &mut Transcript::new(b"vrf"), &statement, proof)
+}
+
+// 32 uniform bytes from a verified output, for use as randomness
+pub fn output_hash<G: Group>(group: &G, output: &G::Element) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(VRF_OUTPUT_LABEL);
+    hasher.update(G::NAME);
+    hasher.update(group.encode_element(output));
+    hasher.finalize().into()
+}
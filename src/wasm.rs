@@ -0,0 +1,27 @@
+use num_bigint::BigUint;
+use wasm_bindgen::prelude::*;
+
+use crate::chaum_pedersen::{Prover, PublicParameters, Verifier, ZKProof};
+
+fn params_from_bytes(p: &[u8], q: &[u8], g: &[u8]) -> PublicParameters {
+    PublicParameters::from_parts(
+        BigUint::from_bytes_be(p),
+        BigUint::from_bytes_be(q),
+        BigUint::from_bytes_be(g),
+        None,
+    )
+}
+
+// Creates a fresh prover for the given big-endian parameters and returns its proof in the canonical byte encoding
+#[wasm_bindgen]
+pub fn create_proof(p: &[u8], q: &[u8], g: &[u8]) -> Vec<u8> {
+    let prover = Prover::new(params_from_bytes(p, q, g));
+    prover.create_proof().to_bytes()
+}
+
+#[wasm_bindgen]
+pub fn verify_proof(p: &[u8], q: &[u8], g: &[u8], proof: &[u8]) -> Result<bool, JsValue> {
+    let proof = ZKProof::from_bytes(proof).map_err(|e| JsValue::from_str(&e.to_string()))?;
+    let verifier = Verifier::new(params_from_bytes(p, q, g));
+    Ok(verifier.verify_proof(&proof))
+}
@@ -0,0 +1,48 @@
+use std::sync::OnceLock;
+
+use proptest::prelude::*;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use zkp_chaum_pedersen_grpc::batch;
+use zkp_chaum_pedersen_grpc::chaum_pedersen::{Prover, PublicParameters};
+
+fn params() -> &'static PublicParameters {
+    static PARAMS: OnceLock<PublicParameters> = OnceLock::new();
+    PARAMS.get_or_init(|| PublicParameters::new(128))
+}
+
+proptest! {
+    // A batch of valid proofs verifies, and one bad response sinks the whole batch
+    #[test]
+    fn one_bad_proof_fails_the_batch(seed: u64, count in 1usize..8, bad in 0usize..8) {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let proofs: Vec<_> = (0..count)
+            .map(|_| Prover::with_rng(params().clone(), &mut rng).create_proof_with_rng(&mut rng))
+            .collect();
+
+        let mut batched = batch::batch(&proofs);
+        prop_assert!(batch::verify_with_rng(params(), &batched, &mut rng));
+
+        let entry = &mut batched.entries[bad % count];
+        entry.z = (&entry.z + 1u32) % params().q();
+        prop_assert!(!batch::verify_with_rng(params(), &batched, &mut rng));
+    }
+
+    // A batch for one session verifies in no other, and standalone proofs in none
+    #[test]
+    fn session_batches_only_verify_in_their_session(seed: u64, count in 1usize..4) {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let provers: Vec<_> = (0..count).map(|_| Prover::with_rng(params().clone(), &mut rng)).collect();
+        let proofs: Vec<_> =
+            provers.iter().map(|prover| prover.create_session_proof_with_rng("session", &mut rng)).collect();
+
+        let batched = batch::batch(&proofs);
+        prop_assert!(batch::verify_for_session_with_rng(params(), "session", &batched, &mut rng));
+        prop_assert!(!batch::verify_for_session_with_rng(params(), "other session", &batched, &mut rng));
+        prop_assert!(!batch::verify_with_rng(params(), &batched, &mut rng));
+
+        let standalone: Vec<_> = provers.iter().map(|prover| prover.create_proof_with_rng(&mut rng)).collect();
+        prop_assert!(!batch::verify_for_session_with_rng(params(), "session", &batch::batch(&standalone), &mut rng));
+    }
+}
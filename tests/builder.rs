@@ -0,0 +1,86 @@
+use proptest::prelude::*;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use zkp_chaum_pedersen_grpc::chaum_pedersen::{
+    ParameterError, ParameterPolicy, PrecomputedParams, Prover, SecurityPolicy, Validation, Verifier,
+};
+use zkp_chaum_pedersen_grpc::strategies;
+
+proptest! {
+    #[test]
+    fn labeled_proofs_need_a_matching_verifier(prover in strategies::prover(), seed in any::<u64>()) {
+        let policy = ParameterPolicy {
+            min_bits: 32,
+            ..ParameterPolicy::default()
+        };
+        let prover = Prover::builder(prover.params)
+            .secrets(prover.secret_a, prover.secret_b)
+            .label(b"integrator/login")
+            .validation(Validation::Strict)
+            .policy(policy.clone())
+            .precompute(true)
+            .build()
+            .unwrap();
+        let proof = prover.create_proof_with_rng(&mut StdRng::seed_from_u64(seed));
+
+        let verifier = Verifier::builder(prover.params.clone())
+            .label(b"integrator/login")
+            .validation(Validation::Strict)
+            .policy(policy)
+            .build()
+            .unwrap();
+        prop_assert!(verifier.verify_proof(&proof));
+        prop_assert!(!Verifier::new(prover.params).verify_proof(&proof));
+    }
+
+    #[test]
+    fn precomputed_parameters_are_shared_and_interoperate(prover in strategies::prover(), seed in any::<u64>()) {
+        let precomputed = PrecomputedParams::new(prover.params.clone());
+        let shared = Prover::builder(precomputed.params().clone())
+            .secrets(prover.secret_a.clone(), prover.secret_b.clone())
+            .build()
+            .unwrap();
+        prop_assert!(shared.params.built_g_table().is_some());
+        prop_assert!(prover.params.built_g_table().is_none());
+
+        // table-backed and plain sides accept each other's proofs
+        let proof = shared.create_proof_with_rng(&mut StdRng::seed_from_u64(seed));
+        prop_assert!(Verifier::new(prover.params.clone()).verify_proof(&proof));
+        let plain = prover.create_proof_with_rng(&mut StdRng::seed_from_u64(seed));
+        let verifier = Verifier::with_precomputation(&precomputed);
+        prop_assert!(verifier.verify_proof(&plain));
+        prop_assert!(verifier.verify_proof(&Prover::with_precomputation(&precomputed).create_proof()));
+    }
+
+    #[test]
+    fn strict_builders_enforce_the_policy(params in strategies::params()) {
+        // Test-sized parameters are below the default minimum size
+        let strict = Verifier::builder(params.clone()).validation(Validation::Strict).build();
+        prop_assert!(strict.is_err());
+        prop_assert!(Prover::builder(params.clone()).validation(Validation::Strict).build().is_err());
+        prop_assert!(Verifier::builder(params).build().is_ok());
+    }
+
+    #[test]
+    fn security_policies_keep_exponents_above_the_floor(params in strategies::params(), seed in any::<u64>()) {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let q_bits = params.q().bits();
+        let refused = Prover::builder(params.clone()).security(SecurityPolicy::default()).build_with_rng(&mut rng);
+        prop_assert_eq!(refused.err(), Some(ParameterError::SubgroupTooSmall { bits: q_bits, min: 256 }));
+
+        let policy = SecurityPolicy::short_exponents(8);
+        let short = Prover::builder(params.clone()).security(policy).build_with_rng(&mut rng).unwrap();
+        prop_assert_eq!((short.secret_a.bits(), short.secret_b.bits()), (16, 16));
+        prop_assert!(Verifier::new(params.clone()).verify_proof(&short.create_proof_with_rng(&mut rng)));
+        let supplied = Prover::builder(params.clone()).secrets(1u32.into(), 2u32.into()).security(policy).build();
+        prop_assert_eq!(supplied.err(), Some(ParameterError::SecretTooShort { bits: 1, min: 16 }));
+
+        // a floor a bit or two below q turns many draws away, and every nonce handed out still reaches it
+        let policy = SecurityPolicy { security_bits: (q_bits - 1) / 2, ..SecurityPolicy::default() };
+        let full = Prover::builder(params).security(policy).build_with_rng(&mut rng).unwrap();
+        let (_, x) = full.generate_proof_challenge_with_rng(&full.generate_commitment(), &mut rng);
+        prop_assert!(x.bits() >= policy.min_exponent_bits());
+        prop_assert!(full.secret_a.bits() >= policy.min_exponent_bits());
+    }
+}
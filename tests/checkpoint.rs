@@ -0,0 +1,42 @@
+use proptest::prelude::*;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use zkp_chaum_pedersen_grpc::chaum_pedersen::{generate_challenge, Verifier, ZKProof};
+use zkp_chaum_pedersen_grpc::checkpoint::{ProtocolState, StateError};
+use zkp_chaum_pedersen_grpc::client_lib::ProtocolSession;
+use zkp_chaum_pedersen_grpc::session::ChallengeMode;
+use zkp_chaum_pedersen_grpc::strategies;
+
+proptest! {
+    #[test]
+    fn saved_states_resume_into_valid_proofs(
+        prover in strategies::prover(),
+        key in any::<[u8; 32]>(),
+        other_key in any::<[u8; 32]>(),
+        seed in any::<u64>(),
+    ) {
+        let session = ProtocolSession {
+            session_id: "session".to_string(),
+            params: prover.params.clone(),
+            challenge_mode: ChallengeMode::FiatShamir,
+            commitment_origin: Default::default(),
+            compressed_elements: false,
+        };
+        let saved = ProtocolState::new_with_rng(session, prover, &mut StdRng::seed_from_u64(seed)).save(&key);
+        if other_key != key {
+            prop_assert_eq!(ProtocolState::load(&saved, &other_key).unwrap_err(), StateError::Decryption);
+        }
+
+        let state = ProtocolState::load(&saved, &key).unwrap();
+        let values = &state.challenge_values;
+        let challenge_hash = generate_challenge(&state.prover.params, &state.commitment, &values.y1, &values.y2);
+        let proof = ZKProof {
+            commitment: state.commitment.clone(),
+            challenge: state.challenge_values.clone(),
+            response: state.prover.generate_response(&state.nonce, &challenge_hash),
+            challenge_hash,
+        };
+        prop_assert!(Verifier::new(state.session.params).verify_proof(&proof));
+    }
+}
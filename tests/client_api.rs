@@ -0,0 +1,97 @@
+use std::sync::OnceLock;
+
+use tonic::{Code, Status};
+
+use zkp_chaum_pedersen_grpc::client::{ClientError, MockZkpClient, ZkpClientApi};
+use zkp_chaum_pedersen_grpc::{Commitment, Prover, PublicParameters};
+use zkp_chaum_pedersen_grpc::session::ChallengeMode;
+use zkp_chaum_pedersen_grpc::testing::TestServer;
+
+fn params() -> &'static PublicParameters {
+    static PARAMS: OnceLock<PublicParameters> = OnceLock::new();
+    PARAMS.get_or_init(|| PublicParameters::new(128))
+}
+
+// A sign-in flow as an application would write it, against the trait
+async fn sign_in(client: &dyn ZkpClientApi, user_id: &str, prover: &Prover, commitment: &Commitment) -> Result<bool, ClientError> {
+    let session = client.initialize_for_user(user_id, ChallengeMode::Interactive).await?;
+    let (values, x) = prover.generate_proof_challenge(commitment);
+    let challenge = client.send_commitment(&session, commitment, &values.y1, &values.y2).await?;
+    let z = prover.generate_response(&x, &challenge).z;
+    Ok(client.prove(&session, &z).await?.verified)
+}
+
+fn status_code(result: Result<impl std::fmt::Debug, ClientError>) -> Code {
+    match result {
+        Err(ClientError::Rpc { status, .. }) => status.code(),
+        other => panic!("expected an RPC error, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn mock_verifies_a_registered_user() {
+    let prover = Prover::new(params().clone());
+    let commitment = prover.generate_commitment();
+    let mock = MockZkpClient::new(params().clone()).with_registration("alice", commitment.clone());
+
+    assert!(sign_in(&mock, "alice", &prover, &commitment).await.unwrap());
+    assert_eq!(mock.calls(), ["InitializeProtocol", "SendCommitment", "VerifyProof"]);
+
+    // someone else's secret does not match alice's registration
+    let impostor = Prover::new(params().clone());
+    let other = impostor.generate_commitment();
+    let result = sign_in(&mock, "alice", &impostor, &other).await;
+    assert_eq!(status_code(result), Code::FailedPrecondition);
+    assert_eq!(status_code(sign_in(&mock, "bob", &prover, &commitment).await), Code::NotFound);
+}
+
+#[tokio::test]
+async fn mock_reports_wrong_responses_and_revocations() {
+    let prover = Prover::new(params().clone());
+    let commitment = prover.generate_commitment();
+    let mock = MockZkpClient::new(params().clone());
+
+    let session = mock.initialize(128).await.unwrap();
+    mock.register(&session, "alice", &commitment).await.unwrap();
+    let session = mock.initialize_for_user("alice", ChallengeMode::FiatShamir).await.unwrap();
+    let (values, x) = prover.generate_proof_challenge(&commitment);
+    let challenge = mock.send_commitment(&session, &commitment, &values.y1, &values.y2).await.unwrap();
+    let z = prover.generate_response(&x, &challenge).z + 1u32;
+    assert!(!mock.prove(&session, &z).await.unwrap().verified);
+    assert_eq!(status_code(mock.prove(&session, &z).await), Code::FailedPrecondition);
+
+    assert!(mock.revoke_registration("alice").await.unwrap().revoked);
+    let result = mock.initialize_for_user("alice", ChallengeMode::FiatShamir).await;
+    assert_eq!(status_code(result), Code::FailedPrecondition);
+}
+
+#[tokio::test]
+async fn mock_returns_queued_failures() {
+    let prover = Prover::new(params().clone());
+    let commitment = prover.generate_commitment();
+    let mock = MockZkpClient::new(params().clone()).with_registration("alice", commitment.clone());
+
+    mock.fail_next("SendCommitment", Status::unavailable("verifier restarting"));
+    match sign_in(&mock, "alice", &prover, &commitment).await {
+        Err(ClientError::Rpc { rpc, status }) => {
+            assert_eq!(rpc, "SendCommitment");
+            assert_eq!(status.code(), Code::Unavailable);
+        }
+        other => panic!("expected the queued failure, got {:?}", other),
+    }
+    // only the next call fails
+    assert!(sign_in(&mock, "alice", &prover, &commitment).await.unwrap());
+}
+
+#[tokio::test]
+async fn the_same_flow_runs_against_a_server() {
+    let server = TestServer::start().await.unwrap();
+    let client = server.client().await.unwrap();
+
+    let session = client.initialize(256).await.unwrap();
+    let prover = Prover::new(session.params.clone());
+    let commitment = prover.generate_commitment();
+    client.register(&session, "alice", &commitment).await.unwrap();
+
+    assert!(sign_in(&client, "alice", &prover, &commitment).await.unwrap());
+}
@@ -0,0 +1,61 @@
+use prost::bytes::Bytes;
+use prost::Message;
+use proptest::prelude::*;
+
+use zkp_chaum_pedersen_grpc::chaum_pedersen::ZKProof;
+use zkp_chaum_pedersen_grpc::convert::{self, ConversionError, FieldLimits};
+use zkp_chaum_pedersen_grpc::strategies;
+use zkp_chaum_pedersen_grpc::zkp;
+
+proptest! {
+    #[test]
+    fn fields_longer_than_the_group_are_refused((prover, proof) in strategies::proof(), extra in 1usize..64) {
+        let limits = FieldLimits::for_params(&prover.params);
+        let mut message = zkp::ZkProofMessage::from(&proof);
+        prop_assert!(limits.check_proof(&message).is_ok());
+
+        // prepended bytes push z past anything below q; z is minimally encoded, so it may start short
+        let padding = limits.scalar + extra - message.z.len();
+        message.z = [vec![1u8; padding], message.z.to_vec()].concat().into();
+        prop_assert_eq!(
+            limits.check_proof(&message),
+            Err(ConversionError::TooLong { field: "z", max: limits.scalar })
+        );
+        prop_assert!(limits.scalar("z", &message.z).is_err());
+    }
+
+    #[test]
+    fn integers_decode_in_place((_, proof) in strategies::proof()) {
+        let buffer = Bytes::from(zkp::ZkProofMessage::from(&proof).encode_to_vec());
+        let message = zkp::ZkProofMessage::decode(buffer.clone()).unwrap();
+
+        // the decoded fields borrow the received buffer instead of copying out of it
+        let range = buffer.as_ptr_range();
+        for field in [&message.z, &message.challenge, &message.commitment.as_ref().unwrap().a1] {
+            prop_assert!(range.contains(&field.as_ptr()));
+        }
+        let decoded = ZKProof::try_from(message).unwrap();
+        prop_assert_eq!(decoded.response.z, proof.response.z);
+        prop_assert_eq!(decoded.commitment.digest(), proof.commitment.digest());
+    }
+
+    #[test]
+    fn compressed_elements_expand_to_themselves((prover, proof) in strategies::proof(), v in any::<u64>()) {
+        let params = &prover.params;
+        let message = convert::compress_commitment(&proof.commitment, params);
+        for value in [&message.a1, &message.b1, &message.c1] {
+            prop_assert!(convert::decode_integer("element", value).unwrap() <= *params.q());
+        }
+        let commitment = convert::decompress_commitment(message.try_into().unwrap(), params).unwrap();
+        prop_assert_eq!(commitment.digest(), proof.commitment.digest());
+        let message = convert::compress_challenge_values(&proof.challenge, params);
+        let values = convert::decompress_challenge_values(message.try_into().unwrap(), params).unwrap();
+        prop_assert_eq!((values.y1, values.y2), (proof.challenge.y1, proof.challenge.y2));
+
+        // whatever representative arrives, it stands for a subgroup element
+        let v = num_bigint::BigUint::from(v) % params.q() + 1u32;
+        let x = params.decompress_element(&v).unwrap();
+        prop_assert!(x.modpow(params.q(), params.p()) == 1u32.into());
+        prop_assert_eq!(params.compress_element(&x), v);
+    }
+}
@@ -0,0 +1,166 @@
+use std::sync::OnceLock;
+
+use num_bigint::BigUint;
+use proptest::prelude::*;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use zkp_chaum_pedersen_grpc::chaum_pedersen::{
+    ChallengeSpace, Commitment, GroupElement, ProofChallenge, ProofResponse, PublicParameters, Scalar, Transcript,
+    Verifier, ZKProof,
+};
+use zkp_chaum_pedersen_grpc::crypto::{
+    compute_y1y2, compute_z, generate_challenge, generate_commitment, generate_interactive_challenge,
+    generate_prover_secret_with_rng, generate_random_element_with_rng, generate_secrets_with_rng,
+    generate_session_challenge, verify_proof,
+};
+
+fn params() -> &'static PublicParameters {
+    static PARAMS: OnceLock<PublicParameters> = OnceLock::new();
+    PARAMS.get_or_init(|| PublicParameters::new(128))
+}
+
+// Every scalar the prover draws is uniform over [1, q), including q - 1
+#[test]
+fn random_scalars_cover_one_to_q_minus_one() {
+    let q = BigUint::from(5u32);
+    let mut rng = StdRng::seed_from_u64(7);
+    let mut seen = [false; 5];
+    for _ in 0..200 {
+        let values = [
+            generate_random_element_with_rng(&q, &mut rng),
+            generate_prover_secret_with_rng(&q, &mut rng),
+            generate_secrets_with_rng(&q, &mut rng).0,
+        ];
+        for value in values {
+            assert!(value >= BigUint::from(1u32) && value < q);
+            seen[usize::try_from(value).unwrap()] = true;
+        }
+    }
+    assert_eq!(seen, [false, true, true, true, true]);
+}
+
+// Scalars reduce mod q and elements must lie in [1, p)
+#[test]
+fn wrappers_keep_their_modulus() {
+    let params = params();
+    assert_eq!(Scalar::new(&(params.q() + 3u32), params).as_biguint(), &BigUint::from(3u32));
+    assert!(GroupElement::new(params.p(), params).is_none());
+    assert!(GroupElement::new(&BigUint::from(0u32), params).is_none());
+
+    // a power of g is the same whether the exponent was reduced or not
+    let g = GroupElement::generator(params);
+    let x = Scalar::new(&BigUint::from(12345u32), params);
+    let wrapped = Scalar::new(&(params.q() + 12345u32), params);
+    assert_eq!(g.pow(&x, params), g.pow(&wrapped, params));
+    assert_eq!(g.pow(&x, params), g.pow_public(&x, params));
+}
+
+// Challenges are uniform over the space: with a bound just past a power of two, reducing a
+// same-width value would make the low residues about twice as likely
+#[test]
+fn challenges_are_unbiased() {
+    let space = ChallengeSpace::new(BigUint::from(9u32));
+    let mut counts = [0u32; 9];
+    for i in 0u32..9000 {
+        let value = space.sample(&i.to_be_bytes());
+        counts[usize::try_from(value).unwrap()] += 1;
+    }
+    assert!(counts.iter().all(|&count| (800..1200).contains(&count)), "{:?}", counts);
+
+    assert_eq!(space.sample(b"seed"), space.sample(b"seed"));
+    let short = ChallengeSpace::with_bits(128, params().q());
+    assert_eq!(short.bound(), &params().q().clone().min(BigUint::from(1u32) << 128));
+    assert_eq!(ChallengeSpace::with_bits(512, params().q()).bound(), params().q());
+}
+
+proptest! {
+    // Commitments and (y1, y2) are powers mod p, and an honest transcript verifies
+    #[test]
+    fn honest_transcripts_verify(seed: u64) {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let params = params();
+        let (a, b) = (Scalar::random_with_rng(params, &mut rng), Scalar::random_with_rng(params, &mut rng));
+        let (a1, b1, c1) = generate_commitment(params, &a, &b);
+        prop_assert_eq!(a1.as_biguint(), &params.g().modpow(a.as_biguint(), params.p()));
+        prop_assert_eq!(c1.as_biguint(), &b1.as_biguint().modpow(a.as_biguint(), params.p()));
+        let commitment = Commitment {
+            a1: a1.as_biguint().clone(),
+            b1: b1.as_biguint().clone(),
+            c1: c1.as_biguint().clone(),
+        };
+
+        let x = Scalar::random_with_rng(params, &mut rng);
+        let (y1, y2) = compute_y1y2(params, &x, &b1);
+        let s = generate_challenge(params, &commitment, y1.as_biguint(), y2.as_biguint());
+        prop_assert!(&s < params.q());
+        prop_assert_eq!(&s, &generate_challenge(params, &commitment, y1.as_biguint(), y2.as_biguint()));
+
+        let s = Scalar::new(&s, params);
+        let z = compute_z(params, &x, &a, &s);
+        prop_assert!(verify_proof(params, &b1, &y1, &y2, &a1, &c1, &s, &z));
+        let wrong = Scalar::new(&(z.as_biguint() + 1u32), params);
+        prop_assert!(!verify_proof(params, &b1, &y1, &y2, &a1, &c1, &s, &wrong));
+    }
+
+    // Session challenges answer for one commitment: any other gets a different challenge
+    #[test]
+    fn session_challenges_are_bound_to_the_commitment(seed: u64) {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let params = params();
+        let (a, b) = (Scalar::random_with_rng(params, &mut rng), Scalar::random_with_rng(params, &mut rng));
+        let (a1, b1, c1) = generate_commitment(params, &a, &b);
+        let commitment = Commitment {
+            a1: a1.as_biguint().clone(),
+            b1: b1.as_biguint().clone(),
+            c1: c1.as_biguint().clone(),
+        };
+        let other = Commitment {
+            c1: &commitment.c1 * params.g() % params.p(),
+            ..commitment.clone()
+        };
+        prop_assert_ne!(commitment.digest(), other.digest());
+
+        let (y1, y2) = compute_y1y2(params, &Scalar::random_with_rng(params, &mut rng), &b1);
+        let (y1, y2, q) = (y1.as_biguint(), y2.as_biguint(), params.q());
+        let s = generate_session_challenge(b"session", &commitment, y1, y2, q);
+        prop_assert_ne!(&s, &generate_session_challenge(b"session", &other, y1, y2, q));
+        prop_assert_ne!(&s, &generate_session_challenge(b"other session", &commitment, y1, y2, q));
+        prop_assert_ne!(&s, &generate_challenge(params, &commitment, y1, y2));
+        let randomness = [7u8; 32];
+        let interactive = generate_interactive_challenge(&randomness, &commitment, y1, y2, q);
+        prop_assert_ne!(&interactive, &generate_interactive_challenge(&randomness, &other, y1, y2, q));
+    }
+
+    // With a challenge over (y1, y2) alone, anyone could pick y1, y2 and z and then solve for a
+    // commitment, a non-DH tuple included, that satisfies both equations. The commitment is in the
+    // transcript, so such a forgery no longer verifies.
+    #[test]
+    fn commitments_solved_for_after_the_challenge_do_not_verify(seed: u64) {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let params = params();
+        let (p, q) = (params.p(), params.q());
+        let [y1, y2, z, r] = [(); 4].map(|_| generate_random_element_with_rng(q, &mut rng));
+        let (y1, y2, b1) = (params.g().modpow(&y1, p), params.g().modpow(&y2, p), params.g().modpow(&r, p));
+
+        let mut transcript = Transcript::new(b"chaum-pedersen/commitment");
+        transcript.append_integer(b"y1", &y1);
+        transcript.append_integer(b"y2", &y2);
+        let s = transcript.challenge_scalar(b"s", q);
+        let s_inv = s.modpow(&(q - 2u32), q);
+        let solve = |base: &BigUint, y: &BigUint| (base.modpow(&z, p) * y.modpow(&(p - 2u32), p) % p).modpow(&s_inv, p);
+        let forged = ZKProof {
+            commitment: Commitment { a1: solve(params.g(), &y1), b1: b1.clone(), c1: solve(&b1, &y2) },
+            challenge: ProofChallenge { y1, y2 },
+            response: ProofResponse { z },
+            challenge_hash: s,
+        };
+
+        let element = |x: &BigUint| GroupElement::new(x, params).unwrap();
+        let Commitment { a1, b1, c1 } = &forged.commitment;
+        let (s, z) = (Scalar::new(&forged.challenge_hash, params), Scalar::new(&forged.response.z, params));
+        let (y1, y2) = (element(&forged.challenge.y1), element(&forged.challenge.y2));
+        prop_assert!(verify_proof(params, &element(b1), &y1, &y2, &element(a1), &element(c1), &s, &z));
+        prop_assert!(!Verifier::new(params.clone()).verify_proof(&forged));
+    }
+}
@@ -0,0 +1,143 @@
+use std::sync::OnceLock;
+
+use curve25519_dalek::constants::RISTRETTO_BASEPOINT_POINT;
+use curve25519_dalek::{RistrettoPoint, Scalar};
+use proptest::prelude::*;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use sha2::{Digest, Sha512};
+
+use zkp_chaum_pedersen_grpc::chaum_pedersen::PublicParameters;
+use zkp_chaum_pedersen_grpc::dleq;
+use zkp_chaum_pedersen_grpc::group::{DleqStatement, Group, Ristretto255};
+use zkp_chaum_pedersen_grpc::interop::{self, CompactDleqProof};
+use zkp_chaum_pedersen_grpc::key_exchange;
+use zkp_chaum_pedersen_grpc::voprf;
+use zkp_chaum_pedersen_grpc::vrf;
+
+fn params() -> &'static PublicParameters {
+    static PARAMS: OnceLock<PublicParameters> = OnceLock::new();
+    PARAMS.get_or_init(|| PublicParameters::new(128))
+}
+
+proptest! {
+    // A proof verifies for (g^x, h^x) and for nothing else
+    #[test]
+    fn proofs_bind_both_values(seed: u64) {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let group = params();
+        let g = group.generator();
+        let h = group.exp(&g, &group.random_scalar(&mut rng));
+        let x = group.random_scalar(&mut rng);
+        let (y1, y2) = (group.exp(&g, &x), group.exp(&h, &x));
+
+        let proof = dleq::prove_with_rng(group, &g, &h, &x, &mut rng);
+        prop_assert!(dleq::verify(group, &g, &h, &y1, &y2, &proof));
+
+        let other = group.exp(&h, &group.random_scalar(&mut rng));
+        prop_assume!(other != y2);
+        prop_assert!(!dleq::verify(group, &g, &h, &y1, &other, &proof));
+    }
+}
+
+proptest! {
+    // A VRF output is deterministic in (sk, input), and its proof does not carry over to other inputs
+    #[test]
+    fn vrf_outputs_verify_for_their_input_only(seed: u64, input: Vec<u8>, other: Vec<u8>) {
+        prop_assume!(input != other);
+        let mut rng = StdRng::seed_from_u64(seed);
+        let group = params();
+        let sk = group.random_scalar(&mut rng);
+        let pk = vrf::public_key(group, &sk);
+
+        let (output, proof) = vrf::eval_with_rng(group, &sk, &input, &mut rng);
+        prop_assert!(vrf::verify(group, &pk, &input, &output, &proof));
+        prop_assert_eq!(&vrf::eval_with_rng(group, &sk, &input, &mut rng).0, &output);
+        prop_assert!(!vrf::verify(group, &pk, &other, &output, &proof));
+    }
+}
+
+proptest! {
+    // Unblinding a checked evaluation gives what the key holder computes directly, and only an
+    // evaluation under the key behind pk is accepted
+    #[test]
+    fn voprf_outputs_match_unblinded_evaluation(seed: u64, input: Vec<u8>) {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let group = params();
+        let k = group.random_scalar(&mut rng);
+        let pk = voprf::public_key(group, &k);
+
+        let (blind, blinded) = voprf::blind_with_rng(group, &input, &mut rng);
+        let (evaluated, proof) = voprf::evaluate_with_rng(group, &k, &blinded, &mut rng);
+        let output = voprf::finalize(group, &pk, &input, &blind, &blinded, &evaluated, &proof);
+        prop_assert_eq!(output, Some(voprf::evaluate_unblinded(group, &k, &input)));
+
+        let other = group.random_scalar(&mut rng);
+        let (evaluated, proof) = voprf::evaluate_with_rng(group, &other, &blinded, &mut rng);
+        prop_assume!(other != k);
+        prop_assert_eq!(voprf::finalize(group, &pk, &input, &blind, &blinded, &evaluated, &proof), None);
+    }
+}
+
+proptest! {
+    // A DH share proof holds for the context it was made in and no other
+    #[test]
+    fn dh_share_proofs_are_bound_to_their_context(seed: u64) {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let group = params();
+        let peer = group.exp(&group.generator(), &group.random_scalar(&mut rng));
+        let a = group.random_scalar(&mut rng);
+
+        let (share, proof) = key_exchange::prove_share_with_rng(group, &peer, &a, b"handshake 1", &mut rng);
+        prop_assert!(key_exchange::verify_share(group, &share, b"handshake 1", &proof));
+        prop_assert!(!key_exchange::verify_share(group, &share, b"handshake 2", &proof));
+    }
+}
+
+proptest! {
+    // Compact proofs survive their byte encoding and are bound to the prover's domain
+    #[test]
+    fn compact_proofs_round_trip_and_bind_the_domain(seed: u64) {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let group = params();
+        let g = group.generator();
+        let h = group.exp(&g, &group.random_scalar(&mut rng));
+        let x = group.random_scalar(&mut rng);
+        let statement = DleqStatement { y1: group.exp(&g, &x), y2: group.exp(&h, &x), g, h };
+
+        let proof = interop::prove_with_rng(group, b"app", &statement, &x, &mut rng);
+        let bytes = proof.to_bytes(group);
+        prop_assert_eq!(bytes.len(), 2 * interop::scalar_len(group));
+        let decoded = CompactDleqProof::from_bytes(group, &bytes).unwrap();
+        prop_assert!(interop::verify(group, b"app", &statement, &decoded));
+        prop_assert!(!interop::verify(group, b"other app", &statement, &decoded));
+        prop_assert!(CompactDleqProof::from_bytes(group, &bytes[1..]).is_none());
+    }
+}
+
+// A proof built with curve25519-dalek alone, as a dalek-based DLEQ crate would
+#[test]
+fn dalek_built_proofs_verify_over_ristretto() {
+    let (g, h) = (RISTRETTO_BASEPOINT_POINT, RistrettoPoint::hash_from_bytes::<Sha512>(b"second base"));
+    let (x, k) = (Scalar::hash_from_bytes::<Sha512>(b"secret"), Scalar::hash_from_bytes::<Sha512>(b"nonce"));
+    let (y1, y2) = (g * x, h * x);
+    let mut hasher = Sha512::new().chain_update(b"dalek dleq");
+    for point in [g, h, y1, y2, g * k, h * k] {
+        hasher.update(point.compress().as_bytes());
+    }
+    let c = Scalar::from_hash(hasher);
+    let bytes = [c.to_bytes(), (k - c * x).to_bytes()].concat();
+
+    let group = Ristretto255::new();
+    let statement = DleqStatement { g, h, y1, y2 };
+    let proof = CompactDleqProof::from_bytes(&group, &bytes).unwrap();
+    assert!(interop::verify(&group, b"dalek dleq", &statement, &proof));
+    assert_eq!(proof.to_bytes(&group), bytes);
+
+    let forged = DleqStatement { y2: h * k, ..statement.clone() };
+    assert!(!interop::verify(&group, b"dalek dleq", &forged, &proof));
+    // s = l, one past the largest scalar
+    let mut unreduced = bytes.clone();
+    unreduced[32..].copy_from_slice(&hex::decode("edd3f55c1a631258d69cf7a2def9de1400000000000000000000000000000010").unwrap());
+    assert!(CompactDleqProof::from_bytes(&group, &unreduced).is_none());
+}
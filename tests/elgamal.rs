@@ -0,0 +1,31 @@
+use std::sync::OnceLock;
+
+use proptest::prelude::*;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use zkp_chaum_pedersen_grpc::chaum_pedersen::PublicParameters;
+use zkp_chaum_pedersen_grpc::elgamal::ElGamal;
+
+fn elgamal() -> &'static ElGamal<PublicParameters> {
+    static ELGAMAL: OnceLock<ElGamal<PublicParameters>> = OnceLock::new();
+    ELGAMAL.get_or_init(|| ElGamal::new(PublicParameters::new(128)))
+}
+
+proptest! {
+    // Sums decrypt to the sum of the plaintexts, and a proof holds for the encrypted value only
+    #[test]
+    fn encryptions_add_up_and_prove_their_value(seed: u64, a in 0u64..100, b in 0u64..100) {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let elgamal = elgamal();
+        let (sk, pk) = elgamal.generate_keypair_with_rng(&mut rng);
+
+        let (ca, ra) = elgamal.encrypt_with_rng(&pk, a, &mut rng);
+        let (cb, _) = elgamal.encrypt_with_rng(&pk, b, &mut rng);
+        prop_assert_eq!(elgamal.decrypt(&sk, &elgamal.add(&ca, &cb), 200), Some(a + b));
+
+        let proof = elgamal.prove_encryption_with_rng(&pk, &ca, a, &ra, &mut rng);
+        prop_assert!(elgamal.verify_encryption(&pk, &ca, a, &proof));
+        prop_assert!(!elgamal.verify_encryption(&pk, &ca, a + 1, &proof));
+    }
+}
@@ -0,0 +1,774 @@
+use std::io;
+use std::time::Duration;
+
+use num_bigint::BigUint;
+use tonic::{Code, Request};
+
+use zkp_chaum_pedersen_grpc::auth::{Operator, Principal};
+use zkp_chaum_pedersen_grpc::chaum_pedersen::{Commitment, MultiProver, MultiZKProof, Prover, PublicParameters};
+use zkp_chaum_pedersen_grpc::client_lib::{ClientError, ClientOptions, ParamsPin};
+use zkp_chaum_pedersen_grpc::config::ServerConfig;
+use zkp_chaum_pedersen_grpc::group::{DleqStatement, Group, GroupKind, Ristretto255};
+use zkp_chaum_pedersen_grpc::{convert, evidence, interop};
+use zkp_chaum_pedersen_grpc::service::ChaumPedersenServer;
+use zkp_chaum_pedersen_grpc::schnorr::{Schnorr, SchnorrCommitment};
+use zkp_chaum_pedersen_grpc::session::{ChallengeMode, CommitmentOrigin, MemorySessionStore, Session, SessionId, SessionState, SessionStore};
+use zkp_chaum_pedersen_grpc::testing::{test_config, TestServer};
+use zkp_chaum_pedersen_grpc::voprf::{self, EvaluationKey};
+use zkp_chaum_pedersen_grpc::zkp::chaum_pedersen_service_server::ChaumPedersenService;
+use zkp_chaum_pedersen_grpc::zkp::{self, CommitmentRequest};
+
+fn status_code(result: Result<impl std::fmt::Debug, ClientError>) -> Code {
+    match result {
+        Err(ClientError::Rpc { status, .. }) => status.code(),
+        other => panic!("expected an RPC error, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn honest_prover_is_verified() {
+    let server = TestServer::start().await.unwrap();
+    let client = server.client().await.unwrap();
+
+    assert!(client.run_protocol(256).await.unwrap().verified);
+    server.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn wrong_response_is_rejected_and_ends_the_session() {
+    let server = TestServer::start().await.unwrap();
+    let client = server.client().await.unwrap();
+
+    let session = client.initialize(256).await.unwrap();
+    let prover = Prover::new(session.params.clone());
+    let commitment = prover.generate_commitment();
+    let (values, x) = prover.generate_proof_challenge(&commitment);
+    let challenge = client.send_commitment(&session, &commitment, &values.y1, &values.y2).await.unwrap();
+
+    let z = prover.generate_response(&x, &challenge).z + 1u32;
+    assert!(!client.prove(&session, &z).await.unwrap().verified);
+    // the correct z comes too late
+    let z = prover.generate_response(&x, &challenge).z;
+    assert!(client.prove(&session, &z).await.is_err());
+}
+
+#[tokio::test]
+async fn commitment_values_cannot_be_replayed_in_another_session() {
+    let server = TestServer::start().await.unwrap();
+    let client = server.client().await.unwrap();
+
+    let session = client.initialize(256).await.unwrap();
+    assert_eq!(session.commitment_origin, CommitmentOrigin::Client);
+    let prover = Prover::new(session.params.clone());
+    let commitment = prover.generate_commitment();
+    client.register(&session, "alice", &commitment).await.unwrap();
+
+    // both sessions run over alice's registered parameters, so (y1, y2) carry over
+    let first = client.initialize_for_user("alice", ChallengeMode::FiatShamir).await.unwrap();
+    assert_eq!(first.commitment_origin, CommitmentOrigin::Registered);
+    let (values, x) = prover.generate_proof_challenge(&commitment);
+    let challenge = client.send_commitment(&first, &commitment, &values.y1, &values.y2).await.unwrap();
+    let z = prover.generate_response(&x, &challenge).z;
+    assert!(client.prove(&first, &z).await.unwrap().verified);
+
+    let second = client.initialize_for_user("alice", ChallengeMode::FiatShamir).await.unwrap();
+    let replayed = client.send_commitment(&second, &commitment, &values.y1, &values.y2).await;
+    assert_eq!(status_code(replayed), Code::AlreadyExists);
+}
+
+#[tokio::test]
+async fn only_operators_change_a_registration_without_a_proof() {
+    let server = TestServer::start_with(ServerConfig {
+        operator_api_keys: vec!["operator-key".to_string()],
+        ..test_config()
+    })
+    .await
+    .unwrap();
+    let client = server.client().await.unwrap();
+    let operator = server
+        .client_with(ClientOptions {
+            api_key: Some("operator-key".to_string()),
+            ..ClientOptions::default()
+        })
+        .await
+        .unwrap();
+
+    let session = client.initialize(256).await.unwrap();
+    let prover = Prover::new(session.params.clone());
+    client.register(&session, "alice", &prover.generate_commitment()).await.unwrap();
+
+    // knowing alice's id is not enough to swap in another commitment or revoke hers
+    let takeover = Prover::new(session.params.clone()).generate_commitment();
+    assert_eq!(status_code(client.rotate_registration(&session, "alice", &takeover).await), Code::PermissionDenied);
+    assert_eq!(status_code(client.revoke_registration("alice").await), Code::PermissionDenied);
+    assert!(!client.registration("alice").await.unwrap().revoked);
+
+    // once revoked, the id stays taken until an operator registers it again
+    assert!(operator.revoke_registration("alice").await.unwrap().revoked);
+    assert_eq!(status_code(client.register(&session, "alice", &takeover).await), Code::PermissionDenied);
+    let registration = operator.register(&session, "alice", &prover.generate_commitment()).await.unwrap();
+    assert!(!registration.revoked);
+}
+
+#[tokio::test]
+async fn the_recovery_commitment_changes_only_with_a_proof() {
+    let server = TestServer::start().await.unwrap();
+    let client = server.client().await.unwrap();
+    let session = client.initialize(256).await.unwrap();
+    let (primary, recovery) = (Prover::new(session.params.clone()), Prover::new(session.params.clone()));
+    let commitment = primary.generate_commitment();
+    let recovery_commitment = recovery.generate_commitment();
+    client.register_with_recovery(&session, "alice", &commitment, &recovery_commitment).await.unwrap();
+
+    // not even an operator can swap the escrowed commitment in without the user's proof
+    let mut request = Request::new(zkp::RegisterRequest {
+        user_id: "alice".to_string(),
+        session_id: session.session_id.clone(),
+        commitment: Some(zkp::Commitment::from(&commitment)),
+        recovery_commitment: Some(zkp::Commitment::from(&primary.generate_commitment())),
+    });
+    request.extensions_mut().insert(Operator(Principal::ApiKey("operator".to_string())));
+    let status = server.server().rotate_commitment(request).await.unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+
+    // proving the primary secret replaces both commitments
+    let user_session = client.initialize_for_user("alice", ChallengeMode::FiatShamir).await.unwrap();
+    let (values, x) = primary.generate_proof_challenge(&commitment);
+    let challenge = client.send_commitment(&user_session, &commitment, &values.y1, &values.y2).await.unwrap();
+    let z = primary.generate_response(&x, &challenge).z;
+    let new_commitment = Prover::new(session.params.clone()).generate_commitment();
+    let new_recovery = Prover::new(session.params.clone());
+    let new_recovery_commitment = new_recovery.generate_commitment();
+    let response = client
+        .rotate_secret_and_recovery(&user_session, &z, &user_session, &new_commitment, &new_recovery_commitment)
+        .await
+        .unwrap();
+    assert!(response.verified);
+
+    // the old recovery secret no longer opens the account, the new one does
+    let recovery_session = client.initialize_for_recovery("alice", ChallengeMode::FiatShamir).await.unwrap();
+    let (values, _) = recovery.generate_proof_challenge(&recovery_commitment);
+    let stale = client.send_commitment(&recovery_session, &recovery_commitment, &values.y1, &values.y2).await;
+    assert_eq!(status_code(stale), Code::FailedPrecondition);
+    let (values, _) = new_recovery.generate_proof_challenge(&new_recovery_commitment);
+    client.send_commitment(&recovery_session, &new_recovery_commitment, &values.y1, &values.y2).await.unwrap();
+}
+
+#[tokio::test]
+async fn statements_and_shares_cannot_speak_for_a_user() {
+    let server = TestServer::start().await.unwrap();
+    let client = server.client().await.unwrap();
+    let session = client.initialize(256).await.unwrap();
+    client.register(&session, "alice", &Prover::new(session.params.clone()).generate_commitment()).await.unwrap();
+
+    // a statement over a commitment the client made up must not end in "alice verified"
+    let user_session = client.initialize_for_user("alice", ChallengeMode::FiatShamir).await.unwrap();
+    let forger = Prover::new(user_session.params.clone());
+    let commitment = forger.generate_commitment();
+    let (values, _) = forger.generate_proof_challenge(&commitment);
+    let added = client.add_statement(&user_session, &commitment, &values.y1, &values.y2).await;
+    assert_eq!(status_code(added), Code::FailedPrecondition);
+    assert_eq!(status_code(client.finalize(&user_session).await), Code::FailedPrecondition);
+
+    let request = Request::new(zkp::PartialResponseRequest {
+        session_id: user_session.session_id.clone(),
+        z: vec![1],
+        ..Default::default()
+    });
+    let status = server.server().submit_partial_response(request).await.unwrap_err();
+    assert_eq!(status.code(), Code::FailedPrecondition);
+}
+
+#[tokio::test]
+async fn other_proof_kinds_cannot_speak_for_a_user() {
+    let server = TestServer::start().await.unwrap();
+    let client = server.client().await.unwrap();
+    let session = client.initialize(256).await.unwrap();
+    client.register(&session, "alice", &Prover::new(session.params.clone()).generate_commitment()).await.unwrap();
+
+    // each is refused on the session before its proof is even read
+    let user_session = client.initialize_for_user("alice", ChallengeMode::FiatShamir).await.unwrap();
+    let session_id = user_session.session_id.clone();
+    let service = server.server();
+    let codes = [
+        service
+            .verify_multi_proof(Request::new(zkp::MultiProofRequest {
+                session_id: session_id.clone(),
+                ..Default::default()
+            }))
+            .await
+            .map(|_| ()),
+        service
+            .verify_proof_batch(Request::new(zkp::ProofBatchRequest {
+                session_id: session_id.clone(),
+                ..Default::default()
+            }))
+            .await
+            .map(|_| ()),
+        service
+            .send_schnorr_commitment(Request::new(zkp::SchnorrCommitmentRequest {
+                session_id: session_id.clone(),
+                ..Default::default()
+            }))
+            .await
+            .map(|_| ()),
+        service
+            .verify_schnorr_proof(Request::new(zkp::SchnorrVerifyRequest {
+                session_id: session_id.clone(),
+                ..Default::default()
+            }))
+            .await
+            .map(|_| ()),
+        service
+            .verify_dleq_proof(Request::new(zkp::DleqProofRequest {
+                session_id: session_id.clone(),
+                ..Default::default()
+            }))
+            .await
+            .map(|_| ()),
+        service
+            .verify_compact_dleq_proof(Request::new(zkp::CompactDleqProofRequest {
+                session_id: session_id.clone(),
+                ..Default::default()
+            }))
+            .await
+            .map(|_| ()),
+        service
+            .verify_dh_share(Request::new(zkp::DhShareRequest {
+                session_id: session_id.clone(),
+                ..Default::default()
+            }))
+            .await
+            .map(|_| ()),
+        service
+            .verify_range_proof(Request::new(zkp::RangeProofRequest {
+                session_id: session_id.clone(),
+                ..Default::default()
+            }))
+            .await
+            .map(|_| ()),
+    ]
+    .map(|result| result.unwrap_err().code());
+    assert_eq!(codes, [Code::FailedPrecondition; 8]);
+}
+
+#[tokio::test]
+async fn one_shot_proofs_only_verify_in_their_session() {
+    // with no replay cache, as after a restart, only the binding keeps a proof from being reused
+    let server = TestServer::start_with(ServerConfig {
+        replay_cache_size: 0,
+        ..test_config()
+    })
+    .await
+    .unwrap();
+    let client = server.client().await.unwrap();
+    let session = client.initialize(256).await.unwrap();
+    let prover = Prover::new(session.params.clone());
+    client.register(&session, "alice", &prover.generate_commitment()).await.unwrap();
+
+    let first = client.initialize_for_user("alice", ChallengeMode::FiatShamir).await.unwrap();
+    let proof = prover.create_session_proof(&first.session_id);
+    assert!(client.prove_one_shot(&first, &proof).await.unwrap().verified);
+
+    let second = client.initialize_for_user("alice", ChallengeMode::FiatShamir).await.unwrap();
+    assert!(!client.prove_one_shot(&second, &proof).await.unwrap().verified);
+    let third = client.initialize_for_user("alice", ChallengeMode::FiatShamir).await.unwrap();
+    assert!(!client.prove_one_shot(&third, &prover.create_proof()).await.unwrap().verified);
+}
+
+async fn verify_multi(server: &TestServer, session_id: &str, proof: &MultiZKProof) -> Result<bool, Code> {
+    let request = Request::new(zkp::MultiProofRequest {
+        session_id: session_id.to_string(),
+        proof: Some(zkp::MultiStatementProof::from(proof)),
+    });
+    server.server().verify_multi_proof(request).await.map(|response| response.into_inner().verified).map_err(|status| status.code())
+}
+
+// A store that gives every session the parameters of the first, as a parameter cache shared by
+// many sessions does, so a proof made for one session is over the parameters of the next
+#[derive(Debug, Default)]
+struct SharedParamsStore {
+    inner: MemorySessionStore,
+    params: std::sync::Mutex<Option<PublicParameters>>,
+}
+
+impl SessionStore for SharedParamsStore {
+    fn get(&self, id: &SessionId) -> io::Result<Option<Session>> {
+        self.inner.get(id)
+    }
+    fn put(&self, id: &SessionId, session: &Session) -> io::Result<()> {
+        let mut shared = self.params.lock().unwrap();
+        if shared.is_none() {
+            *shared = session.params.clone();
+        }
+        let mut session = session.clone();
+        session.params = shared.clone();
+        self.inner.put(id, &session)
+    }
+    fn remove(&self, id: &SessionId) -> io::Result<Option<Session>> {
+        self.inner.remove(id)
+    }
+    fn sweep(&self, ttl: Duration) -> io::Result<usize> {
+        self.inner.sweep(ttl)
+    }
+    fn len(&self) -> io::Result<usize> {
+        self.inner.len()
+    }
+    fn clear(&self) -> io::Result<()> {
+        self.inner.clear()
+    }
+    fn is_durable(&self) -> bool {
+        false
+    }
+}
+
+#[tokio::test]
+async fn multi_statement_proofs_only_verify_in_their_session() {
+    for replay_cache_size in [0, 1000] {
+        let config = ServerConfig {
+            replay_cache_size,
+            ..test_config()
+        };
+        let store = SharedParamsStore::default();
+        let server = ChaumPedersenServer::from_config(&config).with_session_store(Box::new(store));
+        let server = TestServer::serve(server, &config).await.unwrap();
+        let client = server.client().await.unwrap();
+        let first = client.initialize(256).await.unwrap();
+        let params = first.params.clone();
+        let bases = vec![params.g().clone(), params.g() * params.g() % params.p()];
+        let prover = MultiProver::from_bases(params, 7u32.into(), bases);
+
+        let proof = prover.create_session_proof(&first.session_id);
+        assert_eq!(verify_multi(&server, &first.session_id, &proof).await, Ok(true));
+        let second = client.initialize(256).await.unwrap();
+        assert_eq!(verify_multi(&server, &second.session_id, &prover.create_proof()).await, Ok(false));
+        // with no replay cache, as after a restart, only the binding keeps the proof from verifying
+        // elsewhere; with one, its statements are refused before it is checked
+        let replayed = verify_multi(&server, &second.session_id, &proof).await;
+        let expected = if replay_cache_size == 0 { Ok(false) } else { Err(Code::AlreadyExists) };
+        assert_eq!(replayed, expected);
+    }
+}
+
+async fn schnorr_challenge(server: &TestServer, session_id: &str, y: &BigUint, t: &BigUint) -> BigUint {
+    let request = Request::new(zkp::SchnorrCommitmentRequest {
+        session_id: session_id.to_string(),
+        y: y.to_bytes_be(),
+        t: t.to_bytes_be(),
+    });
+    let response = server.server().send_schnorr_commitment(request).await.unwrap();
+    BigUint::from_bytes_be(&response.into_inner().challenge)
+}
+
+async fn verify_schnorr(server: &TestServer, session_id: &str, s: &BigUint) -> bool {
+    let request = Request::new(zkp::SchnorrVerifyRequest {
+        session_id: session_id.to_string(),
+        s: s.to_bytes_be(),
+    });
+    server.server().verify_schnorr_proof(request).await.unwrap().into_inner().verified
+}
+
+#[tokio::test]
+async fn schnorr_answers_only_verify_in_their_session() {
+    let config = test_config();
+    let server = ChaumPedersenServer::from_config(&config).with_session_store(Box::new(SharedParamsStore::default()));
+    let server = TestServer::serve(server, &config).await.unwrap();
+    let client = server.client().await.unwrap();
+    let first = client.initialize(256).await.unwrap();
+    let schnorr = Schnorr::new(first.params.clone());
+    let (x, statement) = schnorr.generate_keypair();
+    let (k, nonce) = schnorr.generate_keypair();
+    let commitment = SchnorrCommitment { t: nonce.y };
+
+    let challenge = schnorr_challenge(&server, &first.session_id, &statement.y, &commitment.t).await;
+    assert_eq!(challenge, schnorr.session_challenge(&first.session_id, &statement, &commitment));
+    let s = schnorr.params.mul_add(&k, &x, &challenge);
+    assert!(verify_schnorr(&server, &first.session_id, &s).await);
+
+    // the same (y, t) gets another challenge in the next session, which the recorded s does not answer
+    let second = client.initialize(256).await.unwrap();
+    let replayed = schnorr_challenge(&server, &second.session_id, &statement.y, &commitment.t).await;
+    assert_ne!(replayed, challenge);
+    assert!(!verify_schnorr(&server, &second.session_id, &s).await);
+}
+
+#[tokio::test]
+async fn the_commitment_sent_is_the_one_verified() {
+    let server = TestServer::start().await.unwrap();
+    let client = server.client().await.unwrap();
+    let session = client.initialize(256).await.unwrap();
+    let prover = Prover::new(session.params.clone());
+    let commitment = prover.generate_commitment();
+    let (values, x) = prover.generate_proof_challenge(&commitment);
+
+    // a session without a registered user has no commitment but the one its client sends
+    let request = Request::new(CommitmentRequest {
+        session_id: session.session_id.clone(),
+        commitment: None,
+        challenge_values: Some(zkp::ProofChallenge::from(&values)),
+    });
+    let missing = server.server().send_commitment(request).await.unwrap_err();
+    assert_eq!(missing.code(), Code::InvalidArgument);
+
+    let challenge = client.send_commitment(&session, &commitment, &values.y1, &values.y2).await.unwrap();
+    assert_eq!(client.send_commitment(&session, &commitment, &values.y1, &values.y2).await.unwrap(), challenge);
+    let other = Prover::new(session.params.clone()).generate_commitment();
+    let swapped = client.send_commitment(&session, &other, &values.y1, &values.y2).await;
+    assert_eq!(status_code(swapped), Code::FailedPrecondition);
+
+    let z = prover.generate_response(&x, &challenge).z;
+    assert!(client.prove(&session, &z).await.unwrap().verified);
+}
+
+#[tokio::test]
+async fn expired_sessions_are_not_found() {
+    let server = TestServer::start_with(ServerConfig {
+        session_ttl_secs: 1,
+        ..test_config()
+    })
+    .await
+    .unwrap();
+    let client = server.client().await.unwrap();
+
+    let session = client.initialize(256).await.unwrap();
+    let prover = Prover::new(session.params.clone());
+    let commitment = prover.generate_commitment();
+    let (values, _) = prover.generate_proof_challenge(&commitment);
+    // expiry counts whole seconds past the TTL
+    tokio::time::sleep(Duration::from_millis(2500)).await;
+
+    let late = client.send_commitment(&session, &commitment, &values.y1, &values.y2).await;
+    assert_eq!(status_code(late), Code::NotFound);
+}
+
+#[tokio::test]
+async fn late_responses_find_the_challenge_expired() {
+    let server = TestServer::start_with(ServerConfig {
+        challenge_ttl_secs: 1,
+        ..test_config()
+    })
+    .await
+    .unwrap();
+    let client = server.client().await.unwrap();
+
+    let session = client.initialize(256).await.unwrap();
+    let prover = Prover::new(session.params.clone());
+    let commitment = prover.generate_commitment();
+    let (values, x) = prover.generate_proof_challenge(&commitment);
+    let challenge = client.send_commitment(&session, &commitment, &values.y1, &values.y2).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(2500)).await;
+
+    // refused without retries, and the same values get no second challenge
+    let z = prover.generate_response(&x, &challenge).z;
+    assert_eq!(status_code(client.prove(&session, &z).await), Code::DeadlineExceeded);
+    let resent = client.send_commitment(&session, &commitment, &values.y1, &values.y2).await;
+    assert_eq!(status_code(resent), Code::DeadlineExceeded);
+
+    let (values, x) = prover.generate_proof_challenge(&commitment);
+    let challenge = client.send_commitment(&session, &commitment, &values.y1, &values.y2).await.unwrap();
+    let z = prover.generate_response(&x, &challenge).z;
+    assert!(client.prove(&session, &z).await.unwrap().verified);
+}
+
+#[tokio::test]
+async fn api_keys_are_enforced() {
+    let server = TestServer::start_with(ServerConfig {
+        api_keys: vec!["test-key".to_string()],
+        ..test_config()
+    })
+    .await
+    .unwrap();
+
+    let anonymous = server.client().await.unwrap();
+    assert_eq!(status_code(anonymous.initialize(256).await), Code::Unauthenticated);
+
+    let client = server
+        .client_with(ClientOptions {
+            api_key: Some("test-key".to_string()),
+            ..ClientOptions::default()
+        })
+        .await
+        .unwrap();
+    assert!(client.run_protocol(256).await.unwrap().verified);
+}
+
+#[tokio::test]
+async fn session_ids_are_checked_at_the_boundary() {
+    let server = TestServer::start().await.unwrap();
+    let client = server.client().await.unwrap();
+
+    let id = client.initialize(256).await.unwrap().session_id;
+    assert_eq!(id.parse::<SessionId>().unwrap().to_string(), id);
+    // only the canonical spelling parses, so each session has one key
+    assert!(id.to_uppercase().parse::<SessionId>().is_err());
+    assert!("0123456789abcdef".parse::<SessionId>().is_err());
+
+    assert_eq!(status_code(client.cancel("not-a-session").await), Code::InvalidArgument);
+    let unknown = SessionId::generate().to_string();
+    assert_eq!(status_code(client.cancel(&unknown).await), Code::NotFound);
+
+    // the proof RPCs refuse a missing ID as malformed and an unknown one as not found
+    let session = client.initialize(256).await.unwrap();
+    let prover = Prover::new(session.params.clone());
+    let commitment = prover.generate_commitment();
+    let (values, _) = prover.generate_proof_challenge(&commitment);
+    for (session_id, code) in [("", Code::InvalidArgument), (unknown.as_str(), Code::NotFound)] {
+        let request = Request::new(CommitmentRequest {
+            session_id: session_id.to_string(),
+            commitment: Some(zkp::Commitment::from(&commitment)),
+            challenge_values: Some(zkp::ProofChallenge::from(&values)),
+        });
+        assert_eq!(server.server().send_commitment(request).await.unwrap_err().code(), code);
+        let request = Request::new(zkp::VerifyProofRequest {
+            session_id: session_id.to_string(),
+            z: vec![1].into(),
+        });
+        assert_eq!(server.server().verify_proof(request).await.unwrap_err().code(), code);
+    }
+    let request = Request::new(zkp::VerifyProofRequest { session_id: String::new(), z: vec![1].into() });
+    assert_eq!(server.server().verify_proof(request).await.unwrap_err().message(), "Missing session ID");
+}
+
+#[tokio::test]
+async fn compressed_sessions_expand_elements_on_arrival() {
+    let server = TestServer::start().await.unwrap();
+    let plain = server.client().await.unwrap();
+    assert!(!plain.initialize(256).await.unwrap().compressed_elements);
+    let client = server
+        .client_with(ClientOptions {
+            compressed_elements: true,
+            ..ClientOptions::default()
+        })
+        .await
+        .unwrap();
+    assert!(client.run_protocol(256).await.unwrap().verified);
+
+    // a user's session compresses the registered commitment it stands in for too
+    let session = client.initialize(256).await.unwrap();
+    let prover = Prover::new(session.params.clone());
+    client.register(&session, "alice", &prover.generate_commitment()).await.unwrap();
+    let session = client.initialize_for_user("alice", ChallengeMode::FiatShamir).await.unwrap();
+    assert!(session.compressed_elements);
+    let commitment = prover.generate_commitment();
+    let (values, x) = prover.generate_proof_challenge(&commitment);
+    let request = Request::new(CommitmentRequest {
+        session_id: session.session_id.clone(),
+        commitment: None,
+        challenge_values: Some(convert::compress_challenge_values(&values, &session.params)),
+    });
+    let response = server.server().send_commitment(request).await.unwrap().into_inner();
+    let challenge = convert::decode_integer("challenge", &response.challenge).unwrap();
+    assert!(client.prove(&session, &prover.generate_response(&x, &challenge).z).await.unwrap().verified);
+
+    // representatives lie in [1, q], so anything above q is no compressed element
+    let session = client.initialize(256).await.unwrap();
+    let prover = Prover::new(session.params.clone());
+    let commitment = prover.generate_commitment();
+    let (values, _) = prover.generate_proof_challenge(&commitment);
+    let mut values = convert::compress_challenge_values(&values, &session.params);
+    values.y1 = convert::encode_integer(&(session.params.q() + 1u32));
+    let request = Request::new(CommitmentRequest {
+        session_id: session.session_id,
+        commitment: Some(convert::compress_commitment(&commitment, &session.params)),
+        challenge_values: Some(values),
+    });
+    let status = server.server().send_commitment(request).await.unwrap_err();
+    assert_eq!((status.code(), status.message()), (Code::InvalidArgument, "invalid y1: not a compressed element"));
+}
+
+#[tokio::test]
+async fn pinned_parameters_cannot_be_swapped() {
+    let server = TestServer::start().await.unwrap();
+    let client = server.client().await.unwrap();
+    let session = client.initialize(256).await.unwrap();
+    let prover = Prover::new(session.params.clone());
+    client.register(&session, "alice", &prover.generate_commitment()).await.unwrap();
+    let fingerprint = session.params.digest();
+
+    // the first session's parameters are trusted, and a user's sessions keep running over them
+    let tofu = server
+        .client_with(ClientOptions {
+            params_pin: ParamsPin::FirstSeen,
+            ..ClientOptions::default()
+        })
+        .await
+        .unwrap();
+    tofu.initialize_for_user("alice", ChallengeMode::FiatShamir).await.unwrap();
+    tofu.initialize_for_user("alice", ChallengeMode::FiatShamir).await.unwrap();
+    assert_eq!(tofu.pinned_params(), Some(fingerprint));
+    // freshly generated parameters are not the pinned ones
+    match tofu.initialize(256).await {
+        Err(ClientError::ParametersChanged { expected, .. }) => assert_eq!(expected, fingerprint),
+        other => panic!("expected a pin mismatch, got {:?}", other),
+    }
+
+    let pinned = |fingerprint| {
+        server.client_with(ClientOptions {
+            params_pin: ParamsPin::Fingerprint(fingerprint),
+            ..ClientOptions::default()
+        })
+    };
+    let client = pinned(fingerprint).await.unwrap();
+    client.initialize_for_user("alice", ChallengeMode::FiatShamir).await.unwrap();
+    let client = pinned([0; 32]).await.unwrap();
+    let result = client.initialize_for_user("alice", ChallengeMode::FiatShamir).await;
+    assert!(matches!(result, Err(ClientError::ParametersChanged { .. })));
+}
+
+// A store that swaps in another commitment once a challenge has been issued, as an attacker with
+// write access to the session database might
+#[derive(Debug)]
+struct SwappingStore {
+    inner: MemorySessionStore,
+    replacement: Commitment,
+}
+
+impl SessionStore for SwappingStore {
+    fn get(&self, id: &SessionId) -> io::Result<Option<Session>> {
+        self.inner.get(id)
+    }
+    fn put(&self, id: &SessionId, session: &Session) -> io::Result<()> {
+        let mut session = session.clone();
+        if let SessionState::AwaitingResponse(exchange) = &mut session.state {
+            exchange.commitment = self.replacement.clone();
+        }
+        self.inner.put(id, &session)
+    }
+    fn remove(&self, id: &SessionId) -> io::Result<Option<Session>> {
+        self.inner.remove(id)
+    }
+    fn sweep(&self, ttl: Duration) -> io::Result<usize> {
+        self.inner.sweep(ttl)
+    }
+    fn len(&self) -> io::Result<usize> {
+        self.inner.len()
+    }
+    fn clear(&self) -> io::Result<()> {
+        self.inner.clear()
+    }
+    fn is_durable(&self) -> bool {
+        false
+    }
+}
+
+#[tokio::test]
+async fn challenges_are_bound_to_their_commitment() {
+    let server = TestServer::start().await.unwrap();
+    let client = server.client().await.unwrap();
+    let session = client.initialize(256).await.unwrap();
+    let prover = Prover::new(session.params.clone());
+    let commitment = prover.generate_commitment();
+    let (values, x) = prover.generate_proof_challenge(&commitment);
+    let challenge = client.send_commitment(&session, &commitment, &values.y1, &values.y2).await.unwrap();
+    assert!(client.prove(&session, &prover.generate_response(&x, &challenge).z).await.unwrap().verified);
+
+    // the signed transcript names the commitment the challenge was derived over
+    let transcript = evidence::open(&client.transcript(&session).await.unwrap(), None).unwrap();
+    assert_eq!(transcript.commitment_digest, commitment.digest());
+
+    // a run whose stored commitment changed after the challenge is refused, not verified
+    let config = test_config();
+    let other = Prover::new(session.params.clone()).generate_commitment();
+    let store = SwappingStore {
+        inner: MemorySessionStore::new(),
+        replacement: other,
+    };
+    let server = TestServer::serve(ChaumPedersenServer::from_config(&config).with_session_store(Box::new(store)), &config)
+        .await
+        .unwrap();
+    let client = server.client().await.unwrap();
+    let session = client.initialize(256).await.unwrap();
+    let prover = Prover::new(session.params.clone());
+    let commitment = prover.generate_commitment();
+    let (values, x) = prover.generate_proof_challenge(&commitment);
+    let challenge = client.send_commitment(&session, &commitment, &values.y1, &values.y2).await.unwrap();
+    let z = prover.generate_response(&x, &challenge).z;
+    assert_eq!(status_code(client.prove(&session, &z).await), Code::FailedPrecondition);
+}
+
+#[tokio::test]
+async fn compact_dleq_proofs_verify_in_ristretto_sessions() {
+    let server = TestServer::start().await.unwrap();
+    let client = server.client().await.unwrap();
+    let group = Ristretto255::new();
+    let g = group.generator();
+    let h = group.hash_to_element(b"test", b"h");
+    let x = group.random_scalar(&mut rand::rngs::OsRng);
+    let statement = DleqStatement { y1: group.exp(&g, &x), y2: group.exp(&h, &x), g, h };
+
+    let session = client.initialize_group(GroupKind::Ristretto255).await.unwrap();
+    let domain = interop::session_domain(b"wallet", &session.session_id);
+    let proof = interop::prove(&group, &domain, &statement, &x);
+    let other = interop::session_domain(b"other", &session.session_id);
+    let wrong_domain = client
+        .verify_compact_dleq(&session.session_id, group.clone(), &other, &statement, &proof)
+        .await
+        .unwrap();
+    assert!(!wrong_domain.verified);
+    let verified = client
+        .verify_compact_dleq(&session.session_id, group.clone(), &domain, &statement, &proof)
+        .await
+        .unwrap();
+    assert!(verified.verified);
+    // the session is consumed
+    let again = client.verify_compact_dleq(&session.session_id, group, &domain, &statement, &proof).await;
+    assert_eq!(status_code(again), Code::NotFound);
+    server.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn compact_dleq_proofs_only_verify_in_their_session() {
+    let server = TestServer::start().await.unwrap();
+    let client = server.client().await.unwrap();
+    let group = Ristretto255::new();
+    let g = group.generator();
+    let h = group.hash_to_element(b"test", b"h");
+    let x = group.random_scalar(&mut rand::rngs::OsRng);
+    let statement = DleqStatement { y1: group.exp(&g, &x), y2: group.exp(&h, &x), g, h };
+
+    let first = client.initialize_group(GroupKind::Ristretto255).await.unwrap();
+    let domain = interop::session_domain(b"wallet", &first.session_id);
+    let proof = interop::prove(&group, &domain, &statement, &x);
+    assert!(client.verify_compact_dleq(&first.session_id, group.clone(), &domain, &statement, &proof).await.unwrap().verified);
+
+    // a captured proof names its session, so another session refuses it before checking it
+    let second = client.initialize_group(GroupKind::Ristretto255).await.unwrap();
+    let replayed = client.verify_compact_dleq(&second.session_id, group.clone(), &domain, &statement, &proof).await;
+    assert_eq!(status_code(replayed), Code::InvalidArgument);
+    // and a proof under a domain that names no session is refused in every one
+    let unbound = interop::prove(&group, b"wallet", &statement, &x);
+    let result = client.verify_compact_dleq(&second.session_id, group, b"wallet", &statement, &unbound).await;
+    assert_eq!(status_code(result), Code::InvalidArgument);
+}
+
+#[tokio::test]
+async fn oprf_evaluations_are_checked_against_the_published_key() {
+    let key = EvaluationKey::from_seed(&[9; 32]);
+    let config = test_config();
+    let server = TestServer::serve(ChaumPedersenServer::from_config(&config).with_oprf_key(key.clone()), &config)
+        .await
+        .unwrap();
+    let client = server.client().await.unwrap();
+    let group = Ristretto255::new();
+
+    let pk = client.oprf_public_key(GroupKind::Ristretto255, &group).await.unwrap();
+    assert_eq!(pk, key.public_key(&group));
+    let inputs: [&[u8]; 2] = [b"token 1", b"token 2"];
+    let outputs = client.evaluate_oprf(GroupKind::Ristretto255, group.clone(), &pk, &inputs).await.unwrap();
+    for (input, output) in inputs.iter().zip(&outputs) {
+        assert_eq!(*output, voprf::evaluate_unblinded(&group, &key.secret(&group), input));
+    }
+    assert_ne!(outputs[0], outputs[1]);
+
+    // evaluations under another key are refused
+    let other = EvaluationKey::from_seed(&[10; 32]).public_key(&group);
+    let result = client.evaluate_oprf(GroupKind::Ristretto255, group, &other, &inputs).await;
+    assert!(matches!(result, Err(ClientError::InvalidResponse(_))), "{:?}", result);
+
+    let modp = zkp::OprfPublicKeyRequest {
+        group: zkp::GroupKind::GroupModp.into(),
+    };
+    let status = server.server().get_oprf_public_key(Request::new(modp)).await.unwrap_err();
+    assert_eq!(status.code(), Code::FailedPrecondition);
+    server.shutdown().await.unwrap();
+}
@@ -0,0 +1,96 @@
+use std::ptr;
+use std::sync::OnceLock;
+
+use zkp_chaum_pedersen_grpc::ffi::*;
+use zkp_chaum_pedersen_grpc::{Prover, PublicParameters};
+
+fn params() -> &'static PublicParameters {
+    static PARAMS: OnceLock<PublicParameters> = OnceLock::new();
+    PARAMS.get_or_init(|| PublicParameters::new(256))
+}
+
+fn empty() -> ZkpBuffer {
+    ZkpBuffer { data: ptr::null_mut(), len: 0 }
+}
+
+// Copies a library buffer out and frees it
+fn take(buffer: ZkpBuffer) -> Vec<u8> {
+    let bytes = unsafe { std::slice::from_raw_parts(buffer.data, buffer.len) }.to_vec();
+    unsafe { zkp_buffer_free(buffer) };
+    bytes
+}
+
+fn load(params: &PublicParameters) -> Result<*mut ZkpParams, ZkpStatus> {
+    let [p, q, g] = [params.p(), params.q(), params.g()].map(|value| value.to_bytes_be());
+    let mut out = ptr::null_mut();
+    let status = unsafe { zkp_params_from_bytes(p.as_ptr(), p.len(), q.as_ptr(), q.len(), g.as_ptr(), g.len(), &mut out) };
+    match status {
+        ZkpStatus::Ok => Ok(out),
+        status => Err(status),
+    }
+}
+
+fn verify(handle: *const ZkpParams, proof: &[u8]) -> Result<bool, ZkpStatus> {
+    let mut verified = false;
+    match unsafe { zkp_verify_proof(handle, proof.as_ptr(), proof.len(), &mut verified) } {
+        ZkpStatus::Ok => Ok(verified),
+        status => Err(status),
+    }
+}
+
+#[test]
+fn proofs_round_trip_through_the_c_api() {
+    let handle = load(params()).unwrap();
+    let prover = Prover::new(params().clone());
+    let (a, b) = (prover.secret_a.to_bytes_be(), prover.secret_b.to_bytes_be());
+
+    let mut proof = empty();
+    let status = unsafe { zkp_create_proof(handle, a.as_ptr(), a.len(), b.as_ptr(), b.len(), &mut proof) };
+    assert_eq!(status, ZkpStatus::Ok);
+    let mut proof = take(proof);
+    assert_eq!(verify(handle, &proof), Ok(true));
+
+    // the proof names the prover's commitment
+    let (mut a1, mut b1, mut c1) = (empty(), empty(), empty());
+    assert_eq!(unsafe { zkp_proof_commitment(proof.as_ptr(), proof.len(), &mut a1, &mut b1, &mut c1) }, ZkpStatus::Ok);
+    let commitment = prover.generate_commitment();
+    assert_eq!([take(a1), take(b1), take(c1)], [&commitment.a1, &commitment.b1, &commitment.c1].map(|v| v.to_bytes_be()));
+
+    // a changed byte either fails to decode or fails to verify
+    let last = proof.len() - 1;
+    proof[last] ^= 1;
+    assert_eq!(verify(handle, &proof), Ok(false));
+    assert_eq!(verify(handle, &proof[1..]), Err(ZkpStatus::InvalidProof));
+
+    unsafe { zkp_params_free(handle) };
+}
+
+#[test]
+fn bad_inputs_are_reported() {
+    // p = 2q + 1 does not hold
+    let wrong = PublicParameters::from_parts(params().p().clone(), params().q() + 2u32, params().g().clone(), None);
+    assert_eq!(load(&wrong), Err(ZkpStatus::InvalidParameters));
+
+    let handle = load(params()).unwrap();
+    let q = params().q().to_bytes_be();
+    let one = [1u8];
+    let mut proof = empty();
+    let status = unsafe { zkp_create_proof(handle, q.as_ptr(), q.len(), one.as_ptr(), one.len(), &mut proof) };
+    assert_eq!(status, ZkpStatus::InvalidSecret);
+    let status = unsafe { zkp_create_proof(handle, ptr::null(), 1, one.as_ptr(), one.len(), &mut proof) };
+    assert_eq!(status, ZkpStatus::NullPointer);
+    assert!(proof.data.is_null());
+
+    let mut out = ptr::null_mut();
+    assert_eq!(unsafe { zkp_params_generate(64, &mut out) }, ZkpStatus::InvalidParameters);
+    unsafe { zkp_params_free(handle) };
+}
+
+#[test]
+fn parameters_export_as_bytes() {
+    let handle = load(params()).unwrap();
+    let (mut p, mut q, mut g) = (empty(), empty(), empty());
+    assert_eq!(unsafe { zkp_params_to_bytes(handle, &mut p, &mut q, &mut g) }, ZkpStatus::Ok);
+    assert_eq!([take(p), take(q), take(g)], [params().p(), params().q(), params().g()].map(|v| v.to_bytes_be()));
+    unsafe { zkp_params_free(handle) };
+}
@@ -0,0 +1,94 @@
+use std::net::{SocketAddr, TcpListener};
+use std::sync::Arc;
+
+use num_bigint::BigUint;
+use serde_json::{json, Value};
+use tokio::sync::oneshot;
+
+use zkp_chaum_pedersen_grpc::auth::Authenticator;
+use zkp_chaum_pedersen_grpc::gateway;
+use zkp_chaum_pedersen_grpc::service::ChaumPedersenServer;
+use zkp_chaum_pedersen_grpc::session::SessionId;
+use zkp_chaum_pedersen_grpc::testing::test_config;
+use zkp_chaum_pedersen_grpc::{Prover, PublicParameters};
+
+// A gateway on an ephemeral loopback port; dropping the sender stops it
+fn start(authenticator: Authenticator) -> (SocketAddr, oneshot::Sender<()>) {
+    let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = Arc::new(ChaumPedersenServer::from_config(&test_config()));
+    let (stop, stopped) = oneshot::channel::<()>();
+    tokio::spawn(gateway::serve(listener, gateway::router(server, authenticator), async {
+        let _ = stopped.await;
+    }));
+    (addr, stop)
+}
+
+async fn post(addr: SocketAddr, path: &str, body: Value, api_key: Option<&str>) -> (u16, Value) {
+    let mut request = reqwest::Client::new()
+        .post(format!("http://{}{}", addr, path))
+        .header("content-type", "application/json")
+        .body(body.to_string());
+    if let Some(key) = api_key {
+        request = request.header("x-api-key", key);
+    }
+    let response = request.send().await.unwrap();
+    let status = response.status().as_u16();
+    (status, serde_json::from_slice(&response.bytes().await.unwrap()).unwrap())
+}
+
+fn integer(value: &Value) -> BigUint {
+    BigUint::parse_bytes(value.as_str().unwrap().as_bytes(), 16).unwrap()
+}
+
+fn hex(value: &BigUint) -> String {
+    hex::encode(value.to_bytes_be())
+}
+
+#[tokio::test]
+async fn a_proof_runs_over_json() {
+    let (addr, _stop) = start(Authenticator::default());
+
+    let (status, session) = post(addr, "/v1/initialize", json!({ "bit_size": 256 }), None).await;
+    assert_eq!(status, 200);
+    assert_eq!(session["challenge_mode"], "CHALLENGE_FIAT_SHAMIR");
+    assert_eq!(session["commitment_origin"], "COMMITMENT_CLIENT");
+    let params = &session["params"];
+    let params = PublicParameters::from_parts(integer(&params["p"]), integer(&params["q"]), integer(&params["g"]), None);
+    assert_eq!(session["params_fingerprint"], hex::encode(params.digest()));
+
+    let prover = Prover::new(params);
+    let commitment = prover.generate_commitment();
+    let (values, x) = prover.generate_proof_challenge(&commitment);
+    let body = json!({
+        "session_id": session["session_id"],
+        "commitment": { "a1": hex(&commitment.a1), "b1": hex(&commitment.b1), "c1": hex(&commitment.c1) },
+        "challenge_values": { "y1": hex(&values.y1), "y2": hex(&values.y2) },
+    });
+    let (status, challenge) = post(addr, "/v1/challenge", body, None).await;
+    assert_eq!(status, 200);
+
+    let z = prover.generate_response(&x, &integer(&challenge["challenge"])).z;
+    let body = json!({ "session_id": session["session_id"], "z": hex(&z) });
+    let (status, verdict) = post(addr, "/v1/verify", body, None).await;
+    assert_eq!(status, 200);
+    assert_eq!(verdict["verified"], true);
+}
+
+#[tokio::test]
+async fn failures_map_to_http_statuses() {
+    let (addr, _stop) = start(Authenticator::new(vec!["secret".to_string()], Default::default()));
+
+    let (status, error) = post(addr, "/v1/initialize", json!({ "bit_size": 256 }), None).await;
+    assert_eq!((status, error["code"].as_i64()), (401, Some(16)));
+
+    // an unknown session, a malformed body, an unknown enum name and a bit size the policy refuses
+    let body = json!({ "session_id": SessionId::generate().to_string(), "z": "01" });
+    let (status, error) = post(addr, "/v1/verify", body, Some("secret")).await;
+    assert_eq!((status, error["code"].as_i64()), (404, Some(5)));
+    let body = json!({ "session_id": "x", "z": "not hex" });
+    assert_eq!(post(addr, "/v1/verify", body, Some("secret")).await.0, 400);
+    let body = json!({ "bit_size": 256, "challenge_mode": "CHALLENGE_SOMETIMES" });
+    assert_eq!(post(addr, "/v1/initialize", body, Some("secret")).await.0, 400);
+    assert_eq!(post(addr, "/v1/initialize", json!({ "bit_size": 64 }), Some("secret")).await.0, 400);
+}
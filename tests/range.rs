@@ -0,0 +1,31 @@
+use std::sync::OnceLock;
+
+use num_bigint::BigUint;
+use proptest::prelude::*;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use zkp_chaum_pedersen_grpc::chaum_pedersen::PublicParameters;
+use zkp_chaum_pedersen_grpc::pedersen::Pedersen;
+use zkp_chaum_pedersen_grpc::range;
+
+fn pedersen() -> &'static Pedersen {
+    static PEDERSEN: OnceLock<Pedersen> = OnceLock::new();
+    PEDERSEN.get_or_init(|| Pedersen::new(PublicParameters::new(128).with_second_generator(b"range tests")).unwrap())
+}
+
+proptest! {
+    // Values inside the range prove, values outside it cannot, and a proof covers only its own range
+    #[test]
+    fn proofs_hold_for_values_in_range(seed: u64, m in 0u64..1 << 12, bits in 1u32..16) {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let (commitment, opening) = pedersen().commit_random_with_rng(&BigUint::from(m), &mut rng);
+
+        let proof = range::prove_with_rng(pedersen(), &commitment, &opening, bits, &mut rng);
+        prop_assert_eq!(proof.is_some(), m < 1 << bits);
+        if let Some(proof) = proof {
+            prop_assert!(range::verify(pedersen(), &commitment, bits, &proof));
+            prop_assert!(!range::verify(pedersen(), &commitment, bits + 1, &proof));
+        }
+    }
+}
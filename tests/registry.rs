@@ -0,0 +1,42 @@
+use proptest::prelude::*;
+
+use zkp_chaum_pedersen_grpc::registry::{MemoryRegistryStore, Recovery, Registration, RegistryStore};
+use zkp_chaum_pedersen_grpc::strategies;
+
+proptest! {
+    #[test]
+    fn registrations_only_change_from_the_version_read(first in strategies::prover(), second in strategies::prover()) {
+        let store = MemoryRegistryStore::new();
+        let registration = Registration::new(first.params.clone(), first.generate_commitment());
+        prop_assert!(store.insert("user", &registration).unwrap());
+        prop_assert!(!store.insert("user", &registration).unwrap());
+
+        let commitment = second.generate_commitment();
+        let rotated = registration.rotated(second.params.clone(), commitment.clone());
+        prop_assert!(store.replace("user", registration.version, &rotated).unwrap());
+        // a writer that read the old version loses
+        prop_assert!(!store.replace("user", registration.version, &registration.revoked()).unwrap());
+
+        let stored = store.get("user").unwrap().unwrap();
+        prop_assert!(stored.matches(&second.params, &commitment));
+        prop_assert!(!stored.matches(&first.params, &registration.commitment));
+        prop_assert!(!stored.revoked);
+    }
+
+    #[test]
+    fn recovery_commitments_survive_rotation(primary in strategies::prover(), recovery in strategies::prover()) {
+        let commitment = primary.generate_commitment();
+        let recovery_commitment = recovery.generate_commitment();
+        let registration = Registration::new(primary.params.clone(), commitment.clone()).with_recovery(Recovery {
+            params: recovery.params.clone(),
+            commitment: recovery_commitment.clone(),
+        });
+        // the two commitments are not interchangeable
+        prop_assert!(registration.matches_recovery(&recovery.params, &recovery_commitment));
+        prop_assert!(!registration.matches_recovery(&primary.params, &commitment));
+        prop_assert!(!registration.matches(&recovery.params, &recovery_commitment));
+
+        let rotated = registration.rotated(recovery.params.clone(), recovery.generate_commitment());
+        prop_assert!(rotated.matches_recovery(&recovery.params, &recovery_commitment));
+    }
+}
@@ -0,0 +1,90 @@
+use std::sync::OnceLock;
+
+use num_bigint::BigUint;
+use proptest::prelude::*;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use zkp_chaum_pedersen_grpc::chaum_pedersen::{modpow, ChaumPedersen, Prover, PublicParameters, Transcript};
+use zkp_chaum_pedersen_grpc::sigma::{self, SigmaProtocol};
+use zkp_chaum_pedersen_grpc::simulator::Simulator;
+
+// One small group shared by every case; generating parameters dominates otherwise
+fn params() -> &'static PublicParameters {
+    static PARAMS: OnceLock<PublicParameters> = OnceLock::new();
+    PARAMS.get_or_init(|| PublicParameters::new(128))
+}
+
+fn is_subgroup_element(x: &BigUint, params: &PublicParameters) -> bool {
+    x.bits() > 0 && x < params.p() && modpow(x, params.q(), params.p()) == BigUint::from(1u32)
+}
+
+proptest! {
+    #[test]
+    fn simulated_transcripts_verify(seed: u64) {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let prover = Prover::with_rng(params().clone(), &mut rng);
+        let statement = prover.generate_commitment();
+        let protocol = ChaumPedersen::new(params().clone());
+
+        let proof = Simulator::simulate_with_rng(params(), &statement, &mut rng);
+        prop_assert!(protocol.verify(&statement, &proof.commitment, &proof.challenge, &proof.response));
+    }
+
+    // (s, z) determines the nonce x = z - a*s mod q, and the simulated (y1, y2) must be exactly what
+    // an honest prover sends for that x. The map between (x, s) and (s, z) is a bijection, so real
+    // and simulated transcripts are identically distributed.
+    #[test]
+    fn simulated_transcripts_match_an_honest_prover(seed: u64) {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let params = params();
+        let prover = Prover::with_rng(params.clone(), &mut rng);
+        let statement = prover.generate_commitment();
+
+        let proof = Simulator::simulate_with_rng(params, &statement, &mut rng);
+        let a_s = &prover.secret_a * &proof.challenge % params.q();
+        let x = (&proof.response.z + params.q() - a_s) % params.q();
+        prop_assert_eq!(modpow(params.g(), &x, params.p()), proof.commitment.y1.clone());
+        prop_assert_eq!(modpow(&statement.b1, &x, params.p()), proof.commitment.y2.clone());
+        prop_assert_eq!(prover.generate_response(&x, &proof.challenge).z, proof.response.z);
+    }
+
+    #[test]
+    fn simulated_commitments_are_group_elements(seed: u64) {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let prover = Prover::with_rng(params().clone(), &mut rng);
+        let statement = prover.generate_commitment();
+
+        let proof = Simulator::simulate_with_rng(params(), &statement, &mut rng);
+        prop_assert!(is_subgroup_element(&proof.commitment.y1, params()));
+        prop_assert!(is_subgroup_element(&proof.commitment.y2, params()));
+        prop_assert!(proof.challenge < *params().q() && proof.response.z < *params().q());
+    }
+
+    // Without the witness the simulator cannot make the challenge a hash of the transcript, so
+    // its output must not pass as a non-interactive proof
+    #[test]
+    fn simulated_transcripts_fail_fiat_shamir(seed: u64) {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let prover = Prover::with_rng(params().clone(), &mut rng);
+        let statement = prover.generate_commitment();
+        let protocol = ChaumPedersen::new(params().clone());
+
+        let proof = Simulator::simulate_with_rng(params(), &statement, &mut rng);
+        prop_assert!(!sigma::verify(&protocol, &statement, &proof));
+    }
+
+    #[test]
+    fn real_transcripts_verify(seed: u64) {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let prover = Prover::with_rng(params().clone(), &mut rng);
+        let statement = prover.generate_commitment();
+        let protocol = ChaumPedersen::new(params().clone());
+        let mut transcript = Transcript::new(ChaumPedersen::LABEL);
+
+        let proof = sigma::prove_with_rng(&protocol, &mut transcript, &statement, &prover.secret_a, &mut rng);
+        prop_assert!(is_subgroup_element(&proof.commitment.y1, params()));
+        prop_assert!(protocol.verify(&statement, &proof.commitment, &proof.challenge, &proof.response));
+        prop_assert!(sigma::verify(&protocol, &statement, &proof));
+    }
+}
@@ -0,0 +1,30 @@
+use proptest::prelude::*;
+
+use zkp_chaum_pedersen_grpc::chaum_pedersen::{ParameterPolicy, Verifier};
+use zkp_chaum_pedersen_grpc::strategies;
+
+proptest! {
+    #[test]
+    fn generated_params_are_valid(params in strategies::params()) {
+        let policy = ParameterPolicy {
+            min_bits: 32,
+            require_provenance: true,
+            ..ParameterPolicy::default()
+        };
+        prop_assert_eq!(params.validate_with(&policy), Ok(()));
+    }
+
+    #[test]
+    fn honest_proofs_verify((prover, proof) in strategies::proof()) {
+        prop_assert!(Verifier::new(prover.params).verify_proof(&proof));
+    }
+
+    #[test]
+    fn mutated_proofs_are_rejected((prover, proof, field) in strategies::mutated_proof()) {
+        prop_assert!(
+            !Verifier::new(prover.params).verify_proof(&proof),
+            "proof with {:?} changed still verified",
+            field
+        );
+    }
+}
@@ -0,0 +1,78 @@
+use std::sync::OnceLock;
+
+use num_bigint::BigUint;
+use proptest::prelude::*;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use zkp_chaum_pedersen_grpc::chaum_pedersen::{secret_fixed_pow, Prover, PublicParameters, Verifier};
+use zkp_chaum_pedersen_grpc::threshold::{self, Coordinator, ShareProver, ThresholdError};
+
+fn params() -> &'static PublicParameters {
+    static PARAMS: OnceLock<PublicParameters> = OnceLock::new();
+    PARAMS.get_or_init(|| PublicParameters::new(128))
+}
+
+// Splits a fresh prover's secret a across `parties` share provers that all use its b1
+fn share_provers(rng: &mut StdRng, parties: usize) -> (Prover, Vec<ShareProver>) {
+    let prover = Prover::with_rng(params().clone(), rng);
+    let b1 = secret_fixed_pow(params().g_table(), &prover.secret_b, params().q());
+    let shares = threshold::split_secret_with_rng(&prover.secret_a, parties, params().q(), rng);
+    let provers = shares
+        .into_iter()
+        .map(|share| ShareProver::new(params().clone(), b1.clone(), share))
+        .collect();
+    (prover, provers)
+}
+
+fn committed(rng: &mut StdRng, provers: &[ShareProver]) -> (Coordinator, Vec<BigUint>) {
+    let mut coordinator = Coordinator::new(params().clone(), provers[0].b1.clone(), provers.len() as u32);
+    let nonces = provers
+        .iter()
+        .enumerate()
+        .map(|(party, prover)| {
+            let (commitment, x) = prover.commit_with_rng(rng);
+            coordinator.add_commitment(party as u32, commitment).unwrap();
+            x
+        })
+        .collect();
+    (coordinator, nonces)
+}
+
+proptest! {
+    // The assembled commitment is exactly the single prover's, and the joint proof verifies as one
+    #[test]
+    fn assembled_proofs_verify(seed: u64, parties in 1usize..6) {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let (prover, provers) = share_provers(&mut rng, parties);
+        let (mut coordinator, nonces) = committed(&mut rng, &provers);
+
+        let challenge = coordinator.challenge().unwrap();
+        for (party, (share_prover, x)) in provers.iter().zip(&nonces).enumerate() {
+            prop_assert!(coordinator.assemble().is_none());
+            coordinator.add_response(party as u32, share_prover.respond(x, &challenge)).unwrap();
+        }
+
+        let proof = coordinator.assemble().unwrap();
+        let single = prover.generate_commitment();
+        prop_assert_eq!(&proof.commitment.a1, &single.a1);
+        prop_assert_eq!(&proof.commitment.c1, &single.c1);
+        prop_assert!(Verifier::new(params().clone()).verify_proof(&proof));
+    }
+
+    #[test]
+    fn a_wrong_share_is_blamed_on_its_party(seed: u64, parties in 2usize..6, bad in 0usize..6) {
+        let bad = bad % parties;
+        let mut rng = StdRng::seed_from_u64(seed);
+        let (_, provers) = share_provers(&mut rng, parties);
+        let (mut coordinator, nonces) = committed(&mut rng, &provers);
+
+        let challenge = coordinator.challenge().unwrap();
+        let mut response = provers[bad].respond(&nonces[bad], &challenge);
+        response.z = (response.z + 1u32) % params().q();
+        prop_assert_eq!(
+            coordinator.add_response(bad as u32, response),
+            Err(ThresholdError::InvalidResponse(bad as u32))
+        );
+    }
+}
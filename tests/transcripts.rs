@@ -0,0 +1,86 @@
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use tonic::Code;
+
+use zkp_chaum_pedersen_grpc::chaum_pedersen::Prover;
+use zkp_chaum_pedersen_grpc::client_lib::ClientError;
+use zkp_chaum_pedersen_grpc::config::ServerConfig;
+use zkp_chaum_pedersen_grpc::evidence::{self, FileTranscriptSink, TranscriptArchive, TranscriptSigner, TranscriptSink};
+use zkp_chaum_pedersen_grpc::service::ChaumPedersenServer;
+use zkp_chaum_pedersen_grpc::session::SessionId;
+use zkp_chaum_pedersen_grpc::testing::{test_config, TestServer};
+use zkp_chaum_pedersen_grpc::zkp;
+
+// A fresh directory under the system temp dir, left behind for inspection if a test fails
+fn archive_dir() -> PathBuf {
+    std::env::temp_dir().join(format!("zkp-transcripts-{}", SessionId::generate()))
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs()
+}
+
+#[tokio::test]
+async fn archived_transcripts_outlive_their_session() {
+    let dir = archive_dir();
+    let signer = TranscriptSigner::from_seed(&[7; 32]);
+    let key = signer.public_key();
+    let config = ServerConfig {
+        session_ttl_secs: 1,
+        ..test_config()
+    };
+    let server = ChaumPedersenServer::from_config(&config)
+        .with_transcript_signer(signer)
+        .with_transcript_archive(TranscriptArchive::new(Box::new(FileTranscriptSink::open(&dir).unwrap())));
+    let server = TestServer::serve(server, &config).await.unwrap();
+    let client = server.client().await.unwrap();
+
+    let session = client.initialize(256).await.unwrap();
+    let prover = Prover::new(session.params.clone());
+    let commitment = prover.generate_commitment();
+    let (values, x) = prover.generate_proof_challenge(&commitment);
+    let challenge = client.send_commitment(&session, &commitment, &values.y1, &values.y2).await.unwrap();
+    let z = prover.generate_response(&x, &challenge).z;
+    assert!(client.prove(&session, &z).await.unwrap().verified);
+    // expiry counts whole seconds past the TTL
+    tokio::time::sleep(Duration::from_millis(2500)).await;
+
+    let transcript = evidence::open(&client.transcript(&session).await.unwrap(), Some(&key)).unwrap();
+    assert_eq!(transcript.session_id, session.session_id);
+    assert!(transcript.verified);
+    assert_eq!(transcript.commitment_digest, commitment.digest());
+
+    let mut unknown = session.clone();
+    unknown.session_id = SessionId::generate().to_string();
+    match client.transcript(&unknown).await {
+        Err(ClientError::Rpc { status, .. }) => assert_eq!(status.code(), Code::NotFound),
+        other => panic!("expected NOT_FOUND, got {:?}", other),
+    }
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn retention_prunes_by_verification_time() {
+    let dir = archive_dir();
+    let sink = FileTranscriptSink::open(&dir).unwrap();
+    let signer = TranscriptSigner::generate();
+    let day = 86_400;
+
+    let (old, recent) = (SessionId::generate(), SessionId::generate());
+    for (id, verified_at) in [(&old, now_secs() - 2 * day), (&recent, now_secs())] {
+        let transcript = zkp::SessionTranscript {
+            session_id: id.to_string(),
+            verified: true,
+            verified_at,
+            ..Default::default()
+        };
+        sink.store(id, &signer.sign(&transcript), verified_at).unwrap();
+    }
+
+    assert_eq!(sink.prune(Duration::from_secs(day)).unwrap(), 1);
+    assert!(sink.load(&old).unwrap().is_none());
+    let kept = sink.load(&recent).unwrap().unwrap();
+    assert_eq!(evidence::open(&kept, Some(&signer.public_key())).unwrap().session_id, recent.to_string());
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,37 @@
+use zkp_chaum_pedersen_grpc::chaum_pedersen::{wire, Verifier};
+use zkp_chaum_pedersen_grpc::test_vectors::{self, TestVectors, CASES};
+
+const PUBLISHED: &str = include_str!("../vectors/chaum_pedersen.json");
+
+// Set ZKP_UPDATE_VECTORS=1 to rewrite the published file after a deliberate change
+#[test]
+fn published_vectors_are_reproduced() {
+    let generated = test_vectors::to_json();
+    if std::env::var_os("ZKP_UPDATE_VECTORS").is_some() {
+        std::fs::write(concat!(env!("CARGO_MANIFEST_DIR"), "/vectors/chaum_pedersen.json"), &generated).unwrap();
+        return;
+    }
+    assert_eq!(generated, PUBLISHED);
+}
+
+#[test]
+fn published_vectors_verify() {
+    let published: TestVectors = wire::from_json(PUBLISHED).unwrap();
+    assert_eq!(published.version, test_vectors::VECTORS_VERSION);
+    assert_eq!(published.vectors.len(), CASES.len());
+    for (vector, case) in published.vectors.iter().zip(CASES) {
+        let params = &vector.params;
+        assert!(params.verify_provenance(), "{}", vector.name);
+        assert_eq!(vector.label.as_bytes(), case.label);
+        assert_eq!(vector.session_id, case.session_id);
+        let verifier = Verifier::builder(params.clone()).label(case.label).build().unwrap();
+        assert!(verifier.verify_proof(&vector.proof()), "{}", vector.name);
+
+        // g^z = y1 * a1^c for the session challenge too
+        let (p, g) = (params.p(), params.g());
+        let a1 = &vector.commitment.a1;
+        let expected = &vector.challenge_values.y1 * a1.modpow(&vector.session_challenge, p) % p;
+        assert_eq!(g.modpow(&vector.session_z, p), expected, "{}", vector.name);
+        assert_eq!(g.modpow(&vector.secret_a, p), *a1);
+    }
+}
@@ -0,0 +1,62 @@
+use num_bigint::BigUint;
+use proptest::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use zkp_chaum_pedersen_grpc::chaum_pedersen::{wire, ProofResponse, PublicParameters, ZKProof};
+use zkp_chaum_pedersen_grpc::strategies;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Integrator {
+    #[serde(with = "wire::biguint_base64url")]
+    value: BigUint,
+}
+
+// Every string in a JSON document, which for the crate's types are exactly the integers
+fn strings(value: &Value) -> Vec<String> {
+    match value {
+        Value::String(text) => vec![text.clone()],
+        Value::Array(items) => items.iter().flat_map(strings).collect(),
+        Value::Object(fields) => fields.values().flat_map(strings).collect(),
+        _ => Vec::new(),
+    }
+}
+
+proptest! {
+    #[test]
+    fn integers_are_byte_aligned_hex_in_json_and_bytes_in_cbor((prover, proof) in strategies::proof()) {
+        let json = wire::to_json(&proof).unwrap();
+        for text in strings(&serde_json::from_str(&json).unwrap()) {
+            prop_assert!(text.len() % 2 == 0 && hex::decode(&text).is_ok(), "{:?}", text);
+        }
+        let params: Value = serde_json::from_str(&wire::to_json(&prover.params).unwrap()).unwrap();
+        prop_assert_eq!(&params["p"], &Value::String(hex::encode(prover.params.p().to_bytes_be())));
+
+        let decoded: ZKProof = wire::from_json(&json).unwrap();
+        prop_assert_eq!(decoded.commitment.digest(), proof.commitment.digest());
+        let decoded: ZKProof = wire::from_cbor(&wire::to_cbor(&proof).unwrap()).unwrap();
+        prop_assert_eq!(decoded.response.z, proof.response.z);
+        let decoded: PublicParameters = wire::from_cbor(&wire::to_cbor(&prover.params).unwrap()).unwrap();
+        prop_assert_eq!(decoded.digest(), prover.params.digest());
+    }
+
+    #[test]
+    fn base64url_round_trips(value in any::<u128>()) {
+        let value = BigUint::from(value);
+        let json = wire::to_json(&Integrator { value: value.clone() }).unwrap();
+        prop_assert!(!json.contains(['+', '/', '=']));
+        prop_assert_eq!(wire::from_json::<Integrator>(&json).unwrap().value, value);
+    }
+}
+
+#[test]
+fn hex_integers_are_read_strictly() {
+    let read = |text: &str| wire::from_json::<ProofResponse>(&format!(r#"{{"z":"{}"}}"#, text)).map(|r| r.z);
+    assert_eq!(read("0a1b").unwrap(), BigUint::from(0x0a1bu32));
+    // written before hex was byte aligned
+    assert_eq!(read("a1b").unwrap(), BigUint::from(0xa1bu32));
+    for text in ["", "0A1B", "0x0a", "0a_1b", "+0a", " 0a"] {
+        assert!(read(text).is_err(), "{:?}", text);
+    }
+    assert!(wire::from_json::<Integrator>(r#"{"value":""}"#).is_err());
+}